@@ -21,6 +21,8 @@ use std::io::Write;
 use std::path::{Path, PathBuf};
 use std::process::Stdio;
 
+mod diff;
+
 /// Generate extension schema files
 #[derive(clap::Args, Debug)]
 #[clap(author)]
@@ -58,6 +60,13 @@ pub(crate) struct Schema {
     /// Skip building a fresh extension shared object.
     #[clap(long)]
     skip_build: bool,
+    /// Output a script upgrading from a previous schema instead, given as a git ref or a SQL file
+    ///
+    /// Both schemas are compared as generated SQL.  An object is taken to depend on another when
+    /// its statement mentions the other's unqualified name, which can recreate more than needed
+    /// and can miss dependencies hidden in strings, so review the script before shipping it.
+    #[clap(long, value_name = "GIT_REF|SQL_FILE")]
+    diff: Option<String>,
 }
 
 impl CommandExecute for Schema {
@@ -93,20 +102,59 @@ impl CommandExecute for Schema {
             if self.release { CargoProfile::Release } else { CargoProfile::Dev },
         )?;
 
+        let Some(from) = self.diff.clone() else {
+            return generate_schema(
+                &pg_config,
+                self.manifest_path.as_ref(),
+                self.package.as_ref(),
+                package_manifest_path,
+                &profile,
+                self.test,
+                &self.features,
+                self.out.as_ref(),
+                self.dot,
+                log_level,
+                self.skip_build,
+                &mut vec![],
+            );
+        };
+
+        // the current schema is only an input to the upgrade script
+        let current = tempfile::NamedTempFile::new()?;
         generate_schema(
             &pg_config,
             self.manifest_path.as_ref(),
             self.package.as_ref(),
-            package_manifest_path,
+            &package_manifest_path,
             &profile,
             self.test,
             &self.features,
-            self.out.as_ref(),
-            self.dot,
+            Some(current.path()),
+            self.dot.as_ref(),
             log_level,
             self.skip_build,
             &mut vec![],
-        )
+        )?;
+        let current = std::fs::read_to_string(current.path())?;
+        let previous = if Path::new(&from).is_file() {
+            std::fs::read_to_string(&from).wrap_err_with(|| format!("couldn't read `{from}`"))?
+        } else {
+            diff::schema_at_git_ref(&from, &self, &package_manifest_path)?
+        };
+
+        let script = diff::upgrade_script(&previous, &current)?;
+        match &self.out {
+            Some(out) => {
+                if let Some(parent) = out.parent() {
+                    std::fs::create_dir_all(parent)
+                        .wrap_err("Could not create parent directory")?;
+                }
+                eprintln!("{} upgrade script to {}", "     Writing".bold().green(), out.display());
+                std::fs::write(out, script)?;
+            }
+            None => print!("{script}"),
+        }
+        Ok(())
     }
 }
 
@@ -254,12 +302,16 @@ fn compute_symbols(profile: &CargoProfile, lib_filename: &str) -> eyre::Result<V
     let mut seen_schemas = Vec::new();
     let mut num_funcs = 0_usize;
     let mut num_triggers = 0_usize;
+    let mut num_event_triggers = 0_usize;
     let mut num_types = 0_usize;
     let mut num_enums = 0_usize;
     let mut num_sqls = 0_usize;
     let mut num_ords = 0_usize;
     let mut num_hashes = 0_usize;
     let mut num_aggregates = 0_usize;
+    let mut num_index_ams = 0_usize;
+    let mut num_fdws = 0_usize;
+    let mut num_table_ams = 0_usize;
     for func in &fns_to_call {
         if func.starts_with("__pgrx_internals_schema_") {
             let schema =
@@ -269,6 +321,8 @@ fn compute_symbols(profile: &CargoProfile, lib_filename: &str) -> eyre::Result<V
             num_funcs += 1;
         } else if func.starts_with("__pgrx_internals_trigger_") {
             num_triggers += 1;
+        } else if func.starts_with("__pgrx_internals_event_trigger_") {
+            num_event_triggers += 1;
         } else if func.starts_with("__pgrx_internals_type_") {
             num_types += 1;
         } else if func.starts_with("__pgrx_internals_enum_") {
@@ -281,11 +335,17 @@ fn compute_symbols(profile: &CargoProfile, lib_filename: &str) -> eyre::Result<V
             num_hashes += 1;
         } else if func.starts_with("__pgrx_internals_aggregate_") {
             num_aggregates += 1;
+        } else if func.starts_with("__pgrx_internals_index_am_") {
+            num_index_ams += 1;
+        } else if func.starts_with("__pgrx_internals_fdw_") {
+            num_fdws += 1;
+        } else if func.starts_with("__pgrx_internals_table_am_") {
+            num_table_ams += 1;
         }
     }
 
     eprintln!(
-        "{} {} SQL entities: {} schemas ({} unique), {} functions, {} types, {} enums, {} sqls, {} ords, {} hashes, {} aggregates, {} triggers",
+        "{} {} SQL entities: {} schemas ({} unique), {} functions, {} types, {} enums, {} sqls, {} ords, {} hashes, {} aggregates, {} triggers, {} event triggers, {} index access methods, {} foreign data wrappers, {} table access methods",
         "  Discovered".bold().green(),
         fns_to_call.len().to_string().bold().cyan(),
         seen_schemas.len().to_string().bold().cyan(),
@@ -298,6 +358,10 @@ fn compute_symbols(profile: &CargoProfile, lib_filename: &str) -> eyre::Result<V
         num_hashes.to_string().bold().cyan(),
         num_aggregates.to_string().bold().cyan(),
         num_triggers.to_string().bold().cyan(),
+        num_event_triggers.to_string().bold().cyan(),
+        num_index_ams.to_string().bold().cyan(),
+        num_fdws.to_string().bold().cyan(),
+        num_table_ams.to_string().bold().cyan(),
     );
 
     tracing::debug!("Collecting {} SQL entities", fns_to_call.len());
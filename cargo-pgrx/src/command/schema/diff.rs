@@ -0,0 +1,1125 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+use super::Schema;
+use eyre::{eyre, WrapErr};
+use owo_colors::OwoColorize;
+use pgrx_pg_config::get_target_dir;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+const HEADER: &str = "\
+/*
+This file is auto generated by `cargo pgrx schema --diff`.
+
+It only knows about the SQL pgrx generates, so review it before shipping it.
+*/
+";
+
+/// Lines `PgrxSql::to_sql()` wraps around groups of objects which aren't worth repeating
+const CONNECTED_MARKERS: &[&str] =
+    &["/* <begin connected objects> */", "/* </end connected objects> */"];
+
+/// `CREATE TYPE` options which decide how values of the type are laid out on disk
+const TYPE_LAYOUT_OPTIONS: &[&str] =
+    &["internallength", "passedbyvalue", "alignment", "storage", "like", "element"];
+
+/// `CREATE TYPE` options `ALTER TYPE ... SET` can change
+const TYPE_ALTERABLE_OPTIONS: &[&str] =
+    &["receive", "send", "typmod_in", "typmod_out", "analyze", "subscript"];
+
+/// Words that end the `RETURNS` clause of `CREATE FUNCTION`
+const ROUTINE_ATTRIBUTES: &[&str] = &[
+    "as",
+    "begin",
+    "called",
+    "cost",
+    "external",
+    "immutable",
+    "language",
+    "leakproof",
+    "not",
+    "parallel",
+    "return",
+    "returns",
+    "rows",
+    "security",
+    "set",
+    "stable",
+    "strict",
+    "support",
+    "transform",
+    "volatile",
+    "window",
+];
+
+/// Words that start a type name made of several words, such as `double precision`
+const MULTI_WORD_TYPES: &[&str] =
+    &["bit", "char", "character", "double", "interval", "national", "time", "timestamp"];
+
+const OPERATOR_CHARS: &[u8] = b"+-*/<>=~!@#%^&|`?:";
+
+/// Generate the schema of the extension as it was at `git_ref`
+///
+/// The extension is checked out into a temporary git worktree and `cargo pgrx schema` runs there,
+/// with its own target directory so the build doesn't clobber the current one.
+pub(super) fn schema_at_git_ref(
+    git_ref: &str,
+    schema: &Schema,
+    package_manifest_path: &Path,
+) -> eyre::Result<String> {
+    let package_dir = package_manifest_path
+        .parent()
+        .ok_or_else(|| eyre!("`{}` has no parent directory", package_manifest_path.display()))?
+        .canonicalize()?;
+    let toplevel =
+        PathBuf::from(git(&package_dir, &["rev-parse", "--show-toplevel"])?).canonicalize()?;
+    git(&package_dir, &["rev-parse", "--verify", "--quiet", &format!("{git_ref}^{{commit}}")])
+        .wrap_err_with(|| format!("`{git_ref}` is neither a SQL file nor a git ref"))?;
+
+    let tempdir = tempfile::tempdir()?;
+    let worktree = Worktree::add(&toplevel, tempdir.path().join("worktree"), git_ref)?;
+    let out = tempdir.path().join("schema.sql");
+
+    let mut command = Command::new(std::env::current_exe()?);
+    command.current_dir(worktree.path.join(package_dir.strip_prefix(&toplevel)?));
+    command.stdin(Stdio::null());
+    command.args(["pgrx", "schema", "--out"]);
+    command.arg(&out);
+    if let Some(package) = &schema.package {
+        command.arg("--package");
+        command.arg(package);
+    }
+    if schema.test {
+        command.arg("--test");
+    }
+    if schema.release {
+        command.arg("--release");
+    }
+    if let Some(profile) = &schema.profile {
+        command.arg("--profile");
+        command.arg(profile);
+    }
+    if let Some(pg_config) = &schema.pg_config {
+        command.arg("--pg-config");
+        command.arg(pg_config);
+    }
+    if !schema.features.features.is_empty() {
+        command.arg("--features");
+        command.arg(schema.features.features.join(" "));
+    }
+    if schema.features.no_default_features {
+        command.arg("--no-default-features");
+    }
+    if schema.features.all_features {
+        command.arg("--all-features");
+    }
+    command.env("CARGO_TARGET_DIR", get_target_dir()?.join("pgrx-schema-diff"));
+
+    eprintln!("{} the schema at `{}`", "  Generating".bold().green(), git_ref.cyan());
+    let command_str = format!("{command:?}");
+    tracing::debug!(command = %command_str, "Running");
+    let status =
+        command.status().wrap_err_with(|| format!("failed to spawn cargo-pgrx: {command_str}"))?;
+    if !status.success() {
+        return Err(eyre!("couldn't generate the schema at `{git_ref}`"));
+    }
+
+    std::fs::read_to_string(&out)
+        .wrap_err_with(|| format!("couldn't read the schema generated at `{git_ref}`"))
+}
+
+/// A git worktree which is removed again when dropped
+struct Worktree {
+    repository: PathBuf,
+    path: PathBuf,
+}
+
+impl Worktree {
+    fn add(repository: &Path, path: PathBuf, git_ref: &str) -> eyre::Result<Self> {
+        let path_str = path.to_str().ok_or_else(|| eyre!("temporary path is not valid UTF8"))?;
+        git(repository, &["worktree", "add", "--detach", path_str, git_ref])?;
+        Ok(Worktree { repository: repository.to_path_buf(), path })
+    }
+}
+
+impl Drop for Worktree {
+    fn drop(&mut self) {
+        let removed = Command::new("git")
+            .arg("-C")
+            .arg(&self.repository)
+            .args(["worktree", "remove", "--force"])
+            .arg(&self.path)
+            .output();
+        if !removed.is_ok_and(|output| output.status.success()) {
+            tracing::warn!(worktree = %self.path.display(), "Could not remove git worktree");
+        }
+    }
+}
+
+fn git(dir: &Path, args: &[&str]) -> eyre::Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .wrap_err("problem running `git`")?;
+    if !output.status.success() {
+        return Err(eyre!(
+            "`git {}` failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8(output.stdout)?.trim().to_string())
+}
+
+/// Generate a script upgrading an extension whose schema was `old_sql` to `new_sql`
+///
+/// Both are SQL generated by `cargo pgrx schema`.  Changes which can't be made without risking
+/// existing data, such as changing the layout of a type or removing an enum label, are errors.
+///
+/// Objects are matched up by their identity in SQL, and dependencies are found by the names
+/// statements mention, as in [`Object::references`], rather than from pgrx's entity graph, which
+/// isn't available for a schema given as a SQL file.  This errs towards recreating too much.
+pub(crate) fn upgrade_script(old_sql: &str, new_sql: &str) -> eyre::Result<String> {
+    let old = objects(old_sql);
+    let new = objects(new_sql);
+    let old_keys = old.iter().enumerate().map(|(idx, object)| (object.key.as_str(), idx));
+    let old_keys = old_keys.collect::<HashMap<_, _>>();
+    let new_keys = new.iter().enumerate().map(|(idx, object)| (object.key.as_str(), idx));
+    let new_keys = new_keys.collect::<HashMap<_, _>>();
+
+    let mut unsafe_changes = Vec::new();
+    let mut actions = new
+        .iter()
+        .map(|object| match old_keys.get(object.key.as_str()) {
+            None => Action::Create,
+            Some(&idx) if old[idx].statement.tokens == object.statement.tokens => Action::Keep,
+            Some(&idx) => changed(&old[idx], object, &mut unsafe_changes),
+        })
+        .collect::<Vec<_>>();
+
+    let removed = old.iter().filter(|object| !new_keys.contains_key(object.key.as_str()));
+    let removed = removed.collect::<Vec<_>>();
+    for object in &removed {
+        if object.kind == Kind::Type {
+            unsafe_changes.push(format!(
+                "type `{}` was removed, and dropping it would drop any data stored in it",
+                object.identity
+            ));
+        }
+    }
+
+    // Objects depending on something which has to be dropped need dropping, and creating, too
+    let mut dropped = removed
+        .iter()
+        .filter(|object| object.kind.droppable())
+        .map(|object| object.name.clone())
+        .chain(
+            new.iter()
+                .zip(&actions)
+                .filter(|(_, action)| matches!(action, Action::Recreate))
+                .map(|(object, _)| object.name.clone()),
+        )
+        .collect::<HashSet<_>>();
+    let mut blocked = HashSet::new();
+    loop {
+        let mut more = false;
+        for (idx, object) in new.iter().enumerate() {
+            if !matches!(actions[idx], Action::Keep) || !object.kind.has_dependencies() {
+                continue;
+            }
+            let Some(dependency) = dropped.iter().find(|name| object.references(name)) else {
+                continue;
+            };
+            if object.kind == Kind::Type {
+                if blocked.insert(idx) {
+                    unsafe_changes.push(format!(
+                        "type `{}` depends on `{dependency}`, which the upgrade has to drop",
+                        object.identity
+                    ));
+                }
+                continue;
+            }
+            actions[idx] = Action::Recreate;
+            dropped.insert(object.name.clone());
+            more = true;
+        }
+        if !more {
+            break;
+        }
+    }
+
+    if !unsafe_changes.is_empty() {
+        return Err(eyre!(
+            "the schema changed in ways an upgrade script can't safely handle:\n{}",
+            unsafe_changes
+                .iter()
+                .map(|change| format!("  - {change}"))
+                .collect::<Vec<_>>()
+                .join("\n")
+        ));
+    }
+
+    let mut script = String::from(HEADER);
+    let mut drops = String::new();
+    for object in old.iter().rev() {
+        let recreated = new_keys
+            .get(object.key.as_str())
+            .is_some_and(|&idx| matches!(actions[idx], Action::Recreate));
+        let removed = !new_keys.contains_key(object.key.as_str());
+        if recreated || (removed && object.kind.droppable()) {
+            drops.push_str(&format!("DROP {} {};\n", object.kind.sql(), object.identity));
+        } else if removed && object.kind == Kind::Sql {
+            drops.push_str("-- no longer part of the extension, undo it by hand if needed:\n");
+            for line in object.statement.code().lines() {
+                drops.push_str(&format!("-- {line}\n"));
+            }
+        }
+    }
+    if !drops.is_empty() {
+        script.push('\n');
+        script.push_str(&drops);
+    }
+
+    for (object, action) in new.iter().zip(&actions) {
+        let sql = match action {
+            Action::Keep => continue,
+            Action::Create | Action::Recreate => object.statement.sql(),
+            Action::Replace => object.statement.or_replace(),
+            Action::Alter(statements) if statements.is_empty() => continue,
+            Action::Alter(statements) => {
+                format!("{}{}", object.statement.comments(), statements.join("\n"))
+            }
+        };
+        script.push('\n');
+        script.push_str(&sql);
+        script.push('\n');
+    }
+
+    Ok(script)
+}
+
+/// What the upgrade script does with an object of the new schema
+#[derive(Debug, PartialEq, Eq)]
+enum Action {
+    /// It's unchanged
+    Keep,
+    /// It's new
+    Create,
+    /// It changed in a way only dropping and creating it again can achieve
+    Recreate,
+    /// It changed in a way `CREATE OR REPLACE` can achieve
+    Replace,
+    /// It changed in a way these `ALTER` statements can achieve
+    Alter(Vec<String>),
+}
+
+/// Decide what to do about an object whose definition changed from `old` to `new`
+fn changed(old: &Object<'_>, new: &Object<'_>, unsafe_changes: &mut Vec<String>) -> Action {
+    let name = &new.identity;
+    match (&old.definition, &new.definition) {
+        (
+            Definition::Routine { arguments: old_arguments, returns: old_returns },
+            Definition::Routine { arguments, returns },
+        ) => {
+            // `CREATE OR REPLACE` can't rename arguments, drop defaults or change the return type
+            if old_arguments == arguments && old_returns == returns {
+                Action::Replace
+            } else {
+                Action::Recreate
+            }
+        }
+        (Definition::Enum(old_labels), Definition::Enum(labels)) => {
+            Action::Alter(enum_changes(name, old_labels, labels, unsafe_changes))
+        }
+        (Definition::BaseType(old_options), Definition::BaseType(options)) => {
+            Action::Alter(base_type_changes(name, old_options, options, unsafe_changes))
+        }
+        _ if new.kind == Kind::Type => {
+            unsafe_changes.push(format!("type `{name}` changed its definition"));
+            Action::Keep
+        }
+        _ if new.kind == Kind::Schema || new.kind == Kind::ShellType => Action::Keep,
+        _ => Action::Recreate,
+    }
+}
+
+fn enum_changes(
+    name: &str,
+    old: &[String],
+    new: &[String],
+    unsafe_changes: &mut Vec<String>,
+) -> Vec<String> {
+    let removed = old.iter().filter(|label| !new.contains(label)).collect::<Vec<_>>();
+    if !removed.is_empty() {
+        let removed = removed.iter().map(|label| label.as_str()).collect::<Vec<_>>();
+        unsafe_changes.push(format!(
+            "enum `{name}` no longer has the label(s) {}, which existing data may still use",
+            removed.join(", ")
+        ));
+        return Vec::new();
+    }
+    if !new.iter().filter(|label| old.contains(label)).eq(old.iter()) {
+        unsafe_changes.push(format!(
+            "enum `{name}` reordered its labels, which changes how existing values sort"
+        ));
+        return Vec::new();
+    }
+
+    // every label must be placed next to one that exists by then, so those ahead of the first
+    // existing label are added backwards from it, each before its successor, and the rest after
+    // their predecessor
+    let leading = new.iter().position(|label| old.contains(label)).unwrap_or(0);
+    let mut changes = new[..leading]
+        .iter()
+        .enumerate()
+        .rev()
+        .map(|(idx, label)| format!("ALTER TYPE {name} ADD VALUE {label} BEFORE {};", new[idx + 1]))
+        .collect::<Vec<_>>();
+    changes.extend(
+        new.iter().enumerate().skip(leading).filter(|(_, label)| !old.contains(label)).map(
+            |(idx, label)| match idx.checked_sub(1) {
+                Some(previous) => {
+                    format!("ALTER TYPE {name} ADD VALUE {label} AFTER {};", new[previous])
+                }
+                None => format!("ALTER TYPE {name} ADD VALUE {label};"),
+            },
+        ),
+    );
+    changes
+}
+
+fn base_type_changes(
+    name: &str,
+    old: &BTreeMap<String, String>,
+    new: &BTreeMap<String, String>,
+    unsafe_changes: &mut Vec<String>,
+) -> Vec<String> {
+    let mut set = Vec::new();
+    for option in old.keys().chain(new.keys()).collect::<BTreeSet<_>>() {
+        let value = new.get(option);
+        if old.get(option) == value {
+            continue;
+        }
+        let option_sql = option.to_uppercase();
+        if TYPE_LAYOUT_OPTIONS.contains(&option.as_str()) {
+            unsafe_changes.push(format!(
+                "type `{name}` changed its {option_sql}, which changes the layout of existing values"
+            ));
+        } else if TYPE_ALTERABLE_OPTIONS.contains(&option.as_str()) {
+            set.push(format!("{option_sql} = {}", value.map_or("NONE", String::as_str)));
+        } else {
+            unsafe_changes.push(format!(
+                "type `{name}` changed its {option_sql}, which can't be altered after the type is created"
+            ));
+        }
+    }
+
+    if set.is_empty() {
+        Vec::new()
+    } else {
+        vec![format!("ALTER TYPE {name} SET ({});", set.join(", "))]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Schema,
+    /// `CREATE TYPE name;`, the placeholder for a base type until its functions exist
+    ShellType,
+    Type,
+    Function,
+    Procedure,
+    Aggregate,
+    Operator,
+    OperatorClass,
+    OperatorFamily,
+    Cast,
+    Trigger,
+    EventTrigger,
+    AccessMethod,
+    ForeignDataWrapper,
+    /// Any other statement, such as those from `extension_sql!()`
+    Sql,
+}
+
+impl Kind {
+    fn sql(self) -> &'static str {
+        match self {
+            Kind::Schema => "SCHEMA",
+            Kind::ShellType | Kind::Type => "TYPE",
+            Kind::Function => "FUNCTION",
+            Kind::Procedure => "PROCEDURE",
+            Kind::Aggregate => "AGGREGATE",
+            Kind::Operator => "OPERATOR",
+            Kind::OperatorClass => "OPERATOR CLASS",
+            Kind::OperatorFamily => "OPERATOR FAMILY",
+            Kind::Cast => "CAST",
+            Kind::Trigger => "TRIGGER",
+            Kind::EventTrigger => "EVENT TRIGGER",
+            Kind::AccessMethod => "ACCESS METHOD",
+            Kind::ForeignDataWrapper => "FOREIGN DATA WRAPPER",
+            Kind::Sql => "SQL",
+        }
+    }
+
+    /// Can a `DROP` statement remove objects of this kind when they are no longer generated?
+    ///
+    /// Types can't be dropped without dropping the data stored in them, and arbitrary SQL can't
+    /// be undone.
+    fn droppable(self) -> bool {
+        !matches!(self, Kind::ShellType | Kind::Type | Kind::Sql)
+    }
+
+    /// Can objects of this kind depend on other objects by name?
+    fn has_dependencies(self) -> bool {
+        !matches!(self, Kind::Schema | Kind::ShellType | Kind::Sql)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Definition {
+    BaseType(BTreeMap<String, String>),
+    Enum(Vec<String>),
+    /// The parts of a function or procedure `CREATE OR REPLACE` can't change
+    Routine {
+        arguments: String,
+        returns: String,
+    },
+    Other,
+}
+
+/// An object created by a statement of a generated schema
+struct Object<'a> {
+    kind: Kind,
+    /// What follows `DROP <kind>` to name the object, such as `tests.add(int, int)`
+    identity: String,
+    /// The unqualified name other objects refer to this one by
+    name: String,
+    /// Identifies the object across both schemas
+    key: String,
+    definition: Definition,
+    statement: Statement<'a>,
+}
+
+impl Object<'_> {
+    /// Does the object depend on the one named `name`?
+    ///
+    /// Routines only depend on their `SUPPORT` function.  Other objects compare `name` with each
+    /// unqualified name in their statement, so an argument or type with the same name counts too,
+    /// while a name within a string doesn't.
+    fn references(&self, name: &str) -> bool {
+        match self.kind {
+            // the only other object Postgres records a routine depending on is its `SUPPORT`
+            // function, the rest of it is looked up when it's called
+            Kind::Function | Kind::Procedure => {
+                let tokens = &self.statement.tokens;
+                position_top_level(tokens, 0, |token| token == "support")
+                    .and_then(|idx| qualified_name(tokens, idx + 1))
+                    .is_some_and(|(_, support, _)| support == name)
+            }
+            _ => self.statement.tokens.iter().any(|token| token == name),
+        }
+    }
+}
+
+fn objects(sql: &str) -> Vec<Object<'_>> {
+    let mut seen = HashMap::<String, usize>::new();
+    statements(sql)
+        .into_iter()
+        .map(|statement| {
+            let (kind, identity, name, definition) =
+                parse(&statement.tokens).unwrap_or_else(|| {
+                    (Kind::Sql, render(&statement.tokens), String::new(), Definition::Other)
+                });
+            let key = format!("{} {identity}", kind.sql());
+            // arbitrary SQL may well repeat itself
+            let count = seen.entry(key.clone()).or_default();
+            let key = if *count == 0 { key } else { format!("{key} #{count}") };
+            *count += 1;
+            Object { kind, identity, name, key, definition, statement }
+        })
+        .collect()
+}
+
+fn parse(tokens: &[String]) -> Option<(Kind, String, String, Definition)> {
+    if word(tokens, 0) != "create" {
+        return None;
+    }
+    let mut idx = 1;
+    if word(tokens, idx) == "or" && word(tokens, idx + 1) == "replace" {
+        idx += 2;
+    }
+    if word(tokens, idx) == "constraint" {
+        idx += 1;
+    }
+
+    let object = match (word(tokens, idx), word(tokens, idx + 1), word(tokens, idx + 2)) {
+        ("schema", ..) => {
+            idx += 1;
+            if word(tokens, idx) == "if" {
+                idx += 3;
+            }
+            let (name, last, _) = qualified_name(tokens, idx)?;
+            (Kind::Schema, name, last, Definition::Other)
+        }
+        ("type", ..) => {
+            let (name, last, idx) = qualified_name(tokens, idx + 1)?;
+            match (word(tokens, idx), word(tokens, idx + 1)) {
+                ("", _) => (Kind::ShellType, name, last, Definition::Other),
+                ("as", "enum") => {
+                    let (labels, _) = group(tokens, idx + 2)?;
+                    let labels = split_top_level(labels, ",").into_iter().map(render).collect();
+                    (Kind::Type, name, last, Definition::Enum(labels))
+                }
+                ("(", _) => {
+                    let (options, _) = group(tokens, idx)?;
+                    (Kind::Type, name, last, Definition::BaseType(parse_options(options)))
+                }
+                _ => (Kind::Type, name, last, Definition::Other),
+            }
+        }
+        (kind @ ("function" | "procedure"), ..) => {
+            let (name, last, idx) = qualified_name(tokens, idx + 1)?;
+            let (arguments, idx) = group(tokens, idx)?;
+            let returns = match position_top_level(tokens, idx, |token| token == "returns") {
+                Some(start) => {
+                    let end = position_top_level(tokens, start + 1, |token| {
+                        ROUTINE_ATTRIBUTES.contains(&token)
+                    })
+                    .unwrap_or(tokens.len());
+                    render(&tokens[start..end])
+                }
+                None => String::new(),
+            };
+            let kind = if kind == "function" { Kind::Function } else { Kind::Procedure };
+            let identity = format!("{name}({})", argument_types(arguments).join(", "));
+            let arguments = render(arguments);
+            (kind, identity, last, Definition::Routine { arguments, returns })
+        }
+        ("aggregate", ..) => {
+            let (name, last, idx) = qualified_name(tokens, idx + 1)?;
+            let (arguments, _) = group(tokens, idx)?;
+            let order_by = (0..arguments.len())
+                .find(|&idx| word(arguments, idx) == "order" && word(arguments, idx + 1) == "by");
+            let signature = match order_by {
+                _ if render(arguments) == "*" => String::from("*"),
+                Some(order_by) => format!(
+                    "{} ORDER BY {}",
+                    argument_types(&arguments[..order_by]).join(", "),
+                    argument_types(&arguments[order_by + 2..]).join(", ")
+                )
+                .trim_start()
+                .to_string(),
+                None => argument_types(arguments).join(", "),
+            };
+            (Kind::Aggregate, format!("{name}({signature})"), last, Definition::Other)
+        }
+        ("operator", kind @ ("class" | "family"), _) => {
+            let (name, last, idx) = qualified_name(tokens, idx + 2)?;
+            let using = position_top_level(tokens, idx, |token| token == "using")?;
+            let identity = format!("{name} USING {}", word(tokens, using + 1));
+            let kind = if kind == "class" { Kind::OperatorClass } else { Kind::OperatorFamily };
+            (kind, identity, last, Definition::Other)
+        }
+        ("operator", ..) => {
+            let (name, last, idx) = qualified_name(tokens, idx + 1)?;
+            let (options, _) = group(tokens, idx)?;
+            let options = parse_options(options);
+            let argument = |option| options.get(option).map_or("NONE", String::as_str);
+            let identity = format!("{name}({}, {})", argument("leftarg"), argument("rightarg"));
+            (Kind::Operator, identity, last, Definition::Other)
+        }
+        ("cast", ..) => {
+            let (types, _) = group(tokens, idx + 1)?;
+            let as_idx = position_top_level(types, 0, |token| token == "as")?;
+            let identity =
+                format!("({} AS {})", render(&types[..as_idx]), render(&types[as_idx + 1..]));
+            (Kind::Cast, identity, String::new(), Definition::Other)
+        }
+        ("trigger", ..) => {
+            let (name, last, idx) = qualified_name(tokens, idx + 1)?;
+            let on = position_top_level(tokens, idx, |token| token == "on")?;
+            let (table, _, _) = qualified_name(tokens, on + 1)?;
+            (Kind::Trigger, format!("{name} ON {table}"), last, Definition::Other)
+        }
+        ("event", "trigger", _) => {
+            let (name, last, _) = qualified_name(tokens, idx + 2)?;
+            (Kind::EventTrigger, name, last, Definition::Other)
+        }
+        ("access", "method", _) => {
+            let (name, last, _) = qualified_name(tokens, idx + 2)?;
+            (Kind::AccessMethod, name, last, Definition::Other)
+        }
+        ("foreign", "data", "wrapper") => {
+            let (name, last, _) = qualified_name(tokens, idx + 3)?;
+            (Kind::ForeignDataWrapper, name, last, Definition::Other)
+        }
+        _ => return None,
+    };
+    Some(object)
+}
+
+fn word(tokens: &[String], idx: usize) -> &str {
+    tokens.get(idx).map_or("", String::as_str)
+}
+
+/// Parse a possibly schema-qualified name, returning it, its last part and the index after it
+fn qualified_name(tokens: &[String], mut idx: usize) -> Option<(String, String, usize)> {
+    let mut parts = vec![tokens.get(idx)?.as_str()];
+    idx += 1;
+    while word(tokens, idx) == "." {
+        parts.push(tokens.get(idx + 1)?);
+        idx += 2;
+    }
+    let last = parts.last()?.to_string();
+    Some((parts.join("."), last, idx))
+}
+
+/// The tokens inside the parentheses starting at `idx`, and the index after them
+fn group(tokens: &[String], idx: usize) -> Option<(&[String], usize)> {
+    if word(tokens, idx) != "(" {
+        return None;
+    }
+    let end = position_top_level(tokens, idx + 1, |token| token == ")")?;
+    Some((&tokens[idx + 1..end], end + 1))
+}
+
+/// The index of the first token from `start` matching `predicate` outside of any parentheses
+fn position_top_level(
+    tokens: &[String],
+    start: usize,
+    predicate: impl Fn(&str) -> bool,
+) -> Option<usize> {
+    let mut depth = 0_usize;
+    for (idx, token) in tokens.iter().enumerate().skip(start) {
+        if depth == 0 && predicate(token) {
+            return Some(idx);
+        }
+        match token.as_str() {
+            "(" | "[" => depth += 1,
+            ")" | "]" => depth = depth.checked_sub(1)?,
+            _ => (),
+        }
+    }
+    None
+}
+
+fn split_top_level<'a>(tokens: &'a [String], separator: &str) -> Vec<&'a [String]> {
+    let mut parts = Vec::new();
+    let mut rest = tokens;
+    while let Some(idx) = position_top_level(rest, 0, |token| token == separator) {
+        parts.push(&rest[..idx]);
+        rest = &rest[idx + 1..];
+    }
+    if !rest.is_empty() {
+        parts.push(rest);
+    }
+    parts
+}
+
+/// Parse `key = value, flag` options, such as those of `CREATE TYPE` or `CREATE OPERATOR`
+fn parse_options(tokens: &[String]) -> BTreeMap<String, String> {
+    split_top_level(tokens, ",")
+        .into_iter()
+        .map(|option| match option {
+            [key, eq, value @ ..] if eq == "=" => (key.clone(), render(value)),
+            _ => (render(option), String::new()),
+        })
+        .collect()
+}
+
+/// The types of the input arguments of a function, which identify it along with its name
+fn argument_types(arguments: &[String]) -> Vec<String> {
+    let is_mode = |token: &str| matches!(token, "in" | "out" | "inout" | "variadic");
+    split_top_level(arguments, ",")
+        .into_iter()
+        .filter_map(|mut argument| {
+            let end = argument.iter().position(|token| token == "default" || token == "=");
+            argument = &argument[..end.unwrap_or(argument.len())];
+            let mut out = false;
+            // `argmode argname argtype` and `argname argmode argtype` are both fine
+            for _ in 0..2 {
+                match argument {
+                    [mode, rest @ ..] if is_mode(mode) && !rest.is_empty() => {
+                        out |= mode == "out";
+                        argument = rest;
+                    }
+                    [name, next, ..]
+                        if !MULTI_WORD_TYPES.contains(&name.as_str())
+                            && !matches!(next.as_str(), "." | "(" | "[" | "%") =>
+                    {
+                        argument = &argument[1..];
+                    }
+                    _ => break,
+                }
+            }
+            (!out).then(|| render(argument))
+        })
+        .collect()
+}
+
+/// A single SQL statement, along with the comments leading up to it
+struct Statement<'a> {
+    raw: &'a str,
+    /// Where the statement itself starts in `raw`
+    code: usize,
+    /// Its tokens, with comments removed and identifiers and keywords folded to lowercase
+    tokens: Vec<String>,
+}
+
+impl Statement<'_> {
+    /// The `--` comments preceding the statement, such as where it was generated from
+    fn comments(&self) -> String {
+        self.raw[..self.code]
+            .lines()
+            .map(str::trim)
+            .filter(|line| line.starts_with("--") && !CONNECTED_MARKERS.contains(line))
+            .map(|line| format!("{line}\n"))
+            .collect()
+    }
+
+    fn code(&self) -> String {
+        let code = self.raw[self.code..].trim_end();
+        if code.ends_with(';') {
+            code.to_string()
+        } else {
+            format!("{code};")
+        }
+    }
+
+    fn sql(&self) -> String {
+        self.comments() + &self.code()
+    }
+
+    /// The statement as a `CREATE OR REPLACE` statement
+    fn or_replace(&self) -> String {
+        let code = self.code();
+        match self.tokens.get(1).map(String::as_str) {
+            Some("or") => self.sql(),
+            // the statement starts with `CREATE`
+            _ => format!("{}CREATE OR REPLACE {}", self.comments(), code[6..].trim_start()),
+        }
+    }
+}
+
+/// Split generated SQL into statements
+///
+/// This understands enough of SQL's lexical structure to not be confused by semicolons or
+/// comments within strings, quoted identifiers and dollar-quoted function bodies.
+fn statements(sql: &str) -> Vec<Statement<'_>> {
+    let is_word = |byte: u8| byte.is_ascii_alphanumeric() || byte == b'_' || byte >= 0x80;
+    let bytes = sql.as_bytes();
+    let mut statements = Vec::new();
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut code = None;
+    let mut idx = 0;
+    while idx < bytes.len() {
+        let token_start = idx;
+        match bytes[idx] {
+            b';' => {
+                idx += 1;
+                if let Some(code) = code.take() {
+                    let tokens = std::mem::take(&mut tokens);
+                    statements.push(Statement {
+                        raw: &sql[start..idx],
+                        code: code - start,
+                        tokens,
+                    });
+                }
+                start = idx;
+                continue;
+            }
+            byte if byte.is_ascii_whitespace() => {
+                idx += 1;
+                continue;
+            }
+            b'-' if bytes.get(idx + 1) == Some(&b'-') => {
+                idx = sql[idx..].find('\n').map_or(bytes.len(), |end| idx + end);
+                continue;
+            }
+            b'/' if bytes.get(idx + 1) == Some(&b'*') => {
+                idx = skip_block_comment(bytes, idx);
+                continue;
+            }
+            b'\'' => idx = skip_quoted(bytes, idx, false),
+            b'"' => idx = skip_quoted(bytes, idx, false),
+            b'$' => {
+                let tag_end = (idx + 1..bytes.len())
+                    .find(|&end| !(bytes[end].is_ascii_alphanumeric() || bytes[end] == b'_'));
+                match tag_end {
+                    Some(tag_end) if bytes[tag_end] == b'$' && !bytes[idx + 1].is_ascii_digit() => {
+                        let tag = &sql[idx..=tag_end];
+                        idx = sql[tag_end + 1..]
+                            .find(tag)
+                            .map_or(bytes.len(), |end| tag_end + 1 + end + tag.len());
+                    }
+                    // a positional parameter, like `$1`
+                    _ => {
+                        idx += 1;
+                        while idx < bytes.len() && is_word(bytes[idx]) {
+                            idx += 1;
+                        }
+                    }
+                }
+            }
+            byte if is_word(byte) => {
+                while idx < bytes.len() && is_word(bytes[idx]) {
+                    idx += 1;
+                }
+                // `E'...'` strings allow backslash escapes
+                if sql[token_start..idx].eq_ignore_ascii_case("e") && bytes.get(idx) == Some(&b'\'')
+                {
+                    idx = skip_quoted(bytes, idx, true);
+                }
+            }
+            byte if OPERATOR_CHARS.contains(&byte) => {
+                idx += 1;
+                while idx < bytes.len()
+                    && OPERATOR_CHARS.contains(&bytes[idx])
+                    && !bytes[idx..].starts_with(b"--")
+                    && !bytes[idx..].starts_with(b"/*")
+                {
+                    idx += 1;
+                }
+            }
+            _ => idx += 1,
+        }
+        code.get_or_insert(token_start);
+        tokens.push(canonical(&sql[token_start..idx]));
+    }
+    if let Some(code) = code {
+        statements.push(Statement { raw: &sql[start..], code: code - start, tokens });
+    }
+    statements
+}
+
+/// The index after the quoted string or identifier starting at `start`
+fn skip_quoted(bytes: &[u8], start: usize, backslash_escapes: bool) -> usize {
+    let quote = bytes[start];
+    let mut idx = start + 1;
+    while idx < bytes.len() {
+        match bytes[idx] {
+            b'\\' if backslash_escapes => idx += 2,
+            // a doubled quote is an escaped one
+            byte if byte == quote && bytes.get(idx + 1) == Some(&quote) => idx += 2,
+            byte if byte == quote => return idx + 1,
+            _ => idx += 1,
+        }
+    }
+    bytes.len()
+}
+
+/// The index after the block comment starting at `start`, which may nest
+fn skip_block_comment(bytes: &[u8], start: usize) -> usize {
+    let mut depth = 0_usize;
+    let mut idx = start;
+    while idx < bytes.len() {
+        if bytes[idx..].starts_with(b"/*") {
+            depth += 1;
+            idx += 2;
+        } else if bytes[idx..].starts_with(b"*/") {
+            depth -= 1;
+            idx += 2;
+            if depth == 0 {
+                return idx;
+            }
+        } else {
+            idx += 1;
+        }
+    }
+    bytes.len()
+}
+
+/// Fold a token the way Postgres would, so equivalent spellings compare equal
+fn canonical(token: &str) -> String {
+    if let Some(ident) = token.strip_prefix('"').and_then(|token| token.strip_suffix('"')) {
+        let is_simple = ident.starts_with(|c: char| c.is_ascii_lowercase() || c == '_')
+            && ident.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_');
+        if is_simple {
+            ident.to_string()
+        } else {
+            token.to_string()
+        }
+    } else if token.starts_with(['\'', '$']) || token.ends_with('\'') {
+        // strings, dollar-quoted strings and positional parameters
+        token.to_string()
+    } else {
+        token.to_ascii_lowercase()
+    }
+}
+
+/// Turn tokens back into SQL
+fn render(tokens: &[impl AsRef<str>]) -> String {
+    let mut sql = String::new();
+    let mut previous = None;
+    for token in tokens {
+        let token = token.as_ref();
+        let glued = matches!(token, "," | ")" | "." | "[" | "]")
+            || matches!(previous, None | Some("(" | "." | "["));
+        if !glued {
+            sql.push(' ');
+        }
+        sql.push_str(token);
+        previous = Some(token);
+    }
+    sql
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FUNCTION: &str = r#"
+/* <begin connected objects> */
+-- src/lib.rs:10
+-- example::add
+CREATE  FUNCTION "add"(
+	"a" INT, /* i32 */
+	"b" INT /* i32 */
+) RETURNS INT /* i32 */
+IMMUTABLE STRICT PARALLEL SAFE
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'add_wrapper';
+/* </end connected objects> */
+"#;
+
+    #[test]
+    fn test_unchanged_schema() {
+        let script = upgrade_script(FUNCTION, FUNCTION).unwrap();
+        assert_eq!(script, HEADER);
+    }
+
+    #[test]
+    fn test_new_and_dropped_functions() {
+        let new = FUNCTION.replace("\"add\"", "\"plus\"");
+        let script = upgrade_script(FUNCTION, &new).unwrap();
+        assert!(script.contains("DROP FUNCTION add(int, int);"));
+        assert!(script.contains("-- example::add\nCREATE  FUNCTION \"plus\"("));
+        assert!(!script.contains("connected objects"));
+    }
+
+    #[test]
+    fn test_changed_function_attributes_are_replaced() {
+        let new = FUNCTION.replace("IMMUTABLE", "STABLE");
+        let script = upgrade_script(FUNCTION, &new).unwrap();
+        assert!(!script.contains("DROP"));
+        assert!(script.contains("CREATE OR REPLACE FUNCTION \"add\"("));
+    }
+
+    #[test]
+    fn test_changed_return_type_is_recreated() {
+        let new = FUNCTION.replace("RETURNS INT", "RETURNS bigint");
+        let script = upgrade_script(FUNCTION, &new).unwrap();
+        let drop = script.find("DROP FUNCTION add(int, int);").unwrap();
+        let create = script.find("CREATE  FUNCTION \"add\"(").unwrap();
+        assert!(drop < create);
+    }
+
+    #[test]
+    fn test_recreated_function_recreates_operator() {
+        let operator = r#"
+CREATE OPERATOR +++ (
+	PROCEDURE="add",
+	LEFTARG=INT, /* i32 */
+	RIGHTARG=INT /* i32 */
+);
+"#;
+        let old = format!("{FUNCTION}{operator}");
+        let new = format!("{}{operator}", FUNCTION.replace("\"b\" INT", "\"c\" INT"));
+        let script = upgrade_script(&old, &new).unwrap();
+        let drop_operator = script.find("DROP OPERATOR +++(int, int);").unwrap();
+        let drop_function = script.find("DROP FUNCTION add(int, int);").unwrap();
+        let create_operator = script.find("CREATE OPERATOR +++ (").unwrap();
+        assert!(drop_operator < drop_function);
+        assert!(drop_function < create_operator);
+    }
+
+    #[test]
+    fn test_recreated_support_function_recreates_function() {
+        let support = r#"
+CREATE  FUNCTION "add_support"(
+	"request" internal /* pgrx::datum::Internal */
+) RETURNS internal /* pgrx::datum::Internal */
+LANGUAGE c /* Rust */
+AS 'MODULE_PATHNAME', 'add_support_wrapper';
+"#;
+        let function = FUNCTION.replace("PARALLEL SAFE", "PARALLEL SAFE SUPPORT add_support");
+        let old = format!("{support}{function}");
+        let new = format!("{}{function}", support.replace("RETURNS internal", "RETURNS int8"));
+        let script = upgrade_script(&old, &new).unwrap();
+        let drop_function = script.find("DROP FUNCTION add(int, int);").unwrap();
+        let drop_support = script.find("DROP FUNCTION add_support(internal);").unwrap();
+        let create_support = script.find("CREATE  FUNCTION \"add_support\"(").unwrap();
+        let create_function = script.find("CREATE  FUNCTION \"add\"(").unwrap();
+        assert!(drop_function < drop_support);
+        assert!(create_support < create_function);
+
+        // a function merely mentioning it is left alone
+        let function = FUNCTION.replace("\"b\" INT", "\"add_support\" INT");
+        let old = format!("{support}{function}");
+        let new = format!("{}{function}", support.replace("RETURNS internal", "RETURNS int8"));
+        let script = upgrade_script(&old, &new).unwrap();
+        assert!(!script.contains("DROP FUNCTION add(int, int);"));
+    }
+
+    #[test]
+    fn test_new_enum_labels_are_added() {
+        let old = "CREATE TYPE Shade AS ENUM (\n\t'Light',\n\t'Dark'\n);";
+        let new = "CREATE TYPE Shade AS ENUM (\n\t'Pale',\n\t'Light',\n\t'Dim',\n\t'Dark'\n);";
+        let script = upgrade_script(old, new).unwrap();
+        assert!(script.contains("ALTER TYPE shade ADD VALUE 'Pale' BEFORE 'Light';"));
+        assert!(script.contains("ALTER TYPE shade ADD VALUE 'Dim' AFTER 'Light';"));
+    }
+
+    #[test]
+    fn test_prepended_enum_labels_are_added_before_existing_ones() {
+        let old = "CREATE TYPE Shade AS ENUM (\n\t'Dark'\n);";
+        let new = "CREATE TYPE Shade AS ENUM (\n\t'Pale',\n\t'Light',\n\t'Dark'\n);";
+        let script = upgrade_script(old, new).unwrap();
+        let light = script.find("ALTER TYPE shade ADD VALUE 'Light' BEFORE 'Dark';").unwrap();
+        let pale = script.find("ALTER TYPE shade ADD VALUE 'Pale' BEFORE 'Light';").unwrap();
+        assert!(light < pale, "{script}");
+    }
+
+    #[test]
+    fn test_removed_enum_labels_are_unsafe() {
+        let old = "CREATE TYPE Shade AS ENUM (\n\t'Light',\n\t'Dark'\n);";
+        let new = "CREATE TYPE Shade AS ENUM (\n\t'Light'\n);";
+        let error = upgrade_script(old, new).unwrap_err().to_string();
+        assert!(error.contains("enum `shade` no longer has the label(s) 'Dark'"), "{error}");
+    }
+
+    #[test]
+    fn test_type_layout_changes_are_unsafe() {
+        let old = "CREATE TYPE Thing;\nCREATE TYPE Thing (\n\tINTERNALLENGTH = variable,\n\tINPUT = thing_in,\n\tOUTPUT = thing_out,\n\tSTORAGE = extended\n);";
+        let new = old.replace("STORAGE = extended", "STORAGE = extended,\n\tALIGNMENT = double");
+        let error = upgrade_script(old, &new).unwrap_err().to_string();
+        assert!(error.contains("type `thing` changed its ALIGNMENT"), "{error}");
+
+        let new = old.replace("STORAGE = extended", "SEND = thing_send,\n\tSTORAGE = extended");
+        let script = upgrade_script(old, &new).unwrap();
+        assert!(script.contains("ALTER TYPE thing SET (SEND = thing_send);"));
+    }
+
+    #[test]
+    fn test_statements_ignore_quoted_semicolons() {
+        let sql = "SELECT 'a;b', \"c;d\"; -- e;f\nCREATE FUNCTION g() RETURNS int AS $$ SELECT 1; $$ LANGUAGE sql;";
+        let statements = statements(sql);
+        assert_eq!(statements.len(), 2);
+        assert_eq!(statements[0].tokens, ["select", "'a;b'", ",", "\"c;d\""]);
+        assert_eq!(statements[1].comments(), "-- e;f\n");
+        assert_eq!(statements[1].tokens.last().unwrap(), "sql");
+    }
+}
@@ -46,6 +46,12 @@ pub(crate) struct Test {
     /// Initialize the test database cluster here, instead of the default location.  If used with `--runas`, then it must be writable by the user
     #[clap(long, value_name = "DIR")]
     pgdata: Option<PathBuf>,
+    /// Create the extension at this older version and `ALTER EXTENSION ... UPDATE` it, instead of creating it fresh
+    #[clap(long, value_name = "VERSION")]
+    upgrade_from: Option<String>,
+    /// Where to find the SQL scripts of older versions (default is `./sql/history`)
+    #[clap(long, value_name = "DIR", requires = "upgrade_from")]
+    upgrade_scripts: Option<PathBuf>,
     #[clap(flatten)]
     features: clap_cargo::Features,
     #[clap(from_global, action = clap::ArgAction::Count)]
@@ -83,6 +89,8 @@ impl CommandExecute for Test {
                 me.testname,
                 me.runas,
                 me.pgdata,
+                me.upgrade_from,
+                me.upgrade_scripts,
             )?;
 
             Ok(())
@@ -125,6 +133,8 @@ pub fn test_extension(
     testname: Option<impl AsRef<str>>,
     runas: Option<String>,
     pgdata: Option<PathBuf>,
+    upgrade_from: Option<String>,
+    upgrade_scripts: Option<PathBuf>,
 ) -> eyre::Result<()> {
     if let Some(ref testname) = testname {
         tracing::Span::current().record("testname", tracing::field::display(&testname.as_ref()));
@@ -158,6 +168,18 @@ pub fn test_extension(
         command.env("CARGO_PGRX_TEST_PGDATA", pgdata);
     }
 
+    if let Some(upgrade_from) = upgrade_from {
+        command.env("CARGO_PGRX_TEST_UPGRADE_FROM", upgrade_from);
+    }
+
+    if let Some(upgrade_scripts) = upgrade_scripts {
+        // the tests run from the package's directory, which may not be ours
+        let upgrade_scripts = upgrade_scripts.canonicalize().wrap_err_with(|| {
+            format!("couldn't find the upgrade scripts directory `{}`", upgrade_scripts.display())
+        })?;
+        command.env("CARGO_PGRX_TEST_UPGRADE_SCRIPTS", upgrade_scripts);
+    }
+
     if let Ok(rust_log) = std::env::var("RUST_LOG") {
         command.env("RUST_LOG", rust_log);
     }
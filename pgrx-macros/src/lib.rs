@@ -19,13 +19,17 @@ use syn::{parse_macro_input, Attribute, Data, DeriveInput, Item, ItemImpl};
 
 use operators::{deriving_postgres_eq, deriving_postgres_hash, deriving_postgres_ord};
 use pgrx_sql_entity_graph as sql_gen;
+use row::{deriving_from_row, deriving_into_row};
 use sql_gen::{
     parse_extern_attributes, CodeEnrichment, ExtensionSql, ExtensionSqlFile, ExternArgs,
-    PgAggregate, PgCast, PgExtern, PostgresEnum, Schema,
+    PgAggregate, PgCast, PgExtern, PgFdw, PgIndexAm, PgTableAm, PostgresEnum, Schema,
 };
+use test_options::PgTestOptions;
 
 mod operators;
 mod rewriter;
+mod row;
+mod test_options;
 
 /// Declare a function as `#[pg_guard]` to indicate that it is called from a Postgres `extern "C"`
 /// function so that Rust `panic!()`s (and Postgres `elog(ERROR)`s) will be properly handled by `pgrx`
@@ -54,11 +58,52 @@ pub fn pg_guard(_attr: TokenStream, item: TokenStream) -> TokenStream {
 ///
 /// This can be combined with test attributes like [`#[should_panic(expected = "..")]`][expected].
 ///
+/// By default every test runs in the same database, inside a transaction that's rolled back once
+/// it's done.  Tests that need more than that can ask for:
+///
+/// * `transaction = false`: `CALL` the test as a procedure outside of any transaction block, so
+///   it can commit with `Spi::connect_nonatomic` and what it commits is visible to other sessions
+///   and background workers.  It always runs in a fresh database.  Statements that must be run at
+///   the top level, like `VACUUM`, can go through `pgrx_tests::top_level_client()`.
+/// * `database = "fresh"`: run the test in a database of its own, copied from the shared one as
+///   it was once the extension was created, and dropped afterwards.
+/// * `restart`: restart Postgres before running the test, once every other running test is done.
+///
+/// Besides `error = "..."`, which expects the test to raise an error with exactly that message,
+/// the error can be described by:
+///
+/// * `sqlstate = ERRCODE_CHECK_VIOLATION`: the error's `PgSqlErrorCode`
+/// * `error_matches = "..."`: a regular expression the error's message must match
+/// * `detail = "..."` and `hint = "..."`: the error's exact detail and hint
+///
+/// ```rust,ignore
+/// #[pg_test(sqlstate = ERRCODE_CHECK_VIOLATION, error_matches = "violates check constraint")]
+/// fn test_negative_balance() -> Result<(), pgrx::spi::Error> {
+///     Spi::run("CREATE TABLE accounts (balance int CHECK (balance >= 0))")?;
+///     Spi::run("INSERT INTO accounts VALUES (-1)")
+/// }
+/// ```
+///
+/// ```rust,ignore
+/// #[pg_test(transaction = false)]
+/// fn test_commits() -> Result<(), pgrx::spi::Error> {
+///     Spi::connect_nonatomic(|client| {
+///         client.update("CREATE TABLE committed (id int)", None, &[])?;
+///         client.commit();
+///         Ok(())
+///     })
+/// }
+/// ```
+///
 /// [expected]: https://doc.rust-lang.org/reference/attributes/testing.html#the-should_panic-attribute
 #[proc_macro_attribute]
 pub fn pg_test(attr: TokenStream, item: TokenStream) -> TokenStream {
     let mut stream = proc_macro2::TokenStream::new();
-    let args = parse_extern_attributes(proc_macro2::TokenStream::from(attr.clone()));
+    let (options, attr) = match PgTestOptions::parse(attr.into()) {
+        Ok(parsed) => parsed,
+        Err(e) => return e.into_compile_error().into(),
+    };
+    let args = parse_extern_attributes(attr.clone());
 
     let mut expected_error = None;
     args.into_iter().for_each(|v| {
@@ -92,15 +137,22 @@ pub fn pg_test(attr: TokenStream, item: TokenStream) -> TokenStream {
 
             func.attrs = non_test_attributes;
 
-            stream.extend(proc_macro2::TokenStream::from(pg_extern(
-                attr,
-                Item::Fn(func.clone()).to_token_stream().into(),
-            )));
+            if options.transaction {
+                stream.extend(proc_macro2::TokenStream::from(pg_extern(
+                    attr.into(),
+                    Item::Fn(func.clone()).to_token_stream().into(),
+                )));
+            } else {
+                // only a procedure `CALL`ed outside of a transaction block can commit
+                let procedure = PgExtern::new(attr, Item::Fn(func.clone()).to_token_stream())
+                    .and_then(|pg_extern| pg_extern.0.as_procedure());
+                match procedure {
+                    Ok(procedure) => stream.extend(CodeEnrichment(procedure).to_token_stream()),
+                    Err(e) => return e.into_compile_error().into(),
+                }
+            }
 
-            let expected_error = match expected_error {
-                Some(msg) => quote! {Some(#msg)},
-                None => quote! {None},
-            };
+            let expected_error = options.expected_error(expected_error);
 
             let sql_funcname = func.sig.ident.to_string();
             let test_func_name = format_ident!("pg_{}", func.sig.ident);
@@ -123,7 +175,7 @@ pub fn pg_test(attr: TokenStream, item: TokenStream) -> TokenStream {
                     #att_stream
 
                     crate::pg_test::setup(options);
-                    let res = pgrx_tests::run_test(#sql_funcname, #expected_error, crate::pg_test::postgresql_conf_options());
+                    let res = pgrx_tests::run_test_with_options(#sql_funcname, #expected_error, crate::pg_test::postgresql_conf_options(), #options);
                     match res {
                         Ok(()) => (),
                         Err(e) => panic!("{e:?}")
@@ -163,6 +215,9 @@ By default if no attribute is specified, the cast function can only be used in a
 Functions MUST accept and return exactly one value whose type MUST be a `pgrx` supported type. `pgrx` supports many PostgreSQL types by default.
 New types can be defined via [`macro@PostgresType`] or [`macro@PostgresEnum`].
 
+A length-coercion cast may additionally accept the target's `pgrx::datum::Typmod`, followed by a `bool` that is
+true for an explicit cast.
+
 `#[pg_cast]` also supports all the attributes supported by the [`macro@pg_extern]` macro, which are
 passed down to the underlying function.
 
@@ -496,6 +551,13 @@ Optionally accepts the following attributes:
 * `parallel_safe`: Corresponds to [`PARALLEL SAFE`](https://www.postgresql.org/docs/current/sql-createfunction.html).
 * `parallel_unsafe`: Corresponds to [`PARALLEL UNSAFE`](https://www.postgresql.org/docs/current/sql-createfunction.html).
 * `parallel_restricted`: Corresponds to [`PARALLEL RESTRICTED`](https://www.postgresql.org/docs/current/sql-createfunction.html).
+* `leakproof`: Corresponds to [`LEAKPROOF`](https://www.postgresql.org/docs/current/sql-createfunction.html).
+* `rows = 100`: Corresponds to [`ROWS`](https://www.postgresql.org/docs/current/sql-createfunction.html), for functions returning a set.
+* `support = my_support_fn`: Corresponds to [`SUPPORT`](https://www.postgresql.org/docs/current/xfunc-optimization.html).
+  + A path names another `#[pg_extern]` function, which usually takes a `pgrx::support::SupportRequest`.
+  + A string literal names an existing SQL function, such as `support = "pg_catalog.textlike_support"`.
+* `set(work_mem = "64MB")`: Corresponds to [`SET`](https://www.postgresql.org/docs/current/sql-createfunction.html).
+  + Parameters which aren't Rust identifiers can be quoted, such as `set("myext.setting" = "on")`.
 * `no_guard`: Do not use `#[pg_guard]` with the function.
 * `sql`: Same arguments as [`#[pgrx(sql = ..)]`](macro@pgrx).
 * `name`: Specifies target function name. Defaults to Rust function name.
@@ -629,6 +691,49 @@ pub fn pg_extern(attr: TokenStream, item: TokenStream) -> TokenStream {
     wrapped(attr, item).unwrap_or_else(|e: syn::Error| e.into_compile_error().into())
 }
 
+/**
+Declare a function as `#[pg_procedure]` to indicate that it represents a Postgres
+[procedure](https://www.postgresql.org/docs/current/sql-createprocedure.html), which is run with `CALL`.
+
+Procedures can't return a value, and only support the attributes of [`macro@pg_extern`] which apply to
+`CREATE PROCEDURE`, such as `security_definer`, `create_or_replace`, `name` and `schema`.
+
+A procedure that's `CALL`ed outside of an explicit transaction block may commit or roll back its work
+part of the way through with `pgrx::spi::Spi::connect_nonatomic`.
+
+```rust,ignore
+use pgrx::prelude::*;
+
+#[pg_procedure]
+fn purge_old_events(batch_size: i64) -> Result<(), spi::Error> {
+    Spi::connect_nonatomic(|client| loop {
+        let deleted = client
+            .update(
+                "DELETE FROM events WHERE ctid = ANY(ARRAY(
+                    SELECT ctid FROM events WHERE created < now() - '1 year'::interval LIMIT $1))",
+                None,
+                &[batch_size.into()],
+            )?
+            .len();
+        client.commit();
+        if deleted == 0 {
+            return Ok(());
+        }
+    })
+}
+```
+*/
+#[proc_macro_attribute]
+#[track_caller]
+pub fn pg_procedure(attr: TokenStream, item: TokenStream) -> TokenStream {
+    fn wrapped(attr: TokenStream, item: TokenStream) -> Result<TokenStream, syn::Error> {
+        let pg_extern = PgExtern::new(attr.into(), item.into())?.0;
+        Ok(CodeEnrichment(pg_extern.as_procedure()?).to_token_stream().into())
+    }
+
+    wrapped(attr, item).unwrap_or_else(|e: syn::Error| e.into_compile_error().into())
+}
+
 /**
 Generate necessary bindings for using the enum with PostgreSQL.
 
@@ -666,15 +771,16 @@ fn impl_postgres_enum(ast: DeriveInput) -> syn::Result<proc_macro2::TokenStream>
         ));
     };
 
+    let mut labels = Vec::new();
     let mut from_datum = proc_macro2::TokenStream::new();
     let mut into_datum = proc_macro2::TokenStream::new();
 
-    for d in enum_data.variants.clone() {
+    for (index, d) in enum_data.variants.iter().enumerate() {
         let label_ident = &d.ident;
-        let label_string = label_ident.to_string();
+        labels.push(label_ident.to_string());
 
-        from_datum.extend(quote! { #label_string => Some(#enum_ident::#label_ident), });
-        into_datum.extend(quote! { #enum_ident::#label_ident => Some(::pgrx::enum_helper::lookup_enum_by_label(#enum_name, #label_string)), });
+        from_datum.extend(quote! { #index => Some(#enum_ident::#label_ident), });
+        into_datum.extend(quote! { #enum_ident::#label_ident => Some(::pgrx::pg_sys::Datum::from(ENUM_CACHE.value_oid(#index))), });
     }
 
     // We need another variant of the params for the ArgAbi impl
@@ -696,60 +802,63 @@ fn impl_postgres_enum(ast: DeriveInput) -> syn::Result<proc_macro2::TokenStream>
         .insert(0, syn::GenericParam::Lifetime(syn::LifetimeParam::new(fcx_lt.clone())));
 
     stream.extend(quote! {
-        impl ::pgrx::datum::FromDatum for #enum_ident {
-            #[inline]
-            unsafe fn from_polymorphic_datum(datum: ::pgrx::pg_sys::Datum, is_null: bool, typeoid: ::pgrx::pg_sys::Oid) -> Option<#enum_ident> {
-                if is_null {
-                    None
-                } else {
-                    // GREPME: non-primitive cast u64 as Oid
-                    let (name, _, _) = ::pgrx::enum_helper::lookup_enum_by_oid(unsafe { ::pgrx::pg_sys::Oid::from_datum(datum, is_null)? } );
-                    match name.as_str() {
-                        #from_datum
-                        _ => panic!("invalid enum value: {name}")
+        const _: () = {
+            static ENUM_CACHE: ::pgrx::enum_helper::EnumCache = ::pgrx::enum_helper::EnumCache::new(#enum_name, &[#(#labels),*]);
+
+            impl ::pgrx::datum::FromDatum for #enum_ident {
+                #[inline]
+                unsafe fn from_polymorphic_datum(datum: ::pgrx::pg_sys::Datum, is_null: bool, typeoid: ::pgrx::pg_sys::Oid) -> Option<#enum_ident> {
+                    if is_null {
+                        None
+                    } else {
+                        // GREPME: non-primitive cast u64 as Oid
+                        match ENUM_CACHE.variant_index(unsafe { ::pgrx::pg_sys::Oid::from_datum(datum, is_null)? }) {
+                            #from_datum
+                            _ => unreachable!("EnumCache returned an index without a variant")
+                        }
                     }
                 }
             }
-        }
-
-        unsafe impl #impl_gens ::pgrx::callconv::ArgAbi<#fcx_lt> for #enum_ident #ty_gens #where_clause {
-            unsafe fn unbox_arg_unchecked(arg: ::pgrx::callconv::Arg<'_, #fcx_lt>) -> Self {
-                let index = arg.index();
-                unsafe { arg.unbox_arg_using_from_datum().unwrap_or_else(|| panic!("argument {index} must not be null")) }
-            }
 
-        }
+            unsafe impl #impl_gens ::pgrx::callconv::ArgAbi<#fcx_lt> for #enum_ident #ty_gens #where_clause {
+                unsafe fn unbox_arg_unchecked(arg: ::pgrx::callconv::Arg<'_, #fcx_lt>) -> Self {
+                    let index = arg.index();
+                    unsafe { arg.unbox_arg_using_from_datum().unwrap_or_else(|| panic!("argument {index} must not be null")) }
+                }
 
-        unsafe impl #generics ::pgrx::datum::UnboxDatum for #enum_ident #generics {
-            type As<'dat> = #enum_ident #generics where Self: 'dat;
-            #[inline]
-            unsafe fn unbox<'dat>(d: ::pgrx::datum::Datum<'dat>) -> Self::As<'dat> where Self: 'dat {
-                Self::from_datum(::core::mem::transmute(d), false).unwrap()
             }
-        }
 
-        impl ::pgrx::datum::IntoDatum for #enum_ident {
-            #[inline]
-            fn into_datum(self) -> Option<::pgrx::pg_sys::Datum> {
-                match self {
-                    #into_datum
+            unsafe impl #generics ::pgrx::datum::UnboxDatum for #enum_ident #generics {
+                type As<'dat> = #enum_ident #generics where Self: 'dat;
+                #[inline]
+                unsafe fn unbox<'dat>(d: ::pgrx::datum::Datum<'dat>) -> Self::As<'dat> where Self: 'dat {
+                    Self::from_datum(::core::mem::transmute(d), false).unwrap()
                 }
             }
 
-            fn type_oid() -> ::pgrx::pg_sys::Oid {
-                ::pgrx::wrappers::regtypein(#enum_name)
-            }
+            impl ::pgrx::datum::IntoDatum for #enum_ident {
+                #[inline]
+                fn into_datum(self) -> Option<::pgrx::pg_sys::Datum> {
+                    match self {
+                        #into_datum
+                    }
+                }
 
-        }
+                fn type_oid() -> ::pgrx::pg_sys::Oid {
+                    ENUM_CACHE.type_oid()
+                }
 
-        unsafe impl ::pgrx::callconv::BoxRet for #enum_ident {
-            unsafe fn box_into<'fcx>(self, fcinfo: &mut ::pgrx::callconv::FcInfo<'fcx>) -> ::pgrx::datum::Datum<'fcx> {
-                match ::pgrx::datum::IntoDatum::into_datum(self) {
-                    None => fcinfo.return_null(),
-                    Some(datum) => unsafe { fcinfo.return_raw_datum(datum) },
+            }
+
+            unsafe impl ::pgrx::callconv::BoxRet for #enum_ident {
+                unsafe fn box_into<'fcx>(self, fcinfo: &mut ::pgrx::callconv::FcInfo<'fcx>) -> ::pgrx::datum::Datum<'fcx> {
+                    match ::pgrx::datum::IntoDatum::into_datum(self) {
+                        None => fcinfo.return_null(),
+                        Some(datum) => unsafe { fcinfo.return_raw_datum(datum) },
+                    }
                 }
             }
-        }
+        };
     });
 
     let sql_graph_entity_item = PostgresEnum::from_derive_input(sql_graph_entity_ast)?;
@@ -781,6 +890,10 @@ Optionally accepts the following attributes:
 
 * `inoutfuncs(some_in_fn, some_out_fn)`: Define custom in/out functions for the type.
 * `pgvarlena_inoutfuncs(some_in_fn, some_out_fn)`: Define custom in/out functions for the `PgVarlena` of this type.
+* `sendrecvfuncs`: Define binary send/receive functions for the type, by implementing `pgrx::inoutfuncs::PgBinaryInOutFuncs`.
+* `typmodfuncs`: Accept a type modifier, as in `mytype(16)`, by implementing `pgrx::inoutfuncs::PgTypmodFuncs`.
+  This also creates an implicit length-coercion cast from the type to itself that applies the modifier,
+  and has the type's input and receive functions apply the modifier they're passed, as `COPY FROM` does.
 * `pgrx(alignment = "<align>")`: Derive Postgres alignment from Rust type. One of `"on"`, or `"off"`.
 * `sql`: Same arguments as [`#[pgrx(sql = ..)]`](macro@pgrx).
 */
@@ -789,6 +902,8 @@ Optionally accepts the following attributes:
     attributes(
         inoutfuncs,
         pgvarlena_inoutfuncs,
+        sendrecvfuncs,
+        typmodfuncs,
         bikeshed_postgres_type_manually_impl_from_into_datum,
         requires,
         pgrx
@@ -806,6 +921,12 @@ fn impl_postgres_type(ast: DeriveInput) -> syn::Result<proc_macro2::TokenStream>
     let has_lifetimes = generics.lifetimes().next();
     let funcname_in = Ident::new(&format!("{name}_in").to_lowercase(), name.span());
     let funcname_out = Ident::new(&format!("{name}_out").to_lowercase(), name.span());
+    let funcname_send = Ident::new(&format!("{name}_send").to_lowercase(), name.span());
+    let funcname_recv = Ident::new(&format!("{name}_recv").to_lowercase(), name.span());
+    let funcname_typmod_in = Ident::new(&format!("{name}_typmod_in").to_lowercase(), name.span());
+    let funcname_typmod_out = Ident::new(&format!("{name}_typmod_out").to_lowercase(), name.span());
+    let funcname_coerce_typmod =
+        Ident::new(&format!("{name}_coerce_typmod").to_lowercase(), name.span());
     let mut args = parse_postgres_type_args(&ast.attrs);
     let mut stream = proc_macro2::TokenStream::new();
 
@@ -825,7 +946,9 @@ fn impl_postgres_type(ast: DeriveInput) -> syn::Result<proc_macro2::TokenStream>
         }
     }
 
-    if args.is_empty() {
+    if args.iter().all(|arg| {
+        matches!(arg, PostgresTypeAttribute::SendRecvFuncs | PostgresTypeAttribute::TypmodFuncs)
+    }) {
         // assume the user wants us to implement the InOutFuncs
         args.insert(PostgresTypeAttribute::Default);
     }
@@ -932,15 +1055,52 @@ fn impl_postgres_type(ast: DeriveInput) -> syn::Result<proc_macro2::TokenStream>
         )
     }
 
+    // the binary send/recv and typmod coercion functions use a PgVarlena along with
+    // PgVarlenaInOutFuncs, just like the _in/_out functions, and an owned type instance otherwise
+    let (datum_ty, from_value, as_ref, into_value) =
+        if args.contains(&PostgresTypeAttribute::PgVarlenaInOutFuncs) {
+            (
+                quote! { ::pgrx::datum::PgVarlena<#name #generics> },
+                quote! {{
+                    let mut varlena = ::pgrx::datum::PgVarlena::<#name #generics>::new();
+                    *varlena = value;
+                    varlena
+                }},
+                quote! { &*input },
+                quote! { *input },
+            )
+        } else {
+            (quote! { #name #generics }, quote! { value }, quote! { &input }, quote! { input })
+        };
+
+    // Postgres passes the input and receive functions of a type with a modifier the typmod of
+    // wherever the value is headed, such as the column `COPY FROM` reads it into
+    let (typmod_args, coerce_input) = if args.contains(&PostgresTypeAttribute::TypmodFuncs) {
+        (
+            quote! { , _typoid: ::pgrx::pg_sys::Oid, typmod: ::pgrx::datum::Typmod },
+            quote! {
+                .map(|input| match typmod.get() {
+                    Some(_) => {
+                        let value = ::pgrx::inoutfuncs::PgTypmodFuncs::coerce_typmod(#into_value, typmod, false);
+                        #from_value
+                    }
+                    None => input,
+                })
+            },
+        )
+    } else {
+        (quote! {}, quote! {})
+    };
+
     // and if we don't have custom inout/funcs, we use the JsonInOutFuncs trait
     // which implements _in and _out #[pg_extern] functions that just return the type itself
     if args.contains(&PostgresTypeAttribute::Default) {
         stream.extend(quote! {
             #[doc(hidden)]
             #[::pgrx::pgrx_macros::pg_extern(immutable,parallel_safe)]
-            pub fn #funcname_in #generics(input: Option<&#lifetime ::core::ffi::CStr>) -> Option<#name #generics> {
+            pub fn #funcname_in #generics(input: Option<&#lifetime ::core::ffi::CStr> #typmod_args) -> Option<#name #generics> {
                 use ::pgrx::inoutfuncs::json_from_slice;
-                input.map(|cstr| json_from_slice(cstr.to_bytes()).ok()).flatten()
+                input.map(|cstr| json_from_slice(cstr.to_bytes()).ok()).flatten() #coerce_input
             }
 
             #[doc(hidden)]
@@ -957,13 +1117,13 @@ fn impl_postgres_type(ast: DeriveInput) -> syn::Result<proc_macro2::TokenStream>
         stream.extend(quote! {
             #[doc(hidden)]
             #[::pgrx::pgrx_macros::pg_extern(immutable,parallel_safe)]
-            pub fn #funcname_in #generics(input: Option<&::core::ffi::CStr>) -> Option<#name #generics> {
+            pub fn #funcname_in #generics(input: Option<&::core::ffi::CStr> #typmod_args) -> Option<#name #generics> {
                 input.map_or_else(|| {
                     for m in <#name as ::pgrx::inoutfuncs::InOutFuncs>::NULL_ERROR_MESSAGE {
                         ::pgrx::pg_sys::error!("{m}");
                     }
                     None
-                }, |i| Some(<#name as ::pgrx::inoutfuncs::InOutFuncs>::input(i)))
+                }, |i| Some(<#name as ::pgrx::inoutfuncs::InOutFuncs>::input(i))) #coerce_input
             }
 
             #[doc(hidden)]
@@ -980,13 +1140,13 @@ fn impl_postgres_type(ast: DeriveInput) -> syn::Result<proc_macro2::TokenStream>
         stream.extend(quote! {
             #[doc(hidden)]
             #[::pgrx::pgrx_macros::pg_extern(immutable,parallel_safe)]
-            pub fn #funcname_in #generics(input: Option<&::core::ffi::CStr>) -> Option<::pgrx::datum::PgVarlena<#name #generics>> {
+            pub fn #funcname_in #generics(input: Option<&::core::ffi::CStr> #typmod_args) -> Option<::pgrx::datum::PgVarlena<#name #generics>> {
                 input.map_or_else(|| {
                     for m in <#name as ::pgrx::inoutfuncs::PgVarlenaInOutFuncs>::NULL_ERROR_MESSAGE {
                         ::pgrx::pg_sys::error!("{m}");
                     }
                     None
-                }, |i| Some(<#name as ::pgrx::inoutfuncs::PgVarlenaInOutFuncs>::input(i)))
+                }, |i| Some(<#name as ::pgrx::inoutfuncs::PgVarlenaInOutFuncs>::input(i))) #coerce_input
             }
 
             #[doc(hidden)]
@@ -1000,6 +1160,62 @@ fn impl_postgres_type(ast: DeriveInput) -> syn::Result<proc_macro2::TokenStream>
         });
     }
 
+    if args.contains(&PostgresTypeAttribute::SendRecvFuncs) {
+        stream.extend(quote! {
+            #[doc(hidden)]
+            #[::pgrx::pgrx_macros::pg_extern(immutable,parallel_safe)]
+            pub fn #funcname_recv #generics(input: ::pgrx::datum::Internal #typmod_args) -> Option<#datum_ty> {
+                // SAFETY: Postgres passes a valid StringInfo to a type's receive function, or NULL
+                let mut buffer = unsafe {
+                    ::pgrx::stringinfo::StringInfo::from_pg(input.unwrap()?.cast_mut_ptr())?
+                };
+                let value = <#name as ::pgrx::inoutfuncs::PgBinaryInOutFuncs>::receive(&mut buffer);
+                Some(#from_value) #coerce_input
+            }
+
+            #[doc(hidden)]
+            #[::pgrx::pgrx_macros::pg_extern(immutable,parallel_safe)]
+            pub fn #funcname_send #generics(input: #datum_ty) -> Vec<u8> {
+                let mut buffer = ::pgrx::stringinfo::StringInfo::new();
+                ::pgrx::inoutfuncs::PgBinaryInOutFuncs::send(#as_ref, &mut buffer);
+                buffer.as_bytes().to_vec()
+            }
+        });
+    }
+
+    if args.contains(&PostgresTypeAttribute::TypmodFuncs) {
+        stream.extend(quote! {
+            #[doc(hidden)]
+            #[::pgrx::pgrx_macros::pg_extern(immutable,parallel_safe)]
+            pub fn #funcname_typmod_in(
+                input: ::pgrx::datum::Array<&::core::ffi::CStr>,
+            ) -> ::pgrx::datum::Typmod {
+                let modifiers = input
+                    .iter()
+                    .map(|modifier| modifier.expect("type modifiers cannot be NULL"))
+                    .collect::<Vec<_>>();
+                <#name as ::pgrx::inoutfuncs::PgTypmodFuncs>::typmod_in(&modifiers)
+            }
+
+            #[doc(hidden)]
+            #[::pgrx::pgrx_macros::pg_extern(immutable,parallel_safe)]
+            pub fn #funcname_typmod_out(typmod: ::pgrx::datum::Typmod) -> ::std::ffi::CString {
+                <#name as ::pgrx::inoutfuncs::PgTypmodFuncs>::typmod_out(typmod)
+            }
+
+            #[doc(hidden)]
+            #[::pgrx::pgrx_macros::pg_cast(implicit,immutable,parallel_safe)]
+            pub fn #funcname_coerce_typmod #generics(
+                input: #datum_ty,
+                typmod: ::pgrx::datum::Typmod,
+                explicit: bool,
+            ) -> #datum_ty {
+                let value = ::pgrx::inoutfuncs::PgTypmodFuncs::coerce_typmod(#into_value, typmod, explicit);
+                #from_value
+            }
+        });
+    }
+
     let sql_graph_entity_item = sql_gen::PostgresTypeDerive::from_derive_input(ast)?;
     sql_graph_entity_item.to_tokens(&mut stream);
 
@@ -1098,6 +1314,8 @@ fn impl_guc_enum(ast: DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
 enum PostgresTypeAttribute {
     InOutFuncs,
     PgVarlenaInOutFuncs,
+    SendRecvFuncs,
+    TypmodFuncs,
     Default,
     ManualFromIntoDatum,
 }
@@ -1115,6 +1333,12 @@ fn parse_postgres_type_args(attributes: &[Attribute]) -> HashSet<PostgresTypeAtt
             "pgvarlena_inoutfuncs" => {
                 categorized_attributes.insert(PostgresTypeAttribute::PgVarlenaInOutFuncs);
             }
+            "sendrecvfuncs" => {
+                categorized_attributes.insert(PostgresTypeAttribute::SendRecvFuncs);
+            }
+            "typmodfuncs" => {
+                categorized_attributes.insert(PostgresTypeAttribute::TypmodFuncs);
+            }
             "bikeshed_postgres_type_manually_impl_from_into_datum" => {
                 categorized_attributes.insert(PostgresTypeAttribute::ManualFromIntoDatum);
             }
@@ -1211,6 +1435,70 @@ pub fn derive_postgres_hash(input: TokenStream) -> TokenStream {
     deriving_postgres_hash(ast).unwrap_or_else(syn::Error::into_compile_error).into()
 }
 
+/**
+Read a struct out of an SPI result row or a composite type's `PgHeapTuple`.
+
+```rust,ignore
+use pgrx::prelude::*;
+use pgrx::row::FromRow;
+
+#[derive(FromRow)]
+struct Dog {
+    name: String,
+    age: Option<i32>,
+    #[row(rename = "scritches")]
+    scritch_count: i64,
+}
+
+let dogs = Spi::connect(|client| {
+    client.select("SELECT * FROM dogs", None, &[])?.rows::<Dog>()?.collect::<Result<Vec<_>, _>>()
+});
+```
+
+Fields map to the column of the same name, or for a tuple struct, in the same position.  An
+`Option` field is `None` when its column is `NULL`, which is an error for other fields.
+
+Optionally accepts the following attributes on fields:
+
+* `rename = "column"`: Map to the column of this name.
+* `position = N`: Map to the column in this 1-based position.
+* `default` or `default = "path::to::fn"`: The value to use when the column is missing or `NULL`.
+* `skip`: Don't read this field, and use its `Default` value.
+
+See the `pgrx::row` module for more.
+*/
+#[proc_macro_derive(FromRow, attributes(row))]
+pub fn derive_from_row(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as syn::DeriveInput);
+    deriving_from_row(ast).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+/**
+Write a struct into a composite type's `PgHeapTuple`.
+
+```rust,ignore
+use pgrx::prelude::*;
+use pgrx::row::IntoRow;
+
+#[derive(IntoRow)]
+struct Dog {
+    name: String,
+    scritches: i32,
+}
+
+let mut dog = PgHeapTuple::new_composite_type("Dog")?;
+dog.set_row(Dog { name: "Nami".into(), scritches: 0 })?;
+```
+
+Accepts the same attributes as [`FromRow`](macro@FromRow).  A field with a `default` is not
+written if its column is missing.
+*/
+#[proc_macro_derive(IntoRow, attributes(row))]
+pub fn derive_into_row(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as syn::DeriveInput);
+    deriving_into_row(ast).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
 /**
 Declare a `pgrx::Aggregate` implementation on a type as able to used by Postgres as an aggregate.
 
@@ -1229,6 +1517,69 @@ pub fn pg_aggregate(_attr: TokenStream, item: TokenStream) -> TokenStream {
     wrapped(parsed_base).unwrap_or_else(|e| e.into_compile_error().into())
 }
 
+/**
+Declare a `pgrx::index_am::IndexAccessMethod` implementation on a type as a Postgres index access method.
+
+This generates the access method's `index_am_handler` function and the matching
+`CREATE ACCESS METHOD ... TYPE INDEX` SQL.  The access method is named after the `NAME` constant
+of the implementation, or the snake case name of the type if it isn't set.
+
+The implementation may use the [`#[pgrx]`](macro@pgrx) attribute to configure SQL generation.
+*/
+#[proc_macro_attribute]
+pub fn pg_index_am(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    fn wrapped(item_impl: ItemImpl) -> Result<TokenStream, syn::Error> {
+        let sql_graph_entity_item = PgIndexAm::new(item_impl)?;
+
+        Ok(sql_graph_entity_item.to_token_stream().into())
+    }
+
+    let parsed_base = parse_macro_input!(item as syn::ItemImpl);
+    wrapped(parsed_base).unwrap_or_else(|e| e.into_compile_error().into())
+}
+
+/**
+Declare a `pgrx::fdw::ForeignDataWrapper` implementation on a type as a Postgres foreign data wrapper.
+
+This generates the wrapper's `fdw_handler` and validator functions and the matching
+`CREATE FOREIGN DATA WRAPPER` SQL.  The wrapper is named after the `NAME` constant of the
+implementation, or the snake case name of the type if it isn't set.
+
+The implementation may use the [`#[pgrx]`](macro@pgrx) attribute to configure SQL generation.
+*/
+#[proc_macro_attribute]
+pub fn pg_fdw(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    fn wrapped(item_impl: ItemImpl) -> Result<TokenStream, syn::Error> {
+        let sql_graph_entity_item = PgFdw::new(item_impl)?;
+
+        Ok(sql_graph_entity_item.to_token_stream().into())
+    }
+
+    let parsed_base = parse_macro_input!(item as syn::ItemImpl);
+    wrapped(parsed_base).unwrap_or_else(|e| e.into_compile_error().into())
+}
+
+/**
+Declare a `pgrx::table_am::TableAccessMethod` implementation on a type as a Postgres table access method.
+
+This generates the access method's `table_am_handler` function and the matching
+`CREATE ACCESS METHOD ... TYPE TABLE` SQL.  The access method is named after the `NAME` constant
+of the implementation, or the snake case name of the type if it isn't set.
+
+The implementation may use the [`#[pgrx]`](macro@pgrx) attribute to configure SQL generation.
+*/
+#[proc_macro_attribute]
+pub fn pg_table_am(_attr: TokenStream, item: TokenStream) -> TokenStream {
+    fn wrapped(item_impl: ItemImpl) -> Result<TokenStream, syn::Error> {
+        let sql_graph_entity_item = PgTableAm::new(item_impl)?;
+
+        Ok(sql_graph_entity_item.to_token_stream().into())
+    }
+
+    let parsed_base = parse_macro_input!(item as syn::ItemImpl);
+    wrapped(parsed_base).unwrap_or_else(|e| e.into_compile_error().into())
+}
+
 /**
 A helper attribute for various contexts.
 
@@ -1279,3 +1630,50 @@ pub fn pg_trigger(attrs: TokenStream, input: TokenStream) -> TokenStream {
 
     wrapped(attrs, input).unwrap_or_else(|e| e.into_compile_error().into())
 }
+
+/**
+Create a [PostgreSQL event trigger function](https://www.postgresql.org/docs/current/event-triggers.html)
+
+The function accepts a `&pgrx::trigger_support::PgEventTrigger` and returns a `Result<(), E>`.
+
+Given an `event`, the extension also creates the event trigger itself:
+
+```rust,ignore
+#[pg_event_trigger(event = ddl_command_end, tags = ["CREATE TABLE", "ALTER TABLE"])]
+fn audit_tables(trigger: &PgEventTrigger) -> Result<(), spi::Error> {
+    for command in trigger.ddl_commands()? {
+        log!("{}: {:?}", command.command_tag, command.object_identity);
+    }
+    Ok(())
+}
+```
+
+The attribute accepts:
+
+* `event = ddl_command_start`, `ddl_command_end`, `sql_drop` or `table_rewrite`: the event to
+  create the event trigger `ON`.  Without one, only the function is created
+* `tags = ["CREATE TABLE", ...]`: only fire for these command tags
+* `name = "my_trigger"`: the name of the event trigger, rather than the function's name
+* `sql = ...`: the same as on [`macro@pg_trigger`]
+
+Review the `pgrx::trigger_support::PgEventTrigger` documentation for use.
+*/
+#[proc_macro_attribute]
+pub fn pg_event_trigger(attrs: TokenStream, input: TokenStream) -> TokenStream {
+    fn wrapped(attrs: TokenStream, input: TokenStream) -> Result<TokenStream, syn::Error> {
+        use pgrx_sql_entity_graph::{PgEventTrigger, PgEventTriggerAttribute};
+        use syn::parse::Parser;
+        use syn::punctuated::Punctuated;
+        use syn::Token;
+
+        let attributes =
+            Punctuated::<PgEventTriggerAttribute, Token![,]>::parse_terminated.parse(attrs)?;
+        let item_fn: syn::ItemFn = syn::parse(input)?;
+        let event_trigger_item = PgEventTrigger::new(item_fn, attributes)?;
+        let event_trigger_tokens = event_trigger_item.to_token_stream();
+
+        Ok(event_trigger_tokens.into())
+    }
+
+    wrapped(attrs, input).unwrap_or_else(|e| e.into_compile_error().into())
+}
@@ -0,0 +1,188 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::spanned::Spanned;
+use syn::{
+    Data, DeriveInput, Fields, GenericArgument, LitInt, LitStr, Member, PathArguments, Type,
+};
+
+/// A struct field, and the column it maps to
+struct RowField<'a> {
+    member: Member,
+    ty: &'a Type,
+    column: TokenStream,
+    default: Option<TokenStream>,
+    skip: bool,
+}
+
+fn row_fields(ast: &DeriveInput) -> syn::Result<Vec<RowField<'_>>> {
+    let Data::Struct(data) = &ast.data else {
+        return Err(syn::Error::new(ast.span(), "rows can only be mapped to structs"));
+    };
+    if matches!(data.fields, Fields::Unit) {
+        return Err(syn::Error::new(ast.span(), "rows can't be mapped to unit structs"));
+    }
+
+    let mut fields = Vec::new();
+    for (i, field) in data.fields.iter().enumerate() {
+        let mut rename = None;
+        let mut position = None;
+        let mut default = None;
+        let mut skip = false;
+        for attr in field.attrs.iter().filter(|attr| attr.path().is_ident("row")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("rename") {
+                    rename = Some(meta.value()?.parse::<LitStr>()?.value());
+                } else if meta.path.is_ident("position") {
+                    position = Some(meta.value()?.parse::<LitInt>()?.base10_parse::<usize>()?);
+                } else if meta.path.is_ident("default") {
+                    default = Some(if meta.input.peek(syn::Token![=]) {
+                        let path = meta.value()?.parse::<LitStr>()?.parse::<syn::ExprPath>()?;
+                        quote! { #path() }
+                    } else {
+                        quote! { ::core::default::Default::default() }
+                    });
+                } else if meta.path.is_ident("skip") {
+                    skip = true;
+                } else {
+                    return Err(meta.error("expected `rename`, `position`, `default` or `skip`"));
+                }
+                Ok(())
+            })?;
+        }
+
+        let has_default = default.is_some();
+        let column = match (&field.ident, rename, position) {
+            (_, Some(_), Some(_)) => {
+                return Err(syn::Error::new(
+                    field.span(),
+                    "a field can't have both a `rename` and a `position`",
+                ))
+            }
+            (_, None, Some(0)) => {
+                return Err(syn::Error::new(field.span(), "column positions start at 1"))
+            }
+            (_, None, Some(position)) => {
+                quote! { ::pgrx::row::RowColumn::position(#position, #has_default) }
+            }
+            (_, Some(name), None) => quote! { ::pgrx::row::RowColumn::name(#name, #has_default) },
+            (Some(ident), None, None) => {
+                let name = ident.to_string();
+                let name = name.strip_prefix("r#").unwrap_or(&name);
+                quote! { ::pgrx::row::RowColumn::name(#name, #has_default) }
+            }
+            (None, None, None) => {
+                let position = i + 1;
+                quote! { ::pgrx::row::RowColumn::position(#position, #has_default) }
+            }
+        };
+        let member = match &field.ident {
+            Some(ident) => Member::Named(ident.clone()),
+            None => Member::Unnamed(i.into()),
+        };
+        fields.push(RowField { member, ty: &field.ty, column, default, skip });
+    }
+    Ok(fields)
+}
+
+/// The `T` of an `Option<T>`
+fn option_inner(ty: &Type) -> Option<&Type> {
+    let Type::Path(path) = ty else { return None };
+    let segment = path.path.segments.last()?;
+    if segment.ident != "Option" {
+        return None;
+    }
+    let PathArguments::AngleBracketed(args) = &segment.arguments else { return None };
+    match args.args.first()? {
+        GenericArgument::Type(inner) if args.args.len() == 1 => Some(inner),
+        _ => None,
+    }
+}
+
+pub(crate) fn deriving_from_row(ast: DeriveInput) -> syn::Result<TokenStream> {
+    let fields = row_fields(&ast)?;
+    let ident = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+    let mut columns = Vec::new();
+    let mut inits = Vec::new();
+    for field in &fields {
+        let member = &field.member;
+        if field.skip {
+            inits.push(quote! { #member: ::core::default::Default::default() });
+            continue;
+        }
+
+        let index = columns.len();
+        columns.push(&field.column);
+        let value = match (option_inner(field.ty), &field.default) {
+            (Some(inner), None) => quote! { row.get::<#inner>(#index)? },
+            (Some(inner), Some(default)) => quote! {
+                match row.get::<#inner>(#index)? {
+                    ::core::option::Option::Some(value) => ::core::option::Option::Some(value),
+                    ::core::option::Option::None => #default,
+                }
+            },
+            (None, None) => {
+                let ty = field.ty;
+                quote! { row.get_required::<#ty>(#index)? }
+            }
+            (None, Some(default)) => {
+                let ty = field.ty;
+                quote! { row.get::<#ty>(#index)?.unwrap_or_else(|| #default) }
+            }
+        };
+        inits.push(quote! { #member: #value });
+    }
+
+    Ok(quote! {
+        impl #impl_generics ::pgrx::row::FromRow for #ident #ty_generics #where_clause {
+            const COLUMNS: &'static [::pgrx::row::RowColumn] = &[#(#columns),*];
+
+            fn from_row<S: ::pgrx::row::RowSource>(
+                row: &::pgrx::row::RowReader<'_, S>,
+            ) -> ::core::result::Result<Self, ::pgrx::row::RowError> {
+                ::core::result::Result::Ok(Self { #(#inits),* })
+            }
+        }
+    })
+}
+
+pub(crate) fn deriving_into_row(ast: DeriveInput) -> syn::Result<TokenStream> {
+    let fields = row_fields(&ast)?;
+    let ident = &ast.ident;
+    let (impl_generics, ty_generics, where_clause) = ast.generics.split_for_impl();
+
+    let mut columns = Vec::new();
+    let mut sets = Vec::new();
+    for field in fields.iter().filter(|field| !field.skip) {
+        let member = &field.member;
+        let index = columns.len();
+        columns.push(&field.column);
+        sets.push(quote! { row.set(#index, self.#member)?; });
+    }
+    // every field is skipped, so there's nothing to write
+    let row = if columns.is_empty() { format_ident!("_row") } else { format_ident!("row") };
+
+    Ok(quote! {
+        impl #impl_generics ::pgrx::row::IntoRow for #ident #ty_generics #where_clause {
+            const COLUMNS: &'static [::pgrx::row::RowColumn] = &[#(#columns),*];
+
+            fn into_row(
+                self,
+                #row: &mut ::pgrx::row::RowWriter<'_, '_>,
+            ) -> ::core::result::Result<(), ::pgrx::row::RowError> {
+                #(#sets)*
+                ::core::result::Result::Ok(())
+            }
+        }
+    })
+}
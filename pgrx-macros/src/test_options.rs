@@ -0,0 +1,218 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+use proc_macro2::{Punct, Spacing, TokenStream, TokenTree};
+use quote::{quote, ToTokens, TokenStreamExt};
+use syn::{Expr, ExprLit, ExprPath, Lit, LitStr, Meta};
+
+/// The `#[pg_test]` arguments meant for the test framework, such as how to isolate the test and
+/// what the error it raises should look like, as opposed to the ones describing the function
+/// itself
+pub(crate) struct PgTestOptions {
+    pub(crate) transaction: bool,
+    fresh_database: bool,
+    restart: bool,
+    sqlstate: Option<syn::Path>,
+    error_matches: Option<LitStr>,
+    detail: Option<LitStr>,
+    hint: Option<LitStr>,
+}
+
+impl PgTestOptions {
+    /// Split the test options out of a `#[pg_test]`'s arguments, returning the rest
+    pub(crate) fn parse(attr: TokenStream) -> syn::Result<(Self, TokenStream)> {
+        let mut transaction = true;
+        let mut database = None;
+        let mut restart = false;
+        let mut sqlstate = None;
+        let mut error_matches = None;
+        let mut detail = None;
+        let mut hint = None;
+        let mut rest = TokenStream::new();
+
+        for arg in split_args(attr) {
+            let is_option = matches!(
+                arg.clone().into_iter().next(),
+                Some(TokenTree::Ident(ident))
+                    if [
+                        "transaction",
+                        "database",
+                        "restart",
+                        "sqlstate",
+                        "error_matches",
+                        "detail",
+                        "hint"
+                    ]
+                    .iter()
+                    .any(|option| ident == option)
+            );
+            if !is_option {
+                if !rest.is_empty() {
+                    rest.append(Punct::new(',', Spacing::Alone));
+                }
+                rest.extend(arg);
+                continue;
+            }
+
+            match syn::parse2::<Meta>(arg)? {
+                Meta::Path(path) if path.is_ident("restart") => restart = true,
+                Meta::NameValue(arg) if arg.path.is_ident("transaction") => match &arg.value {
+                    Expr::Lit(ExprLit { lit: Lit::Bool(value), .. }) => transaction = value.value,
+                    value => {
+                        return Err(syn::Error::new_spanned(
+                            value,
+                            "expected `transaction = true` or `transaction = false`",
+                        ))
+                    }
+                },
+                Meta::NameValue(arg) if arg.path.is_ident("database") => match &arg.value {
+                    Expr::Lit(ExprLit { lit: Lit::Str(value), .. })
+                        if value.value() == "fresh" || value.value() == "shared" =>
+                    {
+                        database = Some((value.value() == "fresh", arg.clone()))
+                    }
+                    value => {
+                        return Err(syn::Error::new_spanned(
+                            value,
+                            "expected `database = \"fresh\"` or `database = \"shared\"`",
+                        ))
+                    }
+                },
+                Meta::NameValue(arg) if arg.path.is_ident("sqlstate") => match arg.value {
+                    // the name of a `PgSqlErrorCode`, such as `ERRCODE_CHECK_VIOLATION`
+                    Expr::Path(ExprPath { path, .. }) if path.get_ident().is_some() => {
+                        let code = path.get_ident().unwrap();
+                        sqlstate = Some(syn::parse_quote! { ::pgrx::PgSqlErrorCode::#code });
+                    }
+                    Expr::Path(ExprPath { path, .. }) => sqlstate = Some(path),
+                    value => return Err(syn::Error::new_spanned(
+                        value,
+                        "expected a `PgSqlErrorCode`, such as `sqlstate = ERRCODE_CHECK_VIOLATION`",
+                    )),
+                },
+                Meta::NameValue(arg)
+                    if arg.path.is_ident("error_matches")
+                        || arg.path.is_ident("detail")
+                        || arg.path.is_ident("hint") =>
+                {
+                    let Expr::Lit(ExprLit { lit: Lit::Str(value), .. }) = arg.value else {
+                        return Err(syn::Error::new_spanned(arg.value, "expected a string"));
+                    };
+                    if arg.path.is_ident("error_matches") {
+                        error_matches = Some(value);
+                    } else if arg.path.is_ident("detail") {
+                        detail = Some(value);
+                    } else {
+                        hint = Some(value);
+                    }
+                }
+                meta => {
+                    return Err(syn::Error::new_spanned(
+                        &meta,
+                        format!("unexpected `{}`", meta.to_token_stream()),
+                    ))
+                }
+            }
+        }
+
+        // what a test commits would outlive it in the shared database
+        let fresh_database = match database {
+            Some((false, arg)) if !transaction => {
+                return Err(syn::Error::new_spanned(
+                    arg,
+                    "tests run with `transaction = false` need a fresh database",
+                ))
+            }
+            Some((fresh, _)) => fresh,
+            None => !transaction,
+        };
+
+        Ok((
+            PgTestOptions {
+                transaction,
+                fresh_database,
+                restart,
+                sqlstate,
+                error_matches,
+                detail,
+                hint,
+            },
+            rest,
+        ))
+    }
+
+    /// The `pgrx_tests::PgTestError` the test expects, given the message from its `error = "..."`
+    pub(crate) fn expected_error(&self, message: Option<String>) -> TokenStream {
+        let PgTestOptions { sqlstate, error_matches, detail, hint, .. } = self;
+        if message.is_none()
+            && sqlstate.is_none()
+            && error_matches.is_none()
+            && detail.is_none()
+            && hint.is_none()
+        {
+            return quote! { None };
+        }
+
+        fn option<T: ToTokens>(value: Option<T>) -> TokenStream {
+            match value {
+                Some(value) => quote! { Some(#value) },
+                None => quote! { None },
+            }
+        }
+        let message = option(message);
+        let sqlstate = option(sqlstate.as_ref());
+        let error_matches = option(error_matches.as_ref());
+        let detail = option(detail.as_ref());
+        let hint = option(hint.as_ref());
+        quote! {
+            Some(pgrx_tests::PgTestError {
+                message: #message,
+                message_matches: #error_matches,
+                sqlstate: #sqlstate,
+                detail: #detail,
+                hint: #hint,
+            })
+        }
+    }
+}
+
+impl ToTokens for PgTestOptions {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let PgTestOptions { transaction, fresh_database, restart, .. } = self;
+        let database = if *fresh_database {
+            quote! { pgrx_tests::PgTestDatabase::Fresh }
+        } else {
+            quote! { pgrx_tests::PgTestDatabase::Shared }
+        };
+        tokens.append_all(quote! {
+            pgrx_tests::PgTestOptions {
+                transaction: #transaction,
+                database: #database,
+                restart: #restart,
+            }
+        });
+    }
+}
+
+/// Split attribute arguments on their top-level commas
+fn split_args(attr: TokenStream) -> Vec<TokenStream> {
+    let mut args = Vec::new();
+    let mut arg = TokenStream::new();
+    for token in attr {
+        match token {
+            TokenTree::Punct(punct) if punct.as_char() == ',' => {
+                args.push(std::mem::take(&mut arg));
+            }
+            token => arg.extend([token]),
+        }
+    }
+    args.push(arg);
+    args.retain(|arg| !arg.is_empty());
+    args
+}
@@ -91,7 +91,9 @@
 #include "foreign/fdwapi.h"
 #include "foreign/foreign.h"
 #include "jit/jit.h"
+#include "lib/dshash.h"
 #include "lib/stringinfo.h"
+#include "libpq/auth.h"
 #include "libpq/pqformat.h"
 #include "mb/pg_wchar.h"
 #include "nodes/execnodes.h"
@@ -169,6 +171,7 @@
 #include "utils/fmgrprotos.h"
 #include "utils/geo_decls.h"
 #include "utils/guc.h"
+#include "utils/inval.h"
 #include "utils/json.h"
 #include "utils/jsonapi.h"
 #include "utils/jsonb.h"
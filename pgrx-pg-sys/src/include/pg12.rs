@@ -9223,6 +9223,11 @@ impl Default for SnapshotData {
 }
 pub type Relation = *mut RelationData;
 pub type RelationPtr = *mut Relation;
+pub type SyscacheCallbackFunction = ::core::option::Option<
+    unsafe extern "C" fn(arg: Datum, cacheid: ::core::ffi::c_int, hashvalue: uint32),
+>;
+pub type RelcacheCallbackFunction =
+    ::core::option::Option<unsafe extern "C" fn(arg: Datum, relid: Oid)>;
 pub mod IndexAttrBitmapKind {
     pub type Type = ::core::ffi::c_uint;
     pub const INDEX_ATTR_BITMAP_ALL: Type = 0;
@@ -9361,6 +9366,42 @@ pub type dsa_pointer_atomic = pg_atomic_uint64;
 pub type dsa_handle = dsm_handle;
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
+pub struct dshash_table {
+    _unused: [u8; 0],
+}
+pub type dshash_table_handle = dsa_pointer;
+pub type dshash_hash = uint32;
+pub type dshash_compare_function = ::core::option::Option<
+    unsafe extern "C" fn(
+        a: *const ::core::ffi::c_void,
+        b: *const ::core::ffi::c_void,
+        size: usize,
+        arg: *mut ::core::ffi::c_void,
+    ) -> ::core::ffi::c_int,
+>;
+pub type dshash_hash_function = ::core::option::Option<
+    unsafe extern "C" fn(
+        v: *const ::core::ffi::c_void,
+        size: usize,
+        arg: *mut ::core::ffi::c_void,
+    ) -> dshash_hash,
+>;
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct dshash_parameters {
+    pub key_size: usize,
+    pub entry_size: usize,
+    pub compare_function: dshash_compare_function,
+    pub hash_function: dshash_hash_function,
+    pub tranche_id: ::core::ffi::c_int,
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct dshash_table_item {
+    _unused: [u8; 0],
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
 pub struct TIDBitmap {
     _unused: [u8; 0],
 }
@@ -20434,6 +20475,8 @@ pub mod PasswordType {
     pub const PASSWORD_TYPE_MD5: Type = 1;
     pub const PASSWORD_TYPE_SCRAM_SHA_256: Type = 2;
 }
+pub type ClientAuthentication_hook_type =
+    ::core::option::Option<unsafe extern "C" fn(arg1: *mut Port, arg2: ::core::ffi::c_int)>;
 pub type check_password_hook_type = ::core::option::Option<
     unsafe extern "C" fn(
         username: *const ::core::ffi::c_char,
@@ -29576,6 +29619,48 @@ unsafe extern "C" {
     ) -> dsa_pointer;
     pub fn dsa_free(area: *mut dsa_area, dp: dsa_pointer);
     pub fn dsa_get_address(area: *mut dsa_area, dp: dsa_pointer) -> *mut ::core::ffi::c_void;
+    pub fn dshash_create(
+        area: *mut dsa_area,
+        params: *const dshash_parameters,
+        arg: *mut ::core::ffi::c_void,
+    ) -> *mut dshash_table;
+    pub fn dshash_attach(
+        area: *mut dsa_area,
+        params: *const dshash_parameters,
+        handle: dshash_table_handle,
+        arg: *mut ::core::ffi::c_void,
+    ) -> *mut dshash_table;
+    pub fn dshash_detach(hash_table: *mut dshash_table);
+    pub fn dshash_get_hash_table_handle(hash_table: *mut dshash_table) -> dshash_table_handle;
+    pub fn dshash_destroy(hash_table: *mut dshash_table);
+    pub fn dshash_find(
+        hash_table: *mut dshash_table,
+        key: *const ::core::ffi::c_void,
+        exclusive: bool,
+    ) -> *mut ::core::ffi::c_void;
+    pub fn dshash_find_or_insert(
+        hash_table: *mut dshash_table,
+        key: *const ::core::ffi::c_void,
+        found: *mut bool,
+    ) -> *mut ::core::ffi::c_void;
+    pub fn dshash_delete_key(
+        hash_table: *mut dshash_table,
+        key: *const ::core::ffi::c_void,
+    ) -> bool;
+    pub fn dshash_delete_entry(hash_table: *mut dshash_table, entry: *mut ::core::ffi::c_void);
+    pub fn dshash_release_lock(hash_table: *mut dshash_table, entry: *mut ::core::ffi::c_void);
+    pub fn dshash_dump(hash_table: *mut dshash_table);
+    pub fn dshash_memcmp(
+        a: *const ::core::ffi::c_void,
+        b: *const ::core::ffi::c_void,
+        size: usize,
+        arg: *mut ::core::ffi::c_void,
+    ) -> ::core::ffi::c_int;
+    pub fn dshash_memhash(
+        v: *const ::core::ffi::c_void,
+        size: usize,
+        arg: *mut ::core::ffi::c_void,
+    ) -> dshash_hash;
     pub fn dsa_trim(area: *mut dsa_area);
     pub fn dsa_dump(area: *mut dsa_area);
     pub fn tbm_create(maxbytes: ::core::ffi::c_long, dsa: *mut dsa_area) -> *mut TIDBitmap;
@@ -35267,6 +35352,8 @@ unsafe extern "C" {
     );
     pub fn pg_utf8_islegal(source: *const ::core::ffi::c_uchar, length: ::core::ffi::c_int)
         -> bool;
+    pub fn ClientAuthentication(port: *mut Port);
+    pub static mut ClientAuthentication_hook: ClientAuthentication_hook_type;
     pub fn pq_beginmessage(buf: StringInfo, msgtype: ::core::ffi::c_char);
     pub fn pq_beginmessage_reuse(buf: StringInfo, msgtype: ::core::ffi::c_char);
     pub fn pq_endmessage(buf: StringInfo);
@@ -41898,6 +41985,22 @@ unsafe extern "C" {
     pub fn RelationInvalidatesSnapshotsOnly(relid: Oid) -> bool;
     pub fn RelationHasSysCache(relid: Oid) -> bool;
     pub fn RelationSupportsSysCache(relid: Oid) -> bool;
+    pub fn AcceptInvalidationMessages();
+    pub fn CommandEndInvalidationMessages();
+    pub fn CacheInvalidateHeapTuple(relation: Relation, tuple: HeapTuple, newtuple: HeapTuple);
+    pub fn CacheInvalidateCatalog(catalogId: Oid);
+    pub fn CacheInvalidateRelcache(relation: Relation);
+    pub fn CacheInvalidateRelcacheAll();
+    pub fn CacheInvalidateRelcacheByRelid(relid: Oid);
+    pub fn CacheInvalidateRelmap(databaseId: Oid);
+    pub fn CacheRegisterSyscacheCallback(
+        cacheid: ::core::ffi::c_int,
+        func: SyscacheCallbackFunction,
+        arg: Datum,
+    );
+    pub fn CacheRegisterRelcacheCallback(func: RelcacheCallbackFunction, arg: Datum);
+    pub fn CallSyscacheCallbacks(cacheid: ::core::ffi::c_int, hashvalue: uint32);
+    pub fn InvalidateSystemCaches();
     pub fn range_contains_elem_internal(
         typcache: *mut TypeCacheEntry,
         r: *mut RangeType,
@@ -31,6 +31,10 @@ pub enum ExternArgs {
     Name(String),
     Cost(String),
     Requires(Vec<PositioningRef>),
+    Leakproof,
+    Rows(String),
+    Support(PositioningRef),
+    Set(String, String),
 }
 
 impl core::fmt::Display for ExternArgs {
@@ -53,6 +57,13 @@ impl core::fmt::Display for ExternArgs {
             ExternArgs::Name(_) => Ok(()),
             ExternArgs::Cost(cost) => write!(f, "COST {cost}"),
             ExternArgs::Requires(_) => Ok(()),
+            ExternArgs::Leakproof => write!(f, "LEAKPROOF"),
+            ExternArgs::Rows(rows) => write!(f, "ROWS {rows}"),
+            // The support function's SQL name is only known to the entity graph
+            ExternArgs::Support(_) => Ok(()),
+            ExternArgs::Set(name, value) => {
+                write!(f, "SET {name} TO '{}'", value.replace('\'', "''"))
+            }
         }
     }
 }
@@ -112,6 +123,31 @@ impl ToTokens for ExternArgs {
                     .to_token_stream(),
                 );
             }
+            ExternArgs::Leakproof => tokens.append(format_ident!("Leakproof")),
+            ExternArgs::Rows(_s) => {
+                tokens.append_all(
+                    quote! {
+                        Rows(String::from("#_s"))
+                    }
+                    .to_token_stream(),
+                );
+            }
+            ExternArgs::Support(item) => {
+                tokens.append_all(
+                    quote! {
+                        Support(#item)
+                    }
+                    .to_token_stream(),
+                );
+            }
+            ExternArgs::Set(_name, _value) => {
+                tokens.append_all(
+                    quote! {
+                        Set(String::from("#_name"), String::from("#_value"))
+                    }
+                    .to_token_stream(),
+                );
+            }
         }
     }
 }
@@ -143,6 +179,7 @@ pub fn parse_extern_attributes(attr: TokenStream) -> HashSet<ExternArgs> {
                     "parallel_safe" => args.insert(ExternArgs::ParallelSafe),
                     "parallel_unsafe" => args.insert(ExternArgs::ParallelUnsafe),
                     "parallel_restricted" => args.insert(ExternArgs::ParallelRestricted),
+                    "leakproof" => args.insert(ExternArgs::Leakproof),
                     "error" | "expected" => {
                         let _punc = itr.next().unwrap();
                         let literal = itr.next().unwrap();
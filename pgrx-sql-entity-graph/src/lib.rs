@@ -25,11 +25,20 @@ pub use extension_sql::entity::{ExtensionSqlEntity, SqlDeclaredEntity};
 pub use extension_sql::{ExtensionSql, ExtensionSqlFile, SqlDeclared};
 pub use extern_args::{parse_extern_attributes, ExternArgs};
 pub use mapping::RustSqlMapping;
+pub use pg_event_trigger::attribute::PgEventTriggerAttribute;
+pub use pg_event_trigger::entity::PgEventTriggerEntity;
+pub use pg_event_trigger::PgEventTrigger;
 pub use pg_extern::entity::{
     PgCastEntity, PgExternArgumentEntity, PgExternEntity, PgExternReturnEntity,
     PgExternReturnEntityIteratedItem, PgOperatorEntity,
 };
 pub use pg_extern::{NameMacro, PgCast, PgExtern, PgExternArgument, PgOperator};
+pub use pg_fdw::entity::PgFdwEntity;
+pub use pg_fdw::PgFdw;
+pub use pg_index_am::entity::PgIndexAmEntity;
+pub use pg_index_am::PgIndexAm;
+pub use pg_table_am::entity::PgTableAmEntity;
+pub use pg_table_am::PgTableAm;
 pub use pg_trigger::attribute::PgTriggerAttribute;
 pub use pg_trigger::entity::PgTriggerEntity;
 pub use pg_trigger::PgTrigger;
@@ -61,7 +70,11 @@ pub(crate) mod fmt;
 pub mod lifetimes;
 pub(crate) mod mapping;
 pub mod metadata;
+pub(crate) mod pg_event_trigger;
 pub(crate) mod pg_extern;
+pub(crate) mod pg_fdw;
+pub(crate) mod pg_index_am;
+pub(crate) mod pg_table_am;
 pub(crate) mod pg_trigger;
 pub(crate) mod pgrx_attribute;
 pub(crate) mod pgrx_sql;
@@ -110,6 +123,10 @@ pub enum SqlGraphEntity {
     Hash(PostgresHashEntity),
     Aggregate(PgAggregateEntity),
     Trigger(PgTriggerEntity),
+    EventTrigger(PgEventTriggerEntity),
+    IndexAm(PgIndexAmEntity),
+    Fdw(PgFdwEntity),
+    TableAm(PgTableAmEntity),
 }
 
 impl SqlGraphEntity {
@@ -168,6 +185,10 @@ impl SqlGraphIdentifier for SqlGraphEntity {
             SqlGraphEntity::Hash(item) => item.dot_identifier(),
             SqlGraphEntity::Aggregate(item) => item.dot_identifier(),
             SqlGraphEntity::Trigger(item) => item.dot_identifier(),
+            SqlGraphEntity::EventTrigger(item) => item.dot_identifier(),
+            SqlGraphEntity::IndexAm(item) => item.dot_identifier(),
+            SqlGraphEntity::Fdw(item) => item.dot_identifier(),
+            SqlGraphEntity::TableAm(item) => item.dot_identifier(),
             SqlGraphEntity::ExtensionRoot(item) => item.dot_identifier(),
         }
     }
@@ -184,6 +205,10 @@ impl SqlGraphIdentifier for SqlGraphEntity {
             SqlGraphEntity::Hash(item) => item.rust_identifier(),
             SqlGraphEntity::Aggregate(item) => item.rust_identifier(),
             SqlGraphEntity::Trigger(item) => item.rust_identifier(),
+            SqlGraphEntity::EventTrigger(item) => item.rust_identifier(),
+            SqlGraphEntity::IndexAm(item) => item.rust_identifier(),
+            SqlGraphEntity::Fdw(item) => item.rust_identifier(),
+            SqlGraphEntity::TableAm(item) => item.rust_identifier(),
             SqlGraphEntity::ExtensionRoot(item) => item.rust_identifier(),
         }
     }
@@ -200,6 +225,10 @@ impl SqlGraphIdentifier for SqlGraphEntity {
             SqlGraphEntity::Hash(item) => item.file(),
             SqlGraphEntity::Aggregate(item) => item.file(),
             SqlGraphEntity::Trigger(item) => item.file(),
+            SqlGraphEntity::EventTrigger(item) => item.file(),
+            SqlGraphEntity::IndexAm(item) => item.file(),
+            SqlGraphEntity::Fdw(item) => item.file(),
+            SqlGraphEntity::TableAm(item) => item.file(),
             SqlGraphEntity::ExtensionRoot(item) => item.file(),
         }
     }
@@ -216,6 +245,10 @@ impl SqlGraphIdentifier for SqlGraphEntity {
             SqlGraphEntity::Hash(item) => item.line(),
             SqlGraphEntity::Aggregate(item) => item.line(),
             SqlGraphEntity::Trigger(item) => item.line(),
+            SqlGraphEntity::EventTrigger(item) => item.line(),
+            SqlGraphEntity::IndexAm(item) => item.line(),
+            SqlGraphEntity::Fdw(item) => item.line(),
+            SqlGraphEntity::TableAm(item) => item.line(),
             SqlGraphEntity::ExtensionRoot(item) => item.line(),
         }
     }
@@ -241,6 +274,11 @@ impl ToSql for SqlGraphEntity {
                             in_fn_module_path,
                             out_fn,
                             out_fn_module_path,
+                            send_fn,
+                            recv_fn,
+                            typmod_in_fn,
+                            typmod_out_fn,
+                            module_path,
                             ..
                         }) = &context.graph[neighbor]
                         else {
@@ -251,7 +289,13 @@ impl ToSql for SqlGraphEntity {
                             && item.full_path.ends_with(in_fn);
                         let is_out_fn = item.full_path.starts_with(out_fn_module_path)
                             && item.full_path.ends_with(out_fn);
-                        is_in_fn || is_out_fn
+                        let is_support_fn = [send_fn, recv_fn, typmod_in_fn, typmod_out_fn]
+                            .into_iter()
+                            .flatten()
+                            .any(|func| {
+                                item.module_path == *module_path && item.unaliased_name == *func
+                            });
+                        is_in_fn || is_out_fn || is_support_fn
                     })
                 {
                     Ok(String::default())
@@ -278,6 +322,18 @@ impl ToSql for SqlGraphEntity {
             SqlGraphEntity::Trigger(item) => {
                 item.to_sql_config.to_sql(self, context).unwrap_or_else(|| item.to_sql(context))
             }
+            SqlGraphEntity::EventTrigger(item) => {
+                item.to_sql_config.to_sql(self, context).unwrap_or_else(|| item.to_sql(context))
+            }
+            SqlGraphEntity::IndexAm(item) => {
+                item.to_sql_config.to_sql(self, context).unwrap_or_else(|| item.to_sql(context))
+            }
+            SqlGraphEntity::Fdw(item) => {
+                item.to_sql_config.to_sql(self, context).unwrap_or_else(|| item.to_sql(context))
+            }
+            SqlGraphEntity::TableAm(item) => {
+                item.to_sql_config.to_sql(self, context).unwrap_or_else(|| item.to_sql(context))
+            }
             SqlGraphEntity::ExtensionRoot(item) => item.to_sql(context),
         }
     }
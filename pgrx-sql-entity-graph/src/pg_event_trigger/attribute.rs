@@ -0,0 +1,88 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+/*!
+
+`#[pg_event_trigger]` attribute related macro expansion for Rust to SQL translation
+
+> Like all of the [`sql_entity_graph`][crate] APIs, this is considered **internal**
+> to the `pgrx` framework and very subject to change between versions. While you may use this, please do it with caution.
+
+*/
+use crate::ToSqlConfig;
+
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::Token;
+
+/// The events an event trigger can be created `ON`
+pub(crate) const EVENTS: &[&str] =
+    &["ddl_command_start", "ddl_command_end", "sql_drop", "table_rewrite"];
+
+#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+pub enum PgEventTriggerAttribute {
+    Sql(ToSqlConfig),
+    Event(syn::Ident),
+    Tags(Vec<syn::LitStr>),
+    Name(syn::LitStr),
+}
+
+impl Parse for PgEventTriggerAttribute {
+    fn parse(input: ParseStream) -> Result<Self, syn::Error> {
+        let ident: syn::Ident = input.parse()?;
+        let found = match ident.to_string().as_str() {
+            "sql" => {
+                use crate::pgrx_attribute::ArgValue;
+                use syn::Lit;
+
+                let _eq: Token![=] = input.parse()?;
+                match input.parse::<ArgValue>()? {
+                    ArgValue::Path(p) => Self::Sql(ToSqlConfig::from(p)),
+                    ArgValue::Lit(Lit::Bool(b)) => Self::Sql(ToSqlConfig::from(b.value)),
+                    ArgValue::Lit(Lit::Str(s)) => Self::Sql(ToSqlConfig::from(s)),
+                    ArgValue::Lit(other) => {
+                        return Err(syn::Error::new(
+                            other.span(),
+                            "expected boolean, path, or string literal",
+                        ))
+                    }
+                }
+            }
+            "event" => {
+                let _eq: Token![=] = input.parse()?;
+                let event: syn::Ident = input.parse()?;
+                if !EVENTS.contains(&event.to_string().as_str()) {
+                    return Err(syn::Error::new(
+                        event.span(),
+                        format!("expected one of {}", EVENTS.join(", ")),
+                    ));
+                }
+                Self::Event(event)
+            }
+            "tags" => {
+                let _eq: Token![=] = input.parse()?;
+                let content;
+                syn::bracketed!(content in input);
+                let tags = Punctuated::<syn::LitStr, Token![,]>::parse_terminated(&content)?;
+                Self::Tags(tags.into_iter().collect())
+            }
+            "name" => {
+                let _eq: Token![=] = input.parse()?;
+                Self::Name(input.parse()?)
+            }
+            e => {
+                return Err(syn::Error::new(
+                    input.span(),
+                    format!("Invalid option `{e}` inside `{ident} {input}`"),
+                ));
+            }
+        };
+        Ok(found)
+    }
+}
@@ -0,0 +1,179 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+/*!
+
+`#[pg_event_trigger]` related macro expansion for Rust to SQL translation
+
+> Like all of the [`sql_entity_graph`][crate] APIs, this is considered **internal**
+> to the `pgrx` framework and very subject to change between versions. While you may use this, please do it with caution.
+
+*/
+pub mod attribute;
+pub mod entity;
+
+use crate::enrich::{ToEntityGraphTokens, ToRustCodeTokens};
+use crate::finfo::{finfo_v1_extern_c, finfo_v1_tokens};
+use crate::{CodeEnrichment, ToSqlConfig};
+use attribute::PgEventTriggerAttribute;
+use proc_macro2::{Ident, Span, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::{spanned::Spanned, ItemFn, Token};
+
+#[derive(Debug, Clone)]
+pub struct PgEventTrigger {
+    func: syn::ItemFn,
+    event: Option<syn::Ident>,
+    tags: Vec<syn::LitStr>,
+    name: Option<syn::LitStr>,
+    to_sql_config: ToSqlConfig,
+}
+
+impl PgEventTrigger {
+    pub fn new(
+        func: ItemFn,
+        attributes: syn::punctuated::Punctuated<PgEventTriggerAttribute, Token![,]>,
+    ) -> Result<CodeEnrichment<Self>, syn::Error> {
+        let mut event = None;
+        let mut tags = None;
+        let mut name = None;
+        let mut to_sql_config = None;
+        for attribute in attributes {
+            let duplicate = match attribute {
+                PgEventTriggerAttribute::Event(ident) => event.replace(ident).is_some(),
+                PgEventTriggerAttribute::Tags(list) => tags.replace(list).is_some(),
+                PgEventTriggerAttribute::Name(lit) => name.replace(lit).is_some(),
+                PgEventTriggerAttribute::Sql(mut config) => {
+                    if let Some(ref mut content) = config.content {
+                        let value = content.value();
+                        // FIXME: find out if we should be using synthetic spans, issue #1667
+                        let span = content.span();
+                        let updated_value = value
+                            .replace("@FUNCTION_NAME@", &(func.sig.ident.to_string() + "_wrapper"))
+                            + "\n";
+                        *content = syn::LitStr::new(&updated_value, span);
+                    };
+                    to_sql_config.replace(config).is_some()
+                }
+            };
+            if duplicate {
+                return Err(syn::Error::new(
+                    func.span(),
+                    "`#[pg_event_trigger]` arguments must be unique",
+                ));
+            }
+        }
+
+        if event.is_none() && (tags.is_some() || name.is_some()) {
+            return Err(syn::Error::new(
+                func.span(),
+                "`tags` and `name` describe an event trigger, which also needs an `event`",
+            ));
+        }
+
+        let to_sql_config = to_sql_config.unwrap_or_default();
+        if !to_sql_config.overrides_default() {
+            crate::ident_is_acceptable_to_postgres(&func.sig.ident)?;
+        }
+
+        Ok(CodeEnrichment(PgEventTrigger {
+            func,
+            event,
+            tags: tags.unwrap_or_default(),
+            name,
+            to_sql_config,
+        }))
+    }
+
+    pub fn wrapper_tokens(&self) -> Result<ItemFn, syn::Error> {
+        let function_ident = self.func.sig.ident.clone();
+        let fcinfo_ident =
+            Ident::new("_fcinfo", Span::mixed_site().located_at(function_ident.span()));
+
+        let tokens = quote! {
+            fn _internal(fcinfo: ::pgrx::pg_sys::FunctionCallInfo) -> ::pgrx::pg_sys::Datum {
+                let fcinfo_ref = unsafe {
+                    // SAFETY:  The caller should be Postgres in this case and it will give us a valid "fcinfo" pointer
+                    fcinfo.as_ref().expect("fcinfo was NULL from Postgres")
+                };
+                let maybe_pg_event_trigger = unsafe { ::pgrx::trigger_support::PgEventTrigger::from_fcinfo(fcinfo_ref) };
+                let pg_event_trigger = maybe_pg_event_trigger.expect("PgEventTrigger::from_fcinfo failed");
+                let event_trigger_fn_result: Result<(), _> = #function_ident(&pg_event_trigger);
+
+                // Event triggers return nothing, which Postgres expects as a NULL pointer
+                event_trigger_fn_result.expect("Event trigger function panic");
+                ::pgrx::pg_sys::Datum::from(0)
+            }
+            ::pgrx::pg_sys::submodules::panic::pgrx_extern_c_guard(move || _internal(#fcinfo_ident))
+        };
+
+        finfo_v1_extern_c(&self.func, fcinfo_ident, tokens)
+    }
+}
+
+impl ToEntityGraphTokens for PgEventTrigger {
+    fn to_entity_graph_tokens(&self) -> TokenStream2 {
+        let func_sig_ident = &self.func.sig.ident;
+        let sql_graph_entity_fn_name =
+            format_ident!("__pgrx_internals_event_trigger_{}", func_sig_ident);
+        let function_name = func_sig_ident.to_string();
+        let event = match &self.event {
+            Some(event) => {
+                let event = event.to_string();
+                quote! { Some(#event) }
+            }
+            None => quote! { None },
+        };
+        let tags = &self.tags;
+        let name = match &self.name {
+            Some(name) => quote! { Some(#name) },
+            None => quote! { None },
+        };
+        let to_sql_config = &self.to_sql_config;
+
+        quote! {
+            #[no_mangle]
+            #[doc(hidden)]
+            #[allow(unknown_lints, clippy::no_mangle_with_rust_abi, nonstandard_style)]
+            pub extern "Rust" fn #sql_graph_entity_fn_name() -> ::pgrx::pgrx_sql_entity_graph::SqlGraphEntity {
+                use core::any::TypeId;
+                extern crate alloc;
+                use alloc::vec::Vec;
+                use alloc::vec;
+                let submission = ::pgrx::pgrx_sql_entity_graph::PgEventTriggerEntity {
+                    function_name: #function_name,
+                    event: #event,
+                    tags: vec![#(#tags),*],
+                    name: #name,
+                    file: file!(),
+                    line: line!(),
+                    full_path: concat!(module_path!(), "::", stringify!(#func_sig_ident)),
+                    module_path: module_path!(),
+                    to_sql_config: #to_sql_config,
+                };
+                ::pgrx::pgrx_sql_entity_graph::SqlGraphEntity::EventTrigger(submission)
+            }
+        }
+    }
+}
+
+impl ToRustCodeTokens for PgEventTrigger {
+    fn to_rust_code_tokens(&self) -> TokenStream2 {
+        let wrapper_func =
+            self.wrapper_tokens().expect("Generating wrapper function for event trigger");
+        let finfo_func = finfo_v1_tokens(wrapper_func.sig.ident.clone()).unwrap();
+        let func = &self.func;
+
+        quote! {
+            #func
+            #wrapper_func
+            #finfo_func
+        }
+    }
+}
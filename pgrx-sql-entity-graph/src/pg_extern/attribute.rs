@@ -43,6 +43,10 @@ pub enum Attribute {
     Cost(syn::Expr),
     Requires(Punctuated<PositioningRef, Token![,]>),
     Sql(ToSqlConfig),
+    Leakproof,
+    Rows(syn::Expr),
+    Support(PositioningRef),
+    Set(syn::LitStr, syn::LitStr),
 }
 
 impl Attribute {
@@ -96,6 +100,18 @@ impl Attribute {
             Attribute::Sql(_) => {
                 quote! {}
             }
+            Attribute::Leakproof => {
+                quote! { ::pgrx::pgrx_sql_entity_graph::ExternArgs::Leakproof }
+            }
+            Attribute::Rows(s) => {
+                quote! { ::pgrx::pgrx_sql_entity_graph::ExternArgs::Rows(format!("{}", #s)) }
+            }
+            Attribute::Support(item) => {
+                quote! { ::pgrx::pgrx_sql_entity_graph::ExternArgs::Support(#item) }
+            }
+            Attribute::Set(name, value) => {
+                quote! { ::pgrx::pgrx_sql_entity_graph::ExternArgs::Set(String::from(#name), String::from(#value)) }
+            }
         }
     }
 }
@@ -145,6 +161,20 @@ impl ToTokens for Attribute {
             Attribute::Sql(to_sql_config) => {
                 quote! { sql = #to_sql_config }
             }
+            Attribute::Leakproof => quote! { leakproof },
+            Attribute::Rows(s) => {
+                quote! { rows = #s }
+            }
+            Attribute::Support(item) => match item {
+                PositioningRef::FullPath(path) => {
+                    let path = syn::parse_str::<syn::Path>(path).expect("support path is invalid");
+                    quote! { support = #path }
+                }
+                PositioningRef::Name(name) => quote! { support = #name },
+            },
+            Attribute::Set(name, value) => {
+                quote! { set(#name = #value) }
+            }
         };
         tokens.append_all(quoted);
     }
@@ -186,6 +216,31 @@ impl Parse for Attribute {
                 let literal: syn::Expr = input.parse()?;
                 Self::Cost(literal)
             }
+            "leakproof" => Self::Leakproof,
+            "rows" => {
+                let _eq: Token![=] = input.parse()?;
+                let literal: syn::Expr = input.parse()?;
+                Self::Rows(literal)
+            }
+            "support" => {
+                let _eq: Token![=] = input.parse()?;
+                Self::Support(input.parse()?)
+            }
+            "set" => {
+                let content;
+                let _paren = syn::parenthesized!(content in input);
+                // configuration parameters like `myext.setting` aren't identifiers, so they can
+                // also be written as a string literal
+                let name = if content.peek(syn::LitStr) {
+                    content.parse::<syn::LitStr>()?
+                } else {
+                    let ident: syn::Ident = content.parse()?;
+                    syn::LitStr::new(&ident.to_string(), ident.span())
+                };
+                let _eq: Token![=] = content.parse()?;
+                let value: syn::LitStr = content.parse()?;
+                Self::Set(name, value)
+            }
             "requires" => {
                 let _eq: syn::token::Eq = input.parse()?;
                 let content;
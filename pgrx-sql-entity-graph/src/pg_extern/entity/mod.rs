@@ -30,7 +30,7 @@ use crate::metadata::{Returns, SqlMapping};
 use crate::pgrx_sql::PgrxSql;
 use crate::to_sql::entity::ToSqlConfigEntity;
 use crate::to_sql::ToSql;
-use crate::{ExternArgs, SqlGraphEntity, SqlGraphIdentifier, TypeMatch};
+use crate::{ExternArgs, PositioningRef, SqlGraphEntity, SqlGraphIdentifier, TypeMatch};
 
 use eyre::{eyre, WrapErr};
 
@@ -51,9 +51,68 @@ pub struct PgExternEntity {
     pub search_path: Option<Vec<&'static str>>,
     pub operator: Option<PgOperatorEntity>,
     pub cast: Option<PgCastEntity>,
+    /// Create a `PROCEDURE` rather than a `FUNCTION`
+    pub procedure: bool,
     pub to_sql_config: ToSqlConfigEntity,
 }
 
+impl PgExternEntity {
+    /// Whether `path`, as written in a `support = path::to::function` attribute of a function in
+    /// `module_path`, names this function
+    ///
+    /// Paths starting with `crate`, `self` or `super` are resolved against `module_path`.  Others
+    /// can't be resolved without knowing what's in scope there, so they name every function whose
+    /// path ends with theirs.
+    pub(crate) fn is_named_by(&self, path: &str, module_path: &str) -> bool {
+        let path = path.trim_start_matches("::").split("::").collect::<Vec<_>>();
+        let own = self.module_path.split("::").chain([self.unaliased_name]).collect::<Vec<_>>();
+        let mut resolved = module_path.split("::").collect::<Vec<_>>();
+        let relative = match path.first() {
+            Some(&"crate") => {
+                resolved.truncate(1);
+                &path[1..]
+            }
+            Some(&"self") => &path[1..],
+            Some(&"super") => {
+                let supers = path.iter().take_while(|segment| **segment == "super").count();
+                if supers >= resolved.len() {
+                    return false;
+                }
+                resolved.truncate(resolved.len() - supers);
+                &path[supers..]
+            }
+            _ => return own.ends_with(&path),
+        };
+        resolved.extend(relative);
+        own == resolved
+    }
+
+    /// The one function among `candidates` that `path`, as written in this function's
+    /// `support = path::to::function` attribute, names
+    pub(crate) fn find_support<'a, T>(
+        &self,
+        path: &str,
+        candidates: impl IntoIterator<Item = (&'a PgExternEntity, T)>,
+    ) -> eyre::Result<(&'a PgExternEntity, T)> {
+        let mut found: Vec<(&'a PgExternEntity, T)> = Vec::new();
+        for (candidate, value) in candidates {
+            let seen = found.iter().any(|(found, _)| std::ptr::eq(*found, candidate));
+            if !seen && candidate.is_named_by(path, self.module_path) {
+                found.push((candidate, value));
+            }
+        }
+        match found.len() {
+            0 => Err(eyre!("Could not find `support` function `{path}` of `{}`", self.full_path)),
+            1 => Ok(found.pop().unwrap()),
+            _ => Err(eyre!(
+                "`support` function `{path}` of `{}` is ambiguous, it could be any of: {}",
+                self.full_path,
+                found.iter().map(|(found, _)| found.full_path).collect::<Vec<_>>().join(", ")
+            )),
+        }
+    }
+}
+
 impl From<PgExternEntity> for SqlGraphEntity {
     fn from(val: PgExternEntity) -> Self {
         SqlGraphEntity::Function(val)
@@ -82,8 +141,9 @@ impl ToSql for PgExternEntity {
         let self_index = context.externs[self];
         let mut extern_attrs = self.extern_attrs.clone();
         // if we already have a STRICT marker we do not need to add it
-        // presume we can upgrade, then disprove it
-        let mut strict_upgrade = !extern_attrs.iter().any(|i| i == &ExternArgs::Strict);
+        // presume we can upgrade, then disprove it.  Procedures can't be `STRICT` at all
+        let mut strict_upgrade =
+            !self.procedure && !extern_attrs.iter().any(|i| i == &ExternArgs::Strict);
         if strict_upgrade {
             // It may be possible to infer a `STRICT` marker though.
             // But we can only do that if the user hasn't used `Option<T>` or `pgrx::Internal`
@@ -261,10 +321,57 @@ impl ToSql for PgExternEntity {
         };
         let PgExternEntity { name, module_path, file, line, .. } = self;
 
+        let (kind, returns) = if self.procedure {
+            let returns_void = match &self.fn_return {
+                PgExternReturnEntity::None => true,
+                PgExternReturnEntity::Type { .. } => matches!(
+                    self.metadata.retval.return_sql,
+                    Ok(Returns::One(SqlMapping::As(ref sql))) if sql.eq_ignore_ascii_case("void")
+                ),
+                _ => false,
+            };
+            if !returns_void {
+                return Err(eyre!("Procedure `{name}` can't return a value"));
+            }
+            ("PROCEDURE", String::new())
+        } else {
+            ("FUNCTION", format!(" {returns}"))
+        };
+
+        let support = match self.extern_attrs.iter().find_map(|attr| match attr {
+            ExternArgs::Support(support) => Some(support),
+            _ => None,
+        }) {
+            Some(PositioningRef::FullPath(path)) => {
+                let (support_fn, support_index) = self.find_support(
+                    path,
+                    context.graph.neighbors_undirected(self_index).filter_map(|neighbor| {
+                        match &context.graph[neighbor] {
+                            SqlGraphEntity::Function(support_fn) => Some((support_fn, neighbor)),
+                            _ => None,
+                        }
+                    }),
+                )?;
+                format!(
+                    "SUPPORT {schema_prefix}\"{name}\" /* {full_path} */\n",
+                    schema_prefix = support_fn
+                        .schema
+                        .map(|schema| format!("{schema}."))
+                        .unwrap_or_else(|| context.schema_prefix_for(&support_index)),
+                    name = support_fn.name,
+                    full_path = support_fn.full_path,
+                )
+            }
+            // a support function that isn't a `#[pg_extern]`, given by its SQL name
+            Some(PositioningRef::Name(name)) => format!("SUPPORT {name}\n"),
+            None => String::new(),
+        };
+
         let fn_sql = format!(
             "\
-                CREATE {or_replace} FUNCTION {schema}\"{name}\"({arguments}) {returns}\n\
+                CREATE {or_replace} {kind} {schema}\"{name}\"({arguments}){returns}\n\
                 {extern_attrs}\
+                {support}\
                 {search_path}\
                 LANGUAGE c /* Rust */\n\
                 AS '{module_pathname}', '{unaliased_name}_wrapper';\
@@ -482,20 +589,23 @@ impl ToSql for PgExternEntity {
                 }
                 Err(err) => return Err(err.into()),
             };
-            if self.metadata.arguments.len() != 1 {
+            // Besides the value, a cast function may take the target's typmod as an `integer` and
+            // whether the cast is explicit as a `boolean`, like a length-coercion cast does
+            if !(1..=3).contains(&self.metadata.arguments.len()) {
                 return Err(eyre!(
-                    "PG cast function ({}) must have exactly one argument, got {}",
+                    "PG cast function ({}) must have one to three arguments, got {}",
                     self.name,
                     self.metadata.arguments.len()
                 ));
             }
-            if self.fn_args.len() != 1 {
+            if !(1..=3).contains(&self.fn_args.len()) {
                 return Err(eyre!(
-                    "PG cast function ({}) must have exactly one argument, got {}",
+                    "PG cast function ({}) must have one to three arguments, got {}",
                     self.name,
                     self.fn_args.len()
                 ));
             }
+            check_cast_modifier_arguments(self.name, &self.metadata.arguments)?;
             let source_arg = self
                 .metadata
                 .arguments
@@ -563,3 +673,136 @@ impl ToSql for PgExternEntity {
         Ok(ext_sql)
     }
 }
+
+/// Check that a cast function's arguments after its source are the target's typmod, as an
+/// `integer`, and whether the cast is explicit, as a `boolean`, like a length-coercion cast's are
+fn check_cast_modifier_arguments(
+    name: &str,
+    arguments: &[crate::metadata::FunctionMetadataTypeEntity],
+) -> eyre::Result<()> {
+    // the same SQL type may be spelled several ways, eg. `Typmod` is an `integer` and `i32` an `INT`
+    const EXPECTED: [(&str, &[&str]); 2] =
+        [("integer", &["integer", "int", "int4"]), ("boolean", &["boolean", "bool"])];
+    for (arg, (expected, spellings)) in arguments.iter().skip(1).zip(EXPECTED) {
+        let matches = matches!(
+            &arg.argument_sql,
+            Ok(SqlMapping::As(sql)) if spellings.iter().any(|spelling| sql.eq_ignore_ascii_case(spelling))
+        );
+        if !matches {
+            return Err(eyre!(
+                "PG cast function ({name}) must take `{expected}` after its source, got `{}`",
+                arg.type_name
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_cast_modifier_arguments, PgExternEntity, PgExternReturnEntity};
+    use crate::metadata::{
+        FunctionMetadataEntity, FunctionMetadataTypeEntity, Returns, SqlMapping, SqlTranslatable,
+    };
+    use crate::to_sql::entity::ToSqlConfigEntity;
+
+    fn extern_fn(module_path: &'static str, name: &'static str) -> PgExternEntity {
+        PgExternEntity {
+            name,
+            unaliased_name: name,
+            module_path,
+            full_path: name,
+            metadata: FunctionMetadataEntity {
+                arguments: Vec::new(),
+                retval: <()>::entity(),
+                path: name,
+            },
+            fn_args: Vec::new(),
+            fn_return: PgExternReturnEntity::None,
+            schema: None,
+            file: "src/lib.rs",
+            line: 1,
+            extern_attrs: Vec::new(),
+            search_path: None,
+            operator: None,
+            cast: None,
+            procedure: false,
+            to_sql_config: ToSqlConfigEntity { enabled: true, callback: None, content: None },
+        }
+    }
+
+    #[test]
+    fn support_paths_match_whole_segments() {
+        let support = extern_fn("ext::planner", "support");
+        assert!(support.is_named_by("support", "ext"));
+        assert!(support.is_named_by("planner::support", "ext"));
+        assert!(support.is_named_by("ext::planner::support", "ext::other"));
+        assert!(!support.is_named_by("anner::support", "ext"));
+        assert!(!support.is_named_by("pport", "ext"));
+        assert!(!support.is_named_by("other::support", "ext"));
+    }
+
+    #[test]
+    fn support_paths_resolve_crate_self_and_super() {
+        let support = extern_fn("ext::planner", "support");
+        assert!(support.is_named_by("crate::planner::support", "ext::functions"));
+        assert!(support.is_named_by("self::support", "ext::planner"));
+        assert!(support.is_named_by("super::support", "ext::planner::inner"));
+        assert!(support.is_named_by("super::super::planner::support", "ext::a::b"));
+        assert!(!support.is_named_by("self::support", "ext::functions"));
+        assert!(!support.is_named_by("crate::support", "ext::planner"));
+        assert!(!support.is_named_by("super::support", "ext"));
+    }
+
+    #[test]
+    fn ambiguous_support_paths_are_rejected() {
+        let function = extern_fn("ext", "function");
+        let first = extern_fn("ext::a", "support");
+        let second = extern_fn("ext::b", "support");
+        let candidates = [(&first, 0), (&second, 1)];
+        let err = function.find_support("support", candidates).unwrap_err();
+        assert!(err.to_string().contains("is ambiguous"), "{err}");
+        let (found, idx) = function.find_support("crate::b::support", candidates).unwrap();
+        assert_eq!((found.module_path, idx), ("ext::b", 1));
+        assert!(function.find_support("c::support", candidates).is_err());
+    }
+
+    fn literal(type_name: &'static str, sql: &'static str) -> FunctionMetadataTypeEntity {
+        FunctionMetadataTypeEntity {
+            type_name,
+            argument_sql: Ok(SqlMapping::literal(sql)),
+            return_sql: Ok(Returns::One(SqlMapping::literal(sql))),
+            variadic: false,
+            optional: false,
+        }
+    }
+
+    #[test]
+    fn cast_takes_typmod_and_explicit() {
+        let source = literal("Source", "source");
+        let typmod = literal("Typmod", "integer");
+        assert!(check_cast_modifier_arguments(
+            "cast_fn",
+            &[source.clone(), typmod, bool::entity()]
+        )
+        .is_ok());
+        assert!(check_cast_modifier_arguments(
+            "cast_fn",
+            &[source.clone(), i32::entity(), bool::entity()]
+        )
+        .is_ok());
+        assert!(check_cast_modifier_arguments("cast_fn", &[source.clone(), i32::entity()]).is_ok());
+    }
+
+    #[test]
+    fn cast_rejects_other_modifier_types() {
+        let source = literal("Source", "source");
+        let err = check_cast_modifier_arguments(
+            "cast_fn",
+            &[source.clone(), i32::entity(), i32::entity()],
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("must take `boolean` after its source"));
+        assert!(check_cast_modifier_arguments("cast_fn", &[source, bool::entity()]).is_err());
+    }
+}
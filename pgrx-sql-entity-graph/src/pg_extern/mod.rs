@@ -93,6 +93,7 @@ pub struct PgExtern {
     to_sql_config: ToSqlConfig,
     operator: Option<PgOperator>,
     cast: Option<PgCast>,
+    procedure: bool,
     search_path: Option<SearchPathList>,
     inputs: Vec<PgExternArgument>,
     input_types: Vec<syn::Type>,
@@ -138,12 +139,21 @@ impl PgExtern {
         let inputs = Self::inputs(&func)?;
         let input_types = Self::input_types(&func)?;
         let returns = Returning::try_from(&func.sig.output)?;
+        if let Some(rows) = attrs.iter().find(|attr| matches!(attr, Attribute::Rows(_))) {
+            if !matches!(returns, Returning::SetOf { .. } | Returning::Iterated { .. }) {
+                return Err(syn::Error::new_spanned(
+                    rows,
+                    "`rows` only applies to functions that return a set of values",
+                ));
+            }
+        }
         Ok(CodeEnrichment(Self {
             attrs,
             func,
             to_sql_config,
             operator,
             cast: None,
+            procedure: false,
             search_path,
             inputs,
             input_types,
@@ -158,6 +168,44 @@ impl PgExtern {
         result
     }
 
+    /// Returns a new instance of this `PgExtern` that creates a `PROCEDURE` rather than a `FUNCTION`.
+    pub fn as_procedure(&self) -> Result<PgExtern, syn::Error> {
+        let ident = &self.func.sig.ident;
+        if matches!(self.returns, Returning::SetOf { .. } | Returning::Iterated { .. }) {
+            return Err(syn::Error::new_spanned(
+                &self.func.sig.output,
+                "procedures can't return a set of values",
+            ));
+        }
+        if self.operator.is_some() {
+            return Err(syn::Error::new_spanned(ident, "procedures can't be operators"));
+        }
+        for attr in &self.attrs {
+            match attr {
+                Attribute::Immutable
+                | Attribute::Strict
+                | Attribute::Stable
+                | Attribute::Volatile
+                | Attribute::ParallelSafe
+                | Attribute::ParallelUnsafe
+                | Attribute::ParallelRestricted
+                | Attribute::Cost(_)
+                | Attribute::Leakproof
+                | Attribute::Rows(_)
+                | Attribute::Support(_) => {
+                    return Err(syn::Error::new_spanned(
+                        ident,
+                        format!("procedures don't support `{}`", quote! { #attr }),
+                    ))
+                }
+                _ => (),
+            }
+        }
+        let mut result = self.clone();
+        result.procedure = true;
+        Ok(result)
+    }
+
     #[track_caller]
     fn input_types(func: &syn::ItemFn) -> syn::Result<Vec<syn::Type>> {
         func.sig
@@ -337,6 +385,7 @@ impl PgExtern {
 
         let operator = self.operator.clone().into_iter();
         let cast = self.cast.clone().into_iter();
+        let procedure = self.procedure;
         let to_sql_config = match self.overridden() {
             None => self.to_sql_config.clone(),
             Some(content) => ToSqlConfig { content: Some(content), ..self.to_sql_config.clone() },
@@ -384,6 +433,7 @@ impl PgExtern {
                     #[allow(clippy::or_fun_call)]
                     operator: None #( .unwrap_or_else(|| Some(#operator)) )*,
                     cast: None #( .unwrap_or_else(|| Some(#cast)) )*,
+                    procedure: #procedure,
                     to_sql_config: #to_sql_config,
                 };
                 ::pgrx::pgrx_sql_entity_graph::SqlGraphEntity::Function(submission)
@@ -435,6 +485,10 @@ impl PgExtern {
                     syn::ReturnType::Default => syn::parse_quote! { () },
                     syn::ReturnType::Type(_, ret_ty) => ret_ty.clone(),
                 };
+                // a procedure's `NonAtomicSpiClient`s need to know whether it may end the transaction
+                let enter_call = self.procedure.then(|| {
+                    quote! { let _call = ::pgrx::spi::ProcedureCall::enter(#fcinfo_ident); }
+                });
                 let wrapper_code = quote_spanned! { self.func.block.span() =>
                     fn _internal_wrapper<#lifetimes>(fcinfo: &mut ::pgrx::callconv::FcInfo<#fc_lt>) -> ::pgrx::datum::Datum<#fc_lt> {
                         #[allow(unused_unsafe)]
@@ -458,6 +512,7 @@ impl PgExtern {
                     // We preserve the invariants
                     let datum = unsafe {
                         ::pgrx::pg_sys::submodules::panic::pgrx_extern_c_guard(|| {
+                            #enter_call
                             let mut fcinfo = ::pgrx::callconv::FcInfo::from_ptr(#fcinfo_ident);
                             _internal_wrapper(&mut fcinfo)
                         })
@@ -0,0 +1,98 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+/*!
+
+`#[pg_fdw]` related entities for Rust to SQL translation
+
+> Like all of the [`sql_entity_graph`][crate] APIs, this is considered **internal**
+> to the `pgrx` framework and very subject to change between versions. While you may use this, please do it with caution.
+
+*/
+use crate::{PgrxSql, SqlGraphEntity, SqlGraphIdentifier, ToSql, ToSqlConfigEntity};
+
+/// The output of a [`PgFdw`](crate::PgFdw) from `quote::ToTokens::to_tokens`.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PgFdwEntity {
+    pub name: &'static str,
+    pub handler: &'static str,
+    pub validator: &'static str,
+    pub full_path: &'static str,
+    pub module_path: &'static str,
+    pub file: &'static str,
+    pub line: u32,
+    pub to_sql_config: ToSqlConfigEntity,
+}
+
+impl PgFdwEntity {
+    /// The Rust type name of the implementing type, without its module path.
+    pub fn type_name(&self) -> &'static str {
+        self.full_path.rsplit("::").next().unwrap_or(self.full_path)
+    }
+
+    fn handler_wrapper_name(&self) -> String {
+        self.handler.to_string() + "_wrapper"
+    }
+
+    fn validator_wrapper_name(&self) -> String {
+        self.validator.to_string() + "_wrapper"
+    }
+}
+
+impl From<PgFdwEntity> for SqlGraphEntity {
+    fn from(val: PgFdwEntity) -> Self {
+        SqlGraphEntity::Fdw(val)
+    }
+}
+
+impl ToSql for PgFdwEntity {
+    fn to_sql(&self, context: &PgrxSql) -> eyre::Result<String> {
+        let self_index = context.fdws[self];
+        let schema = context.schema_prefix_for(&self_index);
+
+        let PgFdwEntity { name, handler, validator, file, line, full_path, .. } = self;
+        let sql = format!(
+            "\n\
+            -- {file}:{line}\n\
+            -- {full_path}\n\
+            CREATE FUNCTION {schema}\"{handler}\"()\n\
+                \tRETURNS fdw_handler\n\
+                \tLANGUAGE c STRICT\n\
+                \tAS '{module_pathname}', '{handler_wrapper_name}';\n\
+            CREATE FUNCTION {schema}\"{validator}\"(text[], oid)\n\
+                \tRETURNS void\n\
+                \tLANGUAGE c STRICT\n\
+                \tAS '{module_pathname}', '{validator_wrapper_name}';\n\
+            CREATE FOREIGN DATA WRAPPER \"{name}\"\n\
+                \tHANDLER {schema}\"{handler}\"\n\
+                \tVALIDATOR {schema}\"{validator}\";",
+            module_pathname = context.get_module_pathname(),
+            handler_wrapper_name = self.handler_wrapper_name(),
+            validator_wrapper_name = self.validator_wrapper_name(),
+        );
+        Ok(sql)
+    }
+}
+
+impl SqlGraphIdentifier for PgFdwEntity {
+    fn dot_identifier(&self) -> String {
+        format!("fdw {}", self.full_path)
+    }
+    fn rust_identifier(&self) -> String {
+        self.full_path.to_string()
+    }
+
+    fn file(&self) -> Option<&'static str> {
+        Some(self.file)
+    }
+
+    fn line(&self) -> Option<u32> {
+        Some(self.line)
+    }
+}
@@ -0,0 +1,88 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+/*!
+
+`#[pg_table_am]` related entities for Rust to SQL translation
+
+> Like all of the [`sql_entity_graph`][crate] APIs, this is considered **internal**
+> to the `pgrx` framework and very subject to change between versions. While you may use this, please do it with caution.
+
+*/
+use crate::{PgrxSql, SqlGraphEntity, SqlGraphIdentifier, ToSql, ToSqlConfigEntity};
+
+/// The output of a [`PgTableAm`](crate::PgTableAm) from `quote::ToTokens::to_tokens`.
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct PgTableAmEntity {
+    pub name: &'static str,
+    pub handler: &'static str,
+    pub full_path: &'static str,
+    pub module_path: &'static str,
+    pub file: &'static str,
+    pub line: u32,
+    pub to_sql_config: ToSqlConfigEntity,
+}
+
+impl PgTableAmEntity {
+    /// The Rust type name of the implementing type, without its module path.
+    pub fn type_name(&self) -> &'static str {
+        self.full_path.rsplit("::").next().unwrap_or(self.full_path)
+    }
+
+    fn handler_wrapper_name(&self) -> String {
+        self.handler.to_string() + "_wrapper"
+    }
+}
+
+impl From<PgTableAmEntity> for SqlGraphEntity {
+    fn from(val: PgTableAmEntity) -> Self {
+        SqlGraphEntity::TableAm(val)
+    }
+}
+
+impl ToSql for PgTableAmEntity {
+    fn to_sql(&self, context: &PgrxSql) -> eyre::Result<String> {
+        let self_index = context.table_ams[self];
+        let schema = context.schema_prefix_for(&self_index);
+
+        let PgTableAmEntity { name, handler, file, line, full_path, .. } = self;
+        let sql = format!(
+            "\n\
+            -- {file}:{line}\n\
+            -- {full_path}\n\
+            CREATE FUNCTION {schema}\"{handler}\"(internal)\n\
+                \tRETURNS table_am_handler\n\
+                \tLANGUAGE c\n\
+                \tAS '{module_pathname}', '{handler_wrapper_name}';\n\
+            CREATE ACCESS METHOD \"{name}\"\n\
+                \tTYPE TABLE\n\
+                \tHANDLER {schema}\"{handler}\";",
+            module_pathname = context.get_module_pathname(),
+            handler_wrapper_name = self.handler_wrapper_name(),
+        );
+        Ok(sql)
+    }
+}
+
+impl SqlGraphIdentifier for PgTableAmEntity {
+    fn dot_identifier(&self) -> String {
+        format!("table am {}", self.full_path)
+    }
+    fn rust_identifier(&self) -> String {
+        self.full_path.to_string()
+    }
+
+    fn file(&self) -> Option<&'static str> {
+        Some(self.file)
+    }
+
+    fn line(&self) -> Option<u32> {
+        Some(self.line)
+    }
+}
@@ -0,0 +1,182 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+/*!
+
+`#[pg_table_am]` related macro expansion for Rust to SQL translation
+
+> Like all of the [`sql_entity_graph`][crate] APIs, this is considered **internal**
+> to the `pgrx` framework and very subject to change between versions. While you may use this, please do it with caution.
+
+*/
+pub mod entity;
+
+use crate::enrich::{CodeEnrichment, ToEntityGraphTokens, ToRustCodeTokens};
+use crate::finfo::finfo_v1_tokens;
+use crate::ToSqlConfig;
+use convert_case::{Case, Casing};
+use proc_macro2::{Ident, TokenStream as TokenStream2};
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::spanned::Spanned;
+use syn::{parse_quote, ItemImpl, Path};
+
+/// A parsed `#[pg_table_am]` item.
+#[derive(Debug, Clone)]
+pub struct PgTableAm {
+    item_impl: ItemImpl,
+    target_path: Path,
+    target_ident: Ident,
+    name: syn::Expr,
+    to_sql_config: ToSqlConfig,
+}
+
+impl PgTableAm {
+    pub fn new(mut item_impl: ItemImpl) -> Result<CodeEnrichment<Self>, syn::Error> {
+        let to_sql_config =
+            ToSqlConfig::from_attributes(item_impl.attrs.as_slice())?.unwrap_or_default();
+
+        match item_impl.trait_ {
+            Some((_, ref path, _)) => {
+                if let Some(last) = path.segments.last() {
+                    if last.ident != "TableAccessMethod" {
+                        return Err(syn::Error::new(
+                            last.ident.span(),
+                            "`#[pg_table_am]` only works with the `TableAccessMethod` trait.",
+                        ));
+                    }
+                }
+            }
+            None => {
+                return Err(syn::Error::new(
+                    item_impl.span(),
+                    "`#[pg_table_am]` only works with the `TableAccessMethod` trait.",
+                ))
+            }
+        }
+
+        let target_path = match &*item_impl.self_ty {
+            syn::Type::Path(type_path) => type_path.path.clone(),
+            something_else => {
+                return Err(syn::Error::new(
+                    something_else.span(),
+                    "`#[pg_table_am]` only works with types.",
+                ))
+            }
+        };
+        let target_ident =
+            target_path.segments.last().map(|segment| segment.ident.clone()).ok_or_else(|| {
+                syn::Error::new(
+                    target_path.span(),
+                    "`#[pg_table_am]` only works with types whose path have a final segment.",
+                )
+            })?;
+        let snake_case_target_ident =
+            Ident::new(&target_ident.to_string().to_case(Case::Snake), target_ident.span());
+        crate::ident_is_acceptable_to_postgres(&format_ident!(
+            "{}_handler",
+            snake_case_target_ident
+        ))?;
+
+        let name_const = item_impl.items.iter().find_map(|item| match item {
+            syn::ImplItem::Const(item_const) if item_const.ident == "NAME" => Some(item_const),
+            _ => None,
+        });
+        let name = match name_const {
+            Some(item_const) => match &item_const.expr {
+                syn::Expr::Lit(syn::ExprLit { lit: syn::Lit::Str(_), .. }) => {
+                    item_const.expr.clone()
+                }
+                e => {
+                    let message =
+                        "`NAME` must be a `&'static str` for `TableAccessMethod` implementations.";
+                    return Err(syn::Error::new(e.span(), message));
+                }
+            },
+            None => {
+                let name = snake_case_target_ident.to_string();
+                item_impl.items.push(parse_quote! {
+                    const NAME: &'static str = #name;
+                });
+                parse_quote! { #name }
+            }
+        };
+
+        Ok(CodeEnrichment(Self { item_impl, target_path, target_ident, name, to_sql_config }))
+    }
+
+    fn handler_ident(&self) -> Ident {
+        let snake_case_target_ident = self.target_ident.to_string().to_case(Case::Snake);
+        Ident::new(&format!("{snake_case_target_ident}_handler"), self.target_ident.span())
+    }
+}
+
+impl ToEntityGraphTokens for PgTableAm {
+    fn to_entity_graph_tokens(&self) -> TokenStream2 {
+        let target_ident = &self.target_ident;
+        let snake_case_target_ident = target_ident.to_string().to_case(Case::Snake);
+        let sql_graph_entity_fn_name = Ident::new(
+            &format!("__pgrx_internals_table_am_{snake_case_target_ident}"),
+            target_ident.span(),
+        );
+        let name = &self.name;
+        let handler = self.handler_ident().to_string();
+        let to_sql_config = &self.to_sql_config;
+
+        quote! {
+            #[no_mangle]
+            #[doc(hidden)]
+            #[allow(unknown_lints, clippy::no_mangle_with_rust_abi)]
+            pub extern "Rust" fn #sql_graph_entity_fn_name() -> ::pgrx::pgrx_sql_entity_graph::SqlGraphEntity {
+                let submission = ::pgrx::pgrx_sql_entity_graph::PgTableAmEntity {
+                    name: #name,
+                    handler: #handler,
+                    full_path: ::core::any::type_name::<#target_ident>(),
+                    module_path: module_path!(),
+                    file: file!(),
+                    line: line!(),
+                    to_sql_config: #to_sql_config,
+                };
+                ::pgrx::pgrx_sql_entity_graph::SqlGraphEntity::TableAm(submission)
+            }
+        }
+    }
+}
+
+impl ToRustCodeTokens for PgTableAm {
+    fn to_rust_code_tokens(&self) -> TokenStream2 {
+        let item_impl = &self.item_impl;
+        let target_path = &self.target_path;
+        let handler_wrapper = format_ident!("{}_wrapper", self.handler_ident());
+        let finfo_func = finfo_v1_tokens(handler_wrapper.clone()).unwrap();
+
+        quote! {
+            #item_impl
+
+            #[no_mangle]
+            #[doc(hidden)]
+            pub unsafe extern "C" fn #handler_wrapper(_fcinfo: ::pgrx::pg_sys::FunctionCallInfo) -> ::pgrx::pg_sys::Datum {
+                // Postgres holds on to the routine for as long as the relcache does, so it
+                // has to outlive any memory context
+                static ROUTINE: ::std::sync::OnceLock<::pgrx::pg_sys::TableAmRoutine> = ::std::sync::OnceLock::new();
+                ::pgrx::pg_sys::submodules::panic::pgrx_extern_c_guard(move || {
+                    let routine = ROUTINE.get_or_init(::pgrx::table_am::table_am_routine::<#target_path>);
+                    ::pgrx::pg_sys::Datum::from(::std::ptr::from_ref(routine))
+                })
+            }
+            #finfo_func
+        }
+    }
+}
+
+impl Parse for CodeEnrichment<PgTableAm> {
+    fn parse(input: ParseStream) -> Result<Self, syn::Error> {
+        PgTableAm::new(input.parse()?)
+    }
+}
@@ -29,7 +29,11 @@ use crate::aggregate::entity::PgAggregateEntity;
 use crate::control_file::ControlFile;
 use crate::extension_sql::entity::{ExtensionSqlEntity, SqlDeclaredEntity};
 use crate::extension_sql::SqlDeclared;
+use crate::pg_event_trigger::entity::PgEventTriggerEntity;
 use crate::pg_extern::entity::PgExternEntity;
+use crate::pg_fdw::entity::PgFdwEntity;
+use crate::pg_index_am::entity::PgIndexAmEntity;
+use crate::pg_table_am::entity::PgTableAmEntity;
 use crate::pg_trigger::entity::PgTriggerEntity;
 use crate::positioning_ref::PositioningRef;
 use crate::postgres_enum::entity::PostgresEnumEntity;
@@ -78,6 +82,10 @@ pub struct PgrxSql {
     pub hashes: HashMap<PostgresHashEntity, NodeIndex>,
     pub aggregates: HashMap<PgAggregateEntity, NodeIndex>,
     pub triggers: HashMap<PgTriggerEntity, NodeIndex>,
+    pub event_triggers: HashMap<PgEventTriggerEntity, NodeIndex>,
+    pub index_ams: HashMap<PgIndexAmEntity, NodeIndex>,
+    pub fdws: HashMap<PgFdwEntity, NodeIndex>,
+    pub table_ams: HashMap<PgTableAmEntity, NodeIndex>,
     pub extension_name: String,
     pub versioned_so: bool,
 }
@@ -103,6 +111,10 @@ impl PgrxSql {
         let mut hashes: Vec<PostgresHashEntity> = Vec::default();
         let mut aggregates: Vec<PgAggregateEntity> = Vec::default();
         let mut triggers: Vec<PgTriggerEntity> = Vec::default();
+        let mut event_triggers: Vec<PgEventTriggerEntity> = Vec::default();
+        let mut index_ams: Vec<PgIndexAmEntity> = Vec::default();
+        let mut fdws: Vec<PgFdwEntity> = Vec::default();
+        let mut table_ams: Vec<PgTableAmEntity> = Vec::default();
         for entity in entities {
             match entity {
                 SqlGraphEntity::ExtensionRoot(input_control) => {
@@ -136,6 +148,18 @@ impl PgrxSql {
                 SqlGraphEntity::Trigger(input_trigger) => {
                     triggers.push(input_trigger);
                 }
+                SqlGraphEntity::EventTrigger(input_event_trigger) => {
+                    event_triggers.push(input_event_trigger);
+                }
+                SqlGraphEntity::IndexAm(input_index_am) => {
+                    index_ams.push(input_index_am);
+                }
+                SqlGraphEntity::Fdw(input_fdw) => {
+                    fdws.push(input_fdw);
+                }
+                SqlGraphEntity::TableAm(input_table_am) => {
+                    table_ams.push(input_table_am);
+                }
             }
         }
 
@@ -177,6 +201,13 @@ impl PgrxSql {
             &mapped_types,
         )?;
         let mapped_triggers = initialize_triggers(&mut graph, root, bootstrap, finalize, triggers)?;
+        let mapped_event_triggers =
+            initialize_event_triggers(&mut graph, root, bootstrap, finalize, event_triggers)?;
+        let mapped_index_ams =
+            initialize_index_ams(&mut graph, root, bootstrap, finalize, index_ams)?;
+        let mapped_fdws = initialize_fdws(&mut graph, root, bootstrap, finalize, fdws)?;
+        let mapped_table_ams =
+            initialize_table_ams(&mut graph, root, bootstrap, finalize, table_ams)?;
 
         // Now we can circle back and build up the edge sets.
         connect_schemas(&mut graph, &mapped_schemas, root);
@@ -188,6 +219,10 @@ impl PgrxSql {
             &mapped_enums,
             &mapped_externs,
             &mapped_triggers,
+            &mapped_event_triggers,
+            &mapped_index_ams,
+            &mapped_fdws,
+            &mapped_table_ams,
         )?;
         connect_enums(&mut graph, &mapped_enums, &mapped_schemas);
         connect_types(&mut graph, &mapped_types, &mapped_schemas);
@@ -201,6 +236,10 @@ impl PgrxSql {
             &mapped_builtin_types,
             &mapped_extension_sqls,
             &mapped_triggers,
+            &mapped_event_triggers,
+            &mapped_index_ams,
+            &mapped_fdws,
+            &mapped_table_ams,
         )?;
         connect_ords(
             &mut graph,
@@ -228,6 +267,10 @@ impl PgrxSql {
             &mapped_externs,
         )?;
         connect_triggers(&mut graph, &mapped_triggers, &mapped_schemas);
+        connect_event_triggers(&mut graph, &mapped_event_triggers, &mapped_schemas);
+        connect_index_ams(&mut graph, &mapped_index_ams, &mapped_schemas);
+        connect_fdws(&mut graph, &mapped_fdws, &mapped_schemas);
+        connect_table_ams(&mut graph, &mapped_table_ams, &mapped_schemas);
 
         let this = Self {
             control,
@@ -241,6 +284,10 @@ impl PgrxSql {
             hashes: mapped_hashes,
             aggregates: mapped_aggregates,
             triggers: mapped_triggers,
+            event_triggers: mapped_event_triggers,
+            index_ams: mapped_index_ams,
+            fdws: mapped_fdws,
+            table_ams: mapped_table_ams,
             graph,
             graph_root: root,
             graph_bootstrap: bootstrap,
@@ -366,6 +413,18 @@ impl PgrxSql {
                     SqlGraphEntity::Trigger(_item) => format!(
                         "label = \"{dot_id}\", penwidth = 0, style = \"filled\", fillcolor = \"#FFE4E0\", weight = 5, shape = \"diamond\""
                     ),
+                    SqlGraphEntity::EventTrigger(_item) => format!(
+                        "label = \"{dot_id}\", penwidth = 0, style = \"filled\", fillcolor = \"#FFE4E0\", weight = 5, shape = \"diamond\""
+                    ),
+                    SqlGraphEntity::IndexAm(_item) => format!(
+                        "label = \"{dot_id}\", penwidth = 0, style = \"filled\", fillcolor = \"#D6E5E3\", weight = 5, shape = \"box\""
+                    ),
+                    SqlGraphEntity::Fdw(_item) => format!(
+                        "label = \"{dot_id}\", penwidth = 0, style = \"filled\", fillcolor = \"#E3D6E5\", weight = 5, shape = \"box\""
+                    ),
+                    SqlGraphEntity::TableAm(_item) => format!(
+                        "label = \"{dot_id}\", penwidth = 0, style = \"filled\", fillcolor = \"#D6E3E5\", weight = 5, shape = \"box\""
+                    ),
                     SqlGraphEntity::CustomSql(_item) => format!(
                         "label = \"{dot_id}\", weight = 3, shape = \"signature\""
                     ),
@@ -541,6 +600,10 @@ pub fn find_positioning_ref_target<'a>(
     schemas: &'a HashMap<SchemaEntity, NodeIndex>,
     extension_sqls: &'a HashMap<ExtensionSqlEntity, NodeIndex>,
     triggers: &'a HashMap<PgTriggerEntity, NodeIndex>,
+    event_triggers: &'a HashMap<PgEventTriggerEntity, NodeIndex>,
+    index_ams: &'a HashMap<PgIndexAmEntity, NodeIndex>,
+    fdws: &'a HashMap<PgFdwEntity, NodeIndex>,
+    table_ams: &'a HashMap<PgTableAmEntity, NodeIndex>,
 ) -> Option<&'a NodeIndex> {
     match positioning_ref {
         PositioningRef::FullPath(path) => {
@@ -579,6 +642,31 @@ pub fn find_positioning_ref_target<'a>(
                     return Some(other_index);
                 }
             }
+
+            for (other, other_index) in event_triggers {
+                if last_segment == &other.function_name && other.module_path.ends_with(&module_path)
+                {
+                    return Some(other_index);
+                }
+            }
+
+            for (other, other_index) in index_ams {
+                if *last_segment == other.type_name() && other.module_path.ends_with(&module_path) {
+                    return Some(other_index);
+                }
+            }
+
+            for (other, other_index) in fdws {
+                if *last_segment == other.type_name() && other.module_path.ends_with(&module_path) {
+                    return Some(other_index);
+                }
+            }
+
+            for (other, other_index) in table_ams {
+                if *last_segment == other.type_name() && other.module_path.ends_with(&module_path) {
+                    return Some(other_index);
+                }
+            }
         }
         PositioningRef::Name(name) => {
             for (other, other_index) in extension_sqls {
@@ -599,6 +687,10 @@ fn connect_extension_sqls(
     enums: &HashMap<PostgresEnumEntity, NodeIndex>,
     externs: &HashMap<PgExternEntity, NodeIndex>,
     triggers: &HashMap<PgTriggerEntity, NodeIndex>,
+    event_triggers: &HashMap<PgEventTriggerEntity, NodeIndex>,
+    index_ams: &HashMap<PgIndexAmEntity, NodeIndex>,
+    fdws: &HashMap<PgFdwEntity, NodeIndex>,
+    table_ams: &HashMap<PgTableAmEntity, NodeIndex>,
 ) -> eyre::Result<()> {
     for (item, &index) in extension_sqls {
         make_schema_connection(
@@ -619,6 +711,10 @@ fn connect_extension_sqls(
                 schemas,
                 extension_sqls,
                 triggers,
+                event_triggers,
+                index_ams,
+                fdws,
+                table_ams,
             ) {
                 graph.add_edge(*target, index, SqlGraphRequires::By);
             } else {
@@ -810,6 +906,10 @@ fn connect_externs(
     builtin_types: &HashMap<String, NodeIndex>,
     extension_sqls: &HashMap<ExtensionSqlEntity, NodeIndex>,
     triggers: &HashMap<PgTriggerEntity, NodeIndex>,
+    event_triggers: &HashMap<PgEventTriggerEntity, NodeIndex>,
+    index_ams: &HashMap<PgIndexAmEntity, NodeIndex>,
+    fdws: &HashMap<PgFdwEntity, NodeIndex>,
+    table_ams: &HashMap<PgTableAmEntity, NodeIndex>,
 ) -> eyre::Result<()> {
     for (item, &index) in externs {
         let mut found_schema_declaration = false;
@@ -826,6 +926,10 @@ fn connect_externs(
                             schemas,
                             extension_sqls,
                             triggers,
+                            event_triggers,
+                            index_ams,
+                            fdws,
+                            table_ams,
                         ) {
                             graph.add_edge(*target, index, SqlGraphRequires::By);
                             has_explicit_requires = true;
@@ -834,6 +938,11 @@ fn connect_externs(
                         }
                     }
                 }
+                crate::ExternArgs::Support(PositioningRef::FullPath(path)) => {
+                    // The support function has to exist before a function can name it
+                    let (_, support_index) = item.find_support(path, externs)?;
+                    graph.add_edge(*support_index, index, SqlGraphRequires::By);
+                }
                 crate::ExternArgs::Schema(declared_schema_name) => {
                     for (schema, schema_index) in schemas {
                         if schema.name == declared_schema_name {
@@ -869,6 +978,15 @@ fn connect_externs(
             }
         }
 
+        // A type's typmod_in and typmod_out functions don't take the type, but are created with it.
+        for (type_item, &type_index) in types {
+            let is_typmod_fn = [type_item.typmod_in_fn, type_item.typmod_out_fn]
+                .contains(&Some(item.unaliased_name));
+            if item.module_path == type_item.module_path && is_typmod_fn {
+                graph.add_edge(type_index, index, SqlGraphRequires::By);
+            }
+        }
+
         for arg in &item.fn_args {
             let found = types
                 .iter()
@@ -1355,6 +1473,146 @@ fn connect_triggers(
     }
 }
 
+fn initialize_event_triggers(
+    graph: &mut StableGraph<SqlGraphEntity, SqlGraphRequires>,
+    root: NodeIndex,
+    bootstrap: Option<NodeIndex>,
+    finalize: Option<NodeIndex>,
+    event_triggers: Vec<PgEventTriggerEntity>,
+) -> eyre::Result<HashMap<PgEventTriggerEntity, NodeIndex>> {
+    let mut mapped_event_triggers = HashMap::default();
+    for item in event_triggers {
+        let entity: SqlGraphEntity = item.clone().into();
+        let index = graph.add_node(entity);
+
+        mapped_event_triggers.insert(item, index);
+        build_base_edges(graph, index, root, bootstrap, finalize);
+    }
+    Ok(mapped_event_triggers)
+}
+
+fn connect_event_triggers(
+    graph: &mut StableGraph<SqlGraphEntity, SqlGraphRequires>,
+    event_triggers: &HashMap<PgEventTriggerEntity, NodeIndex>,
+    schemas: &HashMap<SchemaEntity, NodeIndex>,
+) {
+    for (item, &index) in event_triggers {
+        make_schema_connection(
+            graph,
+            "Event trigger",
+            index,
+            &item.rust_identifier(),
+            item.module_path,
+            schemas,
+        );
+    }
+}
+
+fn initialize_index_ams(
+    graph: &mut StableGraph<SqlGraphEntity, SqlGraphRequires>,
+    root: NodeIndex,
+    bootstrap: Option<NodeIndex>,
+    finalize: Option<NodeIndex>,
+    index_ams: Vec<PgIndexAmEntity>,
+) -> eyre::Result<HashMap<PgIndexAmEntity, NodeIndex>> {
+    let mut mapped_index_ams = HashMap::default();
+    for item in index_ams {
+        let entity: SqlGraphEntity = item.clone().into();
+        let index = graph.add_node(entity);
+
+        mapped_index_ams.insert(item, index);
+        build_base_edges(graph, index, root, bootstrap, finalize);
+    }
+    Ok(mapped_index_ams)
+}
+
+fn connect_index_ams(
+    graph: &mut StableGraph<SqlGraphEntity, SqlGraphRequires>,
+    index_ams: &HashMap<PgIndexAmEntity, NodeIndex>,
+    schemas: &HashMap<SchemaEntity, NodeIndex>,
+) {
+    for (item, &index) in index_ams {
+        make_schema_connection(
+            graph,
+            "Index access method",
+            index,
+            &item.rust_identifier(),
+            item.module_path,
+            schemas,
+        );
+    }
+}
+
+fn initialize_fdws(
+    graph: &mut StableGraph<SqlGraphEntity, SqlGraphRequires>,
+    root: NodeIndex,
+    bootstrap: Option<NodeIndex>,
+    finalize: Option<NodeIndex>,
+    fdws: Vec<PgFdwEntity>,
+) -> eyre::Result<HashMap<PgFdwEntity, NodeIndex>> {
+    let mut mapped_fdws = HashMap::default();
+    for item in fdws {
+        let entity: SqlGraphEntity = item.clone().into();
+        let index = graph.add_node(entity);
+
+        mapped_fdws.insert(item, index);
+        build_base_edges(graph, index, root, bootstrap, finalize);
+    }
+    Ok(mapped_fdws)
+}
+
+fn connect_fdws(
+    graph: &mut StableGraph<SqlGraphEntity, SqlGraphRequires>,
+    fdws: &HashMap<PgFdwEntity, NodeIndex>,
+    schemas: &HashMap<SchemaEntity, NodeIndex>,
+) {
+    for (item, &index) in fdws {
+        make_schema_connection(
+            graph,
+            "Foreign data wrapper",
+            index,
+            &item.rust_identifier(),
+            item.module_path,
+            schemas,
+        );
+    }
+}
+
+fn initialize_table_ams(
+    graph: &mut StableGraph<SqlGraphEntity, SqlGraphRequires>,
+    root: NodeIndex,
+    bootstrap: Option<NodeIndex>,
+    finalize: Option<NodeIndex>,
+    table_ams: Vec<PgTableAmEntity>,
+) -> eyre::Result<HashMap<PgTableAmEntity, NodeIndex>> {
+    let mut mapped_table_ams = HashMap::default();
+    for item in table_ams {
+        let entity: SqlGraphEntity = item.clone().into();
+        let index = graph.add_node(entity);
+
+        mapped_table_ams.insert(item, index);
+        build_base_edges(graph, index, root, bootstrap, finalize);
+    }
+    Ok(mapped_table_ams)
+}
+
+fn connect_table_ams(
+    graph: &mut StableGraph<SqlGraphEntity, SqlGraphRequires>,
+    table_ams: &HashMap<PgTableAmEntity, NodeIndex>,
+    schemas: &HashMap<SchemaEntity, NodeIndex>,
+) {
+    for (item, &index) in table_ams {
+        make_schema_connection(
+            graph,
+            "Table access method",
+            index,
+            &item.rust_identifier(),
+            item.module_path,
+            schemas,
+        );
+    }
+}
+
 fn make_schema_connection(
     graph: &mut StableGraph<SqlGraphEntity, SqlGraphRequires>,
     _kind: &str,
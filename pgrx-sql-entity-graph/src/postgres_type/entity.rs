@@ -105,6 +105,14 @@ pub struct PostgresTypeEntity {
     pub in_fn_module_path: String,
     pub out_fn: &'static str,
     pub out_fn_module_path: String,
+    /// The `SEND` function, which is in the same module as the type
+    pub send_fn: Option<&'static str>,
+    /// The `RECEIVE` function, which is in the same module as the type
+    pub recv_fn: Option<&'static str>,
+    /// The `TYPMOD_IN` function, which is in the same module as the type
+    pub typmod_in_fn: Option<&'static str>,
+    /// The `TYPMOD_OUT` function, which is in the same module as the type
+    pub typmod_out_fn: Option<&'static str>,
     pub to_sql_config: ToSqlConfigEntity,
     pub alignment: Option<usize>,
 }
@@ -152,6 +160,10 @@ impl ToSql for PostgresTypeEntity {
             out_fn,
             out_fn_module_path,
             in_fn,
+            send_fn,
+            recv_fn,
+            typmod_in_fn,
+            typmod_out_fn,
             alignment,
             ..
         }) = item_node
@@ -163,6 +175,8 @@ impl ToSql for PostgresTypeEntity {
         // - CREATE TYPE;
         // - CREATE FUNCTION _in;
         // - CREATE FUNCTION _out;
+        // - CREATE FUNCTION _recv; and _send; (if the type has `#[sendrecvfuncs]`)
+        // - CREATE FUNCTION _typmod_in; and _typmod_out; (if the type has `#[typmodfuncs]`)
         // - CREATE TYPE (...);
 
         let in_fn_module_path = if !in_fn_module_path.is_empty() {
@@ -217,6 +231,33 @@ impl ToSql for PostgresTypeEntity {
             .ok_or_else(|| eyre!("Could not find out_fn graph entity."))?;
         let out_fn_sql = out_fn_entity.to_sql(context)?;
 
+        let mut support_fn_sql = String::new();
+        let mut support_fns = String::new();
+        for (option, func) in [
+            ("RECEIVE", recv_fn),
+            ("SEND", send_fn),
+            ("TYPMOD_IN", typmod_in_fn),
+            ("TYPMOD_OUT", typmod_out_fn),
+        ] {
+            let Some(func) = func else { continue };
+            let func_path = format!("{module_path}::{func}");
+            let (func_graph_index, func_entity) = context
+                .graph
+                .neighbors_undirected(self_index)
+                .find_map(|neighbor| match &context.graph[neighbor] {
+                    SqlGraphEntity::Function(entity) if entity.full_path == func_path => {
+                        Some((neighbor, entity))
+                    }
+                    _ => None,
+                })
+                .ok_or_else(|| eyre!("Could not find `{func_path}` graph entity."))?;
+            support_fn_sql += &(func_entity.to_sql(context)? + "\n");
+            support_fns += &format!(
+                "\t{option} = {schema_prefix}{func}, /* {func_path} */\n",
+                schema_prefix = context.schema_prefix_for(&func_graph_index),
+            );
+        }
+
         let shell_type = format!(
             "\n\
                 -- {file}:{line}\n\
@@ -252,6 +293,7 @@ impl ToSql for PostgresTypeEntity {
                     \tINTERNALLENGTH = variable,\n\
                     \tINPUT = {schema_prefix_in_fn}{in_fn}, /* {in_fn_path} */\n\
                     \tOUTPUT = {schema_prefix_out_fn}{out_fn}, /* {out_fn_path} */\n\
+                    {support_fns}\
                     \tSTORAGE = extended{alignment}\n\
                 );\
             ",
@@ -260,6 +302,13 @@ impl ToSql for PostgresTypeEntity {
             schema_prefix_out_fn = context.schema_prefix_for(&out_fn_graph_index),
         };
 
-        Ok(shell_type + "\n" + &in_fn_sql + "\n" + &out_fn_sql + "\n" + &materialized_type)
+        Ok(shell_type
+            + "\n"
+            + &in_fn_sql
+            + "\n"
+            + &out_fn_sql
+            + "\n"
+            + &support_fn_sql
+            + &materialized_type)
     }
 }
@@ -55,6 +55,8 @@ pub struct PostgresTypeDerive {
     generics: Generics,
     in_fn: Ident,
     out_fn: Ident,
+    send_recv_fns: Option<(Ident, Ident)>,
+    typmod_fns: Option<(Ident, Ident)>,
     to_sql_config: ToSqlConfig,
     alignment: Alignment,
 }
@@ -65,13 +67,44 @@ impl PostgresTypeDerive {
         generics: Generics,
         in_fn: Ident,
         out_fn: Ident,
+        send_recv_fns: Option<(Ident, Ident)>,
+        typmod_fns: Option<(Ident, Ident)>,
         to_sql_config: ToSqlConfig,
         alignment: Alignment,
     ) -> Result<CodeEnrichment<Self>, syn::Error> {
         if !to_sql_config.overrides_default() {
             crate::ident_is_acceptable_to_postgres(&name)?;
         }
-        Ok(CodeEnrichment(Self { generics, name, in_fn, out_fn, to_sql_config, alignment }))
+        Ok(CodeEnrichment(Self {
+            generics,
+            name,
+            in_fn,
+            out_fn,
+            send_recv_fns,
+            typmod_fns,
+            to_sql_config,
+            alignment,
+        }))
+    }
+
+    /// The `_send` and `_recv` functions of a type with a `#[sendrecvfuncs]` attribute
+    fn send_recv_fns(ident: &Ident, attrs: &[syn::Attribute]) -> Option<(Ident, Ident)> {
+        attrs.iter().any(|attr| attr.path().is_ident("sendrecvfuncs")).then(|| {
+            let send_fn = Ident::new(&format!("{ident}_send").to_lowercase(), ident.span());
+            let recv_fn = Ident::new(&format!("{ident}_recv").to_lowercase(), ident.span());
+            (send_fn, recv_fn)
+        })
+    }
+
+    /// The `_typmod_in` and `_typmod_out` functions of a type with a `#[typmodfuncs]` attribute
+    fn typmod_fns(ident: &Ident, attrs: &[syn::Attribute]) -> Option<(Ident, Ident)> {
+        attrs.iter().any(|attr| attr.path().is_ident("typmodfuncs")).then(|| {
+            let typmod_in_fn =
+                Ident::new(&format!("{ident}_typmod_in").to_lowercase(), ident.span());
+            let typmod_out_fn =
+                Ident::new(&format!("{ident}_typmod_out").to_lowercase(), ident.span());
+            (typmod_in_fn, typmod_out_fn)
+        })
     }
 
     pub fn from_derive_input(
@@ -93,12 +126,16 @@ impl PostgresTypeDerive {
             &format!("{}_out", derive_input.ident).to_lowercase(),
             derive_input.ident.span(),
         );
+        let send_recv_fns = Self::send_recv_fns(&derive_input.ident, &derive_input.attrs);
+        let typmod_fns = Self::typmod_fns(&derive_input.ident, &derive_input.attrs);
         let alignment = Alignment::from_attributes(derive_input.attrs.as_slice())?;
         Self::new(
             derive_input.ident,
             derive_input.generics,
             funcname_in,
             funcname_out,
+            send_recv_fns,
+            typmod_fns,
             to_sql_config,
             alignment,
         )
@@ -129,6 +166,19 @@ impl ToEntityGraphTokens for PostgresTypeDerive {
 
         let in_fn = &self.in_fn;
         let out_fn = &self.out_fn;
+        let (send_fn, recv_fn) = match &self.send_recv_fns {
+            Some((send_fn, recv_fn)) => {
+                (quote! { Some(stringify!(#send_fn)) }, quote! { Some(stringify!(#recv_fn)) })
+            }
+            None => (quote! { None }, quote! { None }),
+        };
+        let (typmod_in_fn, typmod_out_fn) = match &self.typmod_fns {
+            Some((typmod_in_fn, typmod_out_fn)) => (
+                quote! { Some(stringify!(#typmod_in_fn)) },
+                quote! { Some(stringify!(#typmod_out_fn)) },
+            ),
+            None => (quote! { None }, quote! { None }),
+        };
 
         let sql_graph_entity_fn_name = format_ident!("__pgrx_internals_type_{}", self.name);
 
@@ -199,6 +249,10 @@ impl ToEntityGraphTokens for PostgresTypeDerive {
                         let _ = path_items.pop(); // Drop the one we don't want.
                         path_items.join("::")
                     },
+                    send_fn: #send_fn,
+                    recv_fn: #recv_fn,
+                    typmod_in_fn: #typmod_in_fn,
+                    typmod_out_fn: #typmod_out_fn,
                     to_sql_config: #to_sql_config,
                     alignment: #alignment,
                 };
@@ -216,7 +270,18 @@ impl Parse for CodeEnrichment<PostgresTypeDerive> {
         let to_sql_config = ToSqlConfig::from_attributes(attrs.as_slice())?.unwrap_or_default();
         let in_fn = Ident::new(&format!("{}_in", ident).to_lowercase(), ident.span());
         let out_fn = Ident::new(&format!("{}_out", ident).to_lowercase(), ident.span());
+        let send_recv_fns = PostgresTypeDerive::send_recv_fns(&ident, &attrs);
+        let typmod_fns = PostgresTypeDerive::typmod_fns(&ident, &attrs);
         let alignment = Alignment::from_attributes(attrs.as_slice())?;
-        PostgresTypeDerive::new(ident, generics, in_fn, out_fn, to_sql_config, alignment)
+        PostgresTypeDerive::new(
+            ident,
+            generics,
+            in_fn,
+            out_fn,
+            send_recv_fns,
+            typmod_fns,
+            to_sql_config,
+            alignment,
+        )
     }
 }
@@ -7,7 +7,7 @@
 //LICENSE All rights reserved.
 //LICENSE
 //LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::process::{Command, Stdio};
 
 use eyre::{eyre, WrapErr};
@@ -22,11 +22,14 @@ use std::env::VarError;
 use std::ffi::OsStr;
 use std::io::{BufRead, BufReader, Write};
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex, OnceLock};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock, PoisonError, RwLock};
 use std::time::Duration;
 use sysinfo::{Pid, System};
 
+mod isolation;
 mod shutdown;
+pub use isolation::{IsolationSchedule, IsolationSession, IsolationTest, StepOutcome};
 pub use shutdown::add_shutdown_hook;
 
 type LogLines = Arc<Mutex<HashMap<String, Vec<String>>>>;
@@ -35,10 +38,139 @@ struct SetupState {
     installed: bool,
     loglines: LogLines,
     system_session_id: String,
+    postmaster: Option<Postmaster>,
+}
+
+/// The Postgres we started, and the thread watching its output
+struct Postmaster {
+    pid: u32,
+    monitor: std::thread::JoinHandle<()>,
 }
 
 static TEST_MUTEX: OnceLock<Mutex<SetupState>> = OnceLock::new();
 
+/// Held for reading by every running test, so a `#[pg_test(restart)]` can wait to have the
+/// server to itself
+static SERVER_LOCK: RwLock<()> = RwLock::new(());
+
+/// How the test framework isolates a `#[pg_test]` from the others
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PgTestOptions {
+    /// Run the test inside a transaction that's rolled back afterwards.  Otherwise it's `CALL`ed
+    /// as a procedure, free to commit, in a [`PgTestDatabase::Fresh`] database.
+    pub transaction: bool,
+    /// The database the test runs in
+    pub database: PgTestDatabase,
+    /// Restart Postgres before running the test
+    pub restart: bool,
+}
+
+impl Default for PgTestOptions {
+    fn default() -> Self {
+        PgTestOptions { transaction: true, database: PgTestDatabase::Shared, restart: false }
+    }
+}
+
+/// What the error a `#[pg_test]` expects to be raised must look like
+///
+/// Only what's set is checked, so an error with any message matches a `PgTestError` that only
+/// sets its `sqlstate`.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct PgTestError<'a> {
+    /// The exact primary message
+    pub message: Option<&'a str>,
+    /// A regular expression the primary message must match
+    pub message_matches: Option<&'a str>,
+    pub sqlstate: Option<PgSqlErrorCode>,
+    /// The exact detail message
+    pub detail: Option<&'a str>,
+    /// The exact hint message
+    pub hint: Option<&'a str>,
+}
+
+impl PgTestError<'_> {
+    /// Describe how `dberror` differs from the expected error
+    fn mismatches(&self, dberror: &DbError) -> eyre::Result<Vec<String>> {
+        let mut mismatches = Vec::new();
+        if let Some(sqlstate) = self.sqlstate {
+            let expected = sqlstate_code(sqlstate);
+            if dberror.code().code() != expected {
+                mismatches.push(format!(
+                    "expected SQLSTATE {expected} ({sqlstate}), got {}",
+                    dberror.code().code()
+                ));
+            }
+        }
+        if let Some(message) = self.message {
+            if dberror.message() != message {
+                mismatches.push(format!("expected message: {message}"));
+            }
+        }
+        if let Some(pattern) = self.message_matches {
+            let regex = regex::Regex::new(pattern)
+                .wrap_err_with(|| format!("`{pattern}` isn't a valid regular expression"))?;
+            if !regex.is_match(dberror.message()) {
+                mismatches.push(format!("expected message matching: {pattern}"));
+            }
+        }
+        if let Some(detail) = self.detail {
+            if dberror.detail() != Some(detail) {
+                mismatches.push(format!(
+                    "expected detail: {detail}\ngot detail: {}",
+                    dberror.detail().unwrap_or("<none>")
+                ));
+            }
+        }
+        if let Some(hint) = self.hint {
+            if dberror.hint() != Some(hint) {
+                mismatches.push(format!(
+                    "expected hint: {hint}\ngot hint: {}",
+                    dberror.hint().unwrap_or("<none>")
+                ));
+            }
+        }
+        Ok(mismatches)
+    }
+}
+
+impl std::fmt::Display for PgTestError<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(sqlstate) = self.sqlstate {
+            parts.push(format!("SQLSTATE {} ({sqlstate})", sqlstate_code(sqlstate)));
+        }
+        if let Some(message) = self.message {
+            parts.push(message.to_string());
+        }
+        if let Some(pattern) = self.message_matches {
+            parts.push(format!("message matching {pattern}"));
+        }
+        if let Some(detail) = self.detail {
+            parts.push(format!("detail {detail}"));
+        }
+        if let Some(hint) = self.hint {
+            parts.push(format!("hint {hint}"));
+        }
+        f.write_str(&parts.join(", "))
+    }
+}
+
+/// The five character SQLSTATE of an error code, as in `errcodes.h`
+fn sqlstate_code(code: PgSqlErrorCode) -> String {
+    // the inverse of `MAKE_SQLSTATE()`, which packs each character into six bits
+    (0..5).map(|i| char::from(((code as i32 >> (6 * i)) & 0x3F) as u8 + b'0')).collect()
+}
+
+/// The database a `#[pg_test]` runs in
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PgTestDatabase {
+    /// The database every other test shares
+    Shared,
+    /// A database of its own, copied from the shared one as it was once the extension was
+    /// created, and dropped once the test is done
+    Fresh,
+}
+
 // The goal of this closure is to allow "wrapping" of anything that might issue
 // an SQL simple_query or query using either a postgres::Client or
 // postgres::Transaction and capture the output. The use of this wrapper is
@@ -107,6 +239,20 @@ pub fn run_test(
     sql_funcname: &str,
     expected_error: Option<&str>,
     postgresql_conf: Vec<&'static str>,
+) -> eyre::Result<()> {
+    run_test_with_options(
+        sql_funcname,
+        expected_error.map(|message| PgTestError { message: Some(message), ..Default::default() }),
+        postgresql_conf,
+        PgTestOptions::default(),
+    )
+}
+
+pub fn run_test_with_options(
+    sql_funcname: &str,
+    expected_error: Option<PgTestError<'_>>,
+    postgresql_conf: Vec<&'static str>,
+    options: PgTestOptions,
 ) -> eyre::Result<()> {
     if std::env::var_os("PGRX_TEST_SKIP").unwrap_or_default() != "" {
         eprintln!(
@@ -116,44 +262,73 @@ pub fn run_test(
     }
     let (loglines, system_session_id) = initialize_test_framework(postgresql_conf)?;
 
-    let (mut client, session_id) = client()?;
+    // a panicking test poisons the lock, which doesn't matter to the tests that come after it
+    let _exclusive =
+        options.restart.then(|| SERVER_LOCK.write().unwrap_or_else(PoisonError::into_inner));
+    let _shared =
+        (!options.restart).then(|| SERVER_LOCK.read().unwrap_or_else(PoisonError::into_inner));
+    let (loglines, system_session_id) =
+        if options.restart { restart_pg()? } else { (loglines, system_session_id) };
+
+    // work a test commits outside of a transaction would leak into the shared database
+    let database = match (options.database, options.transaction) {
+        (PgTestDatabase::Shared, true) => None,
+        _ => Some(create_test_database()?),
+    };
+    let (mut client, session_id) = client_for(database.as_deref().unwrap_or(get_pg_dbname()))?;
 
-    let result = client.transaction().map(|mut tx| {
-        let schema = "tests"; // get_extension_schema();
-        let result = tx.simple_query(&format!("SELECT \"{schema}\".\"{sql_funcname}\"();"));
+    let schema = "tests"; // get_extension_schema();
+    let result = if options.transaction {
+        let result = client.transaction().map(|mut tx| {
+            let result = tx.simple_query(&format!("SELECT \"{schema}\".\"{sql_funcname}\"();"));
 
-        if result.is_ok() {
-            // and abort the transaction when complete
-            tx.rollback()?;
-        }
+            if result.is_ok() {
+                // and abort the transaction when complete
+                tx.rollback()?;
+            }
 
-        result
-    });
+            result
+        });
 
-    // flatten the above result
-    let result = match result {
-        Err(e) => Err(e),
-        Ok(Err(e)) => Err(e),
-        Ok(_) => Ok(()),
+        // flatten the above result
+        match result {
+            Err(e) => Err(e),
+            Ok(Err(e)) => Err(e),
+            Ok(_) => Ok(()),
+        }
+    } else {
+        // a `CALL` outside of a transaction block lets the procedure commit
+        client.simple_query(&format!("CALL \"{schema}\".\"{sql_funcname}\"();")).map(|_| ())
     };
 
+    drop(client);
+    if let Some(dbname) = &database {
+        dropdb(dbname)?;
+    }
+
     if let Err(e) = result {
         let error_as_string = format!("{e}");
         let cause = e.into_source();
 
         let (pg_location, rust_location, message) =
             if let Some(Some(dberror)) = cause.map(|e| e.downcast_ref::<DbError>().cloned()) {
-                let received_error_message = dberror.message();
+                let mut message = dberror.message().to_string();
 
-                if Some(received_error_message) == expected_error {
-                    // the error received is the one we expected, so just return if they match
-                    return Ok(());
+                if let Some(expected_error) = &expected_error {
+                    let mismatches = expected_error.mismatches(&dberror)?;
+                    if mismatches.is_empty() {
+                        // the error received is the one we expected, so just return if they match
+                        return Ok(());
+                    }
+                    for mismatch in mismatches {
+                        message.push_str(&format!("\n{mismatch}"));
+                    }
                 }
 
                 let pg_location = dberror.file().unwrap_or("<unknown>").to_string();
                 let rust_location = dberror.where_().unwrap_or("<unknown>").to_string();
 
-                (pg_location, rust_location, received_error_message.to_string())
+                (pg_location, rust_location, message)
             } else {
                 ("<unknown>".to_string(), "<unknown>".to_string(), error_as_string.to_string())
             };
@@ -171,9 +346,9 @@ pub fn run_test(
                 pg_location = pg_location.dimmed().white(),
                 rust_location = rust_location.yellow()
         );
-    } else if let Some(message) = expected_error {
+    } else if let Some(expected_error) = expected_error {
         // we expected an ERROR, but didn't get one
-        return Err(eyre!("Expected error: {message}"));
+        return Err(eyre!("Expected error: {expected_error}"));
     } else {
         Ok(())
     }
@@ -190,15 +365,14 @@ fn format_loglines(session_id: &str, loglines: &LogLines) -> String {
     result
 }
 
-fn initialize_test_framework(
-    postgresql_conf: Vec<&'static str>,
-) -> eyre::Result<(LogLines, String)> {
-    let mut state = TEST_MUTEX
+fn setup_state() -> MutexGuard<'static, SetupState> {
+    TEST_MUTEX
         .get_or_init(|| {
             Mutex::new(SetupState {
                 installed: false,
                 loglines: Arc::new(Mutex::new(HashMap::new())),
                 system_session_id: "NONE".to_string(),
+                postmaster: None,
             })
         })
         .lock()
@@ -209,18 +383,36 @@ fn initialize_test_framework(
             panic!(
             "Could not obtain test mutex. A previous test may have hard-aborted while holding it."
         );
-        });
+        })
+}
+
+fn initialize_test_framework(
+    postgresql_conf: Vec<&'static str>,
+) -> eyre::Result<(LogLines, String)> {
+    let mut state = setup_state();
 
     if !state.installed {
         shutdown::register_shutdown_hook();
         install_extension()?;
+        let upgrade_from = get_upgrade_from();
+        if let Some(version) = &upgrade_from {
+            install_upgrade_base_script(version)?;
+        }
         initdb(postgresql_conf)?;
 
-        let system_session_id = start_pg(state.loglines.clone())?;
+        let (system_session_id, postmaster) = start_pg(state.loglines.clone())?;
+        state.postmaster = Some(postmaster);
         let pg_config = get_pg_config()?;
-        dropdb()?;
+        dropdb(get_pg_dbname())?;
         createdb(&pg_config, get_pg_dbname(), true, false, get_runas())?;
-        create_extension()?;
+        match &upgrade_from {
+            Some(version) => {
+                create_and_update_extension(version)?;
+                compare_with_fresh_install(version)?;
+            }
+            None => create_extension()?,
+        }
+        create_template_database()?;
         state.installed = true;
         state.system_session_id = system_session_id;
     }
@@ -228,6 +420,20 @@ fn initialize_test_framework(
     Ok((state.loglines.clone(), state.system_session_id.clone()))
 }
 
+/// Stop the running Postgres and start it again, for a `#[pg_test(restart)]`
+fn restart_pg() -> eyre::Result<(LogLines, String)> {
+    let mut state = setup_state();
+
+    if let Some(postmaster) = state.postmaster.take() {
+        stop_pg(postmaster)?;
+    }
+    let (system_session_id, postmaster) = start_pg(state.loglines.clone())?;
+    state.postmaster = Some(postmaster);
+    state.system_session_id = system_session_id;
+
+    Ok((state.loglines.clone(), state.system_session_id.clone()))
+}
+
 fn get_pg_config() -> eyre::Result<PgConfig> {
     let pgrx = Pgrx::from_config().wrap_err("Unable to get PGRX from config")?;
 
@@ -245,14 +451,11 @@ fn get_pg_config() -> eyre::Result<PgConfig> {
 }
 
 pub fn client() -> eyre::Result<(postgres::Client, String)> {
-    let pg_config = get_pg_config()?;
-    let mut client = postgres::Config::new()
-        .host(pg_config.host())
-        .port(pg_config.test_port().expect("unable to determine test port"))
-        .user(&get_pg_user())
-        .dbname(get_pg_dbname())
-        .connect(postgres::NoTls)
-        .wrap_err("Error connecting to Postgres")?;
+    client_for(get_pg_dbname())
+}
+
+fn client_for(dbname: &str) -> eyre::Result<(postgres::Client, String)> {
+    let mut client = connect(dbname)?;
 
     let sid_query_result = query_wrapper(
         Some("SELECT to_hex(trunc(EXTRACT(EPOCH FROM backend_start))::integer) || '.' || to_hex(pid) AS sid FROM pg_stat_activity WHERE pid = pg_backend_pid();".to_string()),
@@ -284,6 +487,43 @@ pub fn client() -> eyre::Result<(postgres::Client, String)> {
     Ok((client, session_id))
 }
 
+/// Connect another session to the database the calling `#[pg_test]` runs in
+///
+/// Postgres only allows statements like `VACUUM` at the top level, never inside a function or
+/// procedure, so a `#[pg_test(transaction = false)]` can run them through this session instead.
+/// It's an ordinary session: it waits on the locks the test holds and can't see what the test
+/// hasn't committed yet.
+pub fn top_level_client() -> eyre::Result<postgres::Client> {
+    let (listen_addresses, port, dbname) = Spi::get_three::<String, i32, String>(
+        "SELECT current_setting('listen_addresses'), current_setting('port')::int, current_database()::text",
+    )?;
+    let user = Spi::get_one::<String>("SELECT current_user::text")?;
+    let listen_addresses = listen_addresses.unwrap_or_default();
+    let host = match listen_addresses.split(',').map(str::trim).next() {
+        None | Some("") | Some("*") => "localhost",
+        Some(host) => host,
+    };
+
+    postgres::Config::new()
+        .host(host)
+        .port(port.and_then(|port| u16::try_from(port).ok()).ok_or_else(|| eyre!("no port"))?)
+        .user(&user.ok_or_else(|| eyre!("no current_user"))?)
+        .dbname(&dbname.ok_or_else(|| eyre!("no current_database()"))?)
+        .connect(postgres::NoTls)
+        .wrap_err("Error connecting to Postgres")
+}
+
+fn connect(dbname: &str) -> eyre::Result<postgres::Client> {
+    let pg_config = get_pg_config()?;
+    postgres::Config::new()
+        .host(pg_config.host())
+        .port(pg_config.test_port().expect("unable to determine test port"))
+        .user(&get_pg_user())
+        .dbname(dbname)
+        .connect(postgres::NoTls)
+        .wrap_err("Error connecting to Postgres")
+}
+
 fn install_extension() -> eyre::Result<()> {
     let profile = std::env::var("PGRX_BUILD_PROFILE").unwrap_or("debug".into());
     let no_schema = std::env::var("PGRX_NO_SCHEMA").unwrap_or("false".into()) == "true";
@@ -519,7 +759,7 @@ fn modify_postgresql_conf(pgdata: PathBuf, postgresql_conf: Vec<&'static str>) -
     Ok(())
 }
 
-fn start_pg(loglines: LogLines) -> eyre::Result<String> {
+fn start_pg(loglines: LogLines) -> eyre::Result<(String, Postmaster)> {
     wait_for_pidfile()?;
 
     let pg_config = get_pg_config()?;
@@ -591,9 +831,33 @@ fn start_pg(loglines: LogLines) -> eyre::Result<String> {
 
     // start Postgres and monitor its stderr in the background
     // also notify the main thread when it's ready to accept connections
-    let session_id = monitor_pg(command, command_str, loglines);
+    Ok(monitor_pg(command, command_str, loglines))
+}
+
+/// Stop Postgres with a "fast" shutdown, and wait for it to be gone
+fn stop_pg(postmaster: Postmaster) -> eyre::Result<()> {
+    let Postmaster { pid, monitor } = postmaster;
+
+    if let Some(_runas) = get_runas() {
+        // NB:  we must be "root" to signal the `sudo` process we spawned to start postgres, which
+        // passes the signal along
+        let status = sudo_command("root")
+            .arg("kill")
+            .arg("-s")
+            .arg("SIGINT")
+            .arg(pid.to_string())
+            .status()
+            .wrap_err("failed to spawn `sudo kill`")?;
+        if !status.success() {
+            return Err(eyre!("`sudo kill` failed to stop postgres (pid={pid})"));
+        }
+    } else if unsafe { libc::kill(pid as libc::pid_t, libc::SIGINT) } != 0 {
+        return Err(std::io::Error::last_os_error())
+            .wrap_err(format!("failed to stop postgres (pid={pid})"));
+    }
 
-    Ok(session_id)
+    // the monitor finishes once Postgres, and every process it started, has exited
+    monitor.join().map_err(|_| eyre!("the thread monitoring postgres (pid={pid}) panicked"))
 }
 
 fn valgrind_suppressions_path(pg_config: &PgConfig) -> Result<PathBuf, eyre::Report> {
@@ -622,10 +886,14 @@ fn wait_for_pidfile() -> Result<(), eyre::Report> {
     Ok(())
 }
 
-fn monitor_pg(mut command: Command, cmd_string: String, loglines: LogLines) -> String {
+fn monitor_pg(
+    mut command: Command,
+    cmd_string: String,
+    loglines: LogLines,
+) -> (String, Postmaster) {
     let (sender, receiver) = std::sync::mpsc::channel();
 
-    std::thread::spawn(move || {
+    let monitor = std::thread::spawn(move || {
         let mut child = command.spawn().expect("postmaster didn't spawn");
 
         let pid = child.id();
@@ -669,7 +937,7 @@ fn monitor_pg(mut command: Command, cmd_string: String, loglines: LogLines) -> S
 
             if line.contains("database system is ready to accept connections") {
                 // Postgres says it's ready to go
-                if let Err(_) = sender.send(session_id.clone()) {
+                if let Err(_) = sender.send((session_id.clone(), pid)) {
                     // The channel is closed.  This is really early in the startup process
                     // and likely indicates that a test crashed Postgres
                     panic!("{}: `monitor_pg()`:  failed to send back session_id `{session_id}`.  Did Postgres crash?", "ERROR".red().bold());
@@ -701,11 +969,9 @@ fn monitor_pg(mut command: Command, cmd_string: String, loglines: LogLines) -> S
         }
 
         // wait for Postgres to really finish
-        match child.try_wait() {
-            Ok(status) => {
-                if let Some(_status) = status {
-                    // we exited normally
-                }
+        match child.wait() {
+            Ok(_status) => {
+                // we exited normally
             }
             Err(e) => panic!("was going to let Postgres finish, but errored this time:\n{e}"),
         }
@@ -713,10 +979,11 @@ fn monitor_pg(mut command: Command, cmd_string: String, loglines: LogLines) -> S
 
     // wait for Postgres to indicate it's ready to accept connection
     // and return its pid when it is
-    receiver.recv().expect("Postgres failed to start")
+    let (session_id, pid) = receiver.recv().expect("Postgres failed to start");
+    (session_id, Postmaster { pid, monitor })
 }
 
-fn dropdb() -> eyre::Result<()> {
+fn dropdb(dbname: &str) -> eyre::Result<()> {
     let pg_config = get_pg_config()?;
     let dropdb_path = pg_config.dropdb_path().expect("unable to determine dropdb path");
     let mut command = if let Some(runas) = get_runas() {
@@ -733,18 +1000,20 @@ fn dropdb() -> eyre::Result<()> {
         .env_remove("PGPORT")
         .env_remove("PGUSER")
         .arg("--if-exists")
+        // don't wait on whatever a test left connected, like a background worker
+        .args((pg_sys::get_pg_major_version_num() >= 13).then_some("--force"))
         .arg("-h")
         .arg(pg_config.host())
         .arg("-p")
         .arg(pg_config.test_port().expect("unable to determine test port").to_string())
-        .arg(get_pg_dbname())
+        .arg(dbname)
         .output()
         .unwrap();
 
     if !output.status.success() {
         // maybe the database didn't exist, and if so that's okay
         let stderr = String::from_utf8_lossy(output.stderr.as_slice());
-        if !stderr.contains(&format!("ERROR:  database \"{}\" does not exist", get_pg_dbname())) {
+        if !stderr.contains(&format!("ERROR:  database \"{dbname}\" does not exist")) {
             // got some error we didn't expect
             let stdout = String::from_utf8_lossy(output.stdout.as_slice());
             eprintln!("unexpected error (stdout):\n{stdout}");
@@ -770,6 +1039,193 @@ fn create_extension() -> eyre::Result<()> {
     Ok(())
 }
 
+/// The database `#[pg_test(database = "fresh")]` databases are copied from
+fn get_template_dbname() -> String {
+    format!("{}_template", get_pg_dbname())
+}
+
+/// Copy the shared database while nothing is connected to it yet, so fresh databases can be
+/// copied from that whenever a test asks for one
+fn create_template_database() -> eyre::Result<()> {
+    let template = get_template_dbname();
+    dropdb(&template)?;
+    let mut client = connect("postgres")?;
+    query_wrapper(
+        Some(format!("CREATE DATABASE \"{template}\" TEMPLATE \"{}\";", get_pg_dbname())),
+        None,
+        |query, _| client.simple_query(query.unwrap().as_str()),
+    )
+    .wrap_err("There was an issue creating the template database for fresh test databases")?;
+
+    Ok(())
+}
+
+/// Create a database of its own for a test to run in
+fn create_test_database() -> eyre::Result<String> {
+    static NEXT_DATABASE: AtomicUsize = AtomicUsize::new(0);
+
+    let dbname = format!("{}_{}", get_pg_dbname(), NEXT_DATABASE.fetch_add(1, Ordering::Relaxed));
+    // it might be left over from a run that crashed
+    dropdb(&dbname)?;
+    let mut client = connect("postgres")?;
+    query_wrapper(
+        Some(format!("CREATE DATABASE \"{dbname}\" TEMPLATE \"{}\";", get_template_dbname())),
+        None,
+        |query, _| client.simple_query(query.unwrap().as_str()),
+    )
+    .wrap_err(format!("There was an issue creating the test database '{dbname}'"))?;
+
+    Ok(dbname)
+}
+
+/// Create the extension at an older `version`, then `ALTER EXTENSION ... UPDATE` it to the
+/// current one
+fn create_and_update_extension(version: &str) -> eyre::Result<()> {
+    let (mut client, _) = client()?;
+    let extension_name = get_extension_name()?;
+    let version_literal = version.replace('\'', "''");
+
+    for query in [
+        format!("CREATE EXTENSION {extension_name} VERSION '{version_literal}' CASCADE;"),
+        format!("ALTER EXTENSION {extension_name} UPDATE;"),
+    ] {
+        query_wrapper(Some(query), None, |query, _| client.simple_query(query.unwrap().as_str()))
+            .wrap_err(format!(
+                "There was an issue updating the extension '{extension_name}' from version '{version}' in Postgres: "
+            ))?;
+    }
+
+    Ok(())
+}
+
+/// Copy the SQL script of the version we're updating from next to the ones `cargo pgrx install`
+/// installed, so `CREATE EXTENSION ... VERSION` can find it
+fn install_upgrade_base_script(version: &str) -> eyre::Result<()> {
+    let filename = format!("{}--{version}.sql", get_extension_name()?);
+    let source = get_upgrade_scripts_path()?.join(&filename);
+    if !source.exists() {
+        return Err(eyre!(
+            "Can't test updating from version '{version}' without its SQL script at `{}`",
+            source.display()
+        ));
+    }
+    let dest = get_pg_config()?.extension_dir()?.join(&filename);
+
+    if requires_runas() {
+        // like `cargo pgrx install --sudo`, only "root" can be trusted to write there
+        let mut command = sudo_command("root");
+        command.arg("cp").arg(&source).arg(&dest);
+        let output = command.output().wrap_err("Failed to execute sudo command")?;
+        if !output.status.success() {
+            return Err(eyre!(
+                "Failed to copy `{}` to `{}`:\n{}",
+                source.display(),
+                dest.display(),
+                String::from_utf8_lossy(&output.stderr)
+            ));
+        }
+    } else {
+        std::fs::copy(&source, &dest).wrap_err_with(|| {
+            format!("Failed to copy `{}` to `{}`", source.display(), dest.display())
+        })?;
+    }
+
+    Ok(())
+}
+
+/// Describe every object belonging to the extension, keyed by its type and identity
+///
+/// The descriptions cover what decides how an object behaves, such as a function's signature and
+/// attributes or a type's layout, rather than its oid or the order objects were created in.
+fn extension_objects(
+    client: &mut postgres::Client,
+    extension_name: &str,
+) -> eyre::Result<BTreeMap<String, String>> {
+    const EXTENSION_OBJECTS: &str = r#"
+        SELECT obj.type || ' ' || obj.identity AS object,
+               coalesce(CASE d.classid
+                   WHEN 'pg_catalog.pg_proc'::regclass THEN (
+                       SELECT concat_ws(' ', pg_get_function_arguments(p.oid), pg_get_function_result(p.oid),
+                              p.prokind, p.provolatile, p.proisstrict, p.proparallel, p.prosecdef,
+                              p.proleakproof, p.procost, p.prorows, p.prosupport, p.prosrc, p.proconfig)
+                       FROM pg_catalog.pg_proc p WHERE p.oid = d.objid)
+                   WHEN 'pg_catalog.pg_type'::regclass THEN (
+                       SELECT concat_ws(' ', t.typlen, t.typbyval, t.typalign, t.typstorage, t.typinput,
+                              t.typoutput, t.typreceive, t.typsend, t.typmodin, t.typmodout,
+                              (SELECT string_agg(e.enumlabel, ', ' ORDER BY e.enumsortorder)
+                               FROM pg_catalog.pg_enum e WHERE e.enumtypid = t.oid))
+                       FROM pg_catalog.pg_type t WHERE t.oid = d.objid)
+                   WHEN 'pg_catalog.pg_operator'::regclass THEN (
+                       SELECT concat_ws(' ', o.oprcode, o.oprcom::regoper, o.oprnegate::regoper,
+                              o.oprrest, o.oprjoin, o.oprcanmerge, o.oprcanhash)
+                       FROM pg_catalog.pg_operator o WHERE o.oid = d.objid)
+                   WHEN 'pg_catalog.pg_cast'::regclass THEN (
+                       SELECT concat_ws(' ', c.castfunc::regprocedure, c.castcontext, c.castmethod)
+                       FROM pg_catalog.pg_cast c WHERE c.oid = d.objid)
+               END, '') AS definition
+        FROM pg_catalog.pg_depend d,
+             LATERAL pg_catalog.pg_identify_object(d.classid, d.objid, d.objsubid) obj
+        WHERE d.refclassid = 'pg_catalog.pg_extension'::regclass
+          AND d.refobjid = (SELECT oid FROM pg_catalog.pg_extension WHERE extname = $1)
+          AND d.deptype = 'e'
+    "#;
+
+    let rows = query_wrapper(
+        Some(EXTENSION_OBJECTS.to_string()),
+        Some(&[&extension_name]),
+        |query, query_params| client.query(&query.unwrap(), query_params.unwrap()),
+    )?;
+    Ok(rows.into_iter().map(|row| (row.get("object"), row.get("definition"))).collect())
+}
+
+/// Check the catalog `ALTER EXTENSION ... UPDATE` produced matches a fresh `CREATE EXTENSION`
+fn compare_with_fresh_install(version: &str) -> eyre::Result<()> {
+    let pg_config = get_pg_config()?;
+    let extension_name = get_extension_name()?;
+    let (mut client, _) = client()?;
+    let updated = extension_objects(&mut client, &extension_name)?;
+
+    let fresh_dbname = format!("{}_fresh", get_pg_dbname());
+    dropdb(&fresh_dbname)?;
+    createdb(&pg_config, &fresh_dbname, true, false, get_runas())?;
+    let fresh = {
+        let mut client = connect(&fresh_dbname)?;
+        query_wrapper(
+            Some(format!("CREATE EXTENSION {extension_name} CASCADE;")),
+            None,
+            |query, _| client.simple_query(query.unwrap().as_str()),
+        )
+        .wrap_err(format!(
+            "There was an issue creating the extension '{extension_name}' in Postgres: "
+        ))?;
+        extension_objects(&mut client, &extension_name)?
+    };
+    dropdb(&fresh_dbname)?;
+
+    let mut differences = Vec::new();
+    for (object, definition) in &fresh {
+        match updated.get(object) {
+            None => differences.push(format!("missing {object}")),
+            Some(updated) if updated != definition => differences.push(format!(
+                "different {object}\n      fresh:   {definition}\n      updated: {updated}"
+            )),
+            Some(_) => (),
+        }
+    }
+    for object in updated.keys().filter(|object| !fresh.contains_key(*object)) {
+        differences.push(format!("unexpected {object}"));
+    }
+
+    if !differences.is_empty() {
+        return Err(eyre!(
+            "Updating the extension '{extension_name}' from version '{version}' doesn't match a fresh install:\n{}",
+            differences.iter().map(|difference| format!("  - {difference}")).collect::<Vec<_>>().join("\n")
+        ));
+    }
+
+    Ok(())
+}
+
 fn get_extension_name() -> eyre::Result<String> {
     // We could replace this with the following if cargo adds the lib name on env var on tests/runs.
     // https://github.com/rust-lang/cargo/issues/11966
@@ -807,6 +1263,21 @@ fn get_pgdata_path() -> eyre::Result<PathBuf> {
     Ok(pgdata_base)
 }
 
+/// The version `cargo pgrx test --upgrade-from` asked to update the extension from, if any
+fn get_upgrade_from() -> Option<String> {
+    std::env::var("CARGO_PGRX_TEST_UPGRADE_FROM").ok().filter(|version| !version.is_empty())
+}
+
+/// The directory holding the SQL scripts of previous versions of the extension
+fn get_upgrade_scripts_path() -> eyre::Result<PathBuf> {
+    if let Some(path) = std::env::var_os("CARGO_PGRX_TEST_UPGRADE_SCRIPTS") {
+        return Ok(PathBuf::from(path));
+    }
+    let dir = std::env::var("CARGO_MANIFEST_DIR")
+        .map_err(|_| eyre!("CARGO_MANIFEST_DIR environment var is unset or invalid UTF-8"))?;
+    Ok(PathBuf::from(dir).join("sql").join("history"))
+}
+
 fn get_pid_file() -> eyre::Result<PathBuf> {
     let mut pgdata = get_pgdata_path()?;
     pgdata.push("postmaster.pid");
@@ -0,0 +1,527 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+//! Tests that drive several sessions through a schedule of steps, in the style of Postgres'
+//! own isolation tester
+//!
+//! ```rust,ignore
+//! use pgrx_tests::{IsolationSession, IsolationTest};
+//!
+//! IsolationTest::new()
+//!     .setup("CREATE TABLE accounts (id int PRIMARY KEY, balance int); INSERT INTO accounts VALUES (1, 100)")
+//!     .session(
+//!         IsolationSession::new("s1")
+//!             .step("s1_begin", "BEGIN")
+//!             .step("s1_update", "UPDATE accounts SET balance = balance - 10 WHERE id = 1")
+//!             .step("s1_commit", "COMMIT"),
+//!     )
+//!     .session(IsolationSession::new("s2").step(
+//!         "s2_update",
+//!         "UPDATE accounts SET balance = balance - 10 WHERE id = 1 RETURNING balance",
+//!     ))
+//!     .permutation(["s1_begin", "s1_update", "s2_update", "s1_commit"])
+//!     .run(crate::pg_test::postgresql_conf_options(), |schedule| {
+//!         assert!(schedule.step("s2_update").waited());
+//!         assert_eq!(schedule.step("s2_update").value(0, 0), Some("80"));
+//!     })
+//!     .unwrap();
+//! ```
+use super::{
+    connect, create_test_database, dropdb, initialize_test_framework, query_wrapper, SERVER_LOCK,
+};
+use eyre::{eyre, WrapErr};
+use postgres::error::DbError;
+use postgres::SimpleQueryMessage;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::PoisonError;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+/// How long a step may run before it's considered stuck, unless it's waiting on a lock
+const STEP_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How often a running step is checked on
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// A test running steps from several sessions, in a given order, against the test cluster
+///
+/// Every permutation runs in a database of its own, where the [`setup`][Self::setup] SQL runs
+/// first, followed by the setup of each session.  Then the steps run one at a time.  A step whose
+/// session waits on a lock, as seen in `pg_locks`, is left waiting while the next step runs,
+/// and once it completes the schedule says so.
+#[derive(Debug, Clone, Default)]
+#[must_use]
+pub struct IsolationTest {
+    setup: Vec<String>,
+    sessions: Vec<IsolationSession>,
+    permutations: Vec<Vec<String>>,
+}
+
+/// One of the sessions of an [`IsolationTest`], and the steps it can run
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct IsolationSession {
+    name: String,
+    setup: Vec<String>,
+    steps: Vec<(String, String)>,
+}
+
+/// The order an [`IsolationTest`]'s steps ran in, and how each of them turned out
+#[derive(Debug, Clone)]
+pub struct IsolationSchedule {
+    permutation: Vec<String>,
+    outcomes: Vec<StepOutcome>,
+    transcript: Vec<String>,
+}
+
+/// How a step of an [`IsolationTest`] turned out
+#[derive(Debug, Clone)]
+pub struct StepOutcome {
+    session: String,
+    step: String,
+    waited: bool,
+    result: Result<Vec<Vec<Option<String>>>, DbError>,
+}
+
+impl IsolationTest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// SQL run before the sessions connect, in each permutation
+    pub fn setup(mut self, sql: impl Into<String>) -> Self {
+        self.setup.push(sql.into());
+        self
+    }
+
+    pub fn session(mut self, session: IsolationSession) -> Self {
+        self.sessions.push(session);
+        self
+    }
+
+    /// The names of the steps to run, in order
+    ///
+    /// Without any permutations, every interleaving of the sessions' steps is run, each keeping
+    /// the order its session declared its steps in.
+    pub fn permutation<I, S>(mut self, steps: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.permutations.push(steps.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Run every permutation, passing its schedule to `check`
+    ///
+    /// If `check` panics, the schedule is printed before the panic carries on.
+    pub fn run<F>(&self, postgresql_conf: Vec<&'static str>, mut check: F) -> eyre::Result<()>
+    where
+        F: FnMut(&IsolationSchedule),
+    {
+        for permutation in &self.permutations {
+            for step in permutation {
+                self.find_step(step)?;
+            }
+        }
+        let permutations = if self.permutations.is_empty() {
+            interleavings(&self.sessions)
+        } else {
+            self.permutations.clone()
+        };
+
+        initialize_test_framework(postgresql_conf)?;
+        // like any other test, don't let a `#[pg_test(restart)]` pull the server out from under us
+        let _shared = SERVER_LOCK.read().unwrap_or_else(PoisonError::into_inner);
+
+        for permutation in permutations {
+            let dbname = create_test_database()?;
+            let schedule = self.run_permutation(&dbname, permutation);
+            dropdb(&dbname)?;
+            let schedule = schedule?;
+
+            if let Err(e) = panic::catch_unwind(AssertUnwindSafe(|| check(&schedule))) {
+                eprintln!("{schedule}");
+                panic::resume_unwind(e);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn find_step(&self, name: &str) -> eyre::Result<(usize, &str)> {
+        self.sessions
+            .iter()
+            .enumerate()
+            .find_map(|(index, session)| {
+                session
+                    .steps
+                    .iter()
+                    .find(|(step, _)| step == name)
+                    .map(|(_, sql)| (index, sql.as_str()))
+            })
+            .ok_or_else(|| eyre!("no session has a step named `{name}`"))
+    }
+
+    fn run_permutation(
+        &self,
+        dbname: &str,
+        permutation: Vec<String>,
+    ) -> eyre::Result<IsolationSchedule> {
+        let mut control = connect(dbname)?;
+        for sql in &self.setup {
+            query_wrapper(Some(sql.clone()), None, |query, _| {
+                control.simple_query(query.unwrap().as_str())
+            })
+            .wrap_err("There was an issue running the isolation test's setup")?;
+        }
+
+        let mut sessions = Vec::new();
+        for session in &self.sessions {
+            sessions.push(SessionRunner::start(dbname, session)?);
+        }
+
+        let mut driver = Driver {
+            control,
+            sessions,
+            outcomes: vec![None; permutation.len()],
+            transcript: Vec::new(),
+        };
+        let result = driver.run(self, &permutation);
+        let Driver { mut control, sessions, outcomes, mut transcript } = driver;
+
+        // whatever is still running isn't going to finish now
+        for session in &sessions {
+            if session.waiting.is_some() || result.is_err() {
+                control.execute("SELECT pg_catalog.pg_terminate_backend($1)", &[&session.pid])?;
+            }
+        }
+        for session in sessions {
+            session.stop();
+        }
+
+        if let Err(e) = result {
+            transcript.push(format!("{e}"));
+            return Err(eyre!(
+                "isolation test failed in permutation {}:\n{}",
+                permutation.join(" "),
+                transcript.join("\n")
+            ));
+        }
+
+        Ok(IsolationSchedule {
+            permutation,
+            outcomes: outcomes.into_iter().map(Option::unwrap).collect(),
+            transcript,
+        })
+    }
+}
+
+impl IsolationSession {
+    pub fn new(name: impl Into<String>) -> Self {
+        IsolationSession { name: name.into(), setup: Vec::new(), steps: Vec::new() }
+    }
+
+    /// SQL the session runs once it connects, before any steps
+    pub fn setup(mut self, sql: impl Into<String>) -> Self {
+        self.setup.push(sql.into());
+        self
+    }
+
+    /// A step the session can run, named so permutations can refer to it
+    pub fn step(mut self, name: impl Into<String>, sql: impl Into<String>) -> Self {
+        self.steps.push((name.into(), sql.into()));
+        self
+    }
+}
+
+impl IsolationSchedule {
+    /// The names of the steps, in the order they were started
+    pub fn permutation(&self) -> &[String] {
+        &self.permutation
+    }
+
+    /// How each step turned out, in the order they were started
+    pub fn outcomes(&self) -> &[StepOutcome] {
+        &self.outcomes
+    }
+
+    /// How the first run of the step named `name` turned out
+    ///
+    /// # Panics
+    ///
+    /// If the step wasn't part of this permutation.
+    pub fn step(&self, name: &str) -> &StepOutcome {
+        self.outcomes
+            .iter()
+            .find(|outcome| outcome.step == name)
+            .unwrap_or_else(|| panic!("step `{name}` wasn't run in this permutation"))
+    }
+}
+
+impl fmt::Display for IsolationSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "permutation {}", self.permutation.join(" "))?;
+        for line in &self.transcript {
+            writeln!(f, "{line}")?;
+        }
+        Ok(())
+    }
+}
+
+impl StepOutcome {
+    pub fn session(&self) -> &str {
+        &self.session
+    }
+
+    pub fn step(&self) -> &str {
+        &self.step
+    }
+
+    /// Did the step have to wait on a lock held by another session?
+    pub fn waited(&self) -> bool {
+        self.waited
+    }
+
+    /// The rows the step returned, as text, or none if it raised an error
+    pub fn rows(&self) -> &[Vec<Option<String>>] {
+        self.result.as_deref().unwrap_or_default()
+    }
+
+    /// A single value the step returned, as text
+    pub fn value(&self, row: usize, column: usize) -> Option<&str> {
+        self.rows().get(row)?.get(column)?.as_deref()
+    }
+
+    /// The error the step raised, if any
+    pub fn error(&self) -> Option<&DbError> {
+        self.result.as_ref().err()
+    }
+}
+
+/// Every interleaving of the sessions' steps that keeps each session's steps in order
+fn interleavings(sessions: &[IsolationSession]) -> Vec<Vec<String>> {
+    fn interleave(
+        sessions: &[IsolationSession],
+        next: &mut [usize],
+        permutation: &mut Vec<String>,
+        permutations: &mut Vec<Vec<String>>,
+    ) {
+        let mut done = true;
+        for (index, session) in sessions.iter().enumerate() {
+            if let Some((step, _)) = session.steps.get(next[index]) {
+                done = false;
+                next[index] += 1;
+                permutation.push(step.clone());
+                interleave(sessions, next, permutation, permutations);
+                permutation.pop();
+                next[index] -= 1;
+            }
+        }
+        if done {
+            permutations.push(permutation.clone());
+        }
+    }
+
+    let mut permutations = Vec::new();
+    interleave(sessions, &mut vec![0; sessions.len()], &mut Vec::new(), &mut permutations);
+    permutations
+}
+
+type StepResult = Result<Vec<SimpleQueryMessage>, postgres::Error>;
+
+/// A session's connection, running steps on a thread of its own so others can run meanwhile
+struct SessionRunner {
+    name: String,
+    pid: i32,
+    steps: Sender<String>,
+    results: Receiver<StepResult>,
+    thread: JoinHandle<()>,
+    /// The position in the permutation of the step it's waiting on
+    waiting: Option<usize>,
+}
+
+impl SessionRunner {
+    fn start(dbname: &str, session: &IsolationSession) -> eyre::Result<Self> {
+        let mut client = connect(dbname)?;
+        let pid = client.query_one("SELECT pg_catalog.pg_backend_pid()", &[])?.get(0);
+        for sql in &session.setup {
+            query_wrapper(Some(sql.clone()), None, |query, _| {
+                client.simple_query(query.unwrap().as_str())
+            })
+            .wrap_err(format!(
+                "There was an issue running the setup of session {}",
+                session.name
+            ))?;
+        }
+
+        let (steps, step_receiver) = mpsc::channel::<String>();
+        let (result_sender, results) = mpsc::channel();
+        let thread = std::thread::spawn(move || {
+            for sql in step_receiver {
+                if result_sender.send(client.simple_query(&sql)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(SessionRunner { name: session.name.clone(), pid, steps, results, thread, waiting: None })
+    }
+
+    fn stop(self) {
+        drop(self.steps);
+        // it only panics if the connection does
+        let _ = self.thread.join();
+    }
+}
+
+enum Progress {
+    Completed(StepResult),
+    Waiting,
+}
+
+struct Driver {
+    control: postgres::Client,
+    sessions: Vec<SessionRunner>,
+    outcomes: Vec<Option<StepOutcome>>,
+    transcript: Vec<String>,
+}
+
+impl Driver {
+    fn run(&mut self, test: &IsolationTest, permutation: &[String]) -> eyre::Result<()> {
+        for (position, name) in permutation.iter().enumerate() {
+            let (index, sql) = test.find_step(name)?;
+            if self.sessions[index].waiting.is_some() {
+                return Err(eyre!(
+                    "step {name} can't start while session {} is still waiting",
+                    self.sessions[index].name
+                ));
+            }
+
+            self.sessions[index]
+                .steps
+                .send(sql.to_string())
+                .map_err(|_| eyre!("session {} has gone away", self.sessions[index].name))?;
+            match self.progress(index)? {
+                Progress::Completed(result) => {
+                    self.transcript.push(format!("step {name}: {sql}"));
+                    self.complete(index, position, name, false, result)?;
+                }
+                Progress::Waiting => {
+                    self.transcript.push(format!("step {name}: {sql} <waiting ...>"));
+                    self.sessions[index].waiting = Some(position);
+                }
+            }
+
+            // the step might have let a waiting one finish, in turn letting others finish
+            let mut finished_any = true;
+            while finished_any {
+                finished_any = false;
+                for index in 0..self.sessions.len() {
+                    let Some(position) = self.sessions[index].waiting else { continue };
+                    if let Progress::Completed(result) = self.progress(index)? {
+                        self.sessions[index].waiting = None;
+                        let name = &permutation[position];
+                        self.transcript.push(format!("step {name}: <... completed>"));
+                        self.complete(index, position, name, true, result)?;
+                        finished_any = true;
+                    }
+                }
+            }
+        }
+
+        match self.sessions.iter().find(|session| session.waiting.is_some()) {
+            Some(session) => {
+                Err(eyre!("the permutation ended with session {} still waiting", session.name))
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Wait for the session's step to complete, or to wait on a lock
+    fn progress(&mut self, index: usize) -> eyre::Result<Progress> {
+        let started = Instant::now();
+        loop {
+            match self.sessions[index].results.recv_timeout(POLL_INTERVAL) {
+                Ok(result) => return Ok(Progress::Completed(result)),
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err(eyre!("session {} has gone away", self.sessions[index].name))
+                }
+                Err(RecvTimeoutError::Timeout) => (),
+            }
+
+            let waiting: bool = self
+                .control
+                .query_one(
+                    "SELECT EXISTS (SELECT 1 FROM pg_catalog.pg_locks WHERE pid = $1 AND NOT granted)",
+                    &[&self.sessions[index].pid],
+                )?
+                .get(0);
+            if waiting {
+                return Ok(Progress::Waiting);
+            }
+            if started.elapsed() > STEP_TIMEOUT {
+                return Err(eyre!(
+                    "session {} neither finished nor waited on a lock within {STEP_TIMEOUT:?}",
+                    self.sessions[index].name
+                ));
+            }
+        }
+    }
+
+    fn complete(
+        &mut self,
+        index: usize,
+        position: usize,
+        name: &str,
+        waited: bool,
+        result: StepResult,
+    ) -> eyre::Result<()> {
+        let result = match result {
+            Ok(messages) => Ok(messages
+                .into_iter()
+                .filter_map(|message| match message {
+                    SimpleQueryMessage::Row(row) => {
+                        Some((0..row.len()).map(|i| row.get(i).map(String::from)).collect())
+                    }
+                    _ => None,
+                })
+                .collect::<Vec<Vec<_>>>()),
+            Err(e) => match e.as_db_error() {
+                Some(dberror) => Err(dberror.clone()),
+                None => return Err(e).wrap_err(format!("step {name} failed")),
+            },
+        };
+
+        match &result {
+            Ok(rows) => {
+                for row in rows {
+                    let values = row
+                        .iter()
+                        .map(|value| value.as_deref().unwrap_or("<NULL>"))
+                        .collect::<Vec<_>>();
+                    self.transcript.push(format!("  {}", values.join(" | ")));
+                }
+            }
+            Err(dberror) => {
+                self.transcript.push(format!("{}:  {}", dberror.severity(), dberror.message()))
+            }
+        }
+
+        self.outcomes[position] = Some(StepOutcome {
+            session: self.sessions[index].name.clone(),
+            step: name.to_string(),
+            waited,
+            result,
+        });
+        Ok(())
+    }
+}
@@ -0,0 +1,183 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+#[cfg(any(test, feature = "pg_test"))]
+#[pgrx::pg_schema]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgrx_tests;
+    use pgrx::custom_scan::{add_path, CustomPathEstimate, CustomScan, Explain, ScanSlot};
+    use pgrx::hooks::{register_hooks, HookPoint, HookRegistration, HookResult, PgHooks};
+    use pgrx::prelude::*;
+    use pgrx::PgRelation;
+    use std::cell::Cell;
+    use std::ffi::CStr;
+
+    thread_local! {
+        // the relation whose scans `CounterHook` offers a `Counter` path for
+        static COUNTED_RELATION: Cell<pg_sys::Oid> = const { Cell::new(pg_sys::InvalidOid) };
+    }
+
+    /// Returns the numbers from one up to its private count, whatever the relation holds
+    pub struct Counter;
+
+    pub struct CounterState {
+        next: i32,
+        last: i32,
+    }
+
+    impl CustomScan for Counter {
+        const NAME: &'static CStr = c"Counter";
+        type Private = i32;
+        type State = CounterState;
+
+        fn begin_scan(last: i32, _relation: Option<&PgRelation>, _eflags: i32) -> CounterState {
+            CounterState { next: 1, last }
+        }
+
+        fn next(state: &mut CounterState, slot: &mut ScanSlot) -> bool {
+            if state.next > state.last {
+                return false;
+            }
+            slot.store_row([state.next.into_datum()]);
+            state.next += 1;
+            true
+        }
+
+        fn rescan(state: &mut CounterState) {
+            state.next = 1;
+        }
+
+        fn explain(state: &CounterState, explain: &mut Explain) {
+            explain.property_integer("Rows To Produce", None, state.last.into());
+        }
+    }
+
+    /// Offers a `Counter` path for scans of `COUNTED_RELATION`, and records planning joins
+    struct CounterHook {
+        joins_planned: usize,
+    }
+
+    impl PgHooks for CounterHook {
+        fn set_rel_pathlist(
+            &mut self,
+            root: PgBox<pg_sys::PlannerInfo>,
+            mut rel: PgBox<pg_sys::RelOptInfo>,
+            rti: pg_sys::Index,
+            rte: PgBox<pg_sys::RangeTblEntry>,
+            prev_hook: fn(
+                PgBox<pg_sys::PlannerInfo>,
+                PgBox<pg_sys::RelOptInfo>,
+                pg_sys::Index,
+                PgBox<pg_sys::RangeTblEntry>,
+            ) -> HookResult<()>,
+        ) -> HookResult<()> {
+            if rte.relid == COUNTED_RELATION.get() {
+                // free, so it wins over every other path
+                let estimate = CustomPathEstimate { rows: 3.0, startup_cost: 0.0, total_cost: 0.0 };
+                add_path::<Counter>(&mut rel, 3, estimate);
+            }
+            prev_hook(root, rel, rti, rte)
+        }
+
+        fn set_join_pathlist(
+            &mut self,
+            root: PgBox<pg_sys::PlannerInfo>,
+            joinrel: PgBox<pg_sys::RelOptInfo>,
+            outerrel: PgBox<pg_sys::RelOptInfo>,
+            innerrel: PgBox<pg_sys::RelOptInfo>,
+            jointype: pg_sys::JoinType::Type,
+            extra: PgBox<pg_sys::JoinPathExtraData>,
+            prev_hook: fn(
+                PgBox<pg_sys::PlannerInfo>,
+                PgBox<pg_sys::RelOptInfo>,
+                PgBox<pg_sys::RelOptInfo>,
+                PgBox<pg_sys::RelOptInfo>,
+                pg_sys::JoinType::Type,
+                PgBox<pg_sys::JoinPathExtraData>,
+            ) -> HookResult<()>,
+        ) -> HookResult<()> {
+            self.joins_planned += 1;
+            prev_hook(root, joinrel, outerrel, innerrel, jointype, extra)
+        }
+    }
+
+    static mut COUNTER_HOOK: CounterHook = CounterHook { joins_planned: 0 };
+
+    /// Plan scans of `relation` as `Counter` scans while running `f`
+    fn with_counter<R>(relation: &str, f: impl FnOnce() -> R) -> R {
+        struct Unregister(HookRegistration);
+
+        impl Drop for Unregister {
+            fn drop(&mut self) {
+                unsafe { self.0.unregister() }
+                COUNTED_RELATION.set(pg_sys::InvalidOid);
+            }
+        }
+
+        Spi::run(&format!("CREATE TABLE {relation} (n int4)")).unwrap();
+        let relid = Spi::get_one::<pg_sys::Oid>(&format!("SELECT '{relation}'::regclass::oid"));
+        COUNTED_RELATION.set(relid.unwrap().unwrap());
+        let _unregister = Unregister(unsafe {
+            register_hooks(
+                &mut *std::ptr::addr_of_mut!(COUNTER_HOOK),
+                &[HookPoint::SetRelPathlist, HookPoint::SetJoinPathlist],
+                0,
+            )
+        });
+        f()
+    }
+
+    #[pg_test]
+    fn test_custom_scan_returns_rows() -> Result<(), spi::Error> {
+        with_counter("custom_scan_rows_test", || {
+            assert_eq!(Spi::get_one::<i64>("SELECT sum(n) FROM custom_scan_rows_test")?, Some(6));
+            Ok(())
+        })
+    }
+
+    #[pg_test]
+    fn test_custom_scan_applies_quals() -> Result<(), spi::Error> {
+        with_counter("custom_scan_quals_test", || {
+            assert_eq!(
+                Spi::get_one::<i64>("SELECT count(*) FROM custom_scan_quals_test WHERE n > 1")?,
+                Some(2)
+            );
+            Ok(())
+        })
+    }
+
+    #[pg_test]
+    fn test_custom_scan_in_join() -> Result<(), spi::Error> {
+        with_counter("custom_scan_join_test", || {
+            let joins_planned = unsafe { COUNTER_HOOK.joins_planned };
+            assert_eq!(
+                Spi::get_one::<i64>(
+                    "SELECT count(*) FROM custom_scan_join_test a \
+                     JOIN custom_scan_join_test b ON a.n = b.n"
+                )?,
+                Some(3)
+            );
+            assert!(unsafe { COUNTER_HOOK.joins_planned } > joins_planned);
+            Ok(())
+        })
+    }
+
+    #[pg_test]
+    fn test_custom_scan_explain() -> Result<(), spi::Error> {
+        with_counter("custom_scan_explain_test", || {
+            let explain = Spi::explain("SELECT * FROM custom_scan_explain_test")?;
+            let plan = &explain.0[0]["Plan"];
+            assert_eq!(plan["Node Type"], "Custom Scan");
+            assert_eq!(plan["Custom Plan Provider"], "Counter");
+            assert_eq!(plan["Rows To Produce"], 3);
+            Ok(())
+        })
+    }
+}
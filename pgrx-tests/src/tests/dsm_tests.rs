@@ -0,0 +1,142 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+#[cfg(any(test, feature = "pg_test"))]
+#[pgrx::pg_schema]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgrx_tests;
+
+    use pgrx::dsm::{DsaArea, DsaPointer, DsmSegment};
+    use pgrx::prelude::*;
+    use pgrx::shmem_hash::PgDshashMap;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    #[pg_test]
+    fn test_dsm_segment() {
+        let segment = DsmSegment::create(1024);
+        assert!(segment.len() >= 1024);
+        assert_ne!(segment.handle(), 0);
+
+        unsafe {
+            segment.as_ptr().write_bytes(0x7f, 1024);
+            assert_eq!(*segment.as_ptr().add(1023), 0x7f);
+        }
+    }
+
+    #[pg_test]
+    fn test_dsm_segment_on_detach() {
+        let detached = Rc::new(Cell::new(false));
+        let segment = DsmSegment::create(64);
+        segment.on_detach({
+            let detached = detached.clone();
+            move || detached.set(true)
+        });
+
+        assert!(!detached.get());
+        drop(segment);
+        assert!(detached.get());
+    }
+
+    #[pg_test]
+    fn test_dsa_allocate() {
+        let area = DsaArea::create(c"pgrx_tests_dsa");
+        let value: DsaPointer<u64> = area.allocate(42);
+        assert!(value.is_valid());
+        unsafe {
+            assert_eq!(*area.get(value), 42);
+            *area.get_mut(value) += 1;
+            assert_eq!(*area.get(value), 43);
+            area.free(value);
+        }
+    }
+
+    #[pg_test]
+    fn test_dsa_allocate_slice() {
+        let area = DsaArea::create(c"pgrx_tests_dsa");
+        let slice = area.allocate_slice(&[1i32, 2, 3]);
+        assert_eq!(slice.len(), 3);
+        assert_eq!(unsafe { area.get_slice(slice) }, &[1, 2, 3]);
+
+        let zeroed = unsafe { area.allocate_zeroed_slice::<i64>(1000) };
+        assert!(unsafe { area.get_slice(zeroed) }.iter().all(|v| *v == 0));
+
+        let empty = area.allocate_slice::<i32>(&[]);
+        assert!(!empty.as_ptr().is_valid());
+        assert!(unsafe { area.get_slice(empty) }.is_empty());
+        unsafe { area.free_slice(empty) };
+
+        let units = area.allocate_slice(&[(), ()]);
+        assert_eq!(unsafe { area.get_slice(units) }.len(), 2);
+    }
+
+    #[pg_test(error = "invalid DsaPointer")]
+    fn test_dsa_invalid_pointer() {
+        let area = DsaArea::create(c"pgrx_tests_dsa");
+        unsafe { area.get(DsaPointer::<u64>::default()) };
+    }
+
+    #[pg_test(error = "cannot allocate a zero-sized type in a DsaArea")]
+    fn test_dsa_allocate_zero_sized() {
+        let area = DsaArea::create(c"pgrx_tests_dsa");
+        area.allocate(());
+    }
+
+    #[pg_test]
+    fn test_dshash_map() {
+        let area = DsaArea::create(c"pgrx_tests_dsa");
+        let map = PgDshashMap::<i32, (i64, f64)>::create(&area, c"pgrx_tests_dshash");
+        assert_eq!(map.insert(1, (10, 1.0)), None);
+        assert_eq!(map.insert(1, (11, 1.5)), Some((10, 1.0)));
+        assert!(map.get(&2).is_none());
+
+        map.get_mut(&1).unwrap().0 += 1;
+        map.get_or_insert_with(2, || (20, 2.0)).0 += 1;
+
+        let attached = PgDshashMap::<i32, (i64, f64)>::attach(&area, map.handle());
+        assert_eq!(attached.get(&1).as_deref(), Some(&(12, 1.5)));
+        assert_eq!(attached.get(&2).as_deref(), Some(&(21, 2.0)));
+        drop(attached);
+
+        assert_eq!(map.remove(&1), Some((12, 1.5)));
+        assert_eq!(map.remove(&1), None);
+        unsafe { map.destroy() };
+    }
+
+    #[pg_test]
+    fn test_dshash_map_insert_panics() {
+        let area = DsaArea::create(c"pgrx_tests_dsa");
+        let map = PgDshashMap::<i32, i32>::create(&area, c"pgrx_tests_dshash");
+        let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            drop(map.get_or_insert_with(1, || panic!("no value")))
+        }));
+        assert!(res.is_err());
+        assert!(map.get(&1).is_none());
+        assert_eq!(*map.get_or_insert_with(1, || 10), 10);
+    }
+
+    #[cfg(any(feature = "pg15", feature = "pg16", feature = "pg17"))]
+    #[pg_test]
+    fn test_dshash_map_iteration() {
+        let area = DsaArea::create(c"pgrx_tests_dsa");
+        let map = PgDshashMap::<i32, i32>::create(&area, c"pgrx_tests_dshash");
+        for i in 0..1000 {
+            map.insert(i, i * 2);
+        }
+
+        map.retain(|k, _| k % 2 == 0);
+        let mut count = 0;
+        map.for_each(|k, v| {
+            assert_eq!(*v, k * 2);
+            count += 1;
+        });
+        assert_eq!(count, 500);
+    }
+}
@@ -24,13 +24,19 @@ fn take_foo_enum(value: Foo) -> Foo {
     Foo::Three
 }
 
+#[derive(PostgresEnum, Copy, Clone, PartialEq, Debug)]
+pub enum Shade {
+    Light,
+    Dark,
+}
+
 #[cfg(any(test, feature = "pg_test"))]
 #[pgrx::pg_schema]
 mod tests {
     #[allow(unused_imports)]
     use crate as pgrx_tests;
 
-    use super::Foo;
+    use super::{Foo, Shade};
     use pgrx::prelude::*;
 
     #[test]
@@ -41,4 +47,26 @@ mod tests {
         let result = Spi::get_one::<Foo>("SELECT take_foo_enum('One');");
         assert_eq!(Ok(Some(Foo::Three)), result);
     }
+
+    #[pg_test]
+    fn test_enum_roundtrip() -> Result<(), spi::Error> {
+        // the second pass is answered from the enum's cache
+        for _ in 0..2 {
+            for shade in [Shade::Light, Shade::Dark] {
+                let label = Spi::get_one_with_args::<String>("SELECT $1::text", &[shade.into()])?;
+                assert_eq!(label, Some(format!("{shade:?}")));
+                let result = Spi::get_one_with_args::<Shade>("SELECT $1", &[shade.into()])?;
+                assert_eq!(result, Some(shade));
+            }
+        }
+        Ok(())
+    }
+
+    #[pg_test(error = "invalid enum value: Dim")]
+    fn test_enum_cache_sees_renamed_value() -> Result<(), spi::Error> {
+        assert_eq!(Spi::get_one::<Shade>("SELECT 'Dark'::shade")?, Some(Shade::Dark));
+        Spi::run("ALTER TYPE shade RENAME VALUE 'Dark' TO 'Dim'")?;
+        Spi::get_one::<Shade>("SELECT 'Dim'::shade")?;
+        Ok(())
+    }
 }
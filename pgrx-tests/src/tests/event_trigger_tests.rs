@@ -0,0 +1,158 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+#[cfg(any(test, feature = "pg_test"))]
+#[pgrx::pg_schema]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgrx_tests;
+    use pgrx::prelude::*;
+    use std::cell::RefCell;
+
+    thread_local! {
+        static EVENTS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    }
+
+    fn record(event: String) {
+        EVENTS.with_borrow_mut(|events| events.push(event));
+    }
+
+    fn take_events() -> Vec<String> {
+        EVENTS.take()
+    }
+
+    #[derive(thiserror::Error, Debug)]
+    enum EventTriggerError {
+        #[error("PgTrigger error: {0}")]
+        PgTrigger(#[from] PgTriggerError),
+        #[error("SPI error: {0}")]
+        Spi(#[from] spi::Error),
+    }
+
+    #[pg_event_trigger(
+        event = ddl_command_end,
+        tags = ["COMMENT"],
+        name = "pgrx_tests_comment_trigger"
+    )]
+    fn record_comments(trigger: &PgEventTrigger) -> Result<(), EventTriggerError> {
+        for command in trigger.ddl_commands()? {
+            record(format!("{} {} {}", trigger.event()?, command.command_tag, command.object_type));
+        }
+        Ok(())
+    }
+
+    #[pg_event_trigger]
+    fn record_event_trigger(trigger: &PgEventTrigger) -> Result<(), EventTriggerError> {
+        let event = trigger.event()?;
+        match event {
+            PgEventTriggerEvent::DdlCommandStart => {
+                // SAFETY:  Postgres gives event triggers a valid parse tree
+                let is_create = unsafe {
+                    pgrx::is_a(trigger.parse_tree().as_ptr(), pg_sys::NodeTag::T_CreateStmt)
+                };
+                record(format!("{event} {} {is_create}", trigger.tag()?));
+            }
+            PgEventTriggerEvent::DdlCommandEnd => {
+                for command in trigger.ddl_commands()? {
+                    record(format!("{event} {} {}", command.command_tag, command.object_type));
+                }
+            }
+            PgEventTriggerEvent::SqlDrop => {
+                for object in
+                    trigger.dropped_objects()?.into_iter().filter(|object| object.original)
+                {
+                    let name = object.object_name.unwrap_or_default();
+                    record(format!("{event} {} {name}", object.object_type));
+                }
+            }
+            PgEventTriggerEvent::TableRewrite => {
+                let oid = trigger.table_rewrite_oid();
+                let reason = trigger.table_rewrite_reason();
+                record(format!("{event} {} {reason}", oid.as_u32()));
+            }
+        }
+        Ok(())
+    }
+
+    #[pg_test]
+    fn test_event_trigger_created() -> Result<(), spi::Error> {
+        let definition = Spi::get_one::<String>(
+            "SELECT evtevent || ' ' || array_to_string(evttags, ',') \
+             FROM pg_event_trigger WHERE evtname = 'pgrx_tests_comment_trigger'",
+        )?;
+        assert_eq!(definition.as_deref(), Some("ddl_command_end COMMENT"));
+
+        take_events();
+        Spi::run("CREATE TABLE commented (id int); COMMENT ON TABLE commented IS 'hello'")?;
+        assert_eq!(take_events(), vec!["ddl_command_end COMMENT table"]);
+        Ok(())
+    }
+
+    #[pg_test]
+    fn test_ddl_command_start() -> Result<(), spi::Error> {
+        Spi::run(
+            "CREATE EVENT TRIGGER record_start ON ddl_command_start \
+             WHEN TAG IN ('CREATE TABLE', 'CREATE VIEW') \
+             EXECUTE FUNCTION tests.record_event_trigger()",
+        )?;
+        take_events();
+        Spi::run("CREATE TABLE started (id int); CREATE VIEW started_view AS SELECT 1")?;
+        assert_eq!(
+            take_events(),
+            vec!["ddl_command_start CREATE TABLE true", "ddl_command_start CREATE VIEW false"]
+        );
+        Ok(())
+    }
+
+    #[pg_test]
+    fn test_ddl_command_end() -> Result<(), spi::Error> {
+        Spi::run(
+            "CREATE EVENT TRIGGER record_end ON ddl_command_end \
+             WHEN TAG IN ('CREATE TABLE', 'CREATE INDEX') \
+             EXECUTE FUNCTION tests.record_event_trigger()",
+        )?;
+        take_events();
+        Spi::run("CREATE TABLE ended (id int); CREATE INDEX ended_idx ON ended (id)")?;
+        assert_eq!(
+            take_events(),
+            vec!["ddl_command_end CREATE TABLE table", "ddl_command_end CREATE INDEX index"]
+        );
+        Ok(())
+    }
+
+    #[pg_test]
+    fn test_sql_drop() -> Result<(), spi::Error> {
+        Spi::run(
+            "CREATE TABLE dropped (id int); \
+             CREATE EVENT TRIGGER record_drop ON sql_drop \
+             EXECUTE FUNCTION tests.record_event_trigger()",
+        )?;
+        take_events();
+        Spi::run("DROP TABLE dropped")?;
+        assert_eq!(take_events(), vec!["sql_drop table dropped"]);
+        Ok(())
+    }
+
+    #[pg_test]
+    fn test_table_rewrite() -> Result<(), spi::Error> {
+        Spi::run(
+            "CREATE TABLE rewritten (id int); \
+             CREATE EVENT TRIGGER record_rewrite ON table_rewrite \
+             EXECUTE FUNCTION tests.record_event_trigger()",
+        )?;
+        let oid = Spi::get_one::<pg_sys::Oid>("SELECT 'rewritten'::regclass::oid")?.unwrap();
+        take_events();
+        Spi::run("ALTER TABLE rewritten ALTER COLUMN id TYPE bigint")?;
+        assert_eq!(
+            take_events(),
+            vec![format!("table_rewrite {} {}", oid.as_u32(), pg_sys::AT_REWRITE_COLUMN_REWRITE)]
+        );
+        Ok(())
+    }
+}
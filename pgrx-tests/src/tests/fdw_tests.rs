@@ -0,0 +1,238 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+#[cfg(any(test, feature = "pg_test"))]
+#[pgrx::pg_schema]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgrx_tests;
+    use pgrx::prelude::*;
+    use pgrx::{AnyElement, PgRelation};
+    use serde::Deserialize;
+    use std::cell::RefCell;
+    use std::collections::{BTreeMap, HashMap};
+
+    #[derive(Deserialize)]
+    pub struct SeriesOptions {
+        rows: i32,
+        #[serde(default)]
+        label: Option<String>,
+    }
+
+    /// Produces `rows` rows numbered from one
+    pub struct Series;
+
+    pub struct SeriesScan {
+        current: i32,
+        rows: i32,
+        label: String,
+    }
+
+    #[pg_fdw]
+    impl ForeignDataWrapper for Series {
+        type Options = SeriesOptions;
+        type ScanState = SeriesScan;
+        type Row = (i32, String);
+
+        fn begin_scan(options: SeriesOptions, _relation: &PgRelation) -> SeriesScan {
+            let label = options.label.unwrap_or_else(|| "row".into());
+            SeriesScan { current: 0, rows: options.rows, label }
+        }
+
+        fn iterate_scan(state: &mut SeriesScan) -> Option<(i32, String)> {
+            if state.current >= state.rows {
+                return None;
+            }
+            state.current += 1;
+            Some((state.current, format!("{} {}", state.label, state.current)))
+        }
+    }
+
+    thread_local! {
+        // the contents of every `key_value` foreign table, keyed by the table's oid
+        static ENTRIES: RefCell<HashMap<pg_sys::Oid, BTreeMap<i32, String>>> =
+            RefCell::new(HashMap::new());
+    }
+
+    #[derive(Deserialize)]
+    pub struct KeyValueOptions {}
+
+    /// A writable foreign table kept in backend memory
+    pub struct KeyValue;
+
+    #[pg_fdw]
+    impl ForeignDataWrapper for KeyValue {
+        type Options = KeyValueOptions;
+        type ScanState = std::vec::IntoIter<(i32, String)>;
+        type Row = (i32, String);
+        type ModifyState = pg_sys::Oid;
+
+        const SUPPORTS_INSERT: bool = true;
+        const SUPPORTS_UPDATE: bool = true;
+        const SUPPORTS_DELETE: bool = true;
+        const ROW_ID_COLUMN: Option<&'static str> = Some("key");
+
+        fn begin_scan(_options: KeyValueOptions, relation: &PgRelation) -> Self::ScanState {
+            ENTRIES.with(|all| {
+                let all = all.borrow();
+                let entries = all.get(&relation.oid()).into_iter().flatten();
+                entries.map(|(k, v)| (*k, v.clone())).collect::<Vec<_>>().into_iter()
+            })
+        }
+
+        fn iterate_scan(state: &mut Self::ScanState) -> Option<(i32, String)> {
+            state.next()
+        }
+
+        fn begin_modify(_options: KeyValueOptions, relation: &PgRelation) -> pg_sys::Oid {
+            relation.oid()
+        }
+
+        fn insert(table: &mut pg_sys::Oid, row: &PgHeapTuple<'_, AllocatedByPostgres>) {
+            let key = row.get_by_name::<i32>("key").unwrap().unwrap();
+            let value = row.get_by_name::<String>("value").unwrap().unwrap_or_default();
+            ENTRIES.with(|all| all.borrow_mut().entry(*table).or_default().insert(key, value));
+        }
+
+        fn update(
+            table: &mut pg_sys::Oid,
+            rowid: AnyElement,
+            row: &PgHeapTuple<'_, AllocatedByPostgres>,
+        ) {
+            let old_key = unsafe { AnyElement::into::<i32>(&rowid) }.unwrap();
+            ENTRIES.with(|all| all.borrow_mut().entry(*table).or_default().remove(&old_key));
+            Self::insert(table, row);
+        }
+
+        fn delete(table: &mut pg_sys::Oid, rowid: AnyElement) {
+            let key = unsafe { AnyElement::into::<i32>(&rowid) }.unwrap();
+            ENTRIES.with(|all| all.borrow_mut().entry(*table).or_default().remove(&key));
+        }
+    }
+
+    extension_sql!(
+        r#"
+        CREATE SERVER series_server FOREIGN DATA WRAPPER series;
+        CREATE SERVER key_value_server FOREIGN DATA WRAPPER key_value;
+        "#,
+        name = "fdw_test_servers",
+        requires = [Series, KeyValue]
+    );
+
+    #[pg_test]
+    fn test_fdw_is_registered() -> Result<(), spi::Error> {
+        let validator = Spi::get_one::<String>(
+            "SELECT fdwvalidator::regproc::text FROM pg_foreign_data_wrapper WHERE fdwname = 'series'",
+        )?;
+        assert!(validator.unwrap().ends_with("series_validator"));
+        Ok(())
+    }
+
+    #[pg_test]
+    fn test_fdw_scan() -> Result<(), spi::Error> {
+        Spi::run(
+            "CREATE FOREIGN TABLE series_test (n int4, label text)
+                SERVER series_server OPTIONS (rows '10')",
+        )?;
+        assert_eq!(Spi::get_one::<i64>("SELECT sum(n) FROM series_test")?, Some(55));
+        assert_eq!(Spi::get_one::<i64>("SELECT count(*) FROM series_test WHERE n > 7")?, Some(3));
+        assert_eq!(
+            Spi::get_one::<String>("SELECT label FROM series_test WHERE n = 2")?,
+            Some("row 2".into())
+        );
+        Ok(())
+    }
+
+    #[pg_test]
+    fn test_fdw_table_options_override_server_options() -> Result<(), spi::Error> {
+        Spi::run("ALTER SERVER series_server OPTIONS (rows '3', label 'server')")?;
+        Spi::run(
+            "CREATE FOREIGN TABLE series_override_test (n int4, label text)
+                SERVER series_server OPTIONS (label 'table')",
+        )?;
+        assert_eq!(
+            Spi::get_one::<String>("SELECT string_agg(label, ',') FROM series_override_test")?,
+            Some("table 1,table 2,table 3".into())
+        );
+        Ok(())
+    }
+
+    #[pg_test]
+    fn test_fdw_skips_dropped_columns() -> Result<(), spi::Error> {
+        Spi::run(
+            "CREATE FOREIGN TABLE series_dropped_test (n int4, gone int8, label text)
+                SERVER series_server OPTIONS (rows '2')",
+        )?;
+        Spi::run("ALTER FOREIGN TABLE series_dropped_test DROP COLUMN gone")?;
+        assert_eq!(
+            Spi::get_one::<String>("SELECT label FROM series_dropped_test WHERE n = 2")?,
+            Some("row 2".into())
+        );
+        Ok(())
+    }
+
+    #[pg_test(error = "invalid option \"bogus\"")]
+    fn test_fdw_rejects_unknown_option() -> Result<(), spi::Error> {
+        Spi::run(
+            "CREATE FOREIGN TABLE series_bogus_test (n int4, label text)
+                SERVER series_server OPTIONS (rows '1', bogus 'yes')",
+        )
+    }
+
+    #[pg_test(error = "invalid value for option \"rows\": invalid digit found in string")]
+    fn test_fdw_rejects_invalid_option_value() -> Result<(), spi::Error> {
+        Spi::run(
+            "CREATE FOREIGN TABLE series_invalid_test (n int4, label text)
+                SERVER series_server OPTIONS (rows 'ten')",
+        )
+    }
+
+    #[pg_test(error = "missing required option \"rows\"")]
+    fn test_fdw_requires_option_at_scan() -> Result<(), spi::Error> {
+        Spi::run(
+            "CREATE FOREIGN TABLE series_missing_test (n int4, label text) SERVER series_server",
+        )?;
+        Spi::run("SELECT * FROM series_missing_test")
+    }
+
+    #[pg_test(error = "column \"n\" is of type bigint, but the row provided a `i32`")]
+    fn test_fdw_rejects_mismatched_row() -> Result<(), spi::Error> {
+        Spi::run(
+            "CREATE FOREIGN TABLE series_mismatch_test (n int8, label text)
+                SERVER series_server OPTIONS (rows '1')",
+        )?;
+        Spi::run("SELECT * FROM series_mismatch_test")
+    }
+
+    #[pg_test]
+    fn test_fdw_modify() -> Result<(), spi::Error> {
+        Spi::run(
+            "CREATE FOREIGN TABLE key_value_test (key int4, value text) SERVER key_value_server",
+        )?;
+        Spi::run("INSERT INTO key_value_test VALUES (1, 'one'), (2, 'two'), (3, 'three')")?;
+        Spi::run("UPDATE key_value_test SET key = 20, value = 'twenty' WHERE key = 2")?;
+        Spi::run("DELETE FROM key_value_test WHERE key = 3")?;
+        assert_eq!(
+            Spi::get_one::<String>(
+                "SELECT string_agg(key || '=' || value, ',' ORDER BY key) FROM key_value_test"
+            )?,
+            Some("1=one,20=twenty".into())
+        );
+        Ok(())
+    }
+
+    #[pg_test(error = "cannot insert into foreign table \"series_readonly_test\"")]
+    fn test_fdw_read_only() -> Result<(), spi::Error> {
+        Spi::run(
+            "CREATE FOREIGN TABLE series_readonly_test (n int4, label text)
+                SERVER series_server OPTIONS (rows '1')",
+        )?;
+        Spi::run("INSERT INTO series_readonly_test VALUES (1, 'one')")
+    }
+}
@@ -225,4 +225,230 @@ mod tests {
             assert_eq!(GUC_NO_SHOW.get(), true, "'no_show' should reset after 'RESET ALL'");
         });
     }
+
+    #[pg_test(error = "test.url must be an https:// URL")]
+    fn test_string_guc_check_hook() {
+        static GUC: GucSetting<Option<&'static CStr>> =
+            GucSetting::<Option<&'static CStr>>::new(None);
+        GucRegistry::define_string_guc_with_hooks(
+            "test.url",
+            "test string guc check hook",
+            "test string guc check hook",
+            &GUC,
+            GucContext::Userset,
+            GucFlags::default(),
+            GucHooks::new().check(|url: Option<&CStr>, _| match url {
+                Some(url) if !url.to_bytes().starts_with(b"https://") => {
+                    Err(GucCheckError::new("test.url must be an https:// URL")
+                        .set_detail(format!("got {url:?}")))
+                }
+                _ => Ok(()),
+            }),
+        );
+
+        Spi::run("SET test.url = 'https://example.com'").expect("SPI failed");
+        assert_eq!(GUC.get(), Some(c"https://example.com"));
+
+        Spi::run("SET test.url = 'ftp://example.com'").expect("SPI failed");
+    }
+
+    #[pg_test]
+    fn test_guc_assign_and_show_hooks() {
+        use std::sync::atomic::{AtomicI32, Ordering};
+
+        static ASSIGNED: AtomicI32 = AtomicI32::new(0);
+        static GUC: GucSetting<i32> = GucSetting::<i32>::new(1);
+        GucRegistry::define_int_guc_with_hooks(
+            "test.widgets",
+            "test int guc assign and show hooks",
+            "test int guc assign and show hooks",
+            &GUC,
+            0,
+            100,
+            GucContext::Userset,
+            GucFlags::default(),
+            GucHooks::new()
+                .assign(|widgets| ASSIGNED.store(widgets, Ordering::Relaxed))
+                .show(|| format!("{} widgets", GUC.get())),
+        );
+        // the assign hook is also called with the boot value
+        assert_eq!(ASSIGNED.load(Ordering::Relaxed), 1);
+
+        Spi::run("SET test.widgets = 7").expect("SPI failed");
+        assert_eq!(ASSIGNED.load(Ordering::Relaxed), 7);
+        assert_eq!(Spi::get_one::<String>("SHOW test.widgets"), Ok(Some("7 widgets".to_string())));
+    }
+
+    #[pg_test(error = "Three is not allowed")]
+    fn test_enum_guc_check_hook() {
+        #[derive(PostgresGucEnum, Clone, Copy, PartialEq, Debug)]
+        enum TestEnum {
+            One,
+            Two,
+            Three,
+        }
+        static GUC: GucSetting<TestEnum> = GucSetting::<TestEnum>::new(TestEnum::One);
+        GucRegistry::define_enum_guc_with_hooks(
+            "test.checked_enum",
+            "test enum guc check hook",
+            "test enum guc check hook",
+            &GUC,
+            GucContext::Userset,
+            GucFlags::default(),
+            GucHooks::new().check(|value, _| match value {
+                TestEnum::Three => Err(GucCheckError::new("Three is not allowed")),
+                _ => Ok(()),
+            }),
+        );
+
+        Spi::run("SET test.checked_enum = 'two'").expect("SPI failed");
+        assert_eq!(GUC.get(), TestEnum::Two);
+
+        Spi::run("SET test.checked_enum = 'three'").expect("SPI failed");
+    }
+
+    #[pg_test]
+    fn test_list_value_guc() {
+        static GUC: GucValueSetting<Vec<String>> = GucValueSetting::new(c"alpha, beta");
+        GucRegistry::define_value_guc(
+            "test.list",
+            "test list gucs",
+            "test list gucs",
+            &GUC,
+            GucContext::Userset,
+            GucFlags::default(),
+        );
+        assert_eq!(*GUC.get(), vec!["alpha", "beta"]);
+        // the parsed value is cached until the GUC changes
+        assert!(std::sync::Arc::ptr_eq(&GUC.get(), &GUC.get()));
+
+        Spi::run("SET test.list = 'x,y , z'").expect("SPI failed");
+        assert_eq!(*GUC.get(), vec!["x", "y", "z"]);
+        assert_eq!(GUC.get_text(), c"x,y , z");
+
+        Spi::run("SET test.list = ''").expect("SPI failed");
+        assert!(GUC.get().is_empty());
+    }
+
+    #[pg_test]
+    fn test_duration_value_guc() {
+        use std::time::Duration;
+
+        static GUC: GucValueSetting<Duration> = GucValueSetting::new(c"30");
+        GucRegistry::define_value_guc(
+            "test.duration",
+            "test duration gucs",
+            "test duration gucs",
+            &GUC,
+            GucContext::Userset,
+            GucFlags::UNIT_S,
+        );
+        assert_eq!(*GUC.get(), Duration::from_secs(30));
+
+        Spi::run("SET test.duration = '250ms'").expect("SPI failed");
+        assert_eq!(*GUC.get(), Duration::from_millis(250));
+
+        Spi::run("SET test.duration = '2min'").expect("SPI failed");
+        assert_eq!(*GUC.get(), Duration::from_secs(120));
+    }
+
+    #[pg_test]
+    fn test_byte_size_value_guc() {
+        static GUC: GucValueSetting<ByteSize> = GucValueSetting::new(c"64");
+        GucRegistry::define_value_guc(
+            "test.byte_size",
+            "test byte size gucs",
+            "test byte size gucs",
+            &GUC,
+            GucContext::Userset,
+            GucFlags::UNIT_KB,
+        );
+        assert_eq!(*GUC.get(), ByteSize(64 * 1024));
+
+        Spi::run("SET test.byte_size = '1MB'").expect("SPI failed");
+        assert_eq!(*GUC.get(), ByteSize(1024 * 1024));
+
+        Spi::run("SET test.byte_size = '100B'").expect("SPI failed");
+        assert_eq!(*GUC.get(), ByteSize(100));
+    }
+
+    #[pg_test(error = "\"5 parsecs\" is not a valid duration")]
+    fn test_value_guc_rejects_invalid() {
+        use std::time::Duration;
+
+        static GUC: GucValueSetting<Duration> = GucValueSetting::new(c"1s");
+        GucRegistry::define_value_guc(
+            "test.invalid_duration",
+            "test invalid duration gucs",
+            "test invalid duration gucs",
+            &GUC,
+            GucContext::Userset,
+            GucFlags::default(),
+        );
+
+        Spi::run("SET test.invalid_duration = '5 parsecs'").expect("SPI failed");
+    }
+
+    #[pg_test(error = "\"1e18\" is not a valid duration")]
+    fn test_value_guc_checks_with_unit() {
+        use std::time::Duration;
+
+        static GUC: GucValueSetting<Duration> = GucValueSetting::new(c"1");
+        GucRegistry::define_value_guc(
+            "test.minutes",
+            "test duration gucs in minutes",
+            "test duration gucs in minutes",
+            &GUC,
+            GucContext::Userset,
+            GucFlags::UNIT_MIN,
+        );
+
+        // fine as milliseconds, but too long a duration as minutes
+        Spi::run("SET test.minutes = '1e18'").expect("SPI failed");
+    }
+
+    #[pg_test]
+    fn test_value_guc_restores_evicted_value() -> Result<(), spi::Error> {
+        use std::time::Duration;
+
+        static GUC: GucValueSetting<Duration> = GucValueSetting::new(c"1s");
+        GucRegistry::define_value_guc(
+            "test.evicted",
+            "test restoring evicted duration gucs",
+            "test restoring evicted duration gucs",
+            &GUC,
+            GucContext::Userset,
+            GucFlags::default(),
+        );
+
+        // the function's `SET` clause restores '2s' when it returns, long after it was last parsed
+        Spi::run("SET test.evicted = '2s'")?;
+        Spi::run(
+            "CREATE FUNCTION set_many_durations() RETURNS bigint LANGUAGE sql \
+             SET test.evicted = '3s' \
+             AS $$ SELECT count(set_config('test.evicted', i || 'ms', false)) \
+                   FROM generate_series(1, 20) i $$",
+        )?;
+        assert_eq!(Spi::get_one::<i64>("SELECT set_many_durations()")?, Some(20));
+        assert_eq!(*GUC.get(), Duration::from_secs(2));
+        Ok(())
+    }
+
+    #[cfg(any(feature = "pg15", feature = "pg16", feature = "pg17"))]
+    #[pg_test(error = "invalid configuration parameter name \"test_reserved.typo\"")]
+    fn test_mark_prefix_reserved() {
+        static GUC: GucSetting<bool> = GucSetting::<bool>::new(true);
+        GucRegistry::define_bool_guc(
+            "test_reserved.flag",
+            "test reserved prefix",
+            "test reserved prefix",
+            &GUC,
+            GucContext::Userset,
+            GucFlags::default(),
+        );
+        GucRegistry::mark_prefix_reserved("test_reserved");
+
+        Spi::run("SET test_reserved.flag = false").expect("SPI failed");
+        Spi::run("SET test_reserved.typo = 1").expect("SPI failed");
+    }
 }
@@ -133,4 +133,119 @@ mod tests {
         // TODO:  it'd be nice to also test that .commit() and .abort() also get called
         //    but I don't see how to do that since we're running *inside* a transaction here
     }
+
+    #[pg_test]
+    unsafe fn test_planner_and_explain_callbacks() {
+        use pgrx::pg_sys::*;
+
+        struct TestHook {
+            explains: u32,
+            relation_infos: u32,
+            object_accesses: u32,
+        }
+        impl PgHooks for TestHook {
+            fn explain_one_query(
+                &mut self,
+                query: PgBox<Query>,
+                cursor_options: i32,
+                into: PgBox<IntoClause>,
+                es: PgBox<ExplainState>,
+                query_string: &core::ffi::CStr,
+                params: PgBox<ParamListInfoData>,
+                query_env: PgBox<QueryEnvironment>,
+                prev_hook: fn(
+                    PgBox<Query>,
+                    i32,
+                    PgBox<IntoClause>,
+                    PgBox<ExplainState>,
+                    &core::ffi::CStr,
+                    PgBox<ParamListInfoData>,
+                    PgBox<QueryEnvironment>,
+                ) -> HookResult<()>,
+            ) -> HookResult<()> {
+                self.explains += 1;
+                prev_hook(query, cursor_options, into, es, query_string, params, query_env)
+            }
+
+            fn get_relation_info(
+                &mut self,
+                root: PgBox<PlannerInfo>,
+                relation_object_id: Oid,
+                inhparent: bool,
+                rel: PgBox<RelOptInfo>,
+                prev_hook: fn(PgBox<PlannerInfo>, Oid, bool, PgBox<RelOptInfo>) -> HookResult<()>,
+            ) -> HookResult<()> {
+                self.relation_infos += 1;
+                prev_hook(root, relation_object_id, inhparent, rel)
+            }
+
+            fn object_access(
+                &mut self,
+                access: ObjectAccessType::Type,
+                class_id: Oid,
+                object_id: Oid,
+                sub_id: i32,
+                arg: *mut std::ffi::c_void,
+                prev_hook: fn(
+                    ObjectAccessType::Type,
+                    Oid,
+                    Oid,
+                    i32,
+                    *mut std::ffi::c_void,
+                ) -> HookResult<()>,
+            ) -> HookResult<()> {
+                if access == ObjectAccessType::OAT_POST_CREATE {
+                    self.object_accesses += 1;
+                }
+                prev_hook(access, class_id, object_id, sub_id, arg)
+            }
+        }
+
+        static mut HOOK: TestHook = TestHook { explains: 0, relation_infos: 0, object_accesses: 0 };
+        pgrx::hooks::register_hook(&mut HOOK);
+
+        Spi::run("CREATE TABLE hooks_test_table (id int)").expect("SPI failed");
+        assert!(HOOK.object_accesses > 0);
+
+        let explain = Spi::explain("SELECT * FROM hooks_test_table").expect("SPI failed");
+        assert_eq!(1, HOOK.explains);
+        assert_eq!(1, HOOK.relation_infos);
+        assert_eq!(explain.0[0]["Plan"]["Relation Name"], "hooks_test_table");
+    }
+
+    #[pg_test]
+    unsafe fn test_multiple_registrations() {
+        use pgrx::pg_sys::*;
+        use std::cell::RefCell;
+
+        thread_local! {
+            static CALLS: RefCell<Vec<&'static str>> = const { RefCell::new(Vec::new()) };
+        }
+
+        struct NamedHook(&'static str);
+        impl PgHooks for NamedHook {
+            fn executor_start(
+                &mut self,
+                query_desc: PgBox<QueryDesc>,
+                eflags: i32,
+                prev_hook: fn(PgBox<QueryDesc>, i32) -> HookResult<()>,
+            ) -> HookResult<()> {
+                CALLS.with_borrow_mut(|calls| calls.push(self.0));
+                prev_hook(query_desc, eflags)
+            }
+        }
+
+        static mut FIRST: NamedHook = NamedHook("first");
+        static mut SECOND: NamedHook = NamedHook("second");
+        let second =
+            register_hooks(&mut *std::ptr::addr_of_mut!(SECOND), &[HookPoint::ExecutorStart], 10);
+        register_hooks(&mut *std::ptr::addr_of_mut!(FIRST), &[HookPoint::ExecutorStart], -10);
+
+        Spi::run("SELECT 1").expect("SPI failed");
+        assert_eq!(CALLS.take(), vec!["first", "second"]);
+
+        second.unregister();
+        Spi::run("SELECT 1").expect("SPI failed");
+        assert_eq!(CALLS.take(), vec!["first"]);
+    }
 }
@@ -0,0 +1,141 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+#[cfg(any(test, feature = "pg_test"))]
+#[pgrx::pg_schema]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgrx_tests;
+    use pgrx::index_am::{scan_heap, IndexBuildStats, IndexScanItem};
+    use pgrx::prelude::*;
+    use pgrx::PgRelation;
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+
+    thread_local! {
+        // every TID handed to a `tid_list` index, keyed by the index's oid
+        static TIDS: RefCell<HashMap<pg_sys::Oid, Vec<pg_sys::ItemPointerData>>> =
+            RefCell::new(HashMap::new());
+    }
+
+    /// An index which remembers every heap TID and lets the executor recheck them all
+    pub struct TidList;
+
+    pub struct TidListScan {
+        tids: Vec<pg_sys::ItemPointerData>,
+        position: usize,
+    }
+
+    #[pg_index_am]
+    impl IndexAccessMethod for TidList {
+        type ScanState = TidListScan;
+
+        const STRATEGIES: u16 = 1;
+
+        fn build(
+            heap: &PgRelation,
+            index: &PgRelation,
+            index_info: &mut pg_sys::IndexInfo,
+        ) -> IndexBuildStats {
+            let mut tids = Vec::new();
+            let mut stats = IndexBuildStats::default();
+            scan_heap(heap, index, index_info, |tid, _values, _isnull, _alive| {
+                tids.push(tid);
+                stats.heap_tuples += 1.0;
+                stats.index_tuples += 1.0;
+            });
+            TIDS.with(|all| all.borrow_mut().insert(index.oid(), tids));
+            stats
+        }
+
+        fn insert(
+            index: &PgRelation,
+            _heap: &PgRelation,
+            heap_tid: pg_sys::ItemPointerData,
+            _values: &[pg_sys::Datum],
+            _isnull: &[bool],
+            _check_unique: pg_sys::IndexUniqueCheck::Type,
+            _index_unchanged: bool,
+        ) -> bool {
+            TIDS.with(|all| all.borrow_mut().entry(index.oid()).or_default().push(heap_tid));
+            false
+        }
+
+        fn bulk_delete(
+            info: &pg_sys::IndexVacuumInfo,
+            stats: &mut pg_sys::IndexBulkDeleteResult,
+            is_dead: &dyn Fn(pg_sys::ItemPointerData) -> bool,
+        ) {
+            let index = unsafe { PgRelation::from_pg(info.index) };
+            TIDS.with(|all| {
+                if let Some(tids) = all.borrow_mut().get_mut(&index.oid()) {
+                    let before = tids.len();
+                    tids.retain(|tid| !is_dead(*tid));
+                    stats.tuples_removed += (before - tids.len()) as f64;
+                    stats.num_index_tuples = tids.len() as f64;
+                }
+            });
+        }
+
+        fn begin_scan(index: &PgRelation, _nkeys: usize, _norderbys: usize) -> TidListScan {
+            let tids = TIDS.with(|all| all.borrow().get(&index.oid()).cloned().unwrap_or_default());
+            TidListScan { tids, position: 0 }
+        }
+
+        fn rescan(
+            state: &mut TidListScan,
+            _keys: &[pg_sys::ScanKeyData],
+            _orderbys: &[pg_sys::ScanKeyData],
+        ) {
+            state.position = 0;
+        }
+
+        fn get_tuple(
+            state: &mut TidListScan,
+            _direction: pg_sys::ScanDirection::Type,
+        ) -> Option<IndexScanItem> {
+            let heap_tid = *state.tids.get(state.position)?;
+            state.position += 1;
+            Some(IndexScanItem { heap_tid, recheck: true })
+        }
+    }
+
+    extension_sql!(
+        r#"
+        CREATE OPERATOR CLASS int4_tid_list_ops DEFAULT FOR TYPE int4 USING tid_list AS
+            OPERATOR 1 = (int4, int4);
+        "#,
+        name = "int4_tid_list_ops",
+        requires = [TidList]
+    );
+
+    #[pg_test]
+    fn test_index_am_is_registered() -> Result<(), spi::Error> {
+        let amtype =
+            Spi::get_one::<i8>("SELECT amtype::\"char\" FROM pg_am WHERE amname = 'tid_list'")?;
+        assert_eq!(amtype, Some(b'i' as i8));
+        Ok(())
+    }
+
+    #[pg_test]
+    fn test_index_am_scan() -> Result<(), spi::Error> {
+        Spi::run("CREATE TABLE tid_list_test (a int4)")?;
+        Spi::run("INSERT INTO tid_list_test SELECT generate_series(1, 100)")?;
+        Spi::run("CREATE INDEX tid_list_test_idx ON tid_list_test USING tid_list (a)")?;
+        Spi::run("INSERT INTO tid_list_test VALUES (42)")?;
+        Spi::run("SET LOCAL enable_seqscan TO off")?;
+
+        for enable_bitmapscan in ["on", "off"] {
+            Spi::run(&format!("SET LOCAL enable_bitmapscan TO {enable_bitmapscan}"))?;
+            let count = Spi::get_one::<i64>("SELECT count(*) FROM tid_list_test WHERE a = 42")?;
+            assert_eq!(count, Some(2));
+        }
+        Ok(())
+    }
+}
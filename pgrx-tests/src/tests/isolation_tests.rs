@@ -0,0 +1,98 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+#[cfg(any(test, feature = "pg_test"))]
+#[pgrx::pg_schema]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgrx_tests;
+
+    use pgrx_tests::{IsolationSession, IsolationTest};
+
+    #[test]
+    fn test_isolation_row_lock() -> eyre::Result<()> {
+        IsolationTest::new()
+            .setup("CREATE TABLE accounts (id int PRIMARY KEY, balance int)")
+            .setup("INSERT INTO accounts VALUES (1, 100)")
+            .session(
+                IsolationSession::new("s1")
+                    .step("s1_begin", "BEGIN")
+                    .step("s1_update", "UPDATE accounts SET balance = balance - 10 WHERE id = 1")
+                    .step("s1_commit", "COMMIT"),
+            )
+            .session(IsolationSession::new("s2").step(
+                "s2_update",
+                "UPDATE accounts SET balance = balance - 10 WHERE id = 1 RETURNING balance",
+            ))
+            .permutation(["s1_begin", "s1_update", "s2_update", "s1_commit"])
+            .permutation(["s1_begin", "s1_update", "s1_commit", "s2_update"])
+            .run(crate::pg_test::postgresql_conf_options(), |schedule| {
+                let update = schedule.step("s2_update");
+                let blocked = schedule.permutation()[2] == "s2_update";
+                assert_eq!(update.waited(), blocked);
+                assert_eq!(update.value(0, 0), Some("80"));
+            })
+    }
+
+    #[test]
+    fn test_isolation_serialization_failure() -> eyre::Result<()> {
+        IsolationTest::new()
+            .setup("CREATE TABLE serialized (id int)")
+            .session(
+                IsolationSession::new("s1")
+                    .setup("BEGIN ISOLATION LEVEL SERIALIZABLE")
+                    .step("s1_read", "SELECT count(*) FROM serialized")
+                    .step("s1_write", "INSERT INTO serialized VALUES (1)")
+                    .step("s1_commit", "COMMIT"),
+            )
+            .session(
+                IsolationSession::new("s2")
+                    .setup("BEGIN ISOLATION LEVEL SERIALIZABLE")
+                    .step("s2_read", "SELECT count(*) FROM serialized")
+                    .step("s2_write", "INSERT INTO serialized VALUES (2)")
+                    .step("s2_commit", "COMMIT"),
+            )
+            .permutation(["s1_read", "s2_read", "s1_write", "s2_write", "s1_commit", "s2_commit"])
+            .run(crate::pg_test::postgresql_conf_options(), |schedule| {
+                assert_eq!(schedule.step("s1_read").value(0, 0), Some("0"));
+                // only one of them gets to commit, however Postgres decides which
+                let errors = schedule
+                    .outcomes()
+                    .iter()
+                    .filter_map(|outcome| outcome.error())
+                    .collect::<Vec<_>>();
+                assert_eq!(errors.len(), 1);
+                assert_eq!(errors[0].code(), &postgres::error::SqlState::T_R_SERIALIZATION_FAILURE);
+            })
+    }
+
+    #[test]
+    fn test_isolation_every_interleaving() -> eyre::Result<()> {
+        let mut permutations = Vec::new();
+        IsolationTest::new()
+            .session(IsolationSession::new("s1").step("s1_a", "SELECT 1").step("s1_b", "SELECT 2"))
+            .session(IsolationSession::new("s2").step("s2_a", "SELECT 3").step("s2_b", "SELECT 4"))
+            .run(crate::pg_test::postgresql_conf_options(), |schedule| {
+                assert!(schedule.outcomes().iter().all(|outcome| !outcome.waited()));
+                permutations.push(schedule.permutation().join(" "));
+            })?;
+        assert_eq!(
+            permutations,
+            [
+                "s1_a s1_b s2_a s2_b",
+                "s1_a s2_a s1_b s2_b",
+                "s1_a s2_a s2_b s1_b",
+                "s2_a s1_a s1_b s2_b",
+                "s2_a s1_a s2_b s1_b",
+                "s2_a s2_b s1_a s1_b",
+            ]
+        );
+        Ok(())
+    }
+}
@@ -21,11 +21,17 @@ mod bytea_tests;
 mod cfg_tests;
 mod complex;
 mod composite_type_tests;
+#[cfg(feature = "cshim")]
+#[allow(deprecated)]
+mod custom_scan_tests;
 mod datetime_tests;
 mod default_arg_value_tests;
 mod derive_pgtype_lifetimes;
+mod dsm_tests;
 mod enum_type_tests;
+mod event_trigger_tests;
 mod fcinfo_tests;
+mod fdw_tests;
 mod fn_call_tests;
 mod from_into_datum_tests;
 mod geo_tests;
@@ -33,8 +39,10 @@ mod guc_tests;
 mod heap_tuple;
 #[cfg(feature = "cshim")]
 mod hooks_tests;
+mod index_am_tests;
 mod inet_tests;
 mod internal_tests;
+mod isolation_tests;
 mod issue1134;
 mod json_tests;
 mod lifetime_tests;
@@ -43,26 +51,35 @@ mod log_tests;
 mod memcxt_tests;
 mod name_tests;
 mod numeric_tests;
+mod output_plugin_tests;
+mod parallel_tests;
 mod pg_cast_tests;
 mod pg_extern_tests;
 mod pg_guard_tests;
 mod pg_operator_tests;
+mod pg_test_options_tests;
 mod pg_try_tests;
 mod pgbox_tests;
 mod pgrx_module_qualification;
 mod postgres_type_tests;
+mod procedure_tests;
 #[cfg(feature = "proptest")]
 mod proptests;
 mod range_tests;
 mod rel_tests;
 mod result_tests;
 mod roundtrip_tests;
+mod row_tests;
 mod schema_tests;
+mod shm_mq_tests;
 mod shmem_tests;
 mod spi_tests;
 mod srf_tests;
 mod struct_type_tests;
+mod support_tests;
+mod table_am_tests;
 mod trigger_tests;
+mod typmod_tests;
 mod uuid_tests;
 mod variadic_tests;
 mod xact_callback_tests;
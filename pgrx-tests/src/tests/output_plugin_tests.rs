@@ -0,0 +1,142 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+#[cfg(any(test, feature = "pg_test"))]
+#[pgrx::pg_schema]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgrx_tests;
+    use pgrx::output_plugin::{Change, Message, OutputWriter, Transaction};
+    use pgrx::prelude::*;
+    use pgrx::PgRelation;
+
+    /// Writes one line per change, naming the relation and the change's kind
+    pub struct ChangeNames {
+        include_xids: bool,
+    }
+
+    impl OutputPlugin for ChangeNames {
+        fn startup(options: &[(String, Option<String>)], _is_init: bool) -> Self {
+            let mut include_xids = false;
+            for (name, value) in options {
+                match name.as_str() {
+                    "include-xids" => include_xids = value.as_deref() != Some("off"),
+                    _ => error!("unrecognized option \"{name}\""),
+                }
+            }
+            ChangeNames { include_xids }
+        }
+
+        fn begin(&mut self, out: &mut OutputWriter, txn: &Transaction) {
+            if self.include_xids {
+                out.write(format!("BEGIN {}", txn.xid()));
+            }
+        }
+
+        fn change(
+            &mut self,
+            out: &mut OutputWriter,
+            _txn: &Transaction,
+            relation: &PgRelation,
+            change: Change,
+        ) {
+            let kind = match change {
+                Change::Insert { .. } => "INSERT",
+                Change::Update { .. } => "UPDATE",
+                Change::Delete { .. } => "DELETE",
+            };
+            out.write(format!("{kind} {}", relation.name()));
+        }
+
+        fn message(
+            &mut self,
+            out: &mut OutputWriter,
+            _txn: Option<&Transaction>,
+            message: Message,
+        ) {
+            out.write(format!("MESSAGE {}", message.prefix));
+        }
+    }
+
+    pg_output_plugin!(ChangeNames);
+
+    #[pg_test]
+    fn test_output_plugin_creates_slot() -> Result<(), spi::Error> {
+        Spi::run(
+            "SELECT pg_create_logical_replication_slot('pgrx_output_plugin_slot', 'pgrx_tests', true)",
+        )?;
+        let plugin = Spi::get_one::<String>(
+            "SELECT plugin::text FROM pg_replication_slots WHERE slot_name = 'pgrx_output_plugin_slot'",
+        )?;
+        assert_eq!(plugin.as_deref(), Some("pgrx_tests"));
+        Ok(())
+    }
+
+    #[pg_test(error = "unrecognized option \"bogus\"")]
+    fn test_output_plugin_startup_options() -> Result<(), spi::Error> {
+        Spi::run(
+            "SELECT pg_create_logical_replication_slot('pgrx_output_plugin_options_slot', 'pgrx_tests', true)",
+        )?;
+        Spi::run(
+            "SELECT * FROM pg_logical_slot_peek_changes('pgrx_output_plugin_options_slot', NULL, NULL, 'bogus', 'on')",
+        )
+    }
+
+    #[pg_test]
+    fn test_output_plugin_decodes_message() -> Result<(), spi::Error> {
+        Spi::run(
+            "SELECT pg_create_logical_replication_slot('pgrx_output_plugin_message_slot', 'pgrx_tests', true)",
+        )?;
+        // non-transactional messages are decoded right away, once they're flushed
+        Spi::run("SELECT pg_logical_emit_message(false, 'pgrx', 'x')")?;
+        unsafe { pg_sys::XLogFlush(pg_sys::GetXLogInsertRecPtr()) };
+
+        let decoded = Spi::get_one::<Vec<String>>(
+            "SELECT array_agg(data) FROM pg_logical_slot_peek_changes('pgrx_output_plugin_message_slot', NULL, NULL)",
+        )?;
+        assert_eq!(decoded, Some(vec![String::from("MESSAGE pgrx")]));
+        Ok(())
+    }
+
+    #[pg_test(transaction = false)]
+    fn test_output_plugin_decodes_changes() -> Result<(), spi::Error> {
+        Spi::connect_nonatomic(|client| {
+            client.update(
+                "SELECT pg_create_logical_replication_slot('pgrx_output_plugin_changes_slot', 'pgrx_tests', true)",
+                None,
+                &[],
+            )?;
+            client.update("CREATE TABLE decoded (id int PRIMARY KEY, value text)", None, &[])?;
+            client.commit();
+
+            client.update("INSERT INTO decoded VALUES (1, 'a')", None, &[])?;
+            client.update("UPDATE decoded SET value = 'b' WHERE id = 1", None, &[])?;
+            client.update("DELETE FROM decoded WHERE id = 1", None, &[])?;
+            client.commit();
+
+            let decoded = client
+                .select(
+                    "SELECT array_agg(data) FROM pg_logical_slot_peek_changes('pgrx_output_plugin_changes_slot', NULL, NULL)",
+                    None,
+                    &[],
+                )?
+                .first()
+                .get_one::<Vec<String>>()?;
+            assert_eq!(
+                decoded,
+                Some(vec![
+                    String::from("INSERT decoded"),
+                    String::from("UPDATE decoded"),
+                    String::from("DELETE decoded"),
+                ])
+            );
+            Ok(())
+        })
+    }
+}
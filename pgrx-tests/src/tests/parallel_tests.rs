@@ -0,0 +1,96 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+use pgrx::parallel::ParallelWorker;
+use pgrx::prelude::*;
+
+/// Sums the numbers below `n` that fall into this worker's share
+fn partial_sum(n: i64, worker_number: usize, nworkers: usize) -> i64 {
+    (0..n).filter(|i| i % nworkers as i64 == worker_number as i64).sum()
+}
+
+#[pg_guard]
+#[no_mangle]
+pub extern "C" fn parallel_sum_worker(seg: *mut pg_sys::dsm_segment, toc: *mut pg_sys::shm_toc) {
+    unsafe {
+        ParallelWorker::run(seg, toc, |n: i64, worker: &ParallelWorker| {
+            partial_sum(n, worker.worker_number(), worker.nworkers())
+        })
+    }
+}
+
+#[pg_guard]
+#[no_mangle]
+pub extern "C" fn parallel_error_worker(seg: *mut pg_sys::dsm_segment, toc: *mut pg_sys::shm_toc) {
+    unsafe {
+        ParallelWorker::run(seg, toc, |(): (), _: &ParallelWorker| -> () {
+            panic!("parallel worker failed")
+        })
+    }
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+#[pgrx::pg_schema]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgrx_tests;
+
+    use super::partial_sum;
+    use pgrx::parallel::ParallelContextBuilder;
+    use pgrx::prelude::*;
+
+    #[pg_test]
+    fn test_parallel_sum() {
+        // Required to avoid bgworker pool exhaustion errors, see `test_dynamic_worker_allocation_failure`
+        Spi::run("SELECT pg_advisory_xact_lock_shared(42)").unwrap();
+        let (n, nworkers) = (100_000i64, 2);
+        let results: Vec<i64> = ParallelContextBuilder::new("pgrx_tests", "parallel_sum_worker")
+            .set_nworkers(nworkers)
+            .set_payload(&n)
+            .run();
+        assert!(results.len() <= nworkers);
+
+        // the leader does the work of any workers that couldn't be launched
+        let total = results.iter().sum::<i64>()
+            + (results.len()..nworkers).map(|w| partial_sum(n, w, nworkers)).sum::<i64>();
+        assert_eq!(total, n * (n - 1) / 2);
+    }
+
+    #[pg_test]
+    fn test_parallel_unaligned_queue_size() {
+        // Required to avoid bgworker pool exhaustion errors, see `test_dynamic_worker_allocation_failure`
+        Spi::run("SELECT pg_advisory_xact_lock_shared(42)").unwrap();
+        let (n, nworkers) = (1_000i64, 3);
+        // every queue after the first would be misaligned if the size weren't rounded up
+        let results: Vec<i64> = ParallelContextBuilder::new("pgrx_tests", "parallel_sum_worker")
+            .set_nworkers(nworkers)
+            .set_payload(&n)
+            .set_queue_size(1001)
+            .run();
+        for (w, result) in results.iter().enumerate() {
+            assert_eq!(*result, partial_sum(n, w, nworkers));
+        }
+    }
+
+    #[pg_test]
+    fn test_parallel_no_workers() {
+        let results: Vec<i64> =
+            ParallelContextBuilder::new("pgrx_tests", "parallel_sum_worker").set_nworkers(0).run();
+        assert!(results.is_empty());
+    }
+
+    #[pg_test(error = "parallel worker failed")]
+    fn test_parallel_worker_error() {
+        // Required to avoid bgworker pool exhaustion errors, see `test_dynamic_worker_allocation_failure`
+        Spi::run("SELECT pg_advisory_xact_lock_shared(42)").unwrap();
+        let _: Vec<()> = ParallelContextBuilder::new("pgrx_tests", "parallel_error_worker")
+            .set_nworkers(1)
+            .run();
+    }
+}
@@ -0,0 +1,75 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+#[cfg(any(test, feature = "pg_test"))]
+#[pgrx::pg_schema]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgrx_tests;
+
+    use pgrx::prelude::*;
+    use pgrx::spi;
+
+    #[pg_test]
+    fn test_shared_database() -> Result<(), spi::Error> {
+        let database = Spi::get_one::<String>("SELECT current_database()::text")?;
+        assert_eq!(database.as_deref(), Some("pgrx_tests"));
+        Ok(())
+    }
+
+    #[pg_test(database = "fresh")]
+    fn test_fresh_database() -> Result<(), spi::Error> {
+        let database = Spi::get_one::<String>("SELECT current_database()::text")?;
+        assert!(database.is_some_and(|name| name.starts_with("pgrx_tests_")));
+        Ok(())
+    }
+
+    #[pg_test(transaction = false)]
+    fn test_commit_outside_transaction() -> Result<(), spi::Error> {
+        Spi::connect_nonatomic(|client| {
+            client.update("CREATE TABLE committed (id int)", None, &[])?;
+            client.commit();
+            client.update("INSERT INTO committed VALUES (1)", None, &[])?;
+            client.rollback();
+            let count =
+                client.select("SELECT count(*) FROM committed", None, &[])?.first().get_one()?;
+            assert_eq!(count, Some(0i64));
+            Ok(())
+        })
+    }
+
+    #[pg_test(transaction = false)]
+    fn test_top_level_vacuum() -> Result<(), Box<dyn std::error::Error>> {
+        Spi::connect_nonatomic(|client| {
+            client.update(
+                "CREATE TABLE vacuumed AS SELECT generate_series(1, 100) AS id",
+                None,
+                &[],
+            )?;
+            client.commit();
+            Ok::<_, spi::Error>(())
+        })?;
+        pgrx_tests::top_level_client()?.simple_query("VACUUM vacuumed")?;
+        Ok(())
+    }
+
+    #[pg_test(transaction = false, error = "division by zero")]
+    fn test_error_outside_transaction() -> Result<(), spi::Error> {
+        Spi::run("SELECT 1 / 0")
+    }
+
+    #[pg_test(restart)]
+    fn test_restart() -> Result<(), spi::Error> {
+        let uptime = Spi::get_one::<bool>(
+            "SELECT now() - pg_postmaster_start_time() < interval '1 minute'",
+        )?;
+        assert_eq!(uptime, Some(true));
+        Ok(())
+    }
+}
@@ -8,9 +8,11 @@
 //LICENSE
 //LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
 use core::ffi::CStr;
+use pgrx::inoutfuncs::PgBinaryInOutFuncs;
 use pgrx::prelude::*;
 use pgrx::StringInfo;
 use serde::{Deserialize, Serialize};
+use std::io::Read;
 use std::str::FromStr;
 
 #[derive(Copy, Clone, PostgresType, Serialize, Deserialize)]
@@ -153,6 +155,51 @@ pub enum JsonEnumType {
     E2 { b: f32 },
 }
 
+#[derive(Debug, PartialEq, Serialize, Deserialize, PostgresType)]
+#[sendrecvfuncs]
+pub struct BinaryType {
+    a: i32,
+    b: String,
+}
+
+impl PgBinaryInOutFuncs for BinaryType {}
+
+#[derive(Copy, Clone, PostgresType, Serialize, Deserialize)]
+#[pgvarlena_inoutfuncs]
+#[sendrecvfuncs]
+pub struct BinaryVarlenaType {
+    a: i32,
+    b: i64,
+}
+
+impl PgVarlenaInOutFuncs for BinaryVarlenaType {
+    fn input(input: &CStr) -> PgVarlena<Self> {
+        let (a, b) = input.to_str().unwrap().split_once(',').expect("expected `a,b`");
+        let mut result = PgVarlena::<BinaryVarlenaType>::new();
+        result.a = i32::from_str(a).expect("a is not a valid i32");
+        result.b = i64::from_str(b).expect("b is not a valid i64");
+        result
+    }
+
+    fn output(&self, buffer: &mut StringInfo) {
+        buffer.push_str(&format!("{},{}", self.a, self.b))
+    }
+}
+
+impl PgBinaryInOutFuncs for BinaryVarlenaType {
+    fn receive(buffer: &mut StringInfo<AllocatedByPostgres>) -> Self {
+        let (mut a, mut b) = ([0; 4], [0; 8]);
+        buffer.read_exact(&mut a).expect("a is missing");
+        buffer.read_exact(&mut b).expect("b is missing");
+        BinaryVarlenaType { a: i32::from_be_bytes(a), b: i64::from_be_bytes(b) }
+    }
+
+    fn send(&self, buffer: &mut StringInfo) {
+        buffer.push_bytes(&self.a.to_be_bytes());
+        buffer.push_bytes(&self.b.to_be_bytes());
+    }
+}
+
 #[cfg(any(test, feature = "pg_test"))]
 #[pgrx::pg_schema]
 mod tests {
@@ -160,11 +207,23 @@ mod tests {
     use crate as pgrx_tests;
 
     use super::{
-        CustomTextFormatSerializedEnumType, CustomTextFormatSerializedType, JsonEnumType, JsonType,
-        VarlenaEnumType, VarlenaType,
+        BinaryType, CustomTextFormatSerializedEnumType, CustomTextFormatSerializedType,
+        JsonEnumType, JsonType, VarlenaEnumType, VarlenaType,
     };
-    use pgrx::datum::PgVarlena;
+    use pgrx::datum::{Internal, PgVarlena};
     use pgrx::prelude::*;
+    use pgrx::StringInfo;
+
+    /// Pass `bytes` to a type's receive function, like Postgres would
+    fn receive<T>(recv: fn(Internal) -> Option<T>, bytes: &[u8]) -> Option<T> {
+        let buffer = StringInfo::from(bytes).into_pg();
+        let value = recv(Internal::from(Some(pg_sys::Datum::from(buffer))));
+        // SAFETY: we just made `buffer`
+        unsafe {
+            assert_eq!((*buffer).cursor, (*buffer).len, "didn't read every byte");
+        }
+        value
+    }
 
     #[pg_test]
     fn test_mytype() -> Result<(), pgrx::spi::Error> {
@@ -254,4 +313,44 @@ mod tests {
         assert!(matches!(result, JsonEnumType::E1 { a } if a == 1.0));
         Ok(())
     }
+
+    #[pg_test]
+    fn test_binary_type_has_send_recv() -> Result<(), pgrx::spi::Error> {
+        let result = Spi::get_one::<bool>(
+            "SELECT typsend::oid <> 0 AND typreceive::oid <> 0 FROM pg_type \
+             WHERE typname = 'binarytype'",
+        )?;
+        assert_eq!(result, Some(true));
+        Ok(())
+    }
+
+    #[pg_test]
+    fn test_binary_type_roundtrip() -> Result<(), pgrx::spi::Error> {
+        let bytes = Spi::get_one::<Vec<u8>>(
+            r#"SELECT binarytype_send('{"a": 42, "b": "Nami"}'::BinaryType)"#,
+        )?
+        .unwrap();
+        assert_eq!(
+            receive(super::binarytype_recv, &bytes),
+            Some(BinaryType { a: 42, b: "Nami".into() })
+        );
+        Ok(())
+    }
+
+    #[pg_test]
+    fn test_binary_varlena_type_roundtrip() -> Result<(), pgrx::spi::Error> {
+        let bytes =
+            Spi::get_one::<Vec<u8>>("SELECT binaryvarlenatype_send('1,2'::BinaryVarlenaType)")?
+                .unwrap();
+        assert_eq!(bytes, [0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 2]);
+
+        let result = receive(super::binaryvarlenatype_recv, &bytes).unwrap();
+        assert_eq!((result.a, result.b), (1, 2));
+        Ok(())
+    }
+
+    #[pg_test]
+    fn test_receive_null() {
+        assert_eq!(super::binarytype_recv(Internal::from(None)), None);
+    }
 }
@@ -0,0 +1,74 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+#[cfg(any(test, feature = "pg_test"))]
+#[pgrx::pg_schema]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgrx_tests;
+
+    use pgrx::prelude::*;
+    use pgrx::spi;
+
+    #[pg_procedure]
+    fn insert_procedure_dog(name: &str, scritches: Option<i32>) -> Result<(), spi::Error> {
+        Spi::connect_mut(|client| {
+            client.update(
+                "INSERT INTO procedure_dogs VALUES ($1, $2)",
+                None,
+                &[name.into(), scritches.into()],
+            )?;
+            Ok(())
+        })
+    }
+
+    #[pg_procedure]
+    fn commit_procedure_dogs() -> Result<(), spi::Error> {
+        Spi::connect_nonatomic(|client| {
+            client.update("INSERT INTO procedure_dogs VALUES ('Brandy', 1)", None, &[])?;
+            client.commit();
+            Ok(())
+        })
+    }
+
+    #[pg_procedure]
+    fn rollback_procedure_dogs() {
+        Spi::connect_nonatomic(|client| client.rollback())
+    }
+
+    #[pg_test]
+    fn test_procedure_is_a_procedure() -> Result<(), spi::Error> {
+        let is_procedure = Spi::get_one::<bool>(
+            "SELECT prokind = 'p' FROM pg_proc WHERE proname = 'insert_procedure_dog'",
+        )?;
+        assert_eq!(is_procedure, Some(true));
+        Ok(())
+    }
+
+    #[pg_test]
+    fn test_call_procedure() -> Result<(), spi::Error> {
+        Spi::run("CREATE TEMPORARY TABLE procedure_dogs (name text, scritches int)")?;
+        Spi::run("CALL tests.insert_procedure_dog('Nami', 42)")?;
+        Spi::run("CALL tests.insert_procedure_dog('Brandy', NULL)")?;
+        let scritches = Spi::get_one::<i64>("SELECT sum(scritches) FROM procedure_dogs")?;
+        assert_eq!(scritches, Some(42));
+        Ok(())
+    }
+
+    #[pg_test(error = "invalid transaction termination")]
+    fn test_commit_in_atomic_call() -> Result<(), spi::Error> {
+        Spi::run("CREATE TEMPORARY TABLE procedure_dogs (name text, scritches int)")?;
+        Spi::run("CALL tests.commit_procedure_dogs()")
+    }
+
+    #[pg_test(error = "invalid transaction termination")]
+    fn test_rollback_in_atomic_call() -> Result<(), spi::Error> {
+        Spi::run("CALL tests.rollback_procedure_dogs()")
+    }
+}
@@ -176,4 +176,41 @@ mod tests {
     fn test_return_result_set_of_error() -> Result<(), spi::Error> {
         Spi::run("SELECT * FROM tests.return_result_set_of_error()")
     }
+
+    #[pg_test(sqlstate = ERRCODE_CHECK_VIOLATION, error_matches = r#"violates check constraint "\w+""#)]
+    fn test_expected_sqlstate() -> Result<(), spi::Error> {
+        Spi::run("CREATE TABLE accounts (balance int CHECK (balance >= 0))")?;
+        Spi::run("INSERT INTO accounts VALUES (-1)")
+    }
+
+    #[pg_test(sqlstate = PgSqlErrorCode::ERRCODE_NO_DATA, error_matches = "^no data for \\d+$")]
+    fn test_expected_message_pattern() -> Result<(), ErrorReport> {
+        let id = 42;
+        Err(ErrorReport::new(
+            PgSqlErrorCode::ERRCODE_NO_DATA,
+            format!("no data for {id}"),
+            function_name!(),
+        ))
+    }
+
+    #[pg_test(error = "raised custom ereport", detail = "some detail", hint = "some hint")]
+    fn test_expected_detail_and_hint() -> Result<(), ErrorReport> {
+        Err(ErrorReport::new(
+            PgSqlErrorCode::ERRCODE_NO_DATA,
+            "raised custom ereport",
+            function_name!(),
+        )
+        .set_detail("some detail")
+        .set_hint("some hint"))
+    }
+
+    #[pg_test(sqlstate = ERRCODE_DATA_EXCEPTION)]
+    #[should_panic]
+    fn test_unexpected_sqlstate() -> Result<(), ErrorReport> {
+        Err(ErrorReport::new(
+            PgSqlErrorCode::ERRCODE_NO_DATA,
+            "raised custom ereport",
+            function_name!(),
+        ))
+    }
 }
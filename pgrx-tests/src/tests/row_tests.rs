@@ -0,0 +1,152 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+#[cfg(any(test, feature = "pg_test"))]
+#[pgrx::pg_schema]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgrx_tests;
+
+    use pgrx::prelude::*;
+    use pgrx::row::{ColumnRef, FromRow, IntoRow, RowError};
+    use pgrx::spi::SpiError;
+
+    #[derive(FromRow, IntoRow, Debug, PartialEq)]
+    struct Dog {
+        name: String,
+        #[row(rename = "scritches")]
+        scritch_count: Option<i32>,
+        #[row(default)]
+        treats: i64,
+        #[row(skip)]
+        good: bool,
+    }
+
+    #[derive(FromRow, Debug, PartialEq)]
+    struct Pair(i32, String);
+
+    fn no_treats() -> i64 {
+        -1
+    }
+
+    #[derive(FromRow, Debug, PartialEq)]
+    struct Positional {
+        #[row(position = 2)]
+        second: String,
+        #[row(default = "no_treats")]
+        treats: i64,
+    }
+
+    #[pg_test]
+    fn test_rows_by_name() -> Result<(), SpiError> {
+        let dogs = Spi::connect(|client| {
+            client
+                .select(
+                    "SELECT * FROM (VALUES ('Nami', 7, 2::bigint), ('Brandy', NULL, NULL)) \
+                     AS t(name, scritches, treats)",
+                    None,
+                    &[],
+                )?
+                .rows::<Dog>()?
+                .collect::<Result<Vec<_>, _>>()
+        })?;
+        assert_eq!(
+            dogs,
+            vec![
+                Dog { name: "Nami".into(), scritch_count: Some(7), treats: 2, good: false },
+                Dog { name: "Brandy".into(), scritch_count: None, treats: 0, good: false },
+            ]
+        );
+        Ok(())
+    }
+
+    #[pg_test]
+    fn test_rows_by_position() -> Result<(), SpiError> {
+        Spi::connect(|client| {
+            let pairs = client
+                .select("SELECT 1, 'one' UNION ALL SELECT 2, 'two'", None, &[])?
+                .rows::<Pair>()?
+                .collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(pairs, vec![Pair(1, "one".into()), Pair(2, "two".into())]);
+
+            let row = client.select("SELECT 1, 'b'", None, &[])?.first().get_row::<Positional>()?;
+            assert_eq!(row, Some(Positional { second: "b".into(), treats: -1 }));
+            Ok(())
+        })
+    }
+
+    #[pg_test]
+    fn test_rows_missing_column() {
+        let result = Spi::connect(|client| {
+            client.select("SELECT 'Nami' AS name", None, &[])?.rows::<Dog>().map(|_| ())
+        });
+        assert_eq!(
+            result,
+            Err(SpiError::RowError(RowError::NoSuchColumn(ColumnRef::Name("scritches"))))
+        );
+    }
+
+    #[pg_test]
+    fn test_rows_unexpected_null() {
+        let result = Spi::connect(|client| {
+            client
+                .select("SELECT NULL::text AS name, 1 AS scritches", None, &[])?
+                .rows::<Dog>()?
+                .collect::<Result<Vec<_>, _>>()
+        });
+        assert_eq!(
+            result,
+            Err(SpiError::RowError(RowError::UnexpectedNull(ColumnRef::Name("name"))))
+        );
+    }
+
+    #[pg_test]
+    fn test_rows_from_cursor() -> Result<(), SpiError> {
+        Spi::connect(|client| {
+            let mut cursor =
+                client.open_cursor("SELECT i, i::text FROM generate_series(1, 5) i", &[]);
+            let first = cursor.fetch(3)?.rows::<Pair>()?.collect::<Result<Vec<_>, _>>()?;
+            let rest = cursor.fetch(3)?.rows::<Pair>()?.collect::<Result<Vec<_>, _>>()?;
+            assert_eq!(first.len(), 3);
+            assert_eq!(rest, vec![Pair(4, "4".into()), Pair(5, "5".into())]);
+            Ok(())
+        })
+    }
+
+    #[pg_test]
+    fn test_composite_row_roundtrip() -> Result<(), RowError> {
+        let mut tuple = PgHeapTuple::new_composite_type("Dog")?;
+        tuple.set_row(Dog {
+            name: "Nami".into(),
+            scritch_count: Some(42),
+            treats: 3,
+            good: true,
+        })?;
+
+        assert_eq!(tuple.get_by_name::<String>("name")?, Some("Nami".into()));
+        assert_eq!(tuple.get_by_name::<i32>("scritches")?, Some(42));
+        // the `Dog` type has no `treats` column, and `good` is skipped
+        assert_eq!(
+            tuple.get_row::<Dog>()?,
+            Dog { name: "Nami".into(), scritch_count: Some(42), treats: 0, good: false }
+        );
+        Ok(())
+    }
+
+    #[pg_test]
+    fn test_composite_row_from_spi() -> Result<(), SpiError> {
+        let dog = Spi::get_one::<pgrx::composite_type!("Dog")>("SELECT ROW('Brandy', 9)::Dog")?
+            .expect("no dog");
+        assert_eq!(
+            dog.get_row::<Dog>()?,
+            Dog { name: "Brandy".into(), scritch_count: Some(9), treats: 0, good: false }
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+use pgrx::prelude::*;
+
+const QUEUE_SIZE: usize = 16384;
+
+#[pg_guard]
+#[no_mangle]
+/// Doubles every value in each `Vec<i32>` it receives, until the launching backend detaches
+pub extern "C" fn shm_mq_doubling_worker(arg: pg_sys::Datum) {
+    use pgrx::bgworkers::*;
+    use pgrx::dsm::DsmSegment;
+    use pgrx::shm_mq::{ShmMq, ShmMqError};
+    BackgroundWorker::attach_signal_handlers(SignalWakeFlags::SIGHUP | SignalWakeFlags::SIGTERM);
+
+    let segment = DsmSegment::attach(arg.value() as u32).expect("segment should exist");
+    let mut requests = unsafe { ShmMq::from_segment(&segment, 0) }.attach_receiver();
+    let mut responses = unsafe { ShmMq::from_segment(&segment, QUEUE_SIZE) }.attach_sender();
+
+    loop {
+        match requests.receive_value::<Vec<i32>>() {
+            Ok(values) => {
+                let doubled = values.into_iter().map(|v| v * 2).collect::<Vec<_>>();
+                responses.send_value(&doubled).expect("failed to send response");
+            }
+            Err(ShmMqError::Detached | ShmMqError::Terminated) => break,
+            Err(e) => panic!("{e}"),
+        }
+    }
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+#[pgrx::pg_schema]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgrx_tests;
+
+    use super::QUEUE_SIZE;
+    use pgrx::bgworkers::*;
+    use pgrx::dsm::DsmSegment;
+    use pgrx::prelude::*;
+    use pgrx::shm_mq::{ShmMq, ShmMqError};
+
+    #[pg_test]
+    fn test_shm_mq_nowait() {
+        let segment = DsmSegment::create(QUEUE_SIZE);
+        // a backend may be both the sender and the receiver of a queue
+        let mut sender = ShmMq::create(&segment, 0, QUEUE_SIZE).attach_sender();
+        let mut receiver = unsafe { ShmMq::from_segment(&segment, 0) }.attach_receiver();
+
+        assert_eq!(receiver.try_receive(), Err(ShmMqError::WouldBlock));
+        sender.try_send(b"hello").expect("failed to send");
+        assert_eq!(receiver.try_receive(), Ok(&b"hello"[..]));
+
+        drop(sender);
+        assert_eq!(receiver.receive(), Err(ShmMqError::Detached));
+    }
+
+    #[pg_test]
+    fn test_shm_mq_background_worker() {
+        // Required to avoid bgworker pool exhaustion errors, see `test_dynamic_worker_allocation_failure`
+        Spi::run("SELECT pg_advisory_xact_lock_shared(42)").unwrap();
+        let segment = DsmSegment::create(2 * QUEUE_SIZE);
+        let mut requests = ShmMq::create(&segment, 0, QUEUE_SIZE).attach_sender();
+        let mut responses = ShmMq::create(&segment, QUEUE_SIZE, QUEUE_SIZE).attach_receiver();
+
+        let worker = BackgroundWorkerBuilder::new("shm_mq_doubling_worker")
+            .set_library("pgrx_tests")
+            .set_function("shm_mq_doubling_worker")
+            .set_argument(Some(pg_sys::Datum::from(segment.handle())))
+            .enable_shmem_access(None)
+            .set_notify_pid(unsafe { pg_sys::MyProcPid })
+            .load_dynamic()
+            .expect("Failed to start worker");
+        requests.set_worker(&worker);
+        responses.set_worker(&worker);
+
+        requests.send_value(&vec![1, 2, 3]).expect("failed to send");
+        assert_eq!(responses.receive_value::<Vec<i32>>(), Ok(vec![2, 4, 6]));
+
+        // much larger than the queue itself
+        let values = (0..100_000).collect::<Vec<i32>>();
+        requests.send_value(&values).expect("failed to send");
+        let doubled = responses.receive_value::<Vec<i32>>().expect("failed to receive");
+        assert!(values.iter().zip(&doubled).all(|(v, d)| v * 2 == *d));
+
+        drop(requests);
+        worker.wait_for_shutdown().expect("aborted shutdown");
+        assert_eq!(responses.receive(), Err(ShmMqError::Detached));
+    }
+}
@@ -8,17 +8,19 @@
 //LICENSE
 //LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
 use pgrx::prelude::*;
-use pgrx::{pg_shmem_init, PgAtomic, PgLwLock, PgSharedMemoryInitialization};
+use pgrx::{pg_shmem_init, PgAtomic, PgLwLock, PgSharedHashMap, PgSharedMemoryInitialization};
 use std::sync::atomic::AtomicBool;
 
 static ATOMIC: PgAtomic<AtomicBool> = PgAtomic::new();
 static LWLOCK: PgLwLock<bool> = PgLwLock::new();
+static HASH_MAP: PgSharedHashMap<i64, i64> = PgSharedHashMap::new(16);
 
 #[pg_guard]
 pub extern "C" fn _PG_init() {
     // This ensures that this functionality works across PostgreSQL versions
     pg_shmem_init!(ATOMIC);
     pg_shmem_init!(LWLOCK);
+    pg_shmem_init!(HASH_MAP);
 }
 #[cfg(any(test, feature = "pg_test"))]
 #[pgrx::pg_schema]
@@ -26,7 +28,7 @@ mod tests {
     #[allow(unused_imports)]
     use crate as pgrx_tests;
 
-    use super::LWLOCK;
+    use super::{HASH_MAP, LWLOCK};
     use pgrx::prelude::*;
 
     #[pg_test]
@@ -53,4 +55,37 @@ mod tests {
         });
         let _lock = LWLOCK.exclusive();
     }
+
+    #[pg_test]
+    pub fn test_shared_hash_map() {
+        assert_eq!(HASH_MAP.insert(1, 10), Ok(None));
+        assert_eq!(HASH_MAP.insert(1, 11), Ok(Some(10)));
+        assert_eq!(HASH_MAP.get(&1).as_deref(), Some(&11));
+        assert!(HASH_MAP.get(&2).is_none());
+
+        *HASH_MAP.get_mut(&1).unwrap() += 1;
+        *HASH_MAP.get_or_insert_with(2, || 20).unwrap() += 1;
+        assert_eq!(HASH_MAP.get(&1).as_deref(), Some(&12));
+        assert_eq!(HASH_MAP.get(&2).as_deref(), Some(&21));
+
+        let mut entries = vec![];
+        HASH_MAP.for_each(|k, v| entries.push((*k, *v)));
+        entries.sort();
+        assert_eq!(entries, vec![(1, 12), (2, 21)]);
+
+        HASH_MAP.retain(|k, _| *k != 2);
+        assert_eq!(HASH_MAP.len(), 1);
+        assert_eq!(HASH_MAP.remove(&1), Some(12));
+        assert_eq!(HASH_MAP.remove(&1), None);
+        assert!(HASH_MAP.is_empty());
+    }
+
+    #[pg_test]
+    pub fn test_shared_hash_map_insert_panics() {
+        let res = std::panic::catch_unwind(|| {
+            HASH_MAP.get_or_insert_with(100, || panic!("no value")).map(|_| ())
+        });
+        assert!(res.is_err());
+        assert!(HASH_MAP.get(&100).is_none());
+    }
 }
@@ -0,0 +1,150 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+use pgrx::prelude::*;
+use pgrx::support::SupportRequest;
+use pgrx::Internal;
+
+#[pg_extern(immutable, parallel_safe)]
+fn ten_rows_support(request: SupportRequest) -> Internal {
+    match request {
+        SupportRequest::Rows(mut rows) => {
+            rows.set_rows(10.0);
+            rows.handled()
+        }
+        _ => SupportRequest::unhandled(),
+    }
+}
+
+#[pg_extern(immutable, parallel_safe, support = ten_rows_support)]
+fn supported_digits() -> SetOfIterator<'static, i32> {
+    SetOfIterator::new(0..10)
+}
+
+#[pg_extern(immutable, parallel_safe, support = "pg_catalog.generate_series_int4_support")]
+fn supported_series(start: i32, finish: i32) -> SetOfIterator<'static, i32> {
+    SetOfIterator::new(start..=finish)
+}
+
+#[pg_extern(immutable, parallel_safe)]
+fn add_one_support(request: SupportRequest) -> Internal {
+    match request {
+        SupportRequest::Simplify(simplify) => match simplify.const_arg::<i32>(0) {
+            Some(value) => simplify.replace_with_const(Some(value + 1)),
+            None => SupportRequest::unhandled(),
+        },
+        _ => SupportRequest::unhandled(),
+    }
+}
+
+// volatile, or Postgres would fold calls with constant arguments itself
+#[pg_extern(volatile, support = add_one_support)]
+fn supported_add_one(value: i32) -> i32 {
+    value + 1
+}
+
+#[pg_extern(immutable, parallel_safe)]
+fn rare_support(request: SupportRequest) -> Internal {
+    match request {
+        SupportRequest::Selectivity(mut selectivity) => {
+            selectivity.set_selectivity(0.1);
+            selectivity.handled()
+        }
+        _ => SupportRequest::unhandled(),
+    }
+}
+
+#[pg_extern(immutable, parallel_safe, support = rare_support)]
+fn supported_is_rare(value: i32) -> bool {
+    value % 10 == 0
+}
+
+#[pg_extern(immutable, parallel_safe, rows = 42)]
+fn rows_attribute() -> SetOfIterator<'static, i32> {
+    SetOfIterator::new(0..42)
+}
+
+#[pg_extern(immutable, parallel_safe, leakproof)]
+fn leakproof_attribute(value: i32) -> i32 {
+    value
+}
+
+#[pg_extern(set(work_mem = "123kB"), set("pgrx_tests.setting" = "it's set"))]
+fn set_attribute() -> Option<String> {
+    Spi::get_one::<String>(
+        "SELECT current_setting('work_mem') || ', ' || current_setting('pgrx_tests.setting')",
+    )
+    .unwrap()
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+#[pgrx::pg_schema]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgrx_tests;
+
+    use pgrx::prelude::*;
+    use pgrx::Json;
+
+    fn explain(query: &str) -> Result<serde_json::Value, spi::Error> {
+        let Json(plan) = Spi::get_one::<Json>(&format!("EXPLAIN (VERBOSE, FORMAT JSON) {query}"))?
+            .expect("EXPLAIN returned NULL");
+        Ok(plan[0]["Plan"].clone())
+    }
+
+    #[pg_test]
+    fn test_support_estimates_rows() -> Result<(), spi::Error> {
+        let plan = explain("SELECT * FROM supported_digits()")?;
+        assert_eq!(plan["Plan Rows"], 10);
+        Ok(())
+    }
+
+    #[pg_test]
+    fn test_support_by_sql_name() -> Result<(), spi::Error> {
+        let plan = explain("SELECT * FROM supported_series(1, 25)")?;
+        assert_eq!(plan["Plan Rows"], 25);
+        Ok(())
+    }
+
+    #[pg_test]
+    fn test_support_simplifies() -> Result<(), spi::Error> {
+        let plan = explain("SELECT supported_add_one(41)")?;
+        assert_eq!(plan["Output"][0], "42");
+        assert_eq!(Spi::get_one::<i32>("SELECT supported_add_one(41)")?, Some(42));
+        Ok(())
+    }
+
+    #[pg_test]
+    fn test_support_estimates_selectivity() -> Result<(), spi::Error> {
+        let plan =
+            explain("SELECT * FROM generate_series(1, 1000) value WHERE supported_is_rare(value)")?;
+        assert_eq!(plan["Plan Rows"], 100);
+        Ok(())
+    }
+
+    #[pg_test]
+    fn test_rows_and_leakproof_attributes() -> Result<(), spi::Error> {
+        let prorows = Spi::get_one::<f32>(
+            "SELECT prorows FROM pg_proc WHERE oid = 'rows_attribute'::regproc",
+        )?;
+        assert_eq!(prorows, Some(42.0));
+        let proleakproof = Spi::get_one::<bool>(
+            "SELECT proleakproof FROM pg_proc WHERE oid = 'leakproof_attribute'::regproc",
+        )?;
+        assert_eq!(proleakproof, Some(true));
+        Ok(())
+    }
+
+    #[pg_test]
+    fn test_set_attribute() -> Result<(), spi::Error> {
+        let settings = Spi::get_one::<String>("SELECT set_attribute()")?;
+        assert_eq!(settings.as_deref(), Some("123kB, it's set"));
+        Ok(())
+    }
+}
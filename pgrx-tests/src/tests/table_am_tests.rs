@@ -0,0 +1,225 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+#[cfg(any(test, feature = "pg_test"))]
+#[pgrx::pg_schema]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgrx_tests;
+    use pgrx::itemptr::{item_pointer_to_u64, u64_to_item_pointer};
+    use pgrx::prelude::*;
+    use pgrx::table_am::{create_storage, slot_values, store_values, RelFileLocator, TupleModify};
+    use pgrx::PgRelation;
+    use std::cell::RefCell;
+    use std::collections::{BTreeMap, HashMap};
+    use std::ffi::c_char;
+
+    type Row = (Vec<pg_sys::Datum>, Vec<bool>);
+
+    thread_local! {
+        // the rows of every `memory_table` table, keyed by the table's oid and then each row's TID
+        static TABLES: RefCell<HashMap<pg_sys::Oid, BTreeMap<u64, Row>>> =
+            RefCell::new(HashMap::new());
+    }
+
+    fn tid(key: u64) -> pg_sys::ItemPointerData {
+        let mut tid = pg_sys::ItemPointerData::default();
+        u64_to_item_pointer(key, &mut tid);
+        tid
+    }
+
+    /// Stores rows of pass-by-value columns in backend memory
+    pub struct MemoryTable;
+
+    pub struct MemoryTableScan {
+        relid: pg_sys::Oid,
+        rows: Vec<(u64, Row)>,
+        position: usize,
+    }
+
+    impl MemoryTable {
+        fn store(relation: &PgRelation, key: Option<u64>, slot: &mut pg_sys::TupleTableSlot) {
+            let (values, isnull) = slot_values(slot);
+            let row = (values.to_vec(), isnull.to_vec());
+            let key = TABLES.with(|tables| {
+                let mut tables = tables.borrow_mut();
+                let table = tables.entry(relation.oid()).or_default();
+                // start at one so every TID has a non-zero offset number
+                let key = key.unwrap_or_else(|| table.keys().last().map_or(1, |last| last + 1));
+                table.insert(key, row);
+                key
+            });
+            slot.tts_tid = tid(key);
+        }
+    }
+
+    #[pg_table_am]
+    impl TableAccessMethod for MemoryTable {
+        type ScanState = MemoryTableScan;
+
+        fn begin_scan(
+            relation: &PgRelation,
+            _snapshot: pg_sys::Snapshot,
+            _keys: &[pg_sys::ScanKeyData],
+        ) -> MemoryTableScan {
+            MemoryTableScan { relid: relation.oid(), rows: Vec::new(), position: 0 }
+        }
+
+        fn rescan(state: &mut MemoryTableScan, _keys: &[pg_sys::ScanKeyData]) {
+            state.rows.clear();
+            state.position = 0;
+        }
+
+        fn next_slot(
+            state: &mut MemoryTableScan,
+            _direction: pg_sys::ScanDirection::Type,
+            slot: &mut pg_sys::TupleTableSlot,
+        ) -> bool {
+            if state.position == 0 {
+                // take our copy of the rows on the first call, so the scan sees the table as it
+                // was when the executor started asking for rows
+                state.rows = TABLES.with(|tables| {
+                    let tables = tables.borrow();
+                    let rows = tables.get(&state.relid).into_iter().flatten();
+                    rows.map(|(key, row)| (*key, row.clone())).collect()
+                });
+            }
+            let Some((key, (values, isnull))) = state.rows.get(state.position) else {
+                return false;
+            };
+            state.position += 1;
+            store_values(slot, tid(*key), values, isnull);
+            true
+        }
+
+        fn insert(
+            relation: &PgRelation,
+            slot: &mut pg_sys::TupleTableSlot,
+            _cid: pg_sys::CommandId,
+            _options: i32,
+        ) {
+            Self::store(relation, None, slot);
+        }
+
+        fn delete(
+            relation: &PgRelation,
+            tid: pg_sys::ItemPointerData,
+            _modify: TupleModify<'_>,
+        ) -> pg_sys::TM_Result::Type {
+            let key = item_pointer_to_u64(tid);
+            let removed = TABLES.with(|tables| {
+                tables.borrow_mut().get_mut(&relation.oid()).and_then(|table| table.remove(&key))
+            });
+            match removed {
+                Some(_) => pg_sys::TM_Result::TM_Ok,
+                None => pg_sys::TM_Result::TM_Deleted,
+            }
+        }
+
+        fn update(
+            relation: &PgRelation,
+            otid: pg_sys::ItemPointerData,
+            slot: &mut pg_sys::TupleTableSlot,
+            _modify: TupleModify<'_>,
+        ) -> pg_sys::TM_Result::Type {
+            // rows keep their TID, as there's only ever one version of them
+            Self::store(relation, Some(item_pointer_to_u64(otid)), slot);
+            pg_sys::TM_Result::TM_Ok
+        }
+
+        fn fetch_row_version(
+            relation: &PgRelation,
+            tid: pg_sys::ItemPointerData,
+            _snapshot: pg_sys::Snapshot,
+            slot: &mut pg_sys::TupleTableSlot,
+        ) -> bool {
+            let row = TABLES.with(|tables| {
+                let tables = tables.borrow();
+                tables.get(&relation.oid())?.get(&item_pointer_to_u64(tid)).cloned()
+            });
+            match row {
+                Some((values, isnull)) => {
+                    store_values(slot, tid, &values, &isnull);
+                    true
+                }
+                None => false,
+            }
+        }
+
+        fn set_new_filelocator(
+            relation: &PgRelation,
+            locator: &RelFileLocator,
+            persistence: c_char,
+        ) {
+            TABLES.with(|tables| tables.borrow_mut().remove(&relation.oid()));
+            create_storage(locator, persistence);
+        }
+
+        fn truncate(relation: &PgRelation) {
+            TABLES.with(|tables| tables.borrow_mut().remove(&relation.oid()));
+        }
+    }
+
+    #[pg_test]
+    fn test_table_am_is_registered() -> Result<(), spi::Error> {
+        let amtype =
+            Spi::get_one::<i8>("SELECT amtype::\"char\" FROM pg_am WHERE amname = 'memory_table'")?;
+        assert_eq!(amtype, Some(b't' as i8));
+        Ok(())
+    }
+
+    #[pg_test]
+    fn test_table_am_insert_and_scan() -> Result<(), spi::Error> {
+        Spi::run("CREATE TABLE memory_table_test (a int4, b int8) USING memory_table")?;
+        Spi::run("INSERT INTO memory_table_test SELECT i, i * 10 FROM generate_series(1, 10) i")?;
+        Spi::run("INSERT INTO memory_table_test VALUES (11, NULL)")?;
+        assert_eq!(Spi::get_one::<i64>("SELECT sum(a) FROM memory_table_test")?, Some(66));
+        assert_eq!(Spi::get_one::<i64>("SELECT count(b) FROM memory_table_test")?, Some(10));
+        assert_eq!(Spi::get_one::<i64>("SELECT b FROM memory_table_test WHERE a = 3")?, Some(30));
+        Ok(())
+    }
+
+    #[pg_test]
+    fn test_table_am_truncate() -> Result<(), spi::Error> {
+        Spi::run("CREATE TABLE memory_table_truncate_test (a int4) USING memory_table")?;
+        Spi::run("INSERT INTO memory_table_truncate_test SELECT generate_series(1, 10)")?;
+        Spi::run("TRUNCATE memory_table_truncate_test")?;
+        assert_eq!(
+            Spi::get_one::<i64>("SELECT count(*) FROM memory_table_truncate_test")?,
+            Some(0)
+        );
+        Ok(())
+    }
+
+    #[pg_test]
+    fn test_table_am_update_and_delete() -> Result<(), spi::Error> {
+        Spi::run("CREATE TABLE memory_table_modify_test (a int4, b int4) USING memory_table")?;
+        Spi::run("INSERT INTO memory_table_modify_test SELECT i, i FROM generate_series(1, 5) i")?;
+        Spi::run("UPDATE memory_table_modify_test SET b = b * 100 WHERE a = 2")?;
+        Spi::run("DELETE FROM memory_table_modify_test WHERE a > 3")?;
+        assert_eq!(
+            Spi::get_one::<String>(
+                "SELECT string_agg(a || '=' || b, ',' ORDER BY a) FROM memory_table_modify_test"
+            )?,
+            Some("1=1,2=200,3=3".into())
+        );
+        Ok(())
+    }
+
+    #[pg_test(error = "unlogged tables are not supported by this table access method")]
+    fn test_table_am_rejects_unlogged_tables() -> Result<(), spi::Error> {
+        Spi::run("CREATE UNLOGGED TABLE memory_table_unlogged_test (a int4) USING memory_table")
+    }
+
+    #[pg_test(error = "table access method \"memory_table\" does not support indexes")]
+    fn test_table_am_rejects_indexes() -> Result<(), spi::Error> {
+        Spi::run("CREATE TABLE memory_table_index_test (a int4) USING memory_table")?;
+        Spi::run("CREATE INDEX ON memory_table_index_test (a)")
+    }
+}
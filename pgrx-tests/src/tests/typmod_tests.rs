@@ -0,0 +1,165 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+use core::ffi::CStr;
+use pgrx::datum::Typmod;
+use pgrx::inoutfuncs::PgTypmodFuncs;
+use pgrx::prelude::*;
+use pgrx::StringInfo;
+use serde::{Deserialize, Serialize};
+use std::ffi::CString;
+
+/// Parse the single positive integer modifier of `fixedvector(3)` and `roundedint(3)`
+fn single_modifier(modifiers: &[&CStr]) -> Typmod {
+    match modifiers {
+        [modifier] => match modifier.to_str().ok().and_then(|m| m.parse::<i32>().ok()) {
+            Some(typmod) if typmod > 0 => Typmod::from_raw(typmod),
+            _ => error!("type modifier must be a positive integer"),
+        },
+        _ => error!("expected exactly one type modifier"),
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize, PostgresType)]
+#[typmodfuncs]
+pub struct FixedVector(Vec<f32>);
+
+impl PgTypmodFuncs for FixedVector {
+    fn typmod_in(modifiers: &[&CStr]) -> Typmod {
+        single_modifier(modifiers)
+    }
+
+    fn typmod_out(typmod: Typmod) -> CString {
+        CString::new(format!("({})", typmod.as_raw())).unwrap()
+    }
+
+    fn coerce_typmod(self, typmod: Typmod, _explicit: bool) -> Self {
+        match typmod.get() {
+            Some(dims) if dims as usize != self.0.len() => {
+                error!("expected {dims} dimensions, not {}", self.0.len())
+            }
+            _ => self,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize, PostgresType)]
+#[pgvarlena_inoutfuncs]
+#[typmodfuncs]
+pub struct RoundedInt(i64);
+
+impl PgVarlenaInOutFuncs for RoundedInt {
+    fn input(input: &CStr) -> PgVarlena<Self> {
+        let mut result = PgVarlena::<Self>::new();
+        result.0 = input.to_str().unwrap().parse().expect("not a valid i64");
+        result
+    }
+
+    fn output(&self, buffer: &mut StringInfo) {
+        buffer.push_str(&self.0.to_string())
+    }
+}
+
+impl PgTypmodFuncs for RoundedInt {
+    fn typmod_in(modifiers: &[&CStr]) -> Typmod {
+        single_modifier(modifiers)
+    }
+
+    fn typmod_out(typmod: Typmod) -> CString {
+        CString::new(format!("({})", typmod.as_raw())).unwrap()
+    }
+
+    /// Round to a multiple of the typmod
+    fn coerce_typmod(self, typmod: Typmod, _explicit: bool) -> Self {
+        match typmod.get() {
+            Some(multiple) => RoundedInt(self.0 / multiple as i64 * multiple as i64),
+            None => self,
+        }
+    }
+}
+
+#[pg_extern]
+fn typmod_or_none(typmod: Typmod) -> Option<i32> {
+    typmod.get()
+}
+
+#[cfg(any(test, feature = "pg_test"))]
+#[pgrx::pg_schema]
+mod tests {
+    #[allow(unused_imports)]
+    use crate as pgrx_tests;
+
+    use super::*;
+
+    #[pg_test]
+    fn test_typmod_in_out() -> Result<(), spi::Error> {
+        Spi::run("CREATE TABLE fixed_vectors (v FixedVector(3))")?;
+        let column_type = Spi::get_one::<String>(
+            "SELECT format_type(atttypid, atttypmod) FROM pg_attribute \
+             WHERE attrelid = 'fixed_vectors'::regclass AND attname = 'v'",
+        )?;
+        assert_eq!(column_type.as_deref(), Some("fixedvector(3)"));
+        Ok(())
+    }
+
+    #[pg_test(error = "type modifier must be a positive integer")]
+    fn test_typmod_in_rejects_invalid_modifier() -> Result<(), spi::Error> {
+        Spi::run("CREATE TABLE invalid_vectors (v FixedVector(0))")
+    }
+
+    #[pg_test]
+    fn test_typmod_has_length_coercion_cast() -> Result<(), spi::Error> {
+        let context = Spi::get_one::<String>(
+            "SELECT castcontext::text FROM pg_cast \
+             WHERE castsource = 'fixedvector'::regtype AND casttarget = 'fixedvector'::regtype",
+        )?;
+        assert_eq!(context.as_deref(), Some("i"));
+        Ok(())
+    }
+
+    #[pg_test]
+    fn test_typmod_coerces_on_insert() -> Result<(), spi::Error> {
+        Spi::run("CREATE TABLE inserted_vectors (v FixedVector(3))")?;
+        Spi::run("INSERT INTO inserted_vectors VALUES ('[1.0, 2.0, 3.0]')")?;
+        let vector = Spi::get_one::<FixedVector>("SELECT v FROM inserted_vectors")?;
+        assert_eq!(vector, Some(FixedVector(vec![1.0, 2.0, 3.0])));
+        Ok(())
+    }
+
+    #[pg_test(error = "expected 3 dimensions, not 2")]
+    fn test_typmod_coercion_rejects_wrong_dimensions() -> Result<(), spi::Error> {
+        Spi::run("CREATE TABLE short_vectors (v FixedVector(3))")?;
+        Spi::run("INSERT INTO short_vectors VALUES ('[1.0, 2.0]')")
+    }
+
+    #[pg_test(error = "expected 3 dimensions, not 4")]
+    fn test_typmod_coercion_applies_to_copy() -> Result<(), spi::Error> {
+        // `COPY FROM` passes the column's typmod to the input function, without a cast
+        Spi::run("CREATE TABLE copied_vectors (v FixedVector(3))")?;
+        Spi::run("COPY copied_vectors FROM PROGRAM 'echo ''[1.0,2.0,3.0]'''")?;
+        assert_eq!(Spi::get_one::<i64>("SELECT count(*) FROM copied_vectors")?, Some(1));
+        Spi::run("COPY copied_vectors FROM PROGRAM 'echo ''[1.0,2.0,3.0,4.0]'''")
+    }
+
+    #[pg_test]
+    fn test_pgvarlena_typmod_coerces_on_cast() -> Result<(), spi::Error> {
+        let rounded = Spi::get_one::<PgVarlena<RoundedInt>>("SELECT '1234'::RoundedInt(100)")?;
+        assert_eq!(rounded.map(|rounded| *rounded), Some(RoundedInt(1200)));
+        let unmodified = Spi::get_one::<PgVarlena<RoundedInt>>("SELECT '1234'::RoundedInt")?;
+        assert_eq!(unmodified.map(|unmodified| *unmodified), Some(RoundedInt(1234)));
+        Ok(())
+    }
+
+    #[pg_test]
+    fn test_typmod_arg() -> Result<(), spi::Error> {
+        assert_eq!(Spi::get_one::<i32>("SELECT typmod_or_none(16)")?, Some(16));
+        assert_eq!(Spi::get_one::<i32>("SELECT typmod_or_none(-1)")?, None);
+        Ok(())
+    }
+}
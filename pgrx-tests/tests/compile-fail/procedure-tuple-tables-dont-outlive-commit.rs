@@ -0,0 +1,15 @@
+use pgrx::prelude::*;
+
+fn oops() -> Result<(), pgrx::spi::Error> {
+    Spi::connect_nonatomic(|client| {
+        let mut dogs = client.select("SELECT 'Nami'", None, &[])?;
+        client.commit();
+        // the tuple table was freed by the commit
+        dogs.next();
+        Ok(())
+    })
+}
+
+fn main() {
+    oops().unwrap();
+}
@@ -279,7 +279,7 @@ unsafe extern "C" fn worker_spi_sigchld(_signal_args: i32) {
 
 /// Dynamic background worker handle
 pub struct DynamicBackgroundWorker {
-    handle: *mut pg_sys::BackgroundWorkerHandle,
+    pub(crate) handle: *mut pg_sys::BackgroundWorkerHandle,
     notify_pid: pg_sys::pid_t,
 }
 
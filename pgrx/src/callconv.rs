@@ -12,7 +12,7 @@
 
 use crate::datum::{
     AnyArray, AnyElement, AnyNumeric, Date, FromDatum, Inet, Internal, Interval, IntoDatum, Json,
-    JsonB, Numeric, PgVarlena, Time, TimeWithTimeZone, Timestamp, TimestampWithTimeZone,
+    JsonB, Numeric, PgVarlena, Time, TimeWithTimeZone, Timestamp, TimestampWithTimeZone, Typmod,
     UnboxDatum, Uuid,
 };
 use crate::datum::{BorrowDatum, Datum};
@@ -264,7 +264,7 @@ macro_rules! argue_from_datum {
 argue_from_datum! { 'fcx; i8, i16, i32, i64, f32, f64, bool, char, String, Vec<u8> }
 argue_from_datum! { 'fcx; Date, Interval, Time, TimeWithTimeZone, Timestamp, TimestampWithTimeZone }
 argue_from_datum! { 'fcx; AnyArray, AnyElement, AnyNumeric }
-argue_from_datum! { 'fcx; Inet, Internal, Json, JsonB, Uuid, PgRelation }
+argue_from_datum! { 'fcx; Inet, Internal, Json, JsonB, Typmod, Uuid, PgRelation }
 argue_from_datum! { 'fcx; pg_sys::BOX, pg_sys::ItemPointerData, pg_sys::Oid, pg_sys::Point }
 // We could use the upcoming impl of ArgAbi for `&'fcx T where T: ?Sized + BorrowDatum`
 // to support these types by implementing BorrowDatum for them also, but we reject this.
@@ -564,7 +564,7 @@ macro_rules! impl_repackage_into_datum {
 
 impl_repackage_into_datum! {
     String, CString, Vec<u8>, char,
-    Json, JsonB, Inet, Uuid, AnyNumeric, AnyArray, AnyElement, Internal,
+    Json, JsonB, Inet, Typmod, Uuid, AnyNumeric, AnyArray, AnyElement, Internal,
     Date, Interval, Time, TimeWithTimeZone, Timestamp, TimestampWithTimeZone,
     pg_sys::BOX, pg_sys::ItemPointerData, pg_sys::Oid, pg_sys::Point
 }
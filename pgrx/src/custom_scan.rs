@@ -0,0 +1,445 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+/*!
+
+[Custom scan provider](https://www.postgresql.org/docs/current/custom-scan.html) support.
+
+Custom scans are created by implementing [`CustomScan`] for a type, and offered to the planner by
+calling [`add_path`] from a `set_rel_pathlist_hook` or `set_join_pathlist_hook`, such as
+[`PgHooks::set_rel_pathlist`](crate::hooks::PgHooks::set_rel_pathlist).  When the planner picks one
+of those paths, pgrx plans it into a `CustomScan` node and runs it by calling
+[`CustomScan::next`] until it runs out of rows.
+
+Data describing a path is its [`CustomScan::Private`] value.  It's kept in the plan as JSON, as
+plans are copied and sent to parallel workers, and handed back to [`CustomScan::begin_scan`] when
+the plan is executed.
+
+# Example
+
+```rust,no_run
+use pgrx::custom_scan::{add_path, CustomPathEstimate, CustomScan, Explain, ScanSlot};
+use pgrx::prelude::*;
+use pgrx::PgRelation;
+use std::ffi::CStr;
+
+pub struct Countdown;
+
+impl CustomScan for Countdown {
+    const NAME: &'static CStr = c"Countdown";
+    type Private = i32;
+    type State = i32;
+
+    fn begin_scan(from: i32, _relation: Option<&PgRelation>, _eflags: i32) -> i32 {
+        from
+    }
+
+    fn next(state: &mut i32, slot: &mut ScanSlot) -> bool {
+        if *state == 0 {
+            return false;
+        }
+        slot.store_row([state.into_datum()]);
+        *state -= 1;
+        true
+    }
+
+    fn explain(state: &i32, explain: &mut Explain) {
+        explain.property_integer("Remaining", None, (*state).into());
+    }
+}
+
+// from a `set_rel_pathlist_hook`
+fn offer_countdown(rel: &mut pg_sys::RelOptInfo) {
+    add_path::<Countdown>(rel, 10, CustomPathEstimate::default());
+}
+```
+*/
+use crate as pgrx; // for #[pg_guard] support from within ourself
+use crate::list::List;
+use crate::{
+    memcx, pg_guard, pg_sys, pg_sys::AsPgCStr, PgBox, PgMemoryContexts, PgRelation, PgTupleDesc,
+};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::ffi::{c_int, CStr};
+use std::marker::PhantomData;
+use std::ptr;
+
+/// The planner's estimate for a custom path, given to [`add_path`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct CustomPathEstimate {
+    /// The number of rows the scan returns after its restriction clauses are applied.
+    pub rows: f64,
+    pub startup_cost: pg_sys::Cost,
+    pub total_cost: pg_sys::Cost,
+}
+
+impl Default for CustomPathEstimate {
+    /// A thousand rows, at one cost unit each after a startup cost of ten.
+    fn default() -> Self {
+        Self { rows: 1000.0, startup_cost: 10.0, total_cost: 1010.0 }
+    }
+}
+
+/// A Postgres custom scan provider.
+///
+/// See the [module documentation](self) for an example.
+pub trait CustomScan: Sized + 'static {
+    /// The name of the provider, which EXPLAIN shows as `Custom Scan (NAME)`.  It must be unique
+    /// among the custom scan providers loaded into a backend.
+    const NAME: &'static CStr;
+
+    /// The `CUSTOMPATH_SUPPORT_*` flags of the provider's paths.
+    const FLAGS: u32 = 0;
+
+    /// Data describing a path, given to [`add_path`] and handed back to
+    /// [`CustomScan::begin_scan`].
+    type Private: Serialize + DeserializeOwned;
+
+    /// Per-scan state created by [`CustomScan::begin_scan`] and dropped after
+    /// [`CustomScan::end_scan`], or when the query's memory context is deleted.
+    type State;
+
+    /// Adjust the `CustomScan` plan node pgrx made for one of the provider's paths.
+    ///
+    /// The node scans the path's relation, with `tlist` as its target list and the path's
+    /// restriction clauses as its quals.  Paths of joins have no relation to scan, so their
+    /// providers must at least set the node's `custom_scan_tlist` to describe the rows returned
+    /// by [`CustomScan::next`].
+    fn plan(
+        _plan: &mut pg_sys::CustomScan,
+        _root: *mut pg_sys::PlannerInfo,
+        _rel: &pg_sys::RelOptInfo,
+        _tlist: *mut pg_sys::List,
+    ) {
+    }
+
+    /// Start executing a scan.  `relation` is the relation being scanned, unless the scan is of a
+    /// join.
+    ///
+    /// `eflags` are the executor's `EXEC_FLAG_*` flags.  `EXEC_FLAG_EXPLAIN_ONLY` is set when the
+    /// scan is only started so that `EXPLAIN` can describe it.
+    fn begin_scan(
+        private: Self::Private,
+        relation: Option<&PgRelation>,
+        eflags: i32,
+    ) -> Self::State;
+
+    /// Store the scan's next row in `slot`, returning `false` once there are no more.
+    ///
+    /// Rows are checked against the scan's quals, and projected to its target list, by Postgres.
+    fn next(state: &mut Self::State, slot: &mut ScanSlot) -> bool;
+
+    /// Start the scan over.
+    fn rescan(_state: &mut Self::State) {}
+
+    fn end_scan(_state: Self::State) {}
+
+    /// Describe a scan in `EXPLAIN`'s output, beyond the name of the provider.
+    fn explain(_state: &Self::State, _explain: &mut Explain) {}
+}
+
+/// The tuple table slot a [`CustomScan`] stores its rows in.
+pub struct ScanSlot<'a> {
+    slot: &'a mut pg_sys::TupleTableSlot,
+}
+
+impl ScanSlot<'_> {
+    /// The descriptor of the rows the slot holds.
+    pub fn tuple_desc(&self) -> PgTupleDesc<'_> {
+        // SAFETY: every slot Postgres hands a scan has a tuple descriptor
+        unsafe { PgTupleDesc::from_pg_unchecked(self.slot.tts_tupleDescriptor) }
+    }
+
+    /// Store a row with the given attribute values, where `None` is NULL.  Attributes past the
+    /// end of `values` are set to NULL.
+    ///
+    /// # Panics
+    ///
+    /// If there are more values than the slot has attributes.
+    pub fn store_row<I: IntoIterator<Item = Option<pg_sys::Datum>>>(&mut self, values: I) {
+        // SAFETY: the slot's arrays have room for every attribute in its tuple descriptor
+        unsafe {
+            let natts = (*self.slot.tts_tupleDescriptor).natts.max(0) as usize;
+            self.clear();
+            let datums = std::slice::from_raw_parts_mut(self.slot.tts_values, natts);
+            let isnull = std::slice::from_raw_parts_mut(self.slot.tts_isnull, natts);
+            datums.fill(pg_sys::Datum::from(0));
+            isnull.fill(true);
+
+            let mut values = values.into_iter();
+            for (datum, isnull) in datums.iter_mut().zip(isnull.iter_mut()) {
+                match values.next() {
+                    Some(Some(value)) => (*datum, *isnull) = (value, false),
+                    Some(None) => {}
+                    None => break,
+                }
+            }
+            assert!(values.next().is_none(), "slot only has {natts} attributes");
+            pg_sys::ExecStoreVirtualTuple(self.slot);
+        }
+    }
+
+    /// Empty the slot.
+    pub fn clear(&mut self) {
+        // SAFETY: every slot has its ops
+        unsafe {
+            if let Some(clear) = (*self.slot.tts_ops).clear {
+                clear(self.slot);
+            }
+        }
+    }
+
+    pub fn as_ptr(&mut self) -> *mut pg_sys::TupleTableSlot {
+        self.slot
+    }
+}
+
+/// Where a [`CustomScan`] describes itself for `EXPLAIN`.
+pub struct Explain<'a> {
+    es: &'a mut pg_sys::ExplainState,
+}
+
+impl Explain<'_> {
+    /// Set for `EXPLAIN (VERBOSE)`.
+    pub fn verbose(&self) -> bool {
+        self.es.verbose
+    }
+
+    /// Set for `EXPLAIN (ANALYZE)`.
+    pub fn analyze(&self) -> bool {
+        self.es.analyze
+    }
+
+    pub fn property_text(&mut self, label: &str, value: &str) {
+        // SAFETY: Postgres copies the strings, which are freed along with the EXPLAIN's memory
+        unsafe { pg_sys::ExplainPropertyText(label.as_pg_cstr(), value.as_pg_cstr(), self.es) }
+    }
+
+    pub fn property_integer(&mut self, label: &str, unit: Option<&str>, value: i64) {
+        // SAFETY: as above
+        unsafe {
+            pg_sys::ExplainPropertyInteger(label.as_pg_cstr(), unit.as_pg_cstr(), value, self.es)
+        }
+    }
+
+    /// Show `value` with `ndigits` digits after the decimal point.
+    pub fn property_float(&mut self, label: &str, unit: Option<&str>, value: f64, ndigits: i32) {
+        // SAFETY: as above
+        unsafe {
+            pg_sys::ExplainPropertyFloat(
+                label.as_pg_cstr(),
+                unit.as_pg_cstr(),
+                value,
+                ndigits,
+                self.es,
+            )
+        }
+    }
+
+    pub fn property_bool(&mut self, label: &str, value: bool) {
+        // SAFETY: as above
+        unsafe { pg_sys::ExplainPropertyBool(label.as_pg_cstr(), value, self.es) }
+    }
+
+    pub fn as_ptr(&mut self) -> *mut pg_sys::ExplainState {
+        self.es
+    }
+}
+
+/// Offer the planner a path which scans `rel` with the custom scan provider `T`.
+///
+/// Call this from a `set_rel_pathlist_hook` for the base relation `rel`, or from a
+/// `set_join_pathlist_hook` for the join relation `rel`.  The path is unparameterized, unordered,
+/// and can't be run by parallel workers.
+pub fn add_path<T: CustomScan>(
+    rel: &mut pg_sys::RelOptInfo,
+    private: T::Private,
+    estimate: CustomPathEstimate,
+) {
+    let private = serde_json::to_string(&private).expect("failed to serialize custom path data");
+    // SAFETY: the planner gave us `rel`, and we're allocating our path in its memory context
+    unsafe {
+        let path = PgBox::<pg_sys::CustomPath>::alloc_node(pg_sys::NodeTag::T_CustomPath).into_pg();
+        (*path).path.pathtype = pg_sys::NodeTag::T_CustomScan;
+        (*path).path.parent = rel;
+        (*path).path.pathtarget = rel.reltarget;
+        (*path).path.rows = estimate.rows;
+        (*path).path.startup_cost = estimate.startup_cost;
+        (*path).path.total_cost = estimate.total_cost;
+        (*path).flags = T::FLAGS;
+        (*path).custom_private =
+            pg_sys::lappend(ptr::null_mut(), pg_sys::makeString(private.as_pg_cstr()).cast());
+        (*path).methods = &Methods::<T>::PATH;
+        pg_sys::add_path(rel, path.cast());
+    }
+}
+
+/// Make the custom scan provider `T` known to Postgres by its name, which parallel workers need
+/// to run its plans.
+///
+/// This must only be called once, typically from `_PG_init()`.
+pub fn register<T: CustomScan>() {
+    // SAFETY: the methods live as long as the library does
+    unsafe { pg_sys::RegisterCustomScanMethods(&Methods::<T>::SCAN) }
+}
+
+struct Methods<T>(PhantomData<T>);
+
+impl<T: CustomScan> Methods<T> {
+    const PATH: pg_sys::CustomPathMethods = pg_sys::CustomPathMethods {
+        CustomName: T::NAME.as_ptr(),
+        PlanCustomPath: Some(plan_custom_path::<T>),
+        ReparameterizeCustomPathByChild: None,
+    };
+
+    const SCAN: pg_sys::CustomScanMethods = pg_sys::CustomScanMethods {
+        CustomName: T::NAME.as_ptr(),
+        CreateCustomScanState: Some(create_custom_scan_state::<T>),
+    };
+
+    const EXEC: pg_sys::CustomExecMethods = pg_sys::CustomExecMethods {
+        CustomName: T::NAME.as_ptr(),
+        BeginCustomScan: Some(begin_custom_scan::<T>),
+        ExecCustomScan: Some(exec_custom_scan::<T>),
+        EndCustomScan: Some(end_custom_scan::<T>),
+        ReScanCustomScan: Some(rescan_custom_scan::<T>),
+        MarkPosCustomScan: None,
+        RestrPosCustomScan: None,
+        EstimateDSMCustomScan: None,
+        InitializeDSMCustomScan: None,
+        ReInitializeDSMCustomScan: None,
+        InitializeWorkerCustomScan: None,
+        ShutdownCustomScan: None,
+        ExplainCustomScan: Some(explain_custom_scan::<T>),
+    };
+}
+
+/// A `CustomScanState` with room for the scan's state.
+#[repr(C)]
+struct ScanNode<T: CustomScan> {
+    css: pg_sys::CustomScanState,
+    state: *mut Option<T::State>,
+}
+
+unsafe fn scan_state<'a, T: CustomScan>(node: *mut pg_sys::CustomScanState) -> &'a mut T::State {
+    let node = node.cast::<ScanNode<T>>();
+    (*(*node).state).as_mut().expect("custom scan has ended")
+}
+
+unsafe fn decode_private<T: CustomScan>(custom_private: *mut pg_sys::List) -> T::Private {
+    let private = memcx::current_context(|cx| {
+        let list = List::<*mut std::ffi::c_void>::downcast_ptr_in_memcx(custom_private, cx)?;
+        let value = *list.get(0)?;
+        #[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14"))]
+        let value = (*value.cast::<pg_sys::Value>()).val.str_;
+        #[cfg(not(any(feature = "pg12", feature = "pg13", feature = "pg14")))]
+        let value = (*value.cast::<pg_sys::String>()).sval;
+        Some(CStr::from_ptr(value).to_str().expect("custom path data is not UTF-8").to_owned())
+    });
+    let private = private.expect("custom scan plan is missing its data");
+    serde_json::from_str(&private).expect("failed to deserialize custom path data")
+}
+
+#[pg_guard]
+unsafe extern "C" fn plan_custom_path<T: CustomScan>(
+    root: *mut pg_sys::PlannerInfo,
+    rel: *mut pg_sys::RelOptInfo,
+    best_path: *mut pg_sys::CustomPath,
+    tlist: *mut pg_sys::List,
+    clauses: *mut pg_sys::List,
+    custom_plans: *mut pg_sys::List,
+) -> *mut pg_sys::Plan {
+    let mut plan = PgBox::<pg_sys::CustomScan>::alloc_node(pg_sys::NodeTag::T_CustomScan);
+    plan.scan.plan.targetlist = tlist;
+    plan.scan.plan.qual = pg_sys::extract_actual_clauses(clauses, false);
+    plan.scan.scanrelid = (*rel).relid;
+    plan.flags = (*best_path).flags;
+    plan.custom_plans = custom_plans;
+    plan.custom_private = (*best_path).custom_private;
+    plan.methods = &Methods::<T>::SCAN;
+    T::plan(&mut plan, root, &*rel, tlist);
+    plan.into_pg().cast()
+}
+
+#[pg_guard]
+unsafe extern "C" fn create_custom_scan_state<T: CustomScan>(
+    _cscan: *mut pg_sys::CustomScan,
+) -> *mut pg_sys::Node {
+    let node = pg_sys::palloc0(std::mem::size_of::<ScanNode<T>>()).cast::<ScanNode<T>>();
+    (*node).css.ss.ps.type_ = pg_sys::NodeTag::T_CustomScanState;
+    (*node).css.methods = &Methods::<T>::EXEC;
+    node.cast()
+}
+
+#[pg_guard]
+unsafe extern "C" fn begin_custom_scan<T: CustomScan>(
+    node: *mut pg_sys::CustomScanState,
+    _estate: *mut pg_sys::EState,
+    eflags: c_int,
+) {
+    let plan = (*node).ss.ps.plan.cast::<pg_sys::CustomScan>();
+    let private = decode_private::<T>((*plan).custom_private);
+    let relation = (*node).ss.ss_currentRelation;
+    let relation = (!relation.is_null()).then(|| PgRelation::from_pg(relation));
+    let state = T::begin_scan(private, relation.as_ref(), eflags);
+    (*node.cast::<ScanNode<T>>()).state =
+        PgMemoryContexts::CurrentMemoryContext.leak_and_drop_on_delete(Some(state));
+}
+
+#[pg_guard]
+unsafe extern "C" fn exec_custom_scan<T: CustomScan>(
+    node: *mut pg_sys::CustomScanState,
+) -> *mut pg_sys::TupleTableSlot {
+    pg_sys::ExecScan(&mut (*node).ss, Some(next_tuple::<T>), Some(recheck_tuple))
+}
+
+#[pg_guard]
+unsafe extern "C" fn next_tuple<T: CustomScan>(
+    node: *mut pg_sys::ScanState,
+) -> *mut pg_sys::TupleTableSlot {
+    // the `ScanState` is the first field of the node's `CustomScanState`
+    let state = scan_state::<T>(node.cast());
+    let mut slot = ScanSlot { slot: &mut *(*node).ss_ScanTupleSlot };
+    if !T::next(state, &mut slot) {
+        slot.clear();
+    }
+    slot.as_ptr()
+}
+
+#[pg_guard]
+unsafe extern "C" fn recheck_tuple(
+    _node: *mut pg_sys::ScanState,
+    _slot: *mut pg_sys::TupleTableSlot,
+) -> bool {
+    // the scan's quals are all there is to recheck, and `ExecScan` does that itself
+    true
+}
+
+#[pg_guard]
+unsafe extern "C" fn rescan_custom_scan<T: CustomScan>(node: *mut pg_sys::CustomScanState) {
+    T::rescan(scan_state::<T>(node));
+}
+
+#[pg_guard]
+unsafe extern "C" fn end_custom_scan<T: CustomScan>(node: *mut pg_sys::CustomScanState) {
+    let state = (*node.cast::<ScanNode<T>>()).state;
+    if let Some(state) = state.as_mut().and_then(Option::take) {
+        T::end_scan(state);
+    }
+}
+
+#[pg_guard]
+unsafe extern "C" fn explain_custom_scan<T: CustomScan>(
+    node: *mut pg_sys::CustomScanState,
+    _ancestors: *mut pg_sys::List,
+    es: *mut pg_sys::ExplainState,
+) {
+    T::explain(scan_state::<T>(node), &mut Explain { es: &mut *es });
+}
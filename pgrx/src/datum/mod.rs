@@ -35,6 +35,7 @@ mod time_stamp;
 mod time_stamp_with_timezone;
 mod time_with_timezone;
 mod tuples;
+mod typmod;
 mod unbox;
 mod uuid;
 mod varlena;
@@ -59,6 +60,7 @@ pub use range::*;
 pub use time_stamp::*;
 pub use time_stamp_with_timezone::*;
 pub use time_with_timezone::*;
+pub use typmod::*;
 pub use unbox::*;
 pub use varlena::*;
 
@@ -0,0 +1,100 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+use crate::{pg_sys, FromDatum, IntoDatum};
+use pgrx_sql_entity_graph::metadata::{
+    ArgumentError, Returns, ReturnsError, SqlMapping, SqlTranslatable,
+};
+
+/// A type modifier, such as the `16` of `varchar(16)`
+///
+/// Postgres passes the typmod of the target type to a type's input function as its third argument,
+/// and to a length-coercion cast function as its second argument. A `#[pg_extern]` or `#[pg_cast]`
+/// function receives it by taking a `Typmod` in that position, which is declared as an `integer`.
+///
+/// Postgres uses `-1` to mean "no modifier", so only a non-negative typmod is an actual modifier.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[repr(transparent)]
+pub struct Typmod(i32);
+
+impl Typmod {
+    /// The typmod of a type without a modifier
+    pub const NONE: Typmod = Typmod(-1);
+
+    /// Wrap the typmod Postgres uses for a type, which is `-1` when it has no modifier
+    #[inline]
+    pub const fn from_raw(typmod: i32) -> Typmod {
+        Typmod(typmod)
+    }
+
+    /// The typmod as Postgres represents it
+    #[inline]
+    pub const fn as_raw(self) -> i32 {
+        self.0
+    }
+
+    /// The modifier, or `None` if the type has no modifier
+    #[inline]
+    pub const fn get(self) -> Option<i32> {
+        if self.0 >= 0 {
+            Some(self.0)
+        } else {
+            None
+        }
+    }
+}
+
+impl Default for Typmod {
+    fn default() -> Self {
+        Typmod::NONE
+    }
+}
+
+impl From<i32> for Typmod {
+    fn from(typmod: i32) -> Self {
+        Typmod(typmod)
+    }
+}
+
+impl From<Typmod> for i32 {
+    fn from(typmod: Typmod) -> Self {
+        typmod.0
+    }
+}
+
+impl FromDatum for Typmod {
+    #[inline]
+    unsafe fn from_polymorphic_datum(
+        datum: pg_sys::Datum,
+        is_null: bool,
+        typoid: pg_sys::Oid,
+    ) -> Option<Typmod> {
+        i32::from_polymorphic_datum(datum, is_null, typoid).map(Typmod)
+    }
+}
+
+impl IntoDatum for Typmod {
+    #[inline]
+    fn into_datum(self) -> Option<pg_sys::Datum> {
+        self.0.into_datum()
+    }
+
+    fn type_oid() -> pg_sys::Oid {
+        pg_sys::INT4OID
+    }
+}
+
+unsafe impl SqlTranslatable for Typmod {
+    fn argument_sql() -> Result<SqlMapping, ArgumentError> {
+        Ok(SqlMapping::literal("integer"))
+    }
+    fn return_sql() -> Result<Returns, ReturnsError> {
+        Ok(Returns::One(SqlMapping::literal("integer")))
+    }
+}
@@ -0,0 +1,499 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+/*!
+
+Dynamic shared memory, created and attached at runtime rather than sized in `_PG_init()`
+
+A [`DsmSegment`] is a single, fixed-size chunk of shared memory that other backends, such as
+dynamic background workers, can attach to by its [`DsmHandle`].  A [`DsaArea`] is an allocator
+built on top of dynamic shared memory: it hands out typed [`DsaPointer`]s and [`DsaSlice`]s, which
+are plain values that can themselves be stored in shared memory and resolved to an address in any
+backend attached to the same area.
+
+Unlike the types used with [`pg_shmem_init!`](crate::pg_shmem_init), neither requires the
+extension to be loaded via `shared_preload_libraries`.
+
+Both types keep their mapping for as long as the Rust value is alive, independent of Postgres'
+resource owners, and detach from it when dropped.  Any references handed out are tied to the
+lifetime of the value they came from.
+
+# Example
+
+```rust,no_run
+use pgrx::dsm::{DsaArea, DsaPointer};
+
+let area = DsaArea::create(c"my_extension_dsa");
+let counter: DsaPointer<u64> = area.allocate(0);
+
+// `area.handle()` and `counter` can be sent to another backend, which then does:
+let other = DsaArea::attach(area.handle());
+// SAFETY: `counter` was allocated from this same area, and hasn't been freed
+assert_eq!(unsafe { *other.get(counter) }, 0);
+```
+*/
+#![deny(unsafe_op_in_unsafe_fn)]
+use crate as pgrx; // for #[pg_guard] support from within ourself
+use crate::{pg_guard, pg_sys, PGRXSharedMemory};
+use core::ffi::CStr;
+use std::marker::PhantomData;
+use std::ptr::NonNull;
+
+/// Identifies a [`DsmSegment`] across backends
+pub type DsmHandle = pg_sys::dsm_handle;
+
+/// Identifies a [`DsaArea`] across backends
+pub type DsaHandle = pg_sys::dsa_handle;
+
+/// A dynamic shared memory segment
+///
+/// The segment is destroyed by Postgres once every backend has detached from it, unless it has
+/// been [pinned](DsmSegment::pin).
+#[derive(Debug)]
+pub struct DsmSegment {
+    seg: NonNull<pg_sys::dsm_segment>,
+}
+
+impl DsmSegment {
+    /// Create a new segment of `size` bytes
+    ///
+    /// # Panics
+    ///
+    /// Raises a Postgres `ERROR` if the system has run out of dynamic shared memory segments
+    pub fn create(size: usize) -> Self {
+        unsafe { Self::from_raw(pg_sys::dsm_create(size, 0)) }.unwrap()
+    }
+
+    /// Create a new segment of `size` bytes, returning `None` rather than raising an `ERROR` if
+    /// the system has run out of dynamic shared memory segments
+    pub fn try_create(size: usize) -> Option<Self> {
+        unsafe {
+            Self::from_raw(pg_sys::dsm_create(size, pg_sys::DSM_CREATE_NULL_IF_MAXSEGMENTS as _))
+        }
+    }
+
+    /// Attach to an existing segment, created by this or another backend
+    ///
+    /// Returns `None` if the segment no longer exists.
+    pub fn attach(handle: DsmHandle) -> Option<Self> {
+        unsafe { Self::from_raw(pg_sys::dsm_attach(handle)) }
+    }
+
+    /// Take ownership of a segment mapped by Postgres, keeping it mapped until this `DsmSegment`
+    /// is dropped
+    ///
+    /// # Safety
+    ///
+    /// `seg` must be a valid segment which nothing else will detach from
+    pub unsafe fn from_raw(seg: *mut pg_sys::dsm_segment) -> Option<Self> {
+        let seg = NonNull::new(seg)?;
+        unsafe {
+            // decouple the mapping from the current resource owner, which would otherwise
+            // detach it out from under us at the end of the transaction
+            pg_sys::dsm_pin_mapping(seg.as_ptr());
+        }
+        Some(DsmSegment { seg })
+    }
+
+    /// The handle other backends can use to [attach](DsmSegment::attach) to this segment
+    pub fn handle(&self) -> DsmHandle {
+        unsafe { pg_sys::dsm_segment_handle(self.seg.as_ptr()) }
+    }
+
+    /// The size of the segment, in bytes
+    pub fn len(&self) -> usize {
+        unsafe { pg_sys::dsm_segment_map_length(self.seg.as_ptr()) }
+    }
+
+    /// Is this a zero-sized segment?
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The address the segment is mapped at in this backend.  This differs between backends,
+    /// so it must never itself be stored in shared memory
+    pub fn as_ptr(&self) -> *mut u8 {
+        unsafe { pg_sys::dsm_segment_address(self.seg.as_ptr()).cast() }
+    }
+
+    /// The underlying Postgres segment
+    pub fn as_raw(&self) -> *mut pg_sys::dsm_segment {
+        self.seg.as_ptr()
+    }
+
+    /// Keep the segment in existence even after every backend has detached from it.  A pinned
+    /// segment lives until the server is restarted
+    pub fn pin(&self) {
+        unsafe { pg_sys::dsm_pin_segment(self.seg.as_ptr()) }
+    }
+
+    /// Run `f` when this backend detaches from the segment, whether because this `DsmSegment`
+    /// was dropped or because the backend is exiting.  Callbacks run in the reverse order of
+    /// their registration
+    pub fn on_detach<F: FnOnce() + 'static>(&self, f: F) {
+        #[pg_guard]
+        unsafe extern "C" fn detach_callback<F: FnOnce()>(
+            _seg: *mut pg_sys::dsm_segment,
+            arg: pg_sys::Datum,
+        ) {
+            let f = unsafe { Box::from_raw(arg.cast_mut_ptr::<F>()) };
+            f()
+        }
+
+        let f = Box::into_raw(Box::new(f));
+        unsafe {
+            pg_sys::on_dsm_detach(
+                self.seg.as_ptr(),
+                Some(detach_callback::<F>),
+                pg_sys::Datum::from(f),
+            )
+        }
+    }
+}
+
+impl Drop for DsmSegment {
+    fn drop(&mut self) {
+        unsafe { pg_sys::dsm_detach(self.seg.as_ptr()) }
+    }
+}
+
+/// A dynamic shared memory area, from which variable amounts of shared memory can be allocated
+///
+/// The area is destroyed by Postgres once every backend has detached from it, unless it has been
+/// [pinned](DsaArea::pin).
+#[derive(Debug)]
+pub struct DsaArea {
+    area: NonNull<pg_sys::dsa_area>,
+}
+
+impl DsaArea {
+    /// Create a new area.  `tranche_name` names the LWLock tranche that guards the area, as shown
+    /// in `pg_stat_activity`
+    pub fn create(tranche_name: &'static CStr) -> Self {
+        unsafe {
+            let tranche_id = pg_sys::LWLockNewTrancheId();
+            pg_sys::LWLockRegisterTranche(tranche_id, tranche_name.as_ptr());
+
+            #[cfg(not(feature = "pg17"))]
+            let area = pg_sys::dsa_create(tranche_id);
+
+            // `dsa_create()` became a macro in v17
+            #[cfg(feature = "pg17")]
+            let area = pg_sys::dsa_create_ext(
+                tranche_id,
+                1024 * 1024,                   // DSA_DEFAULT_INIT_SEGMENT_SIZE
+                1 << pg_sys::DSA_OFFSET_WIDTH, // DSA_MAX_SEGMENT_SIZE
+            );
+
+            Self::from_raw(area)
+        }
+    }
+
+    /// Attach to an existing area, created by this or another backend
+    pub fn attach(handle: DsaHandle) -> Self {
+        unsafe { Self::from_raw(pg_sys::dsa_attach(handle)) }
+    }
+
+    /// # Safety
+    ///
+    /// `area` must be a valid area which nothing else will detach from
+    unsafe fn from_raw(area: *mut pg_sys::dsa_area) -> Self {
+        let area = NonNull::new(area).expect("dsa_area should not be null");
+        unsafe {
+            // decouple the mapping from the current resource owner, which would otherwise
+            // detach it out from under us at the end of the transaction
+            pg_sys::dsa_pin_mapping(area.as_ptr());
+        }
+        DsaArea { area }
+    }
+
+    /// The handle other backends can use to [attach](DsaArea::attach) to this area
+    pub fn handle(&self) -> DsaHandle {
+        unsafe { pg_sys::dsa_get_handle(self.area.as_ptr()) }
+    }
+
+    /// The underlying Postgres area
+    pub fn as_raw(&self) -> *mut pg_sys::dsa_area {
+        self.area.as_ptr()
+    }
+
+    /// Keep the area in existence even after every backend has detached from it
+    pub fn pin(&self) {
+        unsafe { pg_sys::dsa_pin(self.area.as_ptr()) }
+    }
+
+    /// Undo a previous [`DsaArea::pin`]
+    pub fn unpin(&self) {
+        unsafe { pg_sys::dsa_unpin(self.area.as_ptr()) }
+    }
+
+    /// Move `value` into shared memory allocated from this area
+    ///
+    /// # Panics
+    ///
+    /// If `T` is zero-sized, as Postgres can't allocate nothing
+    pub fn allocate<T: PGRXSharedMemory>(&self, value: T) -> DsaPointer<T> {
+        assert!(std::mem::size_of::<T>() != 0, "cannot allocate a zero-sized type in a DsaArea");
+        let ptr = unsafe {
+            // a fresh allocation, sized for a `T`
+            DsaPointer::<T>::from_raw(self.allocate_bytes(std::mem::size_of::<T>(), 0))
+        };
+        unsafe { self.resolve(ptr).write(value) };
+        ptr
+    }
+
+    /// Copy `values` into shared memory allocated from this area
+    ///
+    /// Nothing is allocated for an empty slice, or a slice of a zero-sized type, whose pointer is
+    /// `InvalidDsaPointer`.
+    pub fn allocate_slice<T: PGRXSharedMemory + Copy>(&self, values: &[T]) -> DsaSlice<T> {
+        let slice = self.allocate_slice_uninit::<T>(values.len(), 0);
+        if !slice.ptr.is_valid() {
+            return slice;
+        }
+        unsafe {
+            self.resolve(slice.ptr).copy_from_nonoverlapping(values.as_ptr(), values.len());
+        }
+        slice
+    }
+
+    /// Allocate room for `len` values of `T`, with every byte set to zero
+    ///
+    /// As with [`DsaArea::allocate_slice`], nothing is allocated if that takes no room.
+    ///
+    /// # Safety
+    ///
+    /// A `T` with every byte set to zero must be a valid `T`, as it is for the integer and
+    /// floating point types.  Otherwise borrowing the values is undefined behavior.
+    pub unsafe fn allocate_zeroed_slice<T: PGRXSharedMemory + Copy>(
+        &self,
+        len: usize,
+    ) -> DsaSlice<T> {
+        self.allocate_slice_uninit(len, pg_sys::DSA_ALLOC_ZERO)
+    }
+
+    fn allocate_slice_uninit<T>(&self, len: usize, flags: u32) -> DsaSlice<T> {
+        let size = std::mem::size_of::<T>().checked_mul(len).expect("allocation size overflow");
+        if size == 0 {
+            // `dsa_allocate_extended()` asserts that it's asked for something
+            return DsaSlice { ptr: DsaPointer::default(), len };
+        }
+        let ptr = self.allocate_bytes(size, flags | pg_sys::DSA_ALLOC_HUGE);
+        // a fresh allocation, sized for `len` values of `T`
+        DsaSlice { ptr: unsafe { DsaPointer::from_raw(ptr) }, len }
+    }
+
+    fn allocate_bytes(&self, size: usize, flags: u32) -> pg_sys::dsa_pointer {
+        unsafe { pg_sys::dsa_allocate_extended(self.area.as_ptr(), size, flags as _) }
+    }
+
+    /// Borrow the value `ptr` points to
+    ///
+    /// Other backends may be accessing the same memory, so `T` should provide its own
+    /// synchronization, such as by being an atomic, or access should be coordinated through a lock.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been allocated from this area, whether through this attachment of it or
+    /// another, and not yet freed.  A [`DsaPointer`] carries no record of its area, so nothing
+    /// stops one from being resolved through the wrong one.
+    ///
+    /// # Panics
+    ///
+    /// If `ptr` is invalid
+    pub unsafe fn get<T: PGRXSharedMemory>(&self, ptr: DsaPointer<T>) -> &T {
+        unsafe { &*self.resolve(ptr) }
+    }
+
+    /// Mutably borrow the value `ptr` points to
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been allocated from this area and not yet freed, and no other reference to
+    /// the value may exist, in this backend or any other, while the returned one is alive
+    #[allow(clippy::mut_from_ref)] // the memory belongs to the area, not to `self`
+    pub unsafe fn get_mut<T: PGRXSharedMemory>(&self, ptr: DsaPointer<T>) -> &mut T {
+        unsafe { &mut *self.resolve(ptr) }
+    }
+
+    /// Borrow the values `slice` points to
+    ///
+    /// See [`DsaArea::get`] regarding synchronization.
+    ///
+    /// # Safety
+    ///
+    /// See [`DsaArea::get`]
+    pub unsafe fn get_slice<T: PGRXSharedMemory + Copy>(&self, slice: DsaSlice<T>) -> &[T] {
+        if slice.is_empty() || std::mem::size_of::<T>() == 0 {
+            return unsafe { std::slice::from_raw_parts(NonNull::dangling().as_ptr(), slice.len) };
+        }
+        unsafe { std::slice::from_raw_parts(self.resolve(slice.ptr), slice.len) }
+    }
+
+    /// Mutably borrow the values `slice` points to
+    ///
+    /// # Safety
+    ///
+    /// See [`DsaArea::get_mut`]
+    #[allow(clippy::mut_from_ref)] // the memory belongs to the area, not to `self`
+    pub unsafe fn get_slice_mut<T: PGRXSharedMemory + Copy>(&self, slice: DsaSlice<T>) -> &mut [T] {
+        if slice.is_empty() || std::mem::size_of::<T>() == 0 {
+            return unsafe {
+                std::slice::from_raw_parts_mut(NonNull::dangling().as_ptr(), slice.len)
+            };
+        }
+        unsafe { std::slice::from_raw_parts_mut(self.resolve(slice.ptr), slice.len) }
+    }
+
+    /// Return the memory `ptr` points to to the area.  The value is not dropped
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must have been allocated from this area and not yet freed, and no backend may use
+    /// it, or any copy of it, afterwards
+    pub unsafe fn free<T>(&self, ptr: DsaPointer<T>) {
+        unsafe { pg_sys::dsa_free(self.area.as_ptr(), ptr.as_raw()) }
+    }
+
+    /// Return the memory `slice` points to to the area
+    ///
+    /// # Safety
+    ///
+    /// See [`DsaArea::free`]
+    pub unsafe fn free_slice<T>(&self, slice: DsaSlice<T>) {
+        // nothing was allocated for an empty slice
+        if slice.ptr.is_valid() {
+            unsafe { self.free(slice.ptr) }
+        }
+    }
+
+    fn resolve<T>(&self, ptr: DsaPointer<T>) -> *mut T {
+        assert!(ptr.is_valid(), "invalid DsaPointer");
+        unsafe { pg_sys::dsa_get_address(self.area.as_ptr(), ptr.as_raw()).cast() }
+    }
+}
+
+impl Drop for DsaArea {
+    fn drop(&mut self) {
+        unsafe { pg_sys::dsa_detach(self.area.as_ptr()) }
+    }
+}
+
+/// A pointer to a `T` allocated in a [`DsaArea`]
+///
+/// Unlike a regular pointer, this is valid in every backend attached to the area, so it can be
+/// stored in shared memory or sent to another backend.  It must be resolved through the area with
+/// [`DsaArea::get`].
+///
+/// The default value is an invalid pointer, Postgres' `InvalidDsaPointer`.
+#[repr(transparent)]
+pub struct DsaPointer<T> {
+    ptr: pg_sys::dsa_pointer,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> DsaPointer<T> {
+    /// Wrap a raw `dsa_pointer`
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be `InvalidDsaPointer`, or point to a live `T` allocated from the area it
+    /// will be resolved through.  The pointers handed out by [`DsaArea::allocate`] and friends
+    /// are the safe way to get one.
+    pub const unsafe fn from_raw(ptr: pg_sys::dsa_pointer) -> Self {
+        DsaPointer { ptr, _marker: PhantomData }
+    }
+
+    /// The raw `dsa_pointer`
+    pub const fn as_raw(&self) -> pg_sys::dsa_pointer {
+        self.ptr
+    }
+
+    /// Is this anything other than `InvalidDsaPointer`?
+    pub const fn is_valid(&self) -> bool {
+        self.ptr != 0
+    }
+}
+
+impl<T> Clone for DsaPointer<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for DsaPointer<T> {}
+
+impl<T> Default for DsaPointer<T> {
+    fn default() -> Self {
+        DsaPointer { ptr: 0, _marker: PhantomData }
+    }
+}
+
+impl<T> PartialEq for DsaPointer<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ptr == other.ptr
+    }
+}
+
+impl<T> Eq for DsaPointer<T> {}
+
+impl<T> std::fmt::Debug for DsaPointer<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "DsaPointer({:#018x})", self.ptr)
+    }
+}
+
+unsafe impl<T> PGRXSharedMemory for DsaPointer<T> {}
+
+/// A pointer to a run of `T`s allocated in a [`DsaArea`], along with their count
+///
+/// Like [`DsaPointer`], this is valid in every backend attached to the area.
+pub struct DsaSlice<T> {
+    ptr: DsaPointer<T>,
+    len: usize,
+}
+
+impl<T> DsaSlice<T> {
+    /// The number of values
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Are there no values?
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// A pointer to the first value
+    pub const fn as_ptr(&self) -> DsaPointer<T> {
+        self.ptr
+    }
+}
+
+impl<T> Clone for DsaSlice<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for DsaSlice<T> {}
+
+impl<T> Default for DsaSlice<T> {
+    fn default() -> Self {
+        DsaSlice { ptr: DsaPointer::default(), len: 0 }
+    }
+}
+
+impl<T> std::fmt::Debug for DsaSlice<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DsaSlice").field("ptr", &self.ptr).field("len", &self.len).finish()
+    }
+}
+
+unsafe impl<T> PGRXSharedMemory for DsaSlice<T> {}
@@ -11,6 +11,8 @@
 
 use crate::pg_sys::GETSTRUCT;
 use crate::{ereport, pg_sys, PgLogLevel, PgSqlErrorCode};
+use core::cell::{Cell, RefCell};
+use core::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 
 pub fn lookup_enum_by_oid(enumval: pg_sys::Oid) -> (String, pg_sys::Oid, f32) {
     let tup = unsafe {
@@ -57,6 +59,10 @@ pub fn lookup_enum_by_label(typname: &str, label: &str) -> pg_sys::Datum {
         panic!("could not locate type oid for type: {typname}");
     }
 
+    pg_sys::Datum::from(lookup_enum_oid(enumtypoid, typname, label))
+}
+
+fn lookup_enum_oid(enumtypoid: pg_sys::Oid, typname: &str, label: &str) -> pg_sys::Oid {
     let tup = unsafe {
         let label =
             alloc::ffi::CString::new(label).expect("failed to convert enum typname to a CString");
@@ -77,7 +83,7 @@ pub fn lookup_enum_by_label(typname: &str, label: &str) -> pg_sys::Datum {
     unsafe {
         let oid = extract_enum_oid(tup);
         pg_sys::ReleaseSysCache(tup);
-        pg_sys::Datum::from(oid)
+        oid
     }
 }
 
@@ -89,3 +95,142 @@ unsafe fn extract_enum_oid(tup: *mut pg_sys::HeapTupleData) -> pg_sys::Oid {
     let en = en.as_ref().unwrap();
     en.oid
 }
+
+/// Bumped by our syscache callbacks whenever `pg_enum` or `pg_type` changes
+static GENERATION: AtomicU64 = AtomicU64::new(1);
+/// Whether our callback is registered for changes to `pg_enum` and to `pg_type`, respectively
+static ENUM_CALLBACK_REGISTERED: AtomicBool = AtomicBool::new(false);
+static TYPE_CALLBACK_REGISTERED: AtomicBool = AtomicBool::new(false);
+
+unsafe extern "C" fn invalidate_enum_caches(
+    _arg: pg_sys::Datum,
+    _cacheid: core::ffi::c_int,
+    _hashvalue: u32,
+) {
+    GENERATION.fetch_add(1, Ordering::Relaxed);
+}
+
+fn register_callbacks() {
+    // Postgres only has room for a few dozen syscache callbacks per backend, so every
+    // `EnumCache` shares these two rather than registering its own.  Each is marked once it's in
+    // place, so that if the other fails, retrying doesn't use up another slot on it
+    for (cache, registered) in [
+        (pg_sys::SysCacheIdentifier::ENUMOID, &ENUM_CALLBACK_REGISTERED),
+        (pg_sys::SysCacheIdentifier::TYPEOID, &TYPE_CALLBACK_REGISTERED),
+    ] {
+        if registered.load(Ordering::Relaxed) {
+            continue;
+        }
+        unsafe {
+            pg_sys::CacheRegisterSyscacheCallback(
+                cache as _,
+                Some(invalidate_enum_caches),
+                pg_sys::Datum::from(0),
+            );
+        }
+        registered.store(true, Ordering::Relaxed);
+    }
+}
+
+/// A per-backend cache of the type oid and value oids backing a `#[derive(PostgresEnum)]` type
+///
+/// `#[derive(PostgresEnum)]` keeps one of these in a `static` so that converting a variant to or
+/// from a [`pg_sys::Datum`] doesn't have to search the syscache and compare labels every time.
+/// Variants are identified by their index into `labels`.  Entries are filled in lazily and the
+/// whole cache is thrown away whenever Postgres invalidates anything in `pg_enum` or `pg_type`.
+///
+/// Note that the type is looked up by name, and that the cached oid is not refreshed if only the
+/// `search_path` changes.
+pub struct EnumCache {
+    typname: &'static str,
+    labels: &'static [&'static str],
+    generation: Cell<u64>,
+    typoid: Cell<pg_sys::Oid>,
+    oids: RefCell<Vec<pg_sys::Oid>>,
+}
+
+// SAFETY:  Postgres backends are single-threaded and an `EnumCache` is only used from the backend
+// thread
+unsafe impl Sync for EnumCache {}
+
+impl EnumCache {
+    pub const fn new(typname: &'static str, labels: &'static [&'static str]) -> Self {
+        EnumCache {
+            typname,
+            labels,
+            generation: Cell::new(0),
+            typoid: Cell::new(pg_sys::InvalidOid),
+            oids: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// The oid of the Postgres enum type
+    pub fn type_oid(&self) -> pg_sys::Oid {
+        self.validate();
+        let typoid = self.typoid.get();
+        if typoid != pg_sys::InvalidOid {
+            return typoid;
+        }
+
+        let typoid = crate::regtypein(self.typname);
+        self.typoid.set(typoid);
+        typoid
+    }
+
+    /// The oid of the enum value for the variant at `index`
+    pub fn value_oid(&self, index: usize) -> pg_sys::Oid {
+        self.validate();
+        let oid = self.oids.borrow()[index];
+        if oid != pg_sys::InvalidOid {
+            return oid;
+        }
+
+        let typoid = self.type_oid();
+        if typoid == pg_sys::InvalidOid {
+            panic!("could not locate type oid for type: {}", self.typname);
+        }
+        let oid = lookup_enum_oid(typoid, self.typname, self.labels[index]);
+        self.store(index, oid);
+        oid
+    }
+
+    /// The index of the variant for the enum value `enumval`
+    ///
+    /// # Panics
+    ///
+    /// If `enumval` has a label that doesn't match any variant
+    pub fn variant_index(&self, enumval: pg_sys::Oid) -> usize {
+        self.validate();
+        if let Some(index) = self.oids.borrow().iter().position(|oid| *oid == enumval) {
+            return index;
+        }
+
+        let (name, enumtypid, _) = lookup_enum_by_oid(enumval);
+        let Some(index) = self.labels.iter().position(|label| *label == name) else {
+            panic!("invalid enum value: {name}")
+        };
+        // values of some other enum type that happen to share a label aren't ours to remember
+        if enumtypid == self.type_oid() {
+            self.store(index, enumval);
+        }
+        index
+    }
+
+    fn store(&self, index: usize, oid: pg_sys::Oid) {
+        // if the lookup that produced `oid` processed any invalidations, the next call to
+        // `validate()` clears this again
+        self.oids.borrow_mut()[index] = oid;
+    }
+
+    fn validate(&self) {
+        register_callbacks();
+        let generation = GENERATION.load(Ordering::Relaxed);
+        if self.generation.get() != generation {
+            self.generation.set(generation);
+            self.typoid.set(pg_sys::InvalidOid);
+            let mut oids = self.oids.borrow_mut();
+            oids.clear();
+            oids.resize(self.labels.len(), pg_sys::InvalidOid);
+        }
+    }
+}
@@ -0,0 +1,831 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+/*!
+
+[Foreign Data Wrapper](https://www.postgresql.org/docs/current/fdwhandler.html) support.
+
+Foreign data wrappers are created by implementing [`ForeignDataWrapper`] for a type and decorating
+the implementation with [`#[pg_fdw]`](pgrx_macros::pg_fdw).  The macro generates the `fdw_handler`
+and validator functions Postgres calls and emits the
+[`CREATE FOREIGN DATA WRAPPER`](https://www.postgresql.org/docs/current/sql-createforeigndatawrapper.html)
+SQL for them.
+
+The [`pg_sys::FdwRoutine`] is assembled by pgrx.  Planning is reduced to a row and cost
+[estimate](ForeignDataWrapper::estimate), and every restriction clause is checked by the executor on
+the rows the wrapper returns.  Scans and modifications work with owned state rather than the raw
+`fdw_state` pointers Postgres hands out.
+
+The `OPTIONS (...)` given to the wrapper, its servers, user mappings, and foreign tables are decoded
+into [`ForeignDataWrapper::Options`] using [`serde`].  The validator function rejects option names
+which aren't fields of that type, as well as values which can't be parsed into their field's type.
+
+# Example
+
+```rust,no_run
+use pgrx::prelude::*;
+use pgrx::PgRelation;
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+pub struct CountdownOptions {
+    from: i32,
+}
+
+pub struct Countdown;
+
+#[pg_fdw]
+impl ForeignDataWrapper for Countdown {
+    type Options = CountdownOptions;
+    type ScanState = i32;
+    type Row = (i32,);
+
+    fn begin_scan(options: CountdownOptions, _relation: &PgRelation) -> i32 {
+        options.from
+    }
+
+    fn iterate_scan(state: &mut i32) -> Option<(i32,)> {
+        (*state > 0).then(|| {
+            *state -= 1;
+            (*state + 1,)
+        })
+    }
+}
+
+extension_sql!(
+    r#"
+    CREATE SERVER countdown_server FOREIGN DATA WRAPPER countdown;
+    CREATE FOREIGN TABLE countdown_from_ten (n int4) SERVER countdown_server OPTIONS (from '10');
+    "#,
+    name = "countdown_from_ten",
+    requires = [Countdown]
+);
+```
+*/
+mod options;
+
+pub use options::{FdwOptionCatalog, FdwOptions, FdwOptionsError};
+
+use crate as pgrx; // for #[pg_guard] support from within ourself
+use crate::heap_tuple::PgHeapTuple;
+use crate::pg_sys::panic::ErrorReport;
+use crate::{
+    ereport, name_data_to_str, pg_guard, pg_sys, AllocatedByPostgres, AllocatedByRust, AnyElement,
+    FromDatum, IntoDatum, PgBox, PgLogLevel, PgMemoryContexts, PgRelation, PgSqlErrorCode,
+    PgTupleDesc, WhoAllocated,
+};
+use serde::de::DeserializeOwned;
+use std::ffi::{c_int, CStr};
+use std::ptr;
+
+/// The planner's estimate for scanning a foreign table, returned by [`ForeignDataWrapper::estimate`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct ForeignScanEstimate {
+    /// The number of rows the scan returns before any restriction clauses are applied.
+    pub rows: f64,
+    pub startup_cost: pg_sys::Cost,
+    pub total_cost: pg_sys::Cost,
+}
+
+impl Default for ForeignScanEstimate {
+    /// A thousand rows, at one cost unit each after a startup cost of ten.
+    fn default() -> Self {
+        Self { rows: 1000.0, startup_cost: 10.0, total_cost: 1010.0 }
+    }
+}
+
+/// A Postgres foreign data wrapper.
+///
+/// See the [module documentation](self) for an example.  Apply [`#[pg_fdw]`](pgrx_macros::pg_fdw)
+/// to the implementation.
+///
+/// Foreign tables are read-only unless one of the `SUPPORTS_*` constants is set, in which case
+/// [`ForeignDataWrapper::begin_modify`] and the matching modify function must be implemented.
+pub trait ForeignDataWrapper: Sized + 'static {
+    /// The name of the foreign data wrapper. (eg. What you'd pass to `CREATE SERVER ... FOREIGN DATA WRAPPER name`.)
+    ///
+    /// **Optional:** `#[pg_fdw]` defaults this to the snake case name of the type.
+    const NAME: &'static str;
+
+    /// The options of the wrapper, server, user mapping, and foreign table, merged in that order
+    /// so the more specific object wins.
+    type Options: DeserializeOwned;
+
+    /// Per-scan state created by [`ForeignDataWrapper::begin_scan`] and dropped after
+    /// [`ForeignDataWrapper::end_scan`], or when the query's memory context is deleted.
+    type ScanState;
+
+    /// The rows produced by [`ForeignDataWrapper::iterate_scan`].
+    type Row: ForeignRow;
+
+    /// Per-statement state created by [`ForeignDataWrapper::begin_modify`].
+    ///
+    /// **Optional:** `#[pg_fdw]` defaults this to `()`.
+    type ModifyState;
+
+    /// Set if the current user must have a user mapping for the foreign server, whose options are
+    /// then merged in.
+    const REQUIRES_USER_MAPPING: bool = false;
+
+    const SUPPORTS_INSERT: bool = false;
+    const SUPPORTS_UPDATE: bool = false;
+    const SUPPORTS_DELETE: bool = false;
+
+    /// The column identifying rows to [`ForeignDataWrapper::update`] and [`ForeignDataWrapper::delete`].
+    ///
+    /// Required when either is supported.
+    const ROW_ID_COLUMN: Option<&'static str> = None;
+
+    /// Additional validation of the options given to one of the wrapper's objects.
+    ///
+    /// This is called after the option names and values have been checked against
+    /// [`ForeignDataWrapper::Options`], so it only needs to handle what `serde` can't express.  An
+    /// `Err` is raised as an `ERROR`.
+    fn validate_options(_options: &FdwOptions, _catalog: FdwOptionCatalog) -> Result<(), String> {
+        Ok(())
+    }
+
+    /// Estimate the size and cost of a full scan of `relation`.
+    fn estimate(_options: &Self::Options, _relation: &PgRelation) -> ForeignScanEstimate {
+        ForeignScanEstimate::default()
+    }
+
+    /// Start scanning `relation`.
+    fn begin_scan(options: Self::Options, relation: &PgRelation) -> Self::ScanState;
+
+    /// Return the next row of the scan, or `None` once it is exhausted.
+    fn iterate_scan(state: &mut Self::ScanState) -> Option<Self::Row>;
+
+    /// Restart the scan from the beginning.
+    ///
+    /// The default implementation ends the current scan and begins a new one.
+    fn rescan(state: &mut Self::ScanState, options: Self::Options, relation: &PgRelation) {
+        let previous = std::mem::replace(state, Self::begin_scan(options, relation));
+        Self::end_scan(previous);
+    }
+
+    fn end_scan(_state: Self::ScanState) {}
+
+    /// Prepare to insert, update, or delete rows of `relation`.
+    ///
+    /// **Optional:** `#[pg_fdw]` provides an implementation which panics if none is given.
+    fn begin_modify(options: Self::Options, relation: &PgRelation) -> Self::ModifyState;
+
+    fn insert(_state: &mut Self::ModifyState, _row: &PgHeapTuple<'_, AllocatedByPostgres>) {
+        unsupported::<Self>("INSERT")
+    }
+
+    /// Replace the row identified by `rowid`, the value of [`ForeignDataWrapper::ROW_ID_COLUMN`],
+    /// with `row`.
+    fn update(
+        _state: &mut Self::ModifyState,
+        _rowid: AnyElement,
+        _row: &PgHeapTuple<'_, AllocatedByPostgres>,
+    ) {
+        unsupported::<Self>("UPDATE")
+    }
+
+    /// Delete the row identified by `rowid`, the value of [`ForeignDataWrapper::ROW_ID_COLUMN`].
+    fn delete(_state: &mut Self::ModifyState, _rowid: AnyElement) {
+        unsupported::<Self>("DELETE")
+    }
+
+    fn end_modify(_state: Self::ModifyState) {}
+}
+
+fn unsupported<T: ForeignDataWrapper>(operation: &str) -> ! {
+    ereport!(
+        ERROR,
+        PgSqlErrorCode::ERRCODE_FEATURE_NOT_SUPPORTED,
+        format!("foreign data wrapper \"{}\" does not support {operation}", T::NAME)
+    );
+}
+
+/// An error forming a [`ForeignRow`] into a tuple of the foreign table.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ForeignRowError {
+    #[error("row has {0} columns, but the foreign table has {1}")]
+    IncorrectColumnCount(usize, usize),
+
+    #[error("column \"{column}\" is of type {expected}, but the row provided a `{found}`")]
+    IncompatibleColumn { column: String, expected: String, found: &'static str },
+}
+
+/// A row which can be returned from a foreign table scan.
+///
+/// Implemented for [`PgHeapTuple`]s and tuples of [`IntoDatum`] types.  Tuples provide one value
+/// per column of the foreign table, skipping dropped columns.
+pub trait ForeignRow {
+    /// Form `self` into a tuple described by `tupdesc`.
+    fn into_heap_tuple(self, tupdesc: &PgTupleDesc) -> Result<pg_sys::HeapTuple, ForeignRowError>;
+}
+
+impl<AllocatedBy: WhoAllocated> ForeignRow for PgHeapTuple<'_, AllocatedBy> {
+    fn into_heap_tuple(self, tupdesc: &PgTupleDesc) -> Result<pg_sys::HeapTuple, ForeignRowError> {
+        if self.len() != tupdesc.len() {
+            return Err(ForeignRowError::IncorrectColumnCount(self.len(), tupdesc.len()));
+        }
+        for ((_, found), expected) in self.attributes().zip(tupdesc.iter()) {
+            if !expected.attisdropped && found.atttypid != expected.atttypid {
+                return Err(ForeignRowError::IncompatibleColumn {
+                    column: name_data_to_str(&expected.attname).to_string(),
+                    expected: type_name(expected.atttypid),
+                    found: "composite attribute of another type",
+                });
+            }
+        }
+        Ok(self.into_pg())
+    }
+}
+
+/// One value of a tuple being formed into a row.
+struct RowValue {
+    datum: Option<pg_sys::Datum>,
+    is_compatible_with: fn(pg_sys::Oid) -> bool,
+    type_name: &'static str,
+}
+
+fn form_row(
+    tupdesc: &PgTupleDesc,
+    values: Vec<RowValue>,
+) -> Result<pg_sys::HeapTuple, ForeignRowError> {
+    let columns = tupdesc.iter().filter(|att| !att.attisdropped).count();
+    if values.len() != columns {
+        return Err(ForeignRowError::IncorrectColumnCount(values.len(), columns));
+    }
+
+    let mut values = values.into_iter();
+    let mut datums = Vec::with_capacity(tupdesc.len());
+    let mut nulls = Vec::with_capacity(tupdesc.len());
+    for att in tupdesc.iter() {
+        if att.attisdropped {
+            datums.push(pg_sys::Datum::from(0));
+            nulls.push(true);
+            continue;
+        }
+        let value = values.next().unwrap();
+        if !(value.is_compatible_with)(att.atttypid) {
+            return Err(ForeignRowError::IncompatibleColumn {
+                column: name_data_to_str(&att.attname).to_string(),
+                expected: type_name(att.atttypid),
+                found: value.type_name,
+            });
+        }
+        nulls.push(value.datum.is_none());
+        datums.push(value.datum.unwrap_or(pg_sys::Datum::from(0)));
+    }
+
+    // SAFETY: we built one datum and null flag for every attribute of `tupdesc`, and checked the
+    // types of those that aren't null
+    unsafe {
+        Ok(pg_sys::heap_form_tuple(tupdesc.as_ptr(), datums.as_mut_ptr(), nulls.as_mut_ptr()))
+    }
+}
+
+fn type_name(typoid: pg_sys::Oid) -> String {
+    // SAFETY: `format_type_be()` copes with any oid and returns a palloc'd string
+    unsafe { CStr::from_ptr(pg_sys::format_type_be(typoid)).to_string_lossy().into_owned() }
+}
+
+macro_rules! impl_foreign_row {
+    ($($C:ident),* $(,)?) => {
+        impl<$($C: IntoDatum),*> ForeignRow for ($($C,)*) {
+            fn into_heap_tuple(self, tupdesc: &PgTupleDesc) -> Result<pg_sys::HeapTuple, ForeignRowError> {
+                // shadowing the type names with these identifiers
+                #[allow(nonstandard_style)]
+                let ($($C,)*) = self;
+                let values = vec![$(
+                    RowValue {
+                        datum: $C.into_datum(),
+                        is_compatible_with: <$C as IntoDatum>::is_compatible_with,
+                        type_name: std::any::type_name::<$C>(),
+                    },
+                )*];
+                form_row(tupdesc, values)
+            }
+        }
+    }
+}
+
+impl_foreign_row!(T0);
+impl_foreign_row!(T0, T1);
+impl_foreign_row!(T0, T1, T2);
+impl_foreign_row!(T0, T1, T2, T3);
+impl_foreign_row!(T0, T1, T2, T3, T4);
+impl_foreign_row!(T0, T1, T2, T3, T4, T5);
+impl_foreign_row!(T0, T1, T2, T3, T4, T5, T6);
+impl_foreign_row!(T0, T1, T2, T3, T4, T5, T6, T7);
+impl_foreign_row!(T0, T1, T2, T3, T4, T5, T6, T7, T8);
+impl_foreign_row!(T0, T1, T2, T3, T4, T5, T6, T7, T8, T9);
+impl_foreign_row!(T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10);
+impl_foreign_row!(T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11);
+impl_foreign_row!(T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12);
+impl_foreign_row!(T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13);
+impl_foreign_row!(T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14);
+impl_foreign_row!(T0, T1, T2, T3, T4, T5, T6, T7, T8, T9, T10, T11, T12, T13, T14, T15);
+
+/// Build the [`pg_sys::FdwRoutine`] describing `T`.
+///
+/// This is what the `fdw_handler` generated by [`#[pg_fdw]`](pgrx_macros::pg_fdw) returns to
+/// Postgres.
+pub fn fdw_routine<T: ForeignDataWrapper>() -> PgBox<pg_sys::FdwRoutine, AllocatedByRust> {
+    // SAFETY: `FdwRoutine` is a node and `alloc_node` zeroes it and sets its tag
+    let mut routine =
+        unsafe { PgBox::<pg_sys::FdwRoutine>::alloc_node(pg_sys::NodeTag::T_FdwRoutine) };
+
+    routine.GetForeignRelSize = Some(get_foreign_rel_size::<T>);
+    routine.GetForeignPaths = Some(get_foreign_paths::<T>);
+    routine.GetForeignPlan = Some(get_foreign_plan::<T>);
+    routine.BeginForeignScan = Some(begin_foreign_scan::<T>);
+    routine.IterateForeignScan = Some(iterate_foreign_scan::<T>);
+    routine.ReScanForeignScan = Some(rescan_foreign_scan::<T>);
+    routine.EndForeignScan = Some(end_foreign_scan::<T>);
+
+    routine.IsForeignRelUpdatable = Some(is_foreign_rel_updatable::<T>);
+    if T::SUPPORTS_INSERT || T::SUPPORTS_UPDATE || T::SUPPORTS_DELETE {
+        routine.BeginForeignModify = Some(begin_foreign_modify::<T>);
+        routine.EndForeignModify = Some(end_foreign_modify::<T>);
+    }
+    if T::SUPPORTS_INSERT {
+        routine.ExecForeignInsert = Some(exec_foreign_insert::<T>);
+        routine.BeginForeignInsert = Some(begin_foreign_insert::<T>);
+        routine.EndForeignInsert = Some(end_foreign_modify::<T>);
+    }
+    if T::SUPPORTS_UPDATE || T::SUPPORTS_DELETE {
+        routine.AddForeignUpdateTargets = Some(add_foreign_update_targets::<T>);
+    }
+    if T::SUPPORTS_UPDATE {
+        routine.ExecForeignUpdate = Some(exec_foreign_update::<T>);
+    }
+    if T::SUPPORTS_DELETE {
+        routine.ExecForeignDelete = Some(exec_foreign_delete::<T>);
+    }
+
+    routine
+}
+
+/// Validate the `options` given to one of `T`'s objects, stored in the catalog `catalog`.
+///
+/// This is what the validator function generated by [`#[pg_fdw]`](pgrx_macros::pg_fdw) calls.
+///
+/// # Safety
+///
+/// `options` must be a `text[]` datum of `name=value` pairs, as Postgres passes to validators.
+pub unsafe fn validate_options<T: ForeignDataWrapper>(
+    options: pg_sys::Datum,
+    catalog: pg_sys::Oid,
+) {
+    let options = FdwOptions::from_pg_list(pg_sys::untransformRelOptions(options));
+    let Some(catalog) = FdwOptionCatalog::from_oid(catalog) else {
+        return;
+    };
+
+    if let Some(names) = options::option_names::<T::Options>() {
+        if let Some((name, _)) = options.iter().find(|(name, _)| !names.contains(name)) {
+            ErrorReport::new(
+                PgSqlErrorCode::ERRCODE_FDW_INVALID_OPTION_NAME,
+                format!("invalid option \"{name}\""),
+                pg_sys::function_name!(),
+            )
+            .set_hint(format!("Valid options in this context are: {}", names.join(", ")))
+            .report(PgLogLevel::ERROR);
+        }
+    }
+
+    // the remaining options may well be given to another of the wrapper's objects
+    match options.decode::<T::Options>() {
+        Ok(_) | Err(FdwOptionsError::MissingOption(_)) => {}
+        Err(e) => report_options_error(e),
+    }
+
+    if let Err(message) = T::validate_options(&options, catalog) {
+        ereport!(ERROR, PgSqlErrorCode::ERRCODE_FDW_INVALID_ATTRIBUTE_VALUE, message);
+    }
+}
+
+fn report_options_error(e: FdwOptionsError) -> ! {
+    let errcode = match e {
+        FdwOptionsError::MissingOption(_) => PgSqlErrorCode::ERRCODE_FDW_OPTION_NAME_NOT_FOUND,
+        FdwOptionsError::InvalidValue { .. } => PgSqlErrorCode::ERRCODE_FDW_INVALID_ATTRIBUTE_VALUE,
+        FdwOptionsError::Custom(_) => PgSqlErrorCode::ERRCODE_FDW_ERROR,
+    };
+    ereport!(ERROR, errcode, e.to_string());
+}
+
+/// Merge the options of the foreign table `relid` with those of its server, user mapping, and
+/// wrapper.
+unsafe fn table_options<T: ForeignDataWrapper>(relid: pg_sys::Oid) -> FdwOptions {
+    let table = pg_sys::GetForeignTable(relid);
+    let server = pg_sys::GetForeignServer((*table).serverid);
+    let wrapper = pg_sys::GetForeignDataWrapper((*server).fdwid);
+
+    let mut options = FdwOptions::from_pg_list((*wrapper).options);
+    options.extend_from_pg_list((*server).options);
+    if T::REQUIRES_USER_MAPPING {
+        let user_mapping = pg_sys::GetUserMapping(pg_sys::GetUserId(), (*server).serverid);
+        options.extend_from_pg_list((*user_mapping).options);
+    }
+    options.extend_from_pg_list((*table).options);
+    options
+}
+
+unsafe fn decode_options<T: ForeignDataWrapper>(relid: pg_sys::Oid) -> T::Options {
+    table_options::<T>(relid).decode().unwrap_or_else(|e| report_options_error(e))
+}
+
+#[pg_guard]
+unsafe extern "C" fn get_foreign_rel_size<T: ForeignDataWrapper>(
+    root: *mut pg_sys::PlannerInfo,
+    baserel: *mut pg_sys::RelOptInfo,
+    foreigntableid: pg_sys::Oid,
+) {
+    let relation = PgRelation::open(foreigntableid);
+    let estimate = T::estimate(&decode_options::<T>(foreigntableid), &relation);
+
+    let selectivity = pg_sys::clauselist_selectivity(
+        root,
+        (*baserel).baserestrictinfo,
+        0,
+        pg_sys::JoinType::JOIN_INNER,
+        ptr::null_mut(),
+    );
+    (*baserel).rows = estimate.rows * selectivity;
+
+    // remembered for `get_foreign_paths`
+    let mut private = PgBox::<ForeignScanEstimate>::alloc();
+    *private = estimate;
+    (*baserel).fdw_private = private.into_pg().cast();
+}
+
+#[pg_guard]
+unsafe extern "C" fn get_foreign_paths<T: ForeignDataWrapper>(
+    root: *mut pg_sys::PlannerInfo,
+    baserel: *mut pg_sys::RelOptInfo,
+    _foreigntableid: pg_sys::Oid,
+) {
+    let estimate = *(*baserel).fdw_private.cast::<ForeignScanEstimate>();
+
+    #[cfg(not(feature = "pg17"))]
+    let path = pg_sys::create_foreignscan_path(
+        root,
+        baserel,
+        ptr::null_mut(),
+        (*baserel).rows,
+        estimate.startup_cost,
+        estimate.total_cost,
+        ptr::null_mut(),
+        ptr::null_mut(),
+        ptr::null_mut(),
+        ptr::null_mut(),
+    );
+    #[cfg(feature = "pg17")]
+    let path = pg_sys::create_foreignscan_path(
+        root,
+        baserel,
+        ptr::null_mut(),
+        (*baserel).rows,
+        estimate.startup_cost,
+        estimate.total_cost,
+        ptr::null_mut(),
+        ptr::null_mut(),
+        ptr::null_mut(),
+        ptr::null_mut(),
+        ptr::null_mut(),
+    );
+    pg_sys::add_path(baserel, path.cast());
+}
+
+#[pg_guard]
+unsafe extern "C" fn get_foreign_plan<T: ForeignDataWrapper>(
+    _root: *mut pg_sys::PlannerInfo,
+    baserel: *mut pg_sys::RelOptInfo,
+    _foreigntableid: pg_sys::Oid,
+    _best_path: *mut pg_sys::ForeignPath,
+    tlist: *mut pg_sys::List,
+    scan_clauses: *mut pg_sys::List,
+    outer_plan: *mut pg_sys::Plan,
+) -> *mut pg_sys::ForeignScan {
+    // nothing is pushed down, so the executor checks every clause
+    let scan_clauses = pg_sys::extract_actual_clauses(scan_clauses, false);
+    pg_sys::make_foreignscan(
+        tlist,
+        scan_clauses,
+        (*baserel).relid,
+        ptr::null_mut(),
+        ptr::null_mut(),
+        ptr::null_mut(),
+        ptr::null_mut(),
+        outer_plan,
+    )
+}
+
+#[inline]
+unsafe fn scan_state<'a, T: ForeignDataWrapper>(
+    node: *mut pg_sys::ForeignScanState,
+) -> Option<&'a mut Option<T::ScanState>> {
+    (*node).fdw_state.cast::<Option<T::ScanState>>().as_mut()
+}
+
+#[pg_guard]
+unsafe extern "C" fn begin_foreign_scan<T: ForeignDataWrapper>(
+    node: *mut pg_sys::ForeignScanState,
+    eflags: c_int,
+) {
+    if eflags & pg_sys::EXEC_FLAG_EXPLAIN_ONLY as c_int != 0 {
+        return;
+    }
+
+    let relation = PgRelation::from_pg((*node).ss.ss_currentRelation);
+    let state = T::begin_scan(decode_options::<T>(relation.oid()), &relation);
+
+    // the state lives in the query's memory context so it's dropped even if the scan errors out
+    // before `end_foreign_scan` gets a chance to take it back
+    (*node).fdw_state =
+        PgMemoryContexts::CurrentMemoryContext.leak_and_drop_on_delete(Some(state)).cast();
+}
+
+#[pg_guard]
+unsafe extern "C" fn iterate_foreign_scan<T: ForeignDataWrapper>(
+    node: *mut pg_sys::ForeignScanState,
+) -> *mut pg_sys::TupleTableSlot {
+    let slot = (*node).ss.ss_ScanTupleSlot;
+    if let Some(clear) = (*(*slot).tts_ops).clear {
+        clear(slot);
+    }
+
+    let Some(Some(state)) = scan_state::<T>(node) else {
+        return slot;
+    };
+    if let Some(row) = T::iterate_scan(state) {
+        let tupdesc = PgTupleDesc::from_pg_unchecked((*slot).tts_tupleDescriptor);
+        match row.into_heap_tuple(&tupdesc) {
+            // the tuple lives in the per-tuple memory context, which Postgres resets for us
+            Ok(tuple) => {
+                pg_sys::ExecStoreHeapTuple(tuple, slot, false);
+            }
+            Err(e) => {
+                ereport!(ERROR, PgSqlErrorCode::ERRCODE_FDW_INVALID_DATA_TYPE, e.to_string());
+            }
+        }
+    }
+    slot
+}
+
+#[pg_guard]
+unsafe extern "C" fn rescan_foreign_scan<T: ForeignDataWrapper>(
+    node: *mut pg_sys::ForeignScanState,
+) {
+    if let Some(Some(state)) = scan_state::<T>(node) {
+        let relation = PgRelation::from_pg((*node).ss.ss_currentRelation);
+        T::rescan(state, decode_options::<T>(relation.oid()), &relation);
+    }
+}
+
+#[pg_guard]
+unsafe extern "C" fn end_foreign_scan<T: ForeignDataWrapper>(node: *mut pg_sys::ForeignScanState) {
+    if let Some(state) = scan_state::<T>(node).and_then(Option::take) {
+        T::end_scan(state);
+    }
+}
+
+#[pg_guard]
+unsafe extern "C" fn is_foreign_rel_updatable<T: ForeignDataWrapper>(
+    _rel: pg_sys::Relation,
+) -> c_int {
+    let mut events = 0;
+    if T::SUPPORTS_INSERT {
+        events |= 1 << pg_sys::CmdType::CMD_INSERT;
+    }
+    if T::SUPPORTS_UPDATE {
+        events |= 1 << pg_sys::CmdType::CMD_UPDATE;
+    }
+    if T::SUPPORTS_DELETE {
+        events |= 1 << pg_sys::CmdType::CMD_DELETE;
+    }
+    events
+}
+
+fn row_id_column<T: ForeignDataWrapper>() -> &'static str {
+    match T::ROW_ID_COLUMN {
+        Some(column) => column,
+        None => {
+            ereport!(
+                ERROR,
+                PgSqlErrorCode::ERRCODE_FDW_ERROR,
+                format!("foreign data wrapper \"{}\" does not declare a ROW_ID_COLUMN", T::NAME)
+            );
+        }
+    }
+}
+
+/// A `Var` referencing `column` of `relation`, range table entry `rtindex`.
+unsafe fn row_id_var(
+    relation: pg_sys::Relation,
+    rtindex: pg_sys::Index,
+    column: &str,
+) -> *mut pg_sys::Var {
+    let tupdesc = PgTupleDesc::from_pg_unchecked((*relation).rd_att);
+    let Some(att) =
+        tupdesc.iter().find(|att| !att.attisdropped && name_data_to_str(&att.attname) == column)
+    else {
+        ereport!(
+            ERROR,
+            PgSqlErrorCode::ERRCODE_FDW_COLUMN_NAME_NOT_FOUND,
+            format!("row identity column \"{column}\" does not exist")
+        );
+    };
+    pg_sys::makeVar(rtindex as _, att.attnum, att.atttypid, att.atttypmod, att.attcollation, 0)
+}
+
+#[cfg(any(feature = "pg12", feature = "pg13"))]
+#[pg_guard]
+unsafe extern "C" fn add_foreign_update_targets<T: ForeignDataWrapper>(
+    parsetree: *mut pg_sys::Query,
+    _target_rte: *mut pg_sys::RangeTblEntry,
+    target_relation: pg_sys::Relation,
+) {
+    let column = row_id_column::<T>();
+    let var = row_id_var(target_relation, (*parsetree).resultRelation as pg_sys::Index, column);
+    let target_list = (*parsetree).targetList;
+    let resno = if target_list.is_null() { 1 } else { (*target_list).length + 1 };
+    let entry = pg_sys::makeTargetEntry(
+        var.cast(),
+        resno as pg_sys::AttrNumber,
+        PgMemoryContexts::CurrentMemoryContext.pstrdup(column),
+        true,
+    );
+    (*parsetree).targetList = pg_sys::lappend(target_list, entry.cast());
+}
+
+#[cfg(not(any(feature = "pg12", feature = "pg13")))]
+#[pg_guard]
+unsafe extern "C" fn add_foreign_update_targets<T: ForeignDataWrapper>(
+    root: *mut pg_sys::PlannerInfo,
+    rtindex: pg_sys::Index,
+    _target_rte: *mut pg_sys::RangeTblEntry,
+    target_relation: pg_sys::Relation,
+) {
+    let column = row_id_column::<T>();
+    let var = row_id_var(target_relation, rtindex, column);
+    pg_sys::add_row_identity_var(
+        root,
+        var,
+        rtindex,
+        PgMemoryContexts::CurrentMemoryContext.pstrdup(column),
+    );
+}
+
+/// What `ri_FdwState` points to during a modification.
+struct ModifyContext<S> {
+    state: S,
+    /// The row identity's position in the subplan's output, if updating or deleting
+    row_id_attno: pg_sys::AttrNumber,
+}
+
+#[inline]
+unsafe fn modify_context<'a, T: ForeignDataWrapper>(
+    rinfo: *mut pg_sys::ResultRelInfo,
+) -> Option<&'a mut Option<ModifyContext<T::ModifyState>>> {
+    (*rinfo).ri_FdwState.cast::<Option<ModifyContext<T::ModifyState>>>().as_mut()
+}
+
+unsafe fn begin_modify<T: ForeignDataWrapper>(
+    rinfo: *mut pg_sys::ResultRelInfo,
+    row_id_attno: pg_sys::AttrNumber,
+) {
+    let relation = PgRelation::from_pg((*rinfo).ri_RelationDesc);
+    let state = T::begin_modify(decode_options::<T>(relation.oid()), &relation);
+    (*rinfo).ri_FdwState = PgMemoryContexts::CurrentMemoryContext
+        .leak_and_drop_on_delete(Some(ModifyContext { state, row_id_attno }))
+        .cast();
+}
+
+#[pg_guard]
+unsafe extern "C" fn begin_foreign_modify<T: ForeignDataWrapper>(
+    mtstate: *mut pg_sys::ModifyTableState,
+    rinfo: *mut pg_sys::ResultRelInfo,
+    _fdw_private: *mut pg_sys::List,
+    _subplan_index: c_int,
+    eflags: c_int,
+) {
+    if eflags & pg_sys::EXEC_FLAG_EXPLAIN_ONLY as c_int != 0 {
+        return;
+    }
+
+    let mut row_id_attno = pg_sys::InvalidAttrNumber as pg_sys::AttrNumber;
+    let operation = (*mtstate).operation;
+    if operation == pg_sys::CmdType::CMD_UPDATE || operation == pg_sys::CmdType::CMD_DELETE {
+        #[cfg(any(feature = "pg12", feature = "pg13"))]
+        let subplan = (*(*(*mtstate).mt_plans.add(_subplan_index as usize))).plan;
+        #[cfg(not(any(feature = "pg12", feature = "pg13")))]
+        let subplan = (*(*mtstate).ps.lefttree).plan;
+
+        let column = alloc::ffi::CString::new(row_id_column::<T>()).unwrap();
+        row_id_attno = pg_sys::ExecFindJunkAttributeInTlist((*subplan).targetlist, column.as_ptr());
+    }
+
+    begin_modify::<T>(rinfo, row_id_attno);
+}
+
+#[pg_guard]
+unsafe extern "C" fn begin_foreign_insert<T: ForeignDataWrapper>(
+    _mtstate: *mut pg_sys::ModifyTableState,
+    rinfo: *mut pg_sys::ResultRelInfo,
+) {
+    begin_modify::<T>(rinfo, pg_sys::InvalidAttrNumber as pg_sys::AttrNumber);
+}
+
+/// The tuple stored in `slot`, which stays valid for the rest of the modification.
+unsafe fn slot_tuple<'a>(
+    slot: *mut pg_sys::TupleTableSlot,
+) -> PgHeapTuple<'a, AllocatedByPostgres> {
+    let mut should_free = false;
+    let tuple = pg_sys::ExecFetchSlotHeapTuple(slot, true, &mut should_free);
+    PgHeapTuple::from_heap_tuple(PgTupleDesc::from_pg_unchecked((*slot).tts_tupleDescriptor), tuple)
+}
+
+/// The row identity the subplan produced alongside the row being modified.
+unsafe fn slot_row_id(
+    plan_slot: *mut pg_sys::TupleTableSlot,
+    attno: pg_sys::AttrNumber,
+) -> AnyElement {
+    if attno <= 0 {
+        ereport!(
+            ERROR,
+            PgSqlErrorCode::ERRCODE_FDW_ERROR,
+            "row identity column not found in subplan"
+        );
+    }
+    if ((*plan_slot).tts_nvalid as c_int) < attno as c_int {
+        pg_sys::slot_getsomeattrs_int(plan_slot, attno as c_int);
+    }
+
+    let index = attno as usize - 1;
+    let datum = *(*plan_slot).tts_values.add(index);
+    let is_null = *(*plan_slot).tts_isnull.add(index);
+    let tupdesc = PgTupleDesc::from_pg_unchecked((*plan_slot).tts_tupleDescriptor);
+    let typoid = tupdesc.get(index).map(|att| att.atttypid).unwrap_or(pg_sys::InvalidOid);
+    match AnyElement::from_polymorphic_datum(datum, is_null, typoid) {
+        Some(rowid) => rowid,
+        None => {
+            ereport!(ERROR, PgSqlErrorCode::ERRCODE_FDW_ERROR, "row identity is null");
+        }
+    }
+}
+
+#[pg_guard]
+unsafe extern "C" fn exec_foreign_insert<T: ForeignDataWrapper>(
+    _estate: *mut pg_sys::EState,
+    rinfo: *mut pg_sys::ResultRelInfo,
+    slot: *mut pg_sys::TupleTableSlot,
+    _plan_slot: *mut pg_sys::TupleTableSlot,
+) -> *mut pg_sys::TupleTableSlot {
+    if let Some(Some(context)) = modify_context::<T>(rinfo) {
+        T::insert(&mut context.state, &slot_tuple(slot));
+    }
+    slot
+}
+
+#[pg_guard]
+unsafe extern "C" fn exec_foreign_update<T: ForeignDataWrapper>(
+    _estate: *mut pg_sys::EState,
+    rinfo: *mut pg_sys::ResultRelInfo,
+    slot: *mut pg_sys::TupleTableSlot,
+    plan_slot: *mut pg_sys::TupleTableSlot,
+) -> *mut pg_sys::TupleTableSlot {
+    if let Some(Some(context)) = modify_context::<T>(rinfo) {
+        let rowid = slot_row_id(plan_slot, context.row_id_attno);
+        T::update(&mut context.state, rowid, &slot_tuple(slot));
+    }
+    slot
+}
+
+#[pg_guard]
+unsafe extern "C" fn exec_foreign_delete<T: ForeignDataWrapper>(
+    _estate: *mut pg_sys::EState,
+    rinfo: *mut pg_sys::ResultRelInfo,
+    slot: *mut pg_sys::TupleTableSlot,
+    plan_slot: *mut pg_sys::TupleTableSlot,
+) -> *mut pg_sys::TupleTableSlot {
+    if let Some(Some(context)) = modify_context::<T>(rinfo) {
+        let rowid = slot_row_id(plan_slot, context.row_id_attno);
+        T::delete(&mut context.state, rowid);
+    }
+    slot
+}
+
+#[pg_guard]
+unsafe extern "C" fn end_foreign_modify<T: ForeignDataWrapper>(
+    _estate: *mut pg_sys::EState,
+    rinfo: *mut pg_sys::ResultRelInfo,
+) {
+    if let Some(context) = modify_context::<T>(rinfo).and_then(Option::take) {
+        T::end_modify(context.state);
+    }
+}
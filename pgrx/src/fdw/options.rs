@@ -0,0 +1,326 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+//! Decoding of `OPTIONS (...)` given to foreign data wrapper objects
+use crate::list::List;
+use crate::{memcx, pg_sys};
+use serde::de::{self, DeserializeOwned, DeserializeSeed, IntoDeserializer, MapAccess, Visitor};
+use std::collections::btree_map::{self, BTreeMap};
+use std::ffi::CStr;
+
+/// The catalog an option list being validated belongs to.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FdwOptionCatalog {
+    /// `CREATE FOREIGN DATA WRAPPER ... OPTIONS`
+    ForeignDataWrapper,
+    /// `CREATE SERVER ... OPTIONS`
+    ForeignServer,
+    /// `CREATE USER MAPPING ... OPTIONS`
+    UserMapping,
+    /// `CREATE FOREIGN TABLE ... OPTIONS`
+    ForeignTable,
+    /// Column options of a foreign table
+    Attribute,
+}
+
+impl FdwOptionCatalog {
+    /// The catalog identified by `oid`, as passed to a foreign data wrapper's validator function.
+    pub fn from_oid(oid: pg_sys::Oid) -> Option<Self> {
+        match oid {
+            pg_sys::ForeignDataWrapperRelationId => Some(Self::ForeignDataWrapper),
+            pg_sys::ForeignServerRelationId => Some(Self::ForeignServer),
+            pg_sys::UserMappingRelationId => Some(Self::UserMapping),
+            pg_sys::ForeignTableRelationId => Some(Self::ForeignTable),
+            pg_sys::AttributeRelationId => Some(Self::Attribute),
+            _ => None,
+        }
+    }
+}
+
+/// An error decoding [`FdwOptions`] into a Rust type.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum FdwOptionsError {
+    #[error("missing required option \"{0}\"")]
+    MissingOption(&'static str),
+    #[error("invalid value for option \"{option}\": {message}")]
+    InvalidValue { option: String, message: String },
+    #[error("{0}")]
+    Custom(String),
+}
+
+impl de::Error for FdwOptionsError {
+    fn custom<T: std::fmt::Display>(msg: T) -> Self {
+        FdwOptionsError::Custom(msg.to_string())
+    }
+
+    fn missing_field(field: &'static str) -> Self {
+        FdwOptionsError::MissingOption(field)
+    }
+}
+
+/// A set of `name => value` options given to foreign data wrapper objects.
+///
+/// Postgres stores every option as text.  [`FdwOptions::decode`] turns them into any type
+/// implementing [`serde::Deserialize`], parsing numbers and booleans from their text form along the
+/// way.  Booleans accept the same spellings as Postgres' `defGetBoolean()`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FdwOptions {
+    options: BTreeMap<String, String>,
+}
+
+impl FdwOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read the options out of a Postgres `List` of `DefElem`s.
+    ///
+    /// # Safety
+    ///
+    /// `list` must be null or a valid `List` of `DefElem` pointers whose arguments are strings.
+    pub unsafe fn from_pg_list(list: *mut pg_sys::List) -> Self {
+        let mut options = Self::new();
+        options.extend_from_pg_list(list);
+        options
+    }
+
+    /// Add the options in a Postgres `List` of `DefElem`s, replacing existing ones of the same name.
+    ///
+    /// # Safety
+    ///
+    /// `list` must be null or a valid `List` of `DefElem` pointers whose arguments are strings.
+    pub unsafe fn extend_from_pg_list(&mut self, list: *mut pg_sys::List) {
+        memcx::current_context(|cx| {
+            let Some(list) = List::<*mut std::ffi::c_void>::downcast_ptr_in_memcx(list, cx) else {
+                return;
+            };
+            for def in list.iter().map(|ptr| ptr.cast::<pg_sys::DefElem>()) {
+                let name = CStr::from_ptr((*def).defname).to_string_lossy().into_owned();
+                let value =
+                    CStr::from_ptr(pg_sys::defGetString(def)).to_string_lossy().into_owned();
+                self.options.insert(name, value);
+            }
+        })
+    }
+
+    /// Set an option, returning its previous value.
+    pub fn insert(&mut self, name: impl Into<String>, value: impl Into<String>) -> Option<String> {
+        self.options.insert(name.into(), value.into())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.options.get(name).map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.options.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.options.is_empty()
+    }
+
+    /// Iterate over the `(name, value)` pairs, ordered by name.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.options.iter().map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    /// Decode the options into `T`.
+    pub fn decode<T: DeserializeOwned>(&self) -> Result<T, FdwOptionsError> {
+        T::deserialize(OptionsDeserializer { options: self })
+    }
+}
+
+impl<K: Into<String>, V: Into<String>> FromIterator<(K, V)> for FdwOptions {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        Self { options: iter.into_iter().map(|(k, v)| (k.into(), v.into())).collect() }
+    }
+}
+
+/// The option names `T` declares, if it is a struct.
+///
+/// Types which don't deserialize as a plain struct (eg, those using `#[serde(flatten)]`) return
+/// `None`, as do those which accept any option name.
+pub(crate) fn option_names<T: DeserializeOwned>() -> Option<&'static [&'static str]> {
+    let mut names = None;
+    let _ = T::deserialize(FieldNames { names: &mut names });
+    names
+}
+
+struct FieldNames<'a> {
+    names: &'a mut Option<&'static [&'static str]>,
+}
+
+impl<'de> de::Deserializer<'de> for FieldNames<'_> {
+    type Error = FdwOptionsError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, Self::Error> {
+        Err(de::Error::custom("not a struct"))
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        *self.names = Some(fields);
+        Err(de::Error::custom("only collecting field names"))
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option
+        unit unit_struct newtype_struct seq tuple tuple_struct map enum identifier ignored_any
+    }
+}
+
+struct OptionsDeserializer<'a> {
+    options: &'a FdwOptions,
+}
+
+impl<'de> de::Deserializer<'de> for OptionsDeserializer<'de> {
+    type Error = FdwOptionsError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_map(OptionsMap { iter: self.options.options.iter(), value: None })
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes byte_buf option
+        unit unit_struct newtype_struct seq tuple tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+struct OptionsMap<'de> {
+    iter: btree_map::Iter<'de, String, String>,
+    value: Option<(&'de str, &'de str)>,
+}
+
+impl<'de> MapAccess<'de> for OptionsMap<'de> {
+    type Error = FdwOptionsError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        match self.iter.next() {
+            Some((name, value)) => {
+                self.value = Some((name, value));
+                seed.deserialize(name.as_str().into_deserializer()).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        let (option, value) = self.value.take().expect("option value requested before its name");
+        seed.deserialize(OptionValue { option, value })
+    }
+}
+
+/// A single option's text, which deserializes as whatever type is asked of it.
+struct OptionValue<'de> {
+    option: &'de str,
+    value: &'de str,
+}
+
+impl OptionValue<'_> {
+    fn invalid(&self, message: impl std::fmt::Display) -> FdwOptionsError {
+        FdwOptionsError::InvalidValue {
+            option: self.option.to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    fn parse<T: std::str::FromStr>(&self) -> Result<T, FdwOptionsError>
+    where
+        T::Err: std::fmt::Display,
+    {
+        self.value.trim().parse().map_err(|e| self.invalid(e))
+    }
+}
+
+macro_rules! deserialize_parsed {
+    ($($method:ident => $visit:ident),* $(,)?) => {
+        $(
+            fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+                visitor.$visit(self.parse()?)
+            }
+        )*
+    };
+}
+
+impl<'de> de::Deserializer<'de> for OptionValue<'de> {
+    type Error = FdwOptionsError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        visitor.visit_borrowed_str(self.value)
+    }
+
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        // the spellings accepted by Postgres' `defGetBoolean()`
+        match self.value.trim().to_ascii_lowercase().as_str() {
+            "true" | "on" | "1" => visitor.visit_bool(true),
+            "false" | "off" | "0" => visitor.visit_bool(false),
+            _ => Err(self.invalid("requires a Boolean value")),
+        }
+    }
+
+    deserialize_parsed! {
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_i128 => visit_i128,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_u128 => visit_u128,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64,
+        deserialize_char => visit_char,
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, Self::Error> {
+        // the option is present, otherwise we'd never have been asked
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        let option = self.option;
+        visitor.visit_enum(self.value.into_deserializer()).map_err(|e: FdwOptionsError| match e {
+            FdwOptionsError::Custom(message) => {
+                FdwOptionsError::InvalidValue { option: option.to_string(), message }
+            }
+            e => e,
+        })
+    }
+
+    serde::forward_to_deserialize_any! {
+        str string bytes byte_buf unit unit_struct seq tuple tuple_struct map struct identifier
+        ignored_any
+    }
+}
@@ -8,10 +8,16 @@
 //LICENSE
 //LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
 //! Provides a safe interface into Postgres' Configuration System (GUC)
-use crate::{pg_sys, PgMemoryContexts};
-use core::ffi::CStr;
+use crate as pgrx; // for #[pg_guard] support from within ourself
+use crate::{pg_guard, pg_sys, PgMemoryContexts, PgSqlErrorCode};
+use core::ffi::{c_char, c_int, c_void, CStr};
 pub use pgrx_macros::PostgresGucEnum;
-use std::cell::Cell;
+use std::cell::{Cell, UnsafeCell};
+use std::collections::VecDeque;
+use std::ffi::CString;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Duration;
 
 /// Defines at what level this GUC can be set
 pub enum GucContext {
@@ -208,6 +214,636 @@ where
     }
 }
 
+/// Where a GUC's new value came from, as passed to its check hook
+pub type GucSource = pg_sys::GucSource::Type;
+
+/// Why a check hook rejected a GUC's new value
+///
+/// Postgres reports this as an `ERROR`, or as a `LOG` message when the value came from the
+/// configuration file.
+#[derive(Debug, Clone)]
+pub struct GucCheckError {
+    message: String,
+    detail: Option<String>,
+    hint: Option<String>,
+    sqlerrcode: PgSqlErrorCode,
+}
+
+impl GucCheckError {
+    pub fn new<S: Into<String>>(message: S) -> Self {
+        GucCheckError {
+            message: message.into(),
+            detail: None,
+            hint: None,
+            sqlerrcode: PgSqlErrorCode::ERRCODE_INVALID_PARAMETER_VALUE,
+        }
+    }
+
+    pub fn set_detail<S: Into<String>>(mut self, detail: S) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    pub fn set_hint<S: Into<String>>(mut self, hint: S) -> Self {
+        self.hint = Some(hint.into());
+        self
+    }
+
+    /// Defaults to `ERRCODE_INVALID_PARAMETER_VALUE`
+    pub fn set_sqlerrcode(mut self, sqlerrcode: PgSqlErrorCode) -> Self {
+        self.sqlerrcode = sqlerrcode;
+        self
+    }
+
+    /// The equivalent of Postgres' `GUC_check_errmsg()`, `GUC_check_errdetail()`, etc
+    unsafe fn report(self) {
+        unsafe {
+            let mcxt = PgMemoryContexts::CurrentMemoryContext;
+            pg_sys::GUC_check_errcode(self.sqlerrcode as _);
+            pg_sys::GUC_check_errmsg_string = mcxt.pstrdup(&self.message);
+            if let Some(detail) = self.detail {
+                pg_sys::GUC_check_errdetail_string = mcxt.pstrdup(&detail);
+            }
+            if let Some(hint) = self.hint {
+                pg_sys::GUC_check_errhint_string = mcxt.pstrdup(&hint);
+            }
+        }
+    }
+}
+
+/// The check, assign and show hooks for a GUC holding a `T`
+///
+/// Postgres calls the check hook to validate a new value before it is set, the assign hook after
+/// it is set, and the show hook to render the current value for `SHOW` and `pg_settings`.
+///
+/// Postgres' hooks are plain function pointers, with nowhere to keep a closure's state, so each
+/// hook must be a function or a closure that doesn't capture anything.  They run in whichever
+/// process sets the GUC, including the postmaster while it reloads the configuration file.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use pgrx::guc::*;
+/// use std::ffi::CStr;
+///
+/// static URL: GucSetting<Option<&'static CStr>> = GucSetting::<Option<&'static CStr>>::new(None);
+///
+/// GucRegistry::define_string_guc_with_hooks(
+///     "my_extension.url",
+///     "the server to connect to",
+///     "the server to connect to",
+///     &URL,
+///     GucContext::Userset,
+///     GucFlags::default(),
+///     GucHooks::new().check(|url: Option<&CStr>, _source| match url {
+///         Some(url) if !url.to_bytes().starts_with(b"https://") => Err(GucCheckError::new(
+///             "my_extension.url must be an https:// URL",
+///         )),
+///         _ => Ok(()),
+///     }),
+/// );
+/// ```
+pub struct GucHooks<T> {
+    check: Option<unsafe extern "C" fn()>,
+    assign: Option<unsafe extern "C" fn()>,
+    show: pg_sys::GucShowHook,
+    _marker: PhantomData<fn(T)>,
+}
+
+impl<T> Default for GucHooks<T> {
+    fn default() -> Self {
+        GucHooks { check: None, assign: None, show: None, _marker: PhantomData }
+    }
+}
+
+impl<T: GucHookValue> GucHooks<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Validate a new value, returning a [`GucCheckError`] to reject it
+    pub fn check<F>(mut self, f: F) -> Self
+    where
+        F: Fn(T, GucSource) -> Result<(), GucCheckError> + Copy + 'static,
+    {
+        assert_hook(f);
+        self.check = T::check_hook::<F>();
+        self
+    }
+
+    /// React to the GUC having been set to a new value
+    pub fn assign<F: Fn(T) + Copy + 'static>(mut self, f: F) -> Self {
+        assert_hook(f);
+        self.assign = T::assign_hook::<F>();
+        self
+    }
+
+    /// Render the current value for `SHOW` and `pg_settings`, such as with units
+    pub fn show<F: Fn() -> String + Copy + 'static>(mut self, f: F) -> Self {
+        assert_hook(f);
+        self.show = Some(show_hook::<F>);
+        self
+    }
+
+    /// # Safety
+    ///
+    /// `H` must be the type of Postgres hook the check hook was created as
+    unsafe fn check_hook<H>(&self) -> H {
+        unsafe { std::mem::transmute_copy(&self.check) }
+    }
+
+    /// # Safety
+    ///
+    /// `H` must be the type of Postgres hook the assign hook was created as
+    unsafe fn assign_hook<H>(&self) -> H {
+        unsafe { std::mem::transmute_copy(&self.assign) }
+    }
+}
+
+/// The types a GUC can hold, along with the Postgres check and assign hooks for each
+pub trait GucHookValue: Copy + Sized {
+    #[doc(hidden)]
+    fn check_hook<F>() -> Option<unsafe extern "C" fn()>
+    where
+        F: Fn(Self, GucSource) -> Result<(), GucCheckError>;
+
+    #[doc(hidden)]
+    fn assign_hook<F: Fn(Self)>() -> Option<unsafe extern "C" fn()>;
+}
+
+/// Report the outcome of a check hook to Postgres
+fn check_result(result: Result<(), GucCheckError>) -> bool {
+    match result {
+        Ok(()) => true,
+        Err(e) => {
+            unsafe { e.report() };
+            false
+        }
+    }
+}
+
+macro_rules! guc_hook_value_impl {
+    ($ty:ty, $c_ty:ty, $assign_c_ty:ty, $to_rust:path, $check_hook:ty, $assign_hook:ty) => {
+        impl GucHookValue for $ty {
+            fn check_hook<F>() -> Option<unsafe extern "C" fn()>
+            where
+                F: Fn(Self, GucSource) -> Result<(), GucCheckError>,
+            {
+                #[pg_guard]
+                unsafe extern "C" fn check<F>(
+                    newval: *mut $c_ty,
+                    _extra: *mut *mut c_void,
+                    source: GucSource,
+                ) -> bool
+                where
+                    F: Fn($ty, GucSource) -> Result<(), GucCheckError>,
+                {
+                    let f = unsafe { conjure_hook::<F>() };
+                    check_result(f(unsafe { $to_rust(*newval) }, source))
+                }
+
+                let hook: $check_hook = Some(check::<F>);
+                unsafe { std::mem::transmute_copy(&hook) }
+            }
+
+            fn assign_hook<F: Fn(Self)>() -> Option<unsafe extern "C" fn()> {
+                #[pg_guard]
+                unsafe extern "C" fn assign<F: Fn($ty)>(newval: $assign_c_ty, _extra: *mut c_void) {
+                    let f = unsafe { conjure_hook::<F>() };
+                    f(unsafe { $to_rust(newval) })
+                }
+
+                let hook: $assign_hook = Some(assign::<F>);
+                unsafe { std::mem::transmute_copy(&hook) }
+            }
+        }
+    };
+}
+
+guc_hook_value_impl!(
+    bool,
+    bool,
+    bool,
+    std::convert::identity,
+    pg_sys::GucBoolCheckHook,
+    pg_sys::GucBoolAssignHook
+);
+guc_hook_value_impl!(
+    i32,
+    c_int,
+    c_int,
+    std::convert::identity,
+    pg_sys::GucIntCheckHook,
+    pg_sys::GucIntAssignHook
+);
+guc_hook_value_impl!(
+    f64,
+    f64,
+    f64,
+    std::convert::identity,
+    pg_sys::GucRealCheckHook,
+    pg_sys::GucRealAssignHook
+);
+guc_hook_value_impl!(
+    Option<&'static CStr>,
+    *mut c_char,
+    *const c_char,
+    string_value,
+    pg_sys::GucStringCheckHook,
+    pg_sys::GucStringAssignHook
+);
+
+impl<T> GucHookValue for T
+where
+    T: GucEnum<T> + Copy,
+{
+    fn check_hook<F>() -> Option<unsafe extern "C" fn()>
+    where
+        F: Fn(T, GucSource) -> Result<(), GucCheckError>,
+    {
+        #[pg_guard]
+        unsafe extern "C" fn check<T, F>(
+            newval: *mut c_int,
+            _extra: *mut *mut c_void,
+            source: GucSource,
+        ) -> bool
+        where
+            T: GucEnum<T> + Copy,
+            F: Fn(T, GucSource) -> Result<(), GucCheckError>,
+        {
+            let f = unsafe { conjure_hook::<F>() };
+            check_result(f(T::from_ordinal(unsafe { *newval }), source))
+        }
+
+        let hook: pg_sys::GucEnumCheckHook = Some(check::<T, F>);
+        unsafe { std::mem::transmute_copy(&hook) }
+    }
+
+    fn assign_hook<F: Fn(T)>() -> Option<unsafe extern "C" fn()> {
+        #[pg_guard]
+        unsafe extern "C" fn assign<T, F>(newval: c_int, _extra: *mut c_void)
+        where
+            T: GucEnum<T> + Copy,
+            F: Fn(T),
+        {
+            let f = unsafe { conjure_hook::<F>() };
+            f(T::from_ordinal(newval))
+        }
+
+        let hook: pg_sys::GucEnumAssignHook = Some(assign::<T, F>);
+        unsafe { std::mem::transmute_copy(&hook) }
+    }
+}
+
+/// # Safety
+///
+/// `value` must be null or a valid C string that outlives the GUC's current value
+unsafe fn string_value(value: *const c_char) -> Option<&'static CStr> {
+    (!value.is_null()).then(|| unsafe { CStr::from_ptr(value) })
+}
+
+#[pg_guard]
+unsafe extern "C" fn show_hook<F: Fn() -> String>() -> *const c_char {
+    let f = unsafe { conjure_hook::<F>() };
+    unsafe { PgMemoryContexts::CurrentMemoryContext.pstrdup(&f()) }
+}
+
+fn assert_hook<F: Copy>(_: F) {
+    assert_eq!(
+        std::mem::size_of::<F>(),
+        0,
+        "GUC hooks must be functions or closures that don't capture anything"
+    );
+}
+
+/// Recreate a hook that [`assert_hook`] has verified is zero-sized
+unsafe fn conjure_hook<F>() -> F {
+    debug_assert_eq!(std::mem::size_of::<F>(), 0);
+    unsafe { std::ptr::NonNull::<F>::dangling().as_ptr().read() }
+}
+
+/// A type that can be parsed out of a string GUC, for use with [`GucValueSetting`]
+///
+/// This is the way to take structured configuration, such as lists, durations, byte sizes, or an
+/// enum that carries data, without re-parsing the GUC's text every time it's read.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use pgrx::guc::*;
+///
+/// enum Compression {
+///     Off,
+///     Zstd(i32),
+/// }
+///
+/// impl GucValue for Compression {
+///     fn parse(value: &str, _flags: GucFlags) -> Result<Self, GucCheckError> {
+///         match value.split_once(':') {
+///             None if value == "off" => Ok(Compression::Off),
+///             Some(("zstd", level)) => level
+///                 .parse()
+///                 .map(Compression::Zstd)
+///                 .map_err(|_| GucCheckError::new(format!("invalid zstd level \"{level}\""))),
+///             _ => Err(GucCheckError::new(format!("unknown compression \"{value}\""))
+///                 .set_hint("use \"off\" or \"zstd:<level>\"")),
+///         }
+///     }
+/// }
+///
+/// static COMPRESSION: GucValueSetting<Compression> = GucValueSetting::new(c"zstd:3");
+/// ```
+pub trait GucValue: Sized + 'static {
+    /// Parse the GUC's text.  `flags` are those the GUC was defined with, which carry its unit
+    ///
+    /// This is called by the GUC's check hook, which rejects the new value if it fails.
+    fn parse(value: &str, flags: GucFlags) -> Result<Self, GucCheckError>;
+}
+
+impl GucValue for String {
+    fn parse(value: &str, _flags: GucFlags) -> Result<Self, GucCheckError> {
+        Ok(value.to_owned())
+    }
+}
+
+/// A comma-separated list, with the whitespace around each element trimmed.  An empty string is
+/// an empty list
+impl<T: GucValue> GucValue for Vec<T> {
+    fn parse(value: &str, flags: GucFlags) -> Result<Self, GucCheckError> {
+        if value.trim().is_empty() {
+            return Ok(Vec::new());
+        }
+        value.split(',').map(|element| T::parse(element.trim(), flags)).collect()
+    }
+}
+
+/// A time, such as `250ms` or `5min`.  A bare number is in the unit given by the GUC's
+/// [`GucFlags::UNIT_MS`], [`GucFlags::UNIT_S`] or [`GucFlags::UNIT_MIN`] flag, or milliseconds
+impl GucValue for Duration {
+    fn parse(value: &str, flags: GucFlags) -> Result<Self, GucCheckError> {
+        let (unit, secs) = match flags.bits() & pg_sys::GUC_UNIT_TIME as i32 {
+            unit if unit == pg_sys::GUC_UNIT_S as i32 => (unit, 1.0),
+            unit if unit == pg_sys::GUC_UNIT_MIN as i32 => (unit, 60.0),
+            _ => (pg_sys::GUC_UNIT_MS as i32, 0.001),
+        };
+        let invalid = || GucCheckError::new(format!("\"{value}\" is not a valid duration"));
+        let n = parse_with_unit(value, unit).map_err(|hint| with_hint(invalid(), hint))?;
+        Duration::try_from_secs_f64(n * secs).map_err(|_| invalid())
+    }
+}
+
+/// A number of bytes, as parsed from a memory size such as `64kB` or `1GB`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ByteSize(pub u64);
+
+/// A bare number is in the unit given by the GUC's [`GucFlags::UNIT_BYTE`],
+/// [`GucFlags::UNIT_KB`], [`GucFlags::UNIT_MB`], [`GucFlags::UNIT_BLOCKS`] or
+/// [`GucFlags::UNIT_XBLOCKS`] flag, or bytes
+impl GucValue for ByteSize {
+    fn parse(value: &str, flags: GucFlags) -> Result<Self, GucCheckError> {
+        let (unit, bytes) = match flags.bits() & pg_sys::GUC_UNIT_MEMORY as i32 {
+            unit if unit == pg_sys::GUC_UNIT_KB as i32 => (unit, 1024.0),
+            unit if unit == pg_sys::GUC_UNIT_MB as i32 => (unit, 1024.0 * 1024.0),
+            unit if unit == pg_sys::GUC_UNIT_BLOCKS as i32 => (unit, pg_sys::BLCKSZ as f64),
+            unit if unit == pg_sys::GUC_UNIT_XBLOCKS as i32 => (unit, pg_sys::XLOG_BLCKSZ as f64),
+            _ => (pg_sys::GUC_UNIT_BYTE as i32, 1.0),
+        };
+        let invalid = || GucCheckError::new(format!("\"{value}\" is not a valid byte size"));
+        let n = parse_with_unit(value, unit).map_err(|hint| with_hint(invalid(), hint))? * bytes;
+        if !(0.0..=u64::MAX as f64).contains(&n) {
+            return Err(invalid());
+        }
+        Ok(ByteSize(n.round() as u64))
+    }
+}
+
+/// Parse a number, and an optional unit, into the base `unit` with Postgres' `parse_real()`.  On
+/// failure, returns the hint it gave, if any
+fn parse_with_unit(value: &str, unit: c_int) -> Result<f64, Option<String>> {
+    let value = CString::new(value).map_err(|_| None)?;
+    let mut result = 0.0;
+    let mut hint = std::ptr::null();
+    unsafe {
+        if pg_sys::parse_real(value.as_ptr(), &mut result, unit, &mut hint) {
+            Ok(result)
+        } else {
+            Err((!hint.is_null()).then(|| CStr::from_ptr(hint).to_string_lossy().into_owned()))
+        }
+    }
+}
+
+fn with_hint(error: GucCheckError, hint: Option<String>) -> GucCheckError {
+    match hint {
+        Some(hint) => error.set_hint(hint),
+        None => error,
+    }
+}
+
+fn parse_value<T: GucValue>(value: &CStr, flags: GucFlags) -> Result<T, GucCheckError> {
+    let value = value.to_str().map_err(|_| GucCheckError::new("invalid UTF-8"))?;
+    T::parse(value, flags)
+}
+
+/// A safe wrapper around a global variable that can be edited through a string GUC, whose value is
+/// parsed into a `T`
+///
+/// Each new value is parsed once, by the GUC's check hook, so that reading it is cheap.  The few
+/// most recently used values are kept, so that setting the GUC back to one of them, such as when a
+/// `SET LOCAL` ends, doesn't parse it again.
+pub struct GucValueSetting<T> {
+    value: Cell<usize>,
+    boot_val: &'static CStr,
+    flags: Cell<GucFlags>,
+    current: UnsafeCell<Option<Arc<T>>>,
+    parsed: UnsafeCell<VecDeque<ParsedValue<T>>>,
+    next_id: Cell<u64>,
+}
+
+/// How many recently used values a [`GucValueSetting`] keeps
+const PARSED_VALUES: usize = 8;
+
+/// A value parsed by a [`GucValueSetting`]'s check hook, identified to its assign hook by `id`
+struct ParsedValue<T> {
+    id: u64,
+    text: Box<[u8]>,
+    value: Arc<T>,
+}
+
+unsafe impl<T: GucValue> Sync for GucValueSetting<T> {}
+impl<T: GucValue> GucValueSetting<T> {
+    /// `value` is the text of the GUC's default value
+    pub const fn new(value: &'static CStr) -> Self {
+        GucValueSetting {
+            value: Cell::new(0),
+            boot_val: value,
+            flags: Cell::new(GucFlags::empty()),
+            current: UnsafeCell::new(None),
+            parsed: UnsafeCell::new(VecDeque::new()),
+            next_id: Cell::new(0),
+        }
+    }
+
+    /// The GUC's current value, as text
+    pub fn get_text(&self) -> &CStr {
+        unsafe { string_value(self.value.get() as *const c_char) }.unwrap_or(self.boot_val)
+    }
+
+    /// The GUC's current value
+    ///
+    /// # Panics
+    ///
+    /// If the default value doesn't parse, and the GUC hasn't been defined yet
+    pub fn get(&self) -> Arc<T> {
+        // SAFETY: `current` is only replaced by the assign hook, which doesn't run while we're here
+        if let Some(value) = unsafe { &*self.current.get() } {
+            return value.clone();
+        }
+
+        parse_value::<T>(self.boot_val, self.flags.get()).map(Arc::new).unwrap_or_else(|e| {
+            panic!("could not parse GUC value {:?}: {}", self.boot_val, e.message)
+        })
+    }
+
+    /// Parse `text` with the GUC's flags, unless it's one of the recently used values, returning
+    /// the id of the value
+    fn parse(&self, text: &CStr) -> Result<u64, GucCheckError> {
+        // SAFETY: `parsed` is only borrowed within this function, which doesn't reenter
+        let parsed = unsafe { &mut *self.parsed.get() };
+        if let Some(idx) = parsed.iter().position(|parsed| *parsed.text == *text.to_bytes()) {
+            let parsed_value = parsed.remove(idx).unwrap();
+            let id = parsed_value.id;
+            parsed.push_back(parsed_value);
+            return Ok(id);
+        }
+
+        let value = Arc::new(parse_value::<T>(text, self.flags.get())?);
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+        if parsed.len() == PARSED_VALUES {
+            parsed.pop_front();
+        }
+        parsed.push_back(ParsedValue { id, text: text.to_bytes().into(), value });
+        Ok(id)
+    }
+
+    /// The value `id` identifies, parsing `text` again if it has since been evicted
+    fn parsed(&self, id: u64, text: &CStr) -> Arc<T> {
+        // SAFETY: `parsed` is only borrowed within this function, which doesn't reenter
+        let parsed = unsafe { &*self.parsed.get() };
+        if let Some(parsed_value) = parsed.iter().find(|parsed| parsed.id == id) {
+            return parsed_value.value.clone();
+        }
+        // the check hook already accepted `text`, so this succeeds
+        parse_value::<T>(text, self.flags.get())
+            .map(Arc::new)
+            .unwrap_or_else(|e| panic!("could not parse GUC value {text:?} again: {}", e.message))
+    }
+
+    fn as_ptr(&self) -> *mut *mut c_char {
+        self.value.as_ptr() as *mut *mut c_char
+    }
+}
+
+/// How many [`GucValueSetting`]s a backend can define
+///
+/// Postgres doesn't tell a check or assign hook which GUC it's being called for, so each setting
+/// is given hooks of its own, instantiated for the slot it's kept in.
+const MAX_VALUE_GUCS: usize = 64;
+
+/// The [`GucValueSetting`]s defined in this backend, by slot
+struct ValueGucs {
+    settings: UnsafeCell<[*const c_void; MAX_VALUE_GUCS]>,
+    len: Cell<usize>,
+}
+
+// GUCs are only ever defined and set from the backend's main thread
+unsafe impl Sync for ValueGucs {}
+
+static VALUE_GUCS: ValueGucs =
+    ValueGucs { settings: UnsafeCell::new([std::ptr::null(); MAX_VALUE_GUCS]), len: Cell::new(0) };
+
+/// Keep `setting` in the next free slot, returning the check and assign hooks for it
+fn register_value_guc<T: GucValue>(
+    setting: &'static GucValueSetting<T>,
+) -> (pg_sys::GucStringCheckHook, pg_sys::GucStringAssignHook) {
+    let slot = VALUE_GUCS.len.get();
+    macro_rules! hooks {
+        ($($slot:literal)*) => {
+            match slot {
+                $($slot => (Some(check_value::<T, $slot> as _), Some(assign_value::<T, $slot> as _)),)*
+                _ => panic!("a backend can define at most {MAX_VALUE_GUCS} value GUCs"),
+            }
+        };
+    }
+    let hooks = hooks!(
+        0 1 2 3 4 5 6 7 8 9 10 11 12 13 14 15 16 17 18 19 20 21 22 23 24 25 26 27 28 29 30 31
+        32 33 34 35 36 37 38 39 40 41 42 43 44 45 46 47 48 49 50 51 52 53 54 55 56 57 58 59 60 61
+        62 63
+    );
+    unsafe { (*VALUE_GUCS.settings.get())[slot] = (setting as *const GucValueSetting<T>).cast() };
+    VALUE_GUCS.len.set(slot + 1);
+    hooks
+}
+
+/// # Safety
+///
+/// `SLOT` must have been handed out by [`register_value_guc`] for a `GucValueSetting<T>`
+unsafe fn value_guc<T: GucValue, const SLOT: usize>() -> &'static GucValueSetting<T> {
+    unsafe { &*(*VALUE_GUCS.settings.get())[SLOT].cast::<GucValueSetting<T>>() }
+}
+
+/// Parse the new value, passing it on to [`assign_value`] as the `extra` Postgres keeps alongside
+/// the GUC's text
+#[pg_guard]
+unsafe extern "C" fn check_value<T: GucValue, const SLOT: usize>(
+    newval: *mut *mut c_char,
+    extra: *mut *mut c_void,
+    _source: GucSource,
+) -> bool {
+    let Some(text) = (unsafe { string_value(*newval) }) else {
+        return true;
+    };
+    let setting = unsafe { value_guc::<T, SLOT>() };
+    let id = match setting.parse(text) {
+        Ok(id) => id,
+        Err(e) => return check_result(Err(e)),
+    };
+
+    // Postgres frees the extra itself, without telling us, so rather than own the value it only
+    // identifies it among those `setting` keeps
+    let size = std::mem::size_of::<u64>();
+    unsafe {
+        #[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14", feature = "pg15"))]
+        let ptr = libc::malloc(size);
+        #[cfg(any(feature = "pg16", feature = "pg17"))]
+        let ptr = pg_sys::guc_malloc(pg_sys::LOG as _, size);
+        if ptr.is_null() {
+            return check_result(Err(GucCheckError::new("out of memory")
+                .set_sqlerrcode(PgSqlErrorCode::ERRCODE_OUT_OF_MEMORY)));
+        }
+        ptr.cast::<u64>().write(id);
+        *extra = ptr;
+    }
+    true
+}
+
+/// Make the value [`check_value`] parsed the setting's current one
+#[pg_guard]
+unsafe extern "C" fn assign_value<T: GucValue, const SLOT: usize>(
+    newval: *const c_char,
+    extra: *mut c_void,
+) {
+    let setting = unsafe { value_guc::<T, SLOT>() };
+    // `check_value` only leaves an extra for a non-NULL value
+    let value = match unsafe { string_value(newval) } {
+        Some(text) if !extra.is_null() => {
+            Some(setting.parsed(unsafe { extra.cast::<u64>().read() }, text))
+        }
+        _ => None,
+    };
+    unsafe { *setting.current.get() = value };
+}
+
 /// A struct that has associated functions to register new GUCs
 pub struct GucRegistry {}
 impl GucRegistry {
@@ -218,6 +854,26 @@ impl GucRegistry {
         setting: &GucSetting<bool>,
         context: GucContext,
         flags: GucFlags,
+    ) {
+        Self::define_bool_guc_with_hooks(
+            name,
+            short_description,
+            long_description,
+            setting,
+            context,
+            flags,
+            GucHooks::default(),
+        )
+    }
+
+    pub fn define_bool_guc_with_hooks(
+        name: &str,
+        short_description: &str,
+        long_description: &str,
+        setting: &GucSetting<bool>,
+        context: GucContext,
+        flags: GucFlags,
+        hooks: GucHooks<bool>,
     ) {
         unsafe {
             pg_sys::DefineCustomBoolVariable(
@@ -228,9 +884,9 @@ impl GucRegistry {
                 setting.get(),
                 context as isize as _,
                 flags.bits(),
-                None,
-                None,
-                None,
+                hooks.check_hook::<pg_sys::GucBoolCheckHook>(),
+                hooks.assign_hook::<pg_sys::GucBoolAssignHook>(),
+                hooks.show,
             );
         }
     }
@@ -244,6 +900,30 @@ impl GucRegistry {
         max_value: i32,
         context: GucContext,
         flags: GucFlags,
+    ) {
+        Self::define_int_guc_with_hooks(
+            name,
+            short_description,
+            long_description,
+            setting,
+            min_value,
+            max_value,
+            context,
+            flags,
+            GucHooks::default(),
+        )
+    }
+
+    pub fn define_int_guc_with_hooks(
+        name: &str,
+        short_description: &str,
+        long_description: &str,
+        setting: &GucSetting<i32>,
+        min_value: i32,
+        max_value: i32,
+        context: GucContext,
+        flags: GucFlags,
+        hooks: GucHooks<i32>,
     ) {
         unsafe {
             pg_sys::DefineCustomIntVariable(
@@ -256,9 +936,9 @@ impl GucRegistry {
                 max_value,
                 context as isize as _,
                 flags.bits(),
-                None,
-                None,
-                None,
+                hooks.check_hook::<pg_sys::GucIntCheckHook>(),
+                hooks.assign_hook::<pg_sys::GucIntAssignHook>(),
+                hooks.show,
             )
         }
     }
@@ -270,6 +950,26 @@ impl GucRegistry {
         setting: &GucSetting<Option<&'static CStr>>,
         context: GucContext,
         flags: GucFlags,
+    ) {
+        Self::define_string_guc_with_hooks(
+            name,
+            short_description,
+            long_description,
+            setting,
+            context,
+            flags,
+            GucHooks::default(),
+        )
+    }
+
+    pub fn define_string_guc_with_hooks(
+        name: &str,
+        short_description: &str,
+        long_description: &str,
+        setting: &GucSetting<Option<&'static CStr>>,
+        context: GucContext,
+        flags: GucFlags,
+        hooks: GucHooks<Option<&'static CStr>>,
     ) {
         unsafe {
             let boot_val = setting.boot_val.map_or(std::ptr::null(), |s| s.as_ptr());
@@ -282,9 +982,9 @@ impl GucRegistry {
                 boot_val,
                 context as isize as _,
                 flags.bits(),
-                None,
-                None,
-                None,
+                hooks.check_hook::<pg_sys::GucStringCheckHook>(),
+                hooks.assign_hook::<pg_sys::GucStringAssignHook>(),
+                hooks.show,
             );
         }
     }
@@ -298,6 +998,30 @@ impl GucRegistry {
         max_value: f64,
         context: GucContext,
         flags: GucFlags,
+    ) {
+        Self::define_float_guc_with_hooks(
+            name,
+            short_description,
+            long_description,
+            setting,
+            min_value,
+            max_value,
+            context,
+            flags,
+            GucHooks::default(),
+        )
+    }
+
+    pub fn define_float_guc_with_hooks(
+        name: &str,
+        short_description: &str,
+        long_description: &str,
+        setting: &GucSetting<f64>,
+        min_value: f64,
+        max_value: f64,
+        context: GucContext,
+        flags: GucFlags,
+        hooks: GucHooks<f64>,
     ) {
         unsafe {
             pg_sys::DefineCustomRealVariable(
@@ -310,9 +1034,9 @@ impl GucRegistry {
                 max_value,
                 context as isize as _,
                 flags.bits(),
-                None,
-                None,
-                None,
+                hooks.check_hook::<pg_sys::GucRealCheckHook>(),
+                hooks.assign_hook::<pg_sys::GucRealAssignHook>(),
+                hooks.show,
             );
         }
     }
@@ -326,6 +1050,28 @@ impl GucRegistry {
         flags: GucFlags,
     ) where
         T: GucEnum<T> + Copy,
+    {
+        Self::define_enum_guc_with_hooks(
+            name,
+            short_description,
+            long_description,
+            setting,
+            context,
+            flags,
+            GucHooks::default(),
+        )
+    }
+
+    pub fn define_enum_guc_with_hooks<T>(
+        name: &str,
+        short_description: &str,
+        long_description: &str,
+        setting: &GucSetting<T>,
+        context: GucContext,
+        flags: GucFlags,
+        hooks: GucHooks<T>,
+    ) where
+        T: GucEnum<T> + Copy,
     {
         unsafe {
             let boot_val = setting.boot_val.to_ordinal();
@@ -339,10 +1085,58 @@ impl GucRegistry {
                 setting.get().config_matrix(),
                 context as isize as _,
                 flags.bits(),
-                None,
-                None,
+                hooks.check_hook::<pg_sys::GucEnumCheckHook>(),
+                hooks.assign_hook::<pg_sys::GucEnumAssignHook>(),
+                hooks.show,
+            );
+        }
+    }
+
+    /// Define a string GUC whose value is parsed into a `T`, and rejected if that fails.  Any
+    /// unit in `flags` is passed on to [`GucValue::parse`]
+    ///
+    /// # Panics
+    ///
+    /// If this backend has already defined the most value GUCs it can, which is 64
+    pub fn define_value_guc<T: GucValue>(
+        name: &str,
+        short_description: &str,
+        long_description: &str,
+        setting: &'static GucValueSetting<T>,
+        context: GucContext,
+        flags: GucFlags,
+    ) {
+        setting.flags.set(flags);
+        let (check_hook, assign_hook) = register_value_guc(setting);
+        unsafe {
+            let boot_val = setting.boot_val.as_ptr();
+            *setting.as_ptr() = boot_val as *mut _;
+            pg_sys::DefineCustomStringVariable(
+                PgMemoryContexts::TopMemoryContext.pstrdup(name),
+                PgMemoryContexts::TopMemoryContext.pstrdup(short_description),
+                PgMemoryContexts::TopMemoryContext.pstrdup(long_description),
+                setting.as_ptr(),
+                boot_val,
+                context as isize as _,
+                flags.bits(),
+                check_hook,
+                assign_hook,
                 None,
             );
         }
     }
+
+    /// Reserve `prefix` for this extension's GUCs, so that setting an undefined GUC that starts
+    /// with `prefix.`, such as a misspelled one, is an error.  Call this after defining them all
+    ///
+    /// Before Postgres 15, this only warns about any such GUCs that are already set.
+    pub fn mark_prefix_reserved(prefix: &str) {
+        unsafe {
+            let prefix = PgMemoryContexts::TopMemoryContext.pstrdup(prefix);
+            #[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14"))]
+            pg_sys::EmitWarningsOnPlaceholders(prefix);
+            #[cfg(any(feature = "pg15", feature = "pg16", feature = "pg17"))]
+            pg_sys::MarkGUCPrefixReserved(prefix);
+        }
+    }
 }
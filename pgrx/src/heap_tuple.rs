@@ -17,6 +17,7 @@ use crate::{
 };
 
 use crate::datum::{FromDatum, IntoDatum, TryFromDatumError};
+use crate::row::{FromRow, IntoRow, RowColumns, RowError, RowSource, RowWriter};
 use crate::{PgBox, PgMemoryContexts, PgTupleDesc, TriggerTuple, WhoAllocated};
 use pgrx_pg_sys::errcodes::PgSqlErrorCode;
 use pgrx_pg_sys::PgTryBuilder;
@@ -332,6 +333,18 @@ impl<'mcx> PgHeapTuple<'mcx, AllocatedByRust> {
         }
     }
 
+    /// Write `row` into this [PgHeapTuple], changing the value of each attribute one of its
+    /// fields maps to.
+    ///
+    /// ## Errors
+    /// - return [`RowError::NoSuchColumn`] if an attribute a field maps to does not exist
+    /// - return [`RowError::DatumError`] if a field's type is not compatible with its attribute's
+    ///   Postgres type
+    pub fn set_row<T: IntoRow>(&mut self, row: T) -> Result<(), RowError> {
+        let columns = RowColumns::resolve(T::COLUMNS, &self.tupdesc)?;
+        row.into_row(&mut RowWriter::new(self, columns))
+    }
+
     /// Given the index for an attribute in this [PgHeapTuple], change its value.
     ///
     /// Attribute numbers start at 1, not 0.
@@ -540,6 +553,24 @@ impl<'mcx, AllocatedBy: WhoAllocated> PgHeapTuple<'mcx, AllocatedBy> {
     where
         T: FromDatum + IntoDatum + UnboxDatum<As<'tup> = T> + 'tup,
     {
+        self.attribute_value(attno)
+    }
+
+    /// Read a `T` out of this [`PgHeapTuple`]
+    ///
+    /// ## Errors
+    /// - return [`RowError::NoSuchColumn`] if an attribute a field maps to does not exist
+    /// - return [`RowError::UnexpectedNull`] if an attribute a non-`Option` field maps to is NULL
+    /// - return [`RowError::DatumError`] if a field's type is not compatible with its attribute's
+    ///   Postgres type
+    pub fn get_row<T: FromRow>(&self) -> Result<T, RowError> {
+        RowColumns::resolve(T::COLUMNS, &self.tupdesc)?.read(self)
+    }
+
+    fn attribute_value<T: FromDatum + IntoDatum>(
+        &self,
+        attno: NonZeroUsize,
+    ) -> Result<Option<T>, TryFromDatumError> {
         unsafe {
             // tuple descriptor attribute numbers are zero-based
             match self.tupdesc.get(attno.get() - 1) {
@@ -564,6 +595,15 @@ impl<'mcx, AllocatedBy: WhoAllocated> PgHeapTuple<'mcx, AllocatedBy> {
     }
 }
 
+impl<AllocatedBy: WhoAllocated> RowSource for PgHeapTuple<'_, AllocatedBy> {
+    fn value<T: FromDatum + IntoDatum>(
+        &self,
+        ordinal: NonZeroUsize,
+    ) -> Result<Option<T>, TryFromDatumError> {
+        self.attribute_value(ordinal)
+    }
+}
+
 /** Composite type support
 
 Support for working with types defined by SQL statements like:
@@ -117,7 +117,8 @@ pub trait PgHooks {
         prev_hook(range_table, rte_perm_infos, ereport_on_violation)
     }
 
-    /// Hook for plugins to get control in `ProcessUtility()`
+    /// Hook for plugins to get control in `ProcessUtility()`, which runs every utility statement,
+    /// including all DDL
     fn process_utility_hook(
         &mut self,
         pstmt: PgBox<pg_sys::PlannedStmt>,
@@ -182,6 +183,173 @@ pub trait PgHooks {
         prev_hook(pstate, query, jumble_state)
     }
 
+    /// Hook for plugins to add paths for scanning a base relation, such as those of a
+    /// [`CustomScan`](crate::custom_scan::CustomScan)
+    fn set_rel_pathlist(
+        &mut self,
+        root: PgBox<pg_sys::PlannerInfo>,
+        rel: PgBox<pg_sys::RelOptInfo>,
+        rti: pg_sys::Index,
+        rte: PgBox<pg_sys::RangeTblEntry>,
+        prev_hook: fn(
+            root: PgBox<pg_sys::PlannerInfo>,
+            rel: PgBox<pg_sys::RelOptInfo>,
+            rti: pg_sys::Index,
+            rte: PgBox<pg_sys::RangeTblEntry>,
+        ) -> HookResult<()>,
+    ) -> HookResult<()> {
+        prev_hook(root, rel, rti, rte)
+    }
+
+    /// Hook for plugins to add paths for joining two relations, such as those of a
+    /// [`CustomScan`](crate::custom_scan::CustomScan)
+    fn set_join_pathlist(
+        &mut self,
+        root: PgBox<pg_sys::PlannerInfo>,
+        joinrel: PgBox<pg_sys::RelOptInfo>,
+        outerrel: PgBox<pg_sys::RelOptInfo>,
+        innerrel: PgBox<pg_sys::RelOptInfo>,
+        jointype: pg_sys::JoinType::Type,
+        extra: PgBox<pg_sys::JoinPathExtraData>,
+        prev_hook: fn(
+            root: PgBox<pg_sys::PlannerInfo>,
+            joinrel: PgBox<pg_sys::RelOptInfo>,
+            outerrel: PgBox<pg_sys::RelOptInfo>,
+            innerrel: PgBox<pg_sys::RelOptInfo>,
+            jointype: pg_sys::JoinType::Type,
+            extra: PgBox<pg_sys::JoinPathExtraData>,
+        ) -> HookResult<()>,
+    ) -> HookResult<()> {
+        prev_hook(root, joinrel, outerrel, innerrel, jointype, extra)
+    }
+
+    /// Hook for plugins to get control in ClientAuthentication(), after the client has been
+    /// authenticated but before the connection is accepted.  `status` is either `STATUS_OK` or
+    /// `STATUS_ERROR`
+    fn client_authentication(
+        &mut self,
+        port: PgBox<pg_sys::Port>,
+        status: i32,
+        prev_hook: fn(port: PgBox<pg_sys::Port>, status: i32) -> HookResult<()>,
+    ) -> HookResult<()> {
+        prev_hook(port, status)
+    }
+
+    /// Hook for plugins to get control when database objects are created, altered, dropped,
+    /// or otherwise accessed.  This is how DDL can be audited or vetoed at the object level,
+    /// whereas [`PgHooks::process_utility_hook`] sees the utility statement as a whole
+    fn object_access(
+        &mut self,
+        access: pg_sys::ObjectAccessType::Type,
+        class_id: pg_sys::Oid,
+        object_id: pg_sys::Oid,
+        sub_id: i32,
+        arg: void_mut_ptr,
+        prev_hook: fn(
+            access: pg_sys::ObjectAccessType::Type,
+            class_id: pg_sys::Oid,
+            object_id: pg_sys::Oid,
+            sub_id: i32,
+            arg: void_mut_ptr,
+        ) -> HookResult<()>,
+    ) -> HookResult<()> {
+        prev_hook(access, class_id, object_id, sub_id, arg)
+    }
+
+    /// Hook for plugins to get control in ExplainOneQuery()
+    fn explain_one_query(
+        &mut self,
+        query: PgBox<pg_sys::Query>,
+        cursor_options: i32,
+        into: PgBox<pg_sys::IntoClause>,
+        es: PgBox<pg_sys::ExplainState>,
+        query_string: &core::ffi::CStr,
+        params: PgBox<pg_sys::ParamListInfoData>,
+        query_env: PgBox<pg_sys::QueryEnvironment>,
+        prev_hook: fn(
+            query: PgBox<pg_sys::Query>,
+            cursor_options: i32,
+            into: PgBox<pg_sys::IntoClause>,
+            es: PgBox<pg_sys::ExplainState>,
+            query_string: &core::ffi::CStr,
+            params: PgBox<pg_sys::ParamListInfoData>,
+            query_env: PgBox<pg_sys::QueryEnvironment>,
+        ) -> HookResult<()>,
+    ) -> HookResult<()> {
+        prev_hook(query, cursor_options, into, es, query_string, params, query_env)
+    }
+
+    /// Hook for plugins to decide which functions should be routed through [`PgHooks::fmgr`].
+    /// Postgres caches the answer in the function's `FmgrInfo`, so this is not called on every
+    /// invocation
+    fn needs_fmgr(
+        &mut self,
+        fn_oid: pg_sys::Oid,
+        prev_hook: fn(fn_oid: pg_sys::Oid) -> HookResult<bool>,
+    ) -> HookResult<bool> {
+        prev_hook(fn_oid)
+    }
+
+    /// Hook for plugins to get control when a function for which [`PgHooks::needs_fmgr`]
+    /// returned `true` starts, ends, or aborts
+    fn fmgr(
+        &mut self,
+        event: pg_sys::FmgrHookEventType::Type,
+        flinfo: PgBox<pg_sys::FmgrInfo>,
+        arg: *mut pg_sys::Datum,
+        prev_hook: fn(
+            event: pg_sys::FmgrHookEventType::Type,
+            flinfo: PgBox<pg_sys::FmgrInfo>,
+            arg: *mut pg_sys::Datum,
+        ) -> HookResult<()>,
+    ) -> HookResult<()> {
+        prev_hook(event, flinfo, arg)
+    }
+
+    /// Hook for plugins to request shared memory and LWLocks.  This is only called in the
+    /// postmaster, so the hook must be registered from the `_PG_init()` of a library loaded via
+    /// `shared_preload_libraries`
+    #[cfg(any(feature = "pg15", feature = "pg16", feature = "pg17"))]
+    fn shmem_request(&mut self, prev_hook: fn() -> HookResult<()>) -> HookResult<()> {
+        prev_hook()
+    }
+
+    /// Hook for plugins to get control in CREATE/ALTER ROLE, to enforce a password policy.
+    /// `valid_until` is the `VALID UNTIL` timestamp, if one was given
+    fn check_password(
+        &mut self,
+        username: &core::ffi::CStr,
+        shadow_pass: &core::ffi::CStr,
+        password_type: pg_sys::PasswordType::Type,
+        valid_until: Option<pg_sys::Datum>,
+        prev_hook: fn(
+            username: &core::ffi::CStr,
+            shadow_pass: &core::ffi::CStr,
+            password_type: pg_sys::PasswordType::Type,
+            valid_until: Option<pg_sys::Datum>,
+        ) -> HookResult<()>,
+    ) -> HookResult<()> {
+        prev_hook(username, shadow_pass, password_type, valid_until)
+    }
+
+    /// Hook for plugins to adjust the planner's information about a relation, such as its
+    /// indexes or size estimates
+    fn get_relation_info(
+        &mut self,
+        root: PgBox<pg_sys::PlannerInfo>,
+        relation_object_id: pg_sys::Oid,
+        inhparent: bool,
+        rel: PgBox<pg_sys::RelOptInfo>,
+        prev_hook: fn(
+            root: PgBox<pg_sys::PlannerInfo>,
+            relation_object_id: pg_sys::Oid,
+            inhparent: bool,
+            rel: PgBox<pg_sys::RelOptInfo>,
+        ) -> HookResult<()>,
+    ) -> HookResult<()> {
+        prev_hook(root, relation_object_id, inhparent, rel)
+    }
+
     /// Called when the transaction aborts
     fn abort(&mut self) {}
 
@@ -189,8 +357,114 @@ pub trait PgHooks {
     fn commit(&mut self) {}
 }
 
+/// The individual hook points a [`PgHooks`] implementation can be registered for with
+/// [`register_hooks`].  Each corresponds to one or more [`PgHooks`] methods
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum HookPoint {
+    /// [`PgHooks::emit_log`]
+    EmitLog,
+    /// [`PgHooks::executor_start`]
+    ExecutorStart,
+    /// [`PgHooks::executor_run`]
+    ExecutorRun,
+    /// [`PgHooks::executor_finish`]
+    ExecutorFinish,
+    /// [`PgHooks::executor_end`]
+    ExecutorEnd,
+    /// [`PgHooks::executor_check_perms`]
+    ExecutorCheckPerms,
+    /// [`PgHooks::process_utility_hook`]
+    ProcessUtility,
+    /// [`PgHooks::planner`]
+    Planner,
+    /// [`PgHooks::post_parse_analyze`]
+    PostParseAnalyze,
+    /// [`PgHooks::set_rel_pathlist`]
+    SetRelPathlist,
+    /// [`PgHooks::set_join_pathlist`]
+    SetJoinPathlist,
+    /// [`PgHooks::client_authentication`]
+    ClientAuthentication,
+    /// [`PgHooks::object_access`]
+    ObjectAccess,
+    /// [`PgHooks::explain_one_query`]
+    ExplainOneQuery,
+    /// Both [`PgHooks::needs_fmgr`] and [`PgHooks::fmgr`], which only make sense together
+    Fmgr,
+    /// [`PgHooks::shmem_request`]
+    #[cfg(any(feature = "pg15", feature = "pg16", feature = "pg17"))]
+    ShmemRequest,
+    /// [`PgHooks::check_password`]
+    CheckPassword,
+    /// [`PgHooks::get_relation_info`]
+    GetRelationInfo,
+    /// [`PgHooks::abort`] and [`PgHooks::commit`]
+    Transaction,
+}
+
+impl HookPoint {
+    /// Every hook point supported by the running Postgres version
+    pub const ALL: &'static [HookPoint] = &[
+        HookPoint::EmitLog,
+        HookPoint::ExecutorStart,
+        HookPoint::ExecutorRun,
+        HookPoint::ExecutorFinish,
+        HookPoint::ExecutorEnd,
+        HookPoint::ExecutorCheckPerms,
+        HookPoint::ProcessUtility,
+        HookPoint::Planner,
+        HookPoint::PostParseAnalyze,
+        HookPoint::SetRelPathlist,
+        HookPoint::SetJoinPathlist,
+        HookPoint::ClientAuthentication,
+        HookPoint::ObjectAccess,
+        HookPoint::ExplainOneQuery,
+        HookPoint::Fmgr,
+        #[cfg(any(feature = "pg15", feature = "pg16", feature = "pg17"))]
+        HookPoint::ShmemRequest,
+        HookPoint::CheckPassword,
+        HookPoint::GetRelationInfo,
+        HookPoint::Transaction,
+    ];
+
+    fn bit(self) -> u32 {
+        1 << self as u32
+    }
+}
+
+/// A handle to a set of hooks registered with [`register_hooks`] or [`register_hook`]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct HookRegistration {
+    id: u64,
+}
+
+impl HookRegistration {
+    /// Stop calling this registration's hooks.  Postgres' own hook variables are left pointing
+    /// at pgrx, which will simply pass control on to whatever hook was installed before it
+    pub unsafe fn unregister(self) {
+        if let Some(hooks) = HOOKS.as_mut() {
+            if let Some(registration) = hooks.registrations.iter_mut().find(|r| r.id == self.id) {
+                // leave the slot in place as a chain of hooks may be in progress
+                registration.points = 0;
+            }
+        }
+    }
+}
+
+struct Registration {
+    id: u64,
+    order: i32,
+    points: u32,
+    hook: *mut dyn PgHooks,
+}
+
+#[derive(Default)]
 struct Hooks {
-    current_hook: Box<&'static mut (dyn PgHooks)>,
+    registrations: Vec<Registration>,
+    next_id: u64,
+    installed: u32,
+    /// For each hook point, the index of the registration the next `prev_hook` call dispatches to
+    cursors: [usize; HookPoint::ALL.len()],
     prev_emit_log_hook: pg_sys::emit_log_hook_type,
     prev_executor_start_hook: pg_sys::ExecutorStart_hook_type,
     prev_executor_run_hook: pg_sys::ExecutorRun_hook_type,
@@ -200,68 +474,208 @@ struct Hooks {
     prev_process_utility_hook: pg_sys::ProcessUtility_hook_type,
     prev_planner_hook: pg_sys::planner_hook_type,
     prev_post_parse_analyze_hook: pg_sys::post_parse_analyze_hook_type,
+    prev_set_rel_pathlist_hook: pg_sys::set_rel_pathlist_hook_type,
+    prev_set_join_pathlist_hook: pg_sys::set_join_pathlist_hook_type,
+    prev_client_authentication_hook: pg_sys::ClientAuthentication_hook_type,
+    prev_object_access_hook: pg_sys::object_access_hook_type,
+    prev_explain_one_query_hook: pg_sys::ExplainOneQuery_hook_type,
+    prev_needs_fmgr_hook: pg_sys::needs_fmgr_hook_type,
+    prev_fmgr_hook: pg_sys::fmgr_hook_type,
+    #[cfg(any(feature = "pg15", feature = "pg16", feature = "pg17"))]
+    prev_shmem_request_hook: pg_sys::shmem_request_hook_type,
+    prev_check_password_hook: pg_sys::check_password_hook_type,
+    prev_get_relation_info_hook: pg_sys::get_relation_info_hook_type,
 }
 
 static mut HOOKS: Option<Hooks> = None;
 
-/// Register a `PgHook` instance to respond to the various hook points
-pub unsafe fn register_hook(hook: &'static mut (dyn PgHooks)) {
-    if HOOKS.is_some() {
-        panic!("PgHook instance already registered");
+/// Register a `PgHook` instance to respond to every hook point
+///
+/// This is the same as calling [`register_hooks`] with [`HookPoint::ALL`] and an order of `0`
+pub unsafe fn register_hook(hook: &'static mut (dyn PgHooks)) -> HookRegistration {
+    register_hooks(hook, HookPoint::ALL, 0)
+}
+
+/// Register a `PgHook` instance to respond to only the given hook points
+///
+/// Any number of instances may be registered.  For each hook point, the registered instances
+/// are called in ascending `order`, with ties broken by registration order, and each one's
+/// `prev_hook` argument calls into the next.  After the last instance, `prev_hook` calls
+/// whichever hook was installed before pgrx's, or Postgres' standard implementation.
+///
+/// Postgres' hook variables are only taken over the first time an instance is registered for
+/// the corresponding hook point.  Registrations should not be added from within a hook.
+pub unsafe fn register_hooks(
+    hook: &'static mut dyn PgHooks,
+    points: &[HookPoint],
+    order: i32,
+) -> HookRegistration {
+    let hooks = HOOKS.get_or_insert_with(Hooks::default);
+    hooks.registrations.retain(|r| r.points != 0);
+
+    let id = hooks.next_id;
+    hooks.next_id += 1;
+
+    let position = hooks.registrations.partition_point(|r| r.order <= order);
+    hooks.registrations.insert(
+        position,
+        Registration {
+            id,
+            order,
+            points: points.iter().fold(0, |bits, point| bits | point.bit()),
+            hook,
+        },
+    );
+
+    for point in points {
+        if hooks.installed & point.bit() == 0 {
+            hooks.installed |= point.bit();
+            install(hooks, *point);
+        }
+    }
+
+    HookRegistration { id }
+}
+
+unsafe fn install(hooks: &mut Hooks, point: HookPoint) {
+    match point {
+        HookPoint::EmitLog => {
+            hooks.prev_emit_log_hook = pg_sys::emit_log_hook.replace(pgrx_emit_log);
+        }
+        HookPoint::ExecutorStart => {
+            hooks.prev_executor_start_hook = pg_sys::ExecutorStart_hook
+                .replace(pgrx_executor_start)
+                .or(Some(pgrx_standard_executor_start_wrapper));
+        }
+        HookPoint::ExecutorRun => {
+            hooks.prev_executor_run_hook = pg_sys::ExecutorRun_hook
+                .replace(pgrx_executor_run)
+                .or(Some(pgrx_standard_executor_run_wrapper));
+        }
+        HookPoint::ExecutorFinish => {
+            hooks.prev_executor_finish_hook = pg_sys::ExecutorFinish_hook
+                .replace(pgrx_executor_finish)
+                .or(Some(pgrx_standard_executor_finish_wrapper));
+        }
+        HookPoint::ExecutorEnd => {
+            hooks.prev_executor_end_hook = pg_sys::ExecutorEnd_hook
+                .replace(pgrx_executor_end)
+                .or(Some(pgrx_standard_executor_end_wrapper));
+        }
+        HookPoint::ExecutorCheckPerms => {
+            hooks.prev_executor_check_perms_hook = pg_sys::ExecutorCheckPerms_hook
+                .replace(pgrx_executor_check_perms)
+                .or(Some(pgrx_standard_executor_check_perms_wrapper));
+        }
+        HookPoint::ProcessUtility => {
+            hooks.prev_process_utility_hook = pg_sys::ProcessUtility_hook
+                .replace(pgrx_process_utility)
+                .or(Some(pgrx_standard_process_utility_wrapper));
+        }
+        HookPoint::Planner => {
+            hooks.prev_planner_hook =
+                pg_sys::planner_hook.replace(pgrx_planner).or(Some(pgrx_standard_planner_wrapper));
+        }
+        HookPoint::PostParseAnalyze => {
+            hooks.prev_post_parse_analyze_hook =
+                pg_sys::post_parse_analyze_hook.replace(pgrx_post_parse_analyze);
+        }
+        HookPoint::SetRelPathlist => {
+            hooks.prev_set_rel_pathlist_hook =
+                pg_sys::set_rel_pathlist_hook.replace(pgrx_set_rel_pathlist);
+        }
+        HookPoint::SetJoinPathlist => {
+            hooks.prev_set_join_pathlist_hook =
+                pg_sys::set_join_pathlist_hook.replace(pgrx_set_join_pathlist);
+        }
+        HookPoint::ClientAuthentication => {
+            hooks.prev_client_authentication_hook =
+                pg_sys::ClientAuthentication_hook.replace(pgrx_client_authentication);
+        }
+        HookPoint::ObjectAccess => {
+            hooks.prev_object_access_hook = pg_sys::object_access_hook.replace(pgrx_object_access);
+        }
+        HookPoint::ExplainOneQuery => {
+            hooks.prev_explain_one_query_hook = pg_sys::ExplainOneQuery_hook
+                .replace(pgrx_explain_one_query)
+                .or(Some(pgrx_standard_explain_one_query_wrapper));
+        }
+        HookPoint::Fmgr => {
+            hooks.prev_needs_fmgr_hook = pg_sys::needs_fmgr_hook.replace(pgrx_needs_fmgr);
+            hooks.prev_fmgr_hook = pg_sys::fmgr_hook.replace(pgrx_fmgr);
+        }
+        #[cfg(any(feature = "pg15", feature = "pg16", feature = "pg17"))]
+        HookPoint::ShmemRequest => {
+            hooks.prev_shmem_request_hook = pg_sys::shmem_request_hook.replace(pgrx_shmem_request);
+        }
+        HookPoint::CheckPassword => {
+            hooks.prev_check_password_hook =
+                pg_sys::check_password_hook.replace(pgrx_check_password);
+        }
+        HookPoint::GetRelationInfo => {
+            hooks.prev_get_relation_info_hook =
+                pg_sys::get_relation_info_hook.replace(pgrx_get_relation_info);
+        }
+        HookPoint::Transaction => {
+            pg_sys::RegisterXactCallback(Some(xact_callback), std::ptr::null_mut());
+        }
     }
-    #[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14", feature = "pg15"))]
-    let prev_executor_check_perms_hook = pg_sys::ExecutorCheckPerms_hook
-        .replace(pgrx_executor_check_perms)
-        .or(Some(pgrx_standard_executor_check_perms_wrapper));
-
-    #[cfg(any(feature = "pg16", feature = "pg17"))]
-    let prev_executor_check_perms_hook = pg_sys::ExecutorCheckPerms_hook
-        .replace(pgrx_executor_check_perms)
-        .or(Some(pgrx_standard_executor_check_perms_wrapper));
-
-    HOOKS = Some(Hooks {
-        current_hook: Box::new(hook),
-        prev_executor_start_hook: pg_sys::ExecutorStart_hook
-            .replace(pgrx_executor_start)
-            .or(Some(pgrx_standard_executor_start_wrapper)),
-        prev_executor_run_hook: pg_sys::ExecutorRun_hook
-            .replace(pgrx_executor_run)
-            .or(Some(pgrx_standard_executor_run_wrapper)),
-        prev_executor_finish_hook: pg_sys::ExecutorFinish_hook
-            .replace(pgrx_executor_finish)
-            .or(Some(pgrx_standard_executor_finish_wrapper)),
-        prev_executor_end_hook: pg_sys::ExecutorEnd_hook
-            .replace(pgrx_executor_end)
-            .or(Some(pgrx_standard_executor_end_wrapper)),
-        prev_executor_check_perms_hook,
-        prev_process_utility_hook: pg_sys::ProcessUtility_hook
-            .replace(pgrx_process_utility)
-            .or(Some(pgrx_standard_process_utility_wrapper)),
-        prev_planner_hook: pg_sys::planner_hook
-            .replace(pgrx_planner)
-            .or(Some(pgrx_standard_planner_wrapper)),
-        prev_post_parse_analyze_hook: pg_sys::post_parse_analyze_hook
-            .replace(pgrx_post_parse_analyze),
-        prev_emit_log_hook: pg_sys::emit_log_hook.replace(pgrx_emit_log),
-    });
-
-    #[pg_guard]
-    unsafe extern "C" fn xact_callback(event: pg_sys::XactEvent::Type, _data: void_mut_ptr) {
+}
+
+#[pg_guard]
+unsafe extern "C" fn xact_callback(event: pg_sys::XactEvent::Type, _data: void_mut_ptr) {
+    let _chain = HookChain::start(HookPoint::Transaction);
+    while let Some(hook) = next_hook(HookPoint::Transaction) {
         match event {
-            pg_sys::XactEvent::XACT_EVENT_ABORT => HOOKS.as_mut().unwrap().current_hook.abort(),
-            pg_sys::XactEvent::XACT_EVENT_PRE_COMMIT => {
-                HOOKS.as_mut().unwrap().current_hook.commit()
-            }
+            pg_sys::XactEvent::XACT_EVENT_ABORT => hook.abort(),
+            pg_sys::XactEvent::XACT_EVENT_PRE_COMMIT => hook.commit(),
             _ => { /* noop */ }
         }
     }
+}
 
-    pg_sys::RegisterXactCallback(Some(xact_callback), std::ptr::null_mut());
+/// Returns the next registered hook for `point` in the chain currently being dispatched, if any
+unsafe fn next_hook(point: HookPoint) -> Option<&'static mut dyn PgHooks> {
+    let hooks = HOOKS.as_mut().unwrap();
+    let cursor = &mut hooks.cursors[point as usize];
+    let (index, registration) = hooks
+        .registrations
+        .iter()
+        .enumerate()
+        .skip(*cursor)
+        .find(|(_, r)| r.points & point.bit() != 0)?;
+    *cursor = index + 1;
+    Some(&mut *registration.hook)
+}
+
+/// Starts dispatching a chain of hooks for a hook point, restoring the chain that was in progress,
+/// if the hook point was re-entered, once dropped
+struct HookChain {
+    point: HookPoint,
+    saved: usize,
+}
+
+impl HookChain {
+    unsafe fn start(point: HookPoint) -> Self {
+        let cursor = &mut HOOKS.as_mut().unwrap().cursors[point as usize];
+        HookChain { point, saved: std::mem::replace(cursor, 0) }
+    }
+}
+
+impl Drop for HookChain {
+    fn drop(&mut self) {
+        unsafe {
+            HOOKS.as_mut().unwrap().cursors[self.point as usize] = self.saved;
+        }
+    }
 }
 
 #[pg_guard]
 unsafe extern "C" fn pgrx_executor_start(query_desc: *mut pg_sys::QueryDesc, eflags: i32) {
     fn prev(query_desc: PgBox<pg_sys::QueryDesc>, eflags: i32) -> HookResult<()> {
+        if let Some(hook) = unsafe { next_hook(HookPoint::ExecutorStart) } {
+            return hook.executor_start(query_desc, eflags, prev);
+        }
         unsafe {
             (HOOKS.as_mut().unwrap().prev_executor_start_hook.as_ref().unwrap())(
                 query_desc.into_pg(),
@@ -270,8 +684,8 @@ unsafe extern "C" fn pgrx_executor_start(query_desc: *mut pg_sys::QueryDesc, efl
         }
         HookResult::new(())
     }
-    let hook = &mut HOOKS.as_mut().unwrap().current_hook;
-    hook.executor_start(PgBox::from_pg(query_desc), eflags, prev);
+    let _chain = HookChain::start(HookPoint::ExecutorStart);
+    prev(PgBox::from_pg(query_desc), eflags);
 }
 
 #[pg_guard]
@@ -287,6 +701,9 @@ unsafe extern "C" fn pgrx_executor_run(
         count: u64,
         execute_once: bool,
     ) -> HookResult<()> {
+        if let Some(hook) = unsafe { next_hook(HookPoint::ExecutorRun) } {
+            return hook.executor_run(query_desc, direction, count, execute_once, prev);
+        }
         unsafe {
             (HOOKS.as_mut().unwrap().prev_executor_run_hook.as_ref().unwrap())(
                 query_desc.into_pg(),
@@ -297,13 +714,16 @@ unsafe extern "C" fn pgrx_executor_run(
         }
         HookResult::new(())
     }
-    let hook = &mut HOOKS.as_mut().unwrap().current_hook;
-    hook.executor_run(PgBox::from_pg(query_desc), direction, count, execute_once, prev);
+    let _chain = HookChain::start(HookPoint::ExecutorRun);
+    prev(PgBox::from_pg(query_desc), direction, count, execute_once);
 }
 
 #[pg_guard]
 unsafe extern "C" fn pgrx_executor_finish(query_desc: *mut pg_sys::QueryDesc) {
     fn prev(query_desc: PgBox<pg_sys::QueryDesc>) -> HookResult<()> {
+        if let Some(hook) = unsafe { next_hook(HookPoint::ExecutorFinish) } {
+            return hook.executor_finish(query_desc, prev);
+        }
         unsafe {
             (HOOKS.as_mut().unwrap().prev_executor_finish_hook.as_ref().unwrap())(
                 query_desc.into_pg(),
@@ -311,20 +731,23 @@ unsafe extern "C" fn pgrx_executor_finish(query_desc: *mut pg_sys::QueryDesc) {
         }
         HookResult::new(())
     }
-    let hook = &mut HOOKS.as_mut().unwrap().current_hook;
-    hook.executor_finish(PgBox::from_pg(query_desc), prev);
+    let _chain = HookChain::start(HookPoint::ExecutorFinish);
+    prev(PgBox::from_pg(query_desc));
 }
 
 #[pg_guard]
 unsafe extern "C" fn pgrx_executor_end(query_desc: *mut pg_sys::QueryDesc) {
     fn prev(query_desc: PgBox<pg_sys::QueryDesc>) -> HookResult<()> {
+        if let Some(hook) = unsafe { next_hook(HookPoint::ExecutorEnd) } {
+            return hook.executor_end(query_desc, prev);
+        }
         unsafe {
             (HOOKS.as_mut().unwrap().prev_executor_end_hook.as_ref().unwrap())(query_desc.into_pg())
         }
         HookResult::new(())
     }
-    let hook = &mut HOOKS.as_mut().unwrap().current_hook;
-    hook.executor_end(PgBox::from_pg(query_desc), prev);
+    let _chain = HookChain::start(HookPoint::ExecutorEnd);
+    prev(PgBox::from_pg(query_desc));
 }
 
 #[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14", feature = "pg15"))]
@@ -335,9 +758,17 @@ unsafe extern "C" fn pgrx_executor_check_perms(
 ) -> bool {
     fn prev(
         range_table: PgList<*mut pg_sys::RangeTblEntry>,
-        _rte_perm_infos: Option<*mut pg_sys::List>,
+        rte_perm_infos: Option<*mut pg_sys::List>,
         ereport_on_violation: bool,
     ) -> HookResult<bool> {
+        if let Some(hook) = unsafe { next_hook(HookPoint::ExecutorCheckPerms) } {
+            return hook.executor_check_perms(
+                range_table,
+                rte_perm_infos,
+                ereport_on_violation,
+                prev,
+            );
+        }
         HookResult::new(unsafe {
             (HOOKS.as_mut().unwrap().prev_executor_check_perms_hook.as_ref().unwrap())(
                 range_table.into_pg(),
@@ -345,8 +776,8 @@ unsafe extern "C" fn pgrx_executor_check_perms(
             )
         })
     }
-    let hook = &mut HOOKS.as_mut().unwrap().current_hook;
-    hook.executor_check_perms(PgList::from_pg(range_table), None, ereport_on_violation, prev).inner
+    let _chain = HookChain::start(HookPoint::ExecutorCheckPerms);
+    prev(PgList::from_pg(range_table), None, ereport_on_violation).inner
 }
 
 #[cfg(any(feature = "pg16", feature = "pg17"))]
@@ -361,6 +792,14 @@ unsafe extern "C" fn pgrx_executor_check_perms(
         rte_perm_infos: Option<*mut pg_sys::List>,
         ereport_on_violation: bool,
     ) -> HookResult<bool> {
+        if let Some(hook) = unsafe { next_hook(HookPoint::ExecutorCheckPerms) } {
+            return hook.executor_check_perms(
+                range_table,
+                rte_perm_infos,
+                ereport_on_violation,
+                prev,
+            );
+        }
         HookResult::new(unsafe {
             (HOOKS.as_mut().unwrap().prev_executor_check_perms_hook.as_ref().unwrap())(
                 range_table.into_pg(),
@@ -369,14 +808,8 @@ unsafe extern "C" fn pgrx_executor_check_perms(
             )
         })
     }
-    let hook = &mut HOOKS.as_mut().unwrap().current_hook;
-    hook.executor_check_perms(
-        PgList::from_pg(range_table),
-        Some(rte_perm_infos),
-        ereport_on_violation,
-        prev,
-    )
-    .inner
+    let _chain = HookChain::start(HookPoint::ExecutorCheckPerms);
+    prev(PgList::from_pg(range_table), Some(rte_perm_infos), ereport_on_violation).inner
 }
 
 #[cfg(any(feature = "pg12", feature = "pg13"))]
@@ -393,13 +826,26 @@ unsafe extern "C" fn pgrx_process_utility(
     fn prev(
         pstmt: PgBox<pg_sys::PlannedStmt>,
         query_string: &core::ffi::CStr,
-        _read_only_tree: Option<bool>,
+        read_only_tree: Option<bool>,
         context: pg_sys::ProcessUtilityContext::Type,
         params: PgBox<pg_sys::ParamListInfoData>,
         query_env: PgBox<pg_sys::QueryEnvironment>,
         dest: PgBox<pg_sys::DestReceiver>,
         completion_tag: *mut pg_sys::QueryCompletion,
     ) -> HookResult<()> {
+        if let Some(hook) = unsafe { next_hook(HookPoint::ProcessUtility) } {
+            return hook.process_utility_hook(
+                pstmt,
+                query_string,
+                read_only_tree,
+                context,
+                params,
+                query_env,
+                dest,
+                completion_tag,
+                prev,
+            );
+        }
         HookResult::new(unsafe {
             (HOOKS.as_mut().unwrap().prev_process_utility_hook.as_ref().unwrap())(
                 pstmt.into_pg(),
@@ -413,8 +859,8 @@ unsafe extern "C" fn pgrx_process_utility(
         })
     }
 
-    let hook = &mut HOOKS.as_mut().unwrap().current_hook;
-    hook.process_utility_hook(
+    let _chain = HookChain::start(HookPoint::ProcessUtility);
+    prev(
         PgBox::from_pg(pstmt),
         core::ffi::CStr::from_ptr(query_string),
         None,
@@ -423,7 +869,6 @@ unsafe extern "C" fn pgrx_process_utility(
         PgBox::from_pg(query_env),
         PgBox::from_pg(dest),
         completion_tag,
-        prev,
     )
     .inner
 }
@@ -449,6 +894,19 @@ unsafe extern "C" fn pgrx_process_utility(
         dest: PgBox<pg_sys::DestReceiver>,
         completion_tag: *mut pg_sys::QueryCompletion,
     ) -> HookResult<()> {
+        if let Some(hook) = unsafe { next_hook(HookPoint::ProcessUtility) } {
+            return hook.process_utility_hook(
+                pstmt,
+                query_string,
+                read_only_tree,
+                context,
+                params,
+                query_env,
+                dest,
+                completion_tag,
+                prev,
+            );
+        }
         HookResult::new(unsafe {
             (HOOKS.as_mut().unwrap().prev_process_utility_hook.as_ref().unwrap())(
                 pstmt.into_pg(),
@@ -463,8 +921,8 @@ unsafe extern "C" fn pgrx_process_utility(
         })
     }
 
-    let hook = &mut HOOKS.as_mut().unwrap().current_hook;
-    hook.process_utility_hook(
+    let _chain = HookChain::start(HookPoint::ProcessUtility);
+    prev(
         PgBox::from_pg(pstmt),
         core::ffi::CStr::from_ptr(query_string),
         Some(read_only_tree),
@@ -473,7 +931,6 @@ unsafe extern "C" fn pgrx_process_utility(
         PgBox::from_pg(query_env),
         PgBox::from_pg(dest),
         completion_tag,
-        prev,
     )
     .inner
 }
@@ -518,6 +975,9 @@ unsafe extern "C" fn pgrx_planner_impl(
         cursor_options: i32,
         bound_params: PgBox<pg_sys::ParamListInfoData>,
     ) -> HookResult<*mut pg_sys::PlannedStmt> {
+        if let Some(hook) = unsafe { next_hook(HookPoint::Planner) } {
+            return hook.planner(parse, query_string, cursor_options, bound_params, prev);
+        }
         HookResult::new(unsafe {
             #[cfg(feature = "pg12")]
             {
@@ -545,15 +1005,8 @@ unsafe extern "C" fn pgrx_planner_impl(
             }
         })
     }
-    let hook = &mut HOOKS.as_mut().unwrap().current_hook;
-    hook.planner(
-        PgBox::from_pg(parse),
-        query_string,
-        cursor_options,
-        PgBox::from_pg(bound_params),
-        prev,
-    )
-    .inner
+    let _chain = HookChain::start(HookPoint::Planner);
+    prev(PgBox::from_pg(parse), query_string, cursor_options, PgBox::from_pg(bound_params)).inner
 }
 
 #[cfg(any(feature = "pg12", feature = "pg13"))]
@@ -565,8 +1018,11 @@ unsafe extern "C" fn pgrx_post_parse_analyze(
     fn prev(
         parse_state: PgBox<pg_sys::ParseState>,
         query: PgBox<pg_sys::Query>,
-        _jumble_state: Option<PgBox<JumbleState>>,
+        jumble_state: Option<PgBox<JumbleState>>,
     ) -> HookResult<()> {
+        if let Some(hook) = unsafe { next_hook(HookPoint::PostParseAnalyze) } {
+            return hook.post_parse_analyze(parse_state, query, jumble_state, prev);
+        }
         HookResult::new(unsafe {
             match HOOKS.as_mut().unwrap().prev_post_parse_analyze_hook.as_ref() {
                 None => (),
@@ -575,8 +1031,8 @@ unsafe extern "C" fn pgrx_post_parse_analyze(
         })
     }
 
-    let hook = &mut HOOKS.as_mut().unwrap().current_hook;
-    hook.post_parse_analyze(PgBox::from_pg(parse_state), PgBox::from_pg(query), None, prev).inner
+    let _chain = HookChain::start(HookPoint::PostParseAnalyze);
+    prev(PgBox::from_pg(parse_state), PgBox::from_pg(query), None).inner
 }
 
 #[cfg(any(feature = "pg14", feature = "pg15", feature = "pg16", feature = "pg17"))]
@@ -591,6 +1047,9 @@ unsafe extern "C" fn pgrx_post_parse_analyze(
         query: PgBox<pg_sys::Query>,
         jumble_state: Option<PgBox<JumbleState>>,
     ) -> HookResult<()> {
+        if let Some(hook) = unsafe { next_hook(HookPoint::PostParseAnalyze) } {
+            return hook.post_parse_analyze(parse_state, query, jumble_state, prev);
+        }
         HookResult::new(unsafe {
             match HOOKS.as_mut().unwrap().prev_post_parse_analyze_hook.as_ref() {
                 None => (),
@@ -601,19 +1060,17 @@ unsafe extern "C" fn pgrx_post_parse_analyze(
         })
     }
 
-    let hook = &mut HOOKS.as_mut().unwrap().current_hook;
-    hook.post_parse_analyze(
-        PgBox::from_pg(parse_state),
-        PgBox::from_pg(query),
-        Some(PgBox::from_pg(jumble_state)),
-        prev,
-    )
-    .inner
+    let _chain = HookChain::start(HookPoint::PostParseAnalyze);
+    prev(PgBox::from_pg(parse_state), PgBox::from_pg(query), Some(PgBox::from_pg(jumble_state)))
+        .inner
 }
 
 #[pg_guard]
 unsafe extern "C" fn pgrx_emit_log(error_data: *mut pg_sys::ErrorData) {
     fn prev(error_data: PgBox<pg_sys::ErrorData>) -> HookResult<()> {
+        if let Some(hook) = unsafe { next_hook(HookPoint::EmitLog) } {
+            return hook.emit_log(error_data, prev);
+        }
         HookResult::new(unsafe {
             match HOOKS.as_mut().unwrap().prev_emit_log_hook.as_ref() {
                 None => (),
@@ -622,8 +1079,321 @@ unsafe extern "C" fn pgrx_emit_log(error_data: *mut pg_sys::ErrorData) {
         })
     }
 
-    let hook = &mut HOOKS.as_mut().unwrap().current_hook;
-    hook.emit_log(PgBox::from_pg(error_data), prev).inner
+    let _chain = HookChain::start(HookPoint::EmitLog);
+    prev(PgBox::from_pg(error_data)).inner
+}
+
+#[pg_guard]
+unsafe extern "C" fn pgrx_set_rel_pathlist(
+    root: *mut pg_sys::PlannerInfo,
+    rel: *mut pg_sys::RelOptInfo,
+    rti: pg_sys::Index,
+    rte: *mut pg_sys::RangeTblEntry,
+) {
+    fn prev(
+        root: PgBox<pg_sys::PlannerInfo>,
+        rel: PgBox<pg_sys::RelOptInfo>,
+        rti: pg_sys::Index,
+        rte: PgBox<pg_sys::RangeTblEntry>,
+    ) -> HookResult<()> {
+        if let Some(hook) = unsafe { next_hook(HookPoint::SetRelPathlist) } {
+            return hook.set_rel_pathlist(root, rel, rti, rte, prev);
+        }
+        HookResult::new(unsafe {
+            match HOOKS.as_mut().unwrap().prev_set_rel_pathlist_hook.as_ref() {
+                None => (),
+                Some(f) => (f)(root.as_ptr(), rel.as_ptr(), rti, rte.as_ptr()),
+            }
+        })
+    }
+
+    let _chain = HookChain::start(HookPoint::SetRelPathlist);
+    prev(PgBox::from_pg(root), PgBox::from_pg(rel), rti, PgBox::from_pg(rte)).inner
+}
+
+#[pg_guard]
+unsafe extern "C" fn pgrx_set_join_pathlist(
+    root: *mut pg_sys::PlannerInfo,
+    joinrel: *mut pg_sys::RelOptInfo,
+    outerrel: *mut pg_sys::RelOptInfo,
+    innerrel: *mut pg_sys::RelOptInfo,
+    jointype: pg_sys::JoinType::Type,
+    extra: *mut pg_sys::JoinPathExtraData,
+) {
+    fn prev(
+        root: PgBox<pg_sys::PlannerInfo>,
+        joinrel: PgBox<pg_sys::RelOptInfo>,
+        outerrel: PgBox<pg_sys::RelOptInfo>,
+        innerrel: PgBox<pg_sys::RelOptInfo>,
+        jointype: pg_sys::JoinType::Type,
+        extra: PgBox<pg_sys::JoinPathExtraData>,
+    ) -> HookResult<()> {
+        if let Some(hook) = unsafe { next_hook(HookPoint::SetJoinPathlist) } {
+            return hook
+                .set_join_pathlist(root, joinrel, outerrel, innerrel, jointype, extra, prev);
+        }
+        HookResult::new(unsafe {
+            match HOOKS.as_mut().unwrap().prev_set_join_pathlist_hook.as_ref() {
+                None => (),
+                Some(f) => (f)(
+                    root.as_ptr(),
+                    joinrel.as_ptr(),
+                    outerrel.as_ptr(),
+                    innerrel.as_ptr(),
+                    jointype,
+                    extra.as_ptr(),
+                ),
+            }
+        })
+    }
+
+    let _chain = HookChain::start(HookPoint::SetJoinPathlist);
+    prev(
+        PgBox::from_pg(root),
+        PgBox::from_pg(joinrel),
+        PgBox::from_pg(outerrel),
+        PgBox::from_pg(innerrel),
+        jointype,
+        PgBox::from_pg(extra),
+    )
+    .inner
+}
+
+#[pg_guard]
+unsafe extern "C" fn pgrx_client_authentication(port: *mut pg_sys::Port, status: i32) {
+    fn prev(port: PgBox<pg_sys::Port>, status: i32) -> HookResult<()> {
+        if let Some(hook) = unsafe { next_hook(HookPoint::ClientAuthentication) } {
+            return hook.client_authentication(port, status, prev);
+        }
+        HookResult::new(unsafe {
+            match HOOKS.as_mut().unwrap().prev_client_authentication_hook.as_ref() {
+                None => (),
+                Some(f) => (f)(port.as_ptr(), status),
+            }
+        })
+    }
+
+    let _chain = HookChain::start(HookPoint::ClientAuthentication);
+    prev(PgBox::from_pg(port), status).inner
+}
+
+#[pg_guard]
+unsafe extern "C" fn pgrx_object_access(
+    access: pg_sys::ObjectAccessType::Type,
+    class_id: pg_sys::Oid,
+    object_id: pg_sys::Oid,
+    sub_id: i32,
+    arg: void_mut_ptr,
+) {
+    fn prev(
+        access: pg_sys::ObjectAccessType::Type,
+        class_id: pg_sys::Oid,
+        object_id: pg_sys::Oid,
+        sub_id: i32,
+        arg: void_mut_ptr,
+    ) -> HookResult<()> {
+        if let Some(hook) = unsafe { next_hook(HookPoint::ObjectAccess) } {
+            return hook.object_access(access, class_id, object_id, sub_id, arg, prev);
+        }
+        HookResult::new(unsafe {
+            match HOOKS.as_mut().unwrap().prev_object_access_hook.as_ref() {
+                None => (),
+                Some(f) => (f)(access, class_id, object_id, sub_id, arg),
+            }
+        })
+    }
+
+    let _chain = HookChain::start(HookPoint::ObjectAccess);
+    prev(access, class_id, object_id, sub_id, arg).inner
+}
+
+#[pg_guard]
+unsafe extern "C" fn pgrx_explain_one_query(
+    query: *mut pg_sys::Query,
+    cursor_options: i32,
+    into: *mut pg_sys::IntoClause,
+    es: *mut pg_sys::ExplainState,
+    query_string: *const ::std::os::raw::c_char,
+    params: pg_sys::ParamListInfo,
+    query_env: *mut pg_sys::QueryEnvironment,
+) {
+    fn prev(
+        query: PgBox<pg_sys::Query>,
+        cursor_options: i32,
+        into: PgBox<pg_sys::IntoClause>,
+        es: PgBox<pg_sys::ExplainState>,
+        query_string: &core::ffi::CStr,
+        params: PgBox<pg_sys::ParamListInfoData>,
+        query_env: PgBox<pg_sys::QueryEnvironment>,
+    ) -> HookResult<()> {
+        if let Some(hook) = unsafe { next_hook(HookPoint::ExplainOneQuery) } {
+            return hook.explain_one_query(
+                query,
+                cursor_options,
+                into,
+                es,
+                query_string,
+                params,
+                query_env,
+                prev,
+            );
+        }
+        HookResult::new(unsafe {
+            (HOOKS.as_mut().unwrap().prev_explain_one_query_hook.as_ref().unwrap())(
+                query.into_pg(),
+                cursor_options,
+                into.into_pg(),
+                es.into_pg(),
+                query_string.as_ptr(),
+                params.into_pg(),
+                query_env.into_pg(),
+            )
+        })
+    }
+
+    let _chain = HookChain::start(HookPoint::ExplainOneQuery);
+    prev(
+        PgBox::from_pg(query),
+        cursor_options,
+        PgBox::from_pg(into),
+        PgBox::from_pg(es),
+        core::ffi::CStr::from_ptr(query_string),
+        PgBox::from_pg(params),
+        PgBox::from_pg(query_env),
+    )
+    .inner
+}
+
+#[pg_guard]
+unsafe extern "C" fn pgrx_needs_fmgr(fn_oid: pg_sys::Oid) -> bool {
+    fn prev(fn_oid: pg_sys::Oid) -> HookResult<bool> {
+        if let Some(hook) = unsafe { next_hook(HookPoint::Fmgr) } {
+            return hook.needs_fmgr(fn_oid, prev);
+        }
+        HookResult::new(unsafe {
+            match HOOKS.as_mut().unwrap().prev_needs_fmgr_hook.as_ref() {
+                None => false,
+                Some(f) => (f)(fn_oid),
+            }
+        })
+    }
+
+    let _chain = HookChain::start(HookPoint::Fmgr);
+    prev(fn_oid).inner
+}
+
+#[pg_guard]
+unsafe extern "C" fn pgrx_fmgr(
+    event: pg_sys::FmgrHookEventType::Type,
+    flinfo: *mut pg_sys::FmgrInfo,
+    arg: *mut pg_sys::Datum,
+) {
+    fn prev(
+        event: pg_sys::FmgrHookEventType::Type,
+        flinfo: PgBox<pg_sys::FmgrInfo>,
+        arg: *mut pg_sys::Datum,
+    ) -> HookResult<()> {
+        if let Some(hook) = unsafe { next_hook(HookPoint::Fmgr) } {
+            return hook.fmgr(event, flinfo, arg, prev);
+        }
+        HookResult::new(unsafe {
+            match HOOKS.as_mut().unwrap().prev_fmgr_hook.as_ref() {
+                None => (),
+                Some(f) => (f)(event, flinfo.as_ptr(), arg),
+            }
+        })
+    }
+
+    let _chain = HookChain::start(HookPoint::Fmgr);
+    prev(event, PgBox::from_pg(flinfo), arg).inner
+}
+
+#[cfg(any(feature = "pg15", feature = "pg16", feature = "pg17"))]
+#[pg_guard]
+unsafe extern "C" fn pgrx_shmem_request() {
+    fn prev() -> HookResult<()> {
+        if let Some(hook) = unsafe { next_hook(HookPoint::ShmemRequest) } {
+            return hook.shmem_request(prev);
+        }
+        HookResult::new(unsafe {
+            match HOOKS.as_mut().unwrap().prev_shmem_request_hook.as_ref() {
+                None => (),
+                Some(f) => (f)(),
+            }
+        })
+    }
+
+    let _chain = HookChain::start(HookPoint::ShmemRequest);
+    prev().inner
+}
+
+#[pg_guard]
+unsafe extern "C" fn pgrx_check_password(
+    username: *const ::std::os::raw::c_char,
+    shadow_pass: *const ::std::os::raw::c_char,
+    password_type: pg_sys::PasswordType::Type,
+    validuntil_time: pg_sys::Datum,
+    validuntil_null: bool,
+) {
+    fn prev(
+        username: &core::ffi::CStr,
+        shadow_pass: &core::ffi::CStr,
+        password_type: pg_sys::PasswordType::Type,
+        valid_until: Option<pg_sys::Datum>,
+    ) -> HookResult<()> {
+        if let Some(hook) = unsafe { next_hook(HookPoint::CheckPassword) } {
+            return hook.check_password(username, shadow_pass, password_type, valid_until, prev);
+        }
+        HookResult::new(unsafe {
+            match HOOKS.as_mut().unwrap().prev_check_password_hook.as_ref() {
+                None => (),
+                Some(f) => (f)(
+                    username.as_ptr(),
+                    shadow_pass.as_ptr(),
+                    password_type,
+                    valid_until.unwrap_or(pg_sys::Datum::from(0)),
+                    valid_until.is_none(),
+                ),
+            }
+        })
+    }
+
+    let _chain = HookChain::start(HookPoint::CheckPassword);
+    prev(
+        core::ffi::CStr::from_ptr(username),
+        core::ffi::CStr::from_ptr(shadow_pass),
+        password_type,
+        (!validuntil_null).then_some(validuntil_time),
+    )
+    .inner
+}
+
+#[pg_guard]
+unsafe extern "C" fn pgrx_get_relation_info(
+    root: *mut pg_sys::PlannerInfo,
+    relation_object_id: pg_sys::Oid,
+    inhparent: bool,
+    rel: *mut pg_sys::RelOptInfo,
+) {
+    fn prev(
+        root: PgBox<pg_sys::PlannerInfo>,
+        relation_object_id: pg_sys::Oid,
+        inhparent: bool,
+        rel: PgBox<pg_sys::RelOptInfo>,
+    ) -> HookResult<()> {
+        if let Some(hook) = unsafe { next_hook(HookPoint::GetRelationInfo) } {
+            return hook.get_relation_info(root, relation_object_id, inhparent, rel, prev);
+        }
+        HookResult::new(unsafe {
+            match HOOKS.as_mut().unwrap().prev_get_relation_info_hook.as_ref() {
+                None => (),
+                Some(f) => (f)(root.as_ptr(), relation_object_id, inhparent, rel.as_ptr()),
+            }
+        })
+    }
+
+    let _chain = HookChain::start(HookPoint::GetRelationInfo);
+    prev(PgBox::from_pg(root), relation_object_id, inhparent, PgBox::from_pg(rel)).inner
 }
 
 #[pg_guard]
@@ -745,3 +1515,80 @@ unsafe extern "C" fn pgrx_standard_planner_wrapper(
 ) -> *mut pg_sys::PlannedStmt {
     pg_sys::standard_planner(parse, query_string, cursor_options, bound_params)
 }
+
+#[cfg(feature = "pg17")]
+#[pg_guard]
+unsafe extern "C" fn pgrx_standard_explain_one_query_wrapper(
+    query: *mut pg_sys::Query,
+    cursor_options: i32,
+    into: *mut pg_sys::IntoClause,
+    es: *mut pg_sys::ExplainState,
+    query_string: *const ::std::os::raw::c_char,
+    params: pg_sys::ParamListInfo,
+    query_env: *mut pg_sys::QueryEnvironment,
+) {
+    pg_sys::standard_ExplainOneQuery(
+        query,
+        cursor_options,
+        into,
+        es,
+        query_string,
+        params,
+        query_env,
+    )
+}
+
+/// Prior to v17, Postgres' standard `ExplainOneQuery()` is static, so this does what it does:
+/// plan the query, timing the planner, and explain the resulting plan
+#[cfg(any(
+    feature = "pg12",
+    feature = "pg13",
+    feature = "pg14",
+    feature = "pg15",
+    feature = "pg16"
+))]
+#[pg_guard]
+unsafe extern "C" fn pgrx_standard_explain_one_query_wrapper(
+    query: *mut pg_sys::Query,
+    cursor_options: i32,
+    into: *mut pg_sys::IntoClause,
+    es: *mut pg_sys::ExplainState,
+    query_string: *const ::std::os::raw::c_char,
+    params: pg_sys::ParamListInfo,
+    query_env: *mut pg_sys::QueryEnvironment,
+) {
+    #[cfg(not(feature = "pg12"))]
+    let bufusage_start = pg_sys::pgBufferUsage;
+    let plan_start = std::time::Instant::now();
+
+    #[cfg(feature = "pg12")]
+    let plan = pg_sys::pg_plan_query(query, cursor_options, params);
+    #[cfg(not(feature = "pg12"))]
+    let plan = pg_sys::pg_plan_query(query, query_string, cursor_options, params);
+
+    let elapsed = plan_start.elapsed();
+    #[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14", feature = "pg15"))]
+    let planduration =
+        pg_sys::instr_time { tv_sec: elapsed.as_secs() as _, tv_nsec: elapsed.subsec_nanos() as _ };
+    #[cfg(feature = "pg16")]
+    let planduration = pg_sys::instr_time { ticks: elapsed.as_nanos() as _ };
+
+    #[cfg(feature = "pg12")]
+    pg_sys::ExplainOnePlan(plan, into, es, query_string, params, query_env, &planduration);
+
+    #[cfg(not(feature = "pg12"))]
+    {
+        let mut bufusage = pg_sys::BufferUsage::default();
+        pg_sys::BufferUsageAccumDiff(&mut bufusage, &pg_sys::pgBufferUsage, &bufusage_start);
+        pg_sys::ExplainOnePlan(
+            plan,
+            into,
+            es,
+            query_string,
+            params,
+            query_env,
+            &planduration,
+            if (*es).buffers { &bufusage } else { std::ptr::null() },
+        );
+    }
+}
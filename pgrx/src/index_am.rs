@@ -0,0 +1,651 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+/*!
+
+[Index Access Method](https://www.postgresql.org/docs/current/indexam.html) support.
+
+Index access methods are created by implementing [`IndexAccessMethod`] for a type and decorating the
+implementation with [`#[pg_index_am]`](pgrx_macros::pg_index_am).  The macro generates the
+`index_am_handler` function Postgres calls to obtain the access method's [`pg_sys::IndexAmRoutine`]
+and emits the [`CREATE ACCESS METHOD`](https://www.postgresql.org/docs/current/sql-create-access-method.html)
+SQL for it.
+
+The `IndexAmRoutine` is assembled by pgrx from the trait's associated constants and functions, taking
+care of the differences between the supported Postgres versions.  Implementations work with
+[`PgRelation`]s, slices, and owned scan state rather than the raw pointers Postgres hands out.
+
+Operator classes are declared with [`extension_sql!`](crate::extension_sql) and should list the
+implementing type in `requires` so they are created after the access method.
+
+# Example
+
+```rust,no_run
+use pgrx::index_am::{IndexBuildStats, IndexScanItem};
+use pgrx::prelude::*;
+use pgrx::PgRelation;
+
+pub struct NoopIndex;
+
+#[pg_index_am]
+impl IndexAccessMethod for NoopIndex {
+    type ScanState = ();
+
+    fn build(
+        _heap: &PgRelation,
+        _index: &PgRelation,
+        _index_info: &mut pg_sys::IndexInfo,
+    ) -> IndexBuildStats {
+        IndexBuildStats::default()
+    }
+
+    fn insert(
+        _index: &PgRelation,
+        _heap: &PgRelation,
+        _heap_tid: pg_sys::ItemPointerData,
+        _values: &[pg_sys::Datum],
+        _isnull: &[bool],
+        _check_unique: pg_sys::IndexUniqueCheck::Type,
+        _index_unchanged: bool,
+    ) -> bool {
+        false
+    }
+
+    fn bulk_delete(
+        _info: &pg_sys::IndexVacuumInfo,
+        _stats: &mut pg_sys::IndexBulkDeleteResult,
+        _is_dead: &dyn Fn(pg_sys::ItemPointerData) -> bool,
+    ) {
+    }
+
+    fn begin_scan(_index: &PgRelation, _nkeys: usize, _norderbys: usize) -> Self::ScanState {}
+
+    fn rescan(_state: &mut (), _keys: &[pg_sys::ScanKeyData], _orderbys: &[pg_sys::ScanKeyData]) {}
+
+    fn get_tuple(
+        _state: &mut (),
+        _direction: pg_sys::ScanDirection::Type,
+    ) -> Option<IndexScanItem> {
+        None
+    }
+}
+
+extension_sql!(
+    r#"
+    CREATE OPERATOR CLASS noop_int4_ops DEFAULT FOR TYPE int4 USING noop_index AS STORAGE int4;
+    "#,
+    name = "noop_int4_ops",
+    requires = [NoopIndex]
+);
+```
+*/
+use crate as pgrx; // for #[pg_guard] support from within ourself
+use crate::{pg_guard, pg_sys, AllocatedByRust, PgBox, PgMemoryContexts, PgRelation};
+use std::ffi::c_void;
+
+/// The totals reported back to Postgres at the end of [`IndexAccessMethod::build`].
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct IndexBuildStats {
+    /// The number of tuples found in the heap.
+    pub heap_tuples: f64,
+    /// The number of tuples added to the index.
+    pub index_tuples: f64,
+}
+
+/// A single match produced by [`IndexAccessMethod::get_tuple`].
+#[derive(Debug, Copy, Clone)]
+pub struct IndexScanItem {
+    /// The TID of the matching heap tuple.
+    pub heap_tid: pg_sys::ItemPointerData,
+    /// Set if the executor must recheck the scan keys against the heap tuple.
+    pub recheck: bool,
+}
+
+/// The estimates returned by [`IndexAccessMethod::cost_estimate`].
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub struct IndexCosts {
+    pub startup_cost: pg_sys::Cost,
+    pub total_cost: pg_sys::Cost,
+    pub selectivity: pg_sys::Selectivity,
+    pub correlation: f64,
+    pub pages: f64,
+}
+
+/// A Postgres index access method.
+///
+/// See the [module documentation](self) for an example.  Apply
+/// [`#[pg_index_am]`](pgrx_macros::pg_index_am) to the implementation.
+///
+/// The associated constants map directly onto the capability flags of [`pg_sys::IndexAmRoutine`]
+/// and default to the most conservative setting.
+pub trait IndexAccessMethod: Sized + 'static {
+    /// The name of the access method. (eg. What you'd pass to `CREATE INDEX ... USING name`.)
+    ///
+    /// **Optional:** `#[pg_index_am]` defaults this to the snake case name of the type.
+    const NAME: &'static str;
+
+    /// Per-scan state created by [`IndexAccessMethod::begin_scan`] and dropped after
+    /// [`IndexAccessMethod::end_scan`], or when the scan's memory context is deleted.
+    type ScanState;
+
+    /// Number of operator strategies, or zero if the access method has no fixed set of them.
+    const STRATEGIES: u16 = 0;
+    /// Number of support functions an operator class can provide.
+    const SUPPORT: u16 = 0;
+    /// Support function number used for opclass options, or zero if there is none.
+    #[cfg(not(feature = "pg12"))]
+    const OPTIONS_PROC_NUM: u16 = 0;
+    const CAN_ORDER: bool = false;
+    const CAN_ORDER_BY_OP: bool = false;
+    const CAN_BACKWARD: bool = false;
+    const CAN_UNIQUE: bool = false;
+    const CAN_MULTICOL: bool = false;
+    const OPTIONAL_KEY: bool = false;
+    const SEARCH_ARRAY: bool = false;
+    const SEARCH_NULLS: bool = false;
+    const STORAGE: bool = false;
+    const CLUSTERABLE: bool = false;
+    const PREDICATE_LOCKS: bool = false;
+    const CAN_INCLUDE: bool = false;
+    /// Type of the data stored in the index, or [`pg_sys::InvalidOid`] if it varies.
+    const KEY_TYPE: pg_sys::Oid = pg_sys::InvalidOid;
+
+    /// Set if [`IndexAccessMethod::get_tuple`] should be exposed as `amgettuple`.
+    const SUPPORTS_TUPLE_SCAN: bool = true;
+    /// Set if [`IndexAccessMethod::get_bitmap`] should be exposed as `amgetbitmap`.
+    const SUPPORTS_BITMAP_SCAN: bool = true;
+
+    /// Build a new index over the existing contents of `heap`.
+    ///
+    /// [`scan_heap`] drives the table access method's build scan for you.
+    fn build(
+        heap: &PgRelation,
+        index: &PgRelation,
+        index_info: &mut pg_sys::IndexInfo,
+    ) -> IndexBuildStats;
+
+    /// Build an empty index in the `INIT` fork of an unlogged index.
+    fn build_empty(_index: &PgRelation) {}
+
+    /// Insert a single entry into the index.
+    ///
+    /// `index_unchanged` is the executor's hint that the indexed columns were not modified by
+    /// an `UPDATE`; it is always `false` before Postgres 14.
+    ///
+    /// The return value only matters for deferred uniqueness checks.
+    fn insert(
+        index: &PgRelation,
+        heap: &PgRelation,
+        heap_tid: pg_sys::ItemPointerData,
+        values: &[pg_sys::Datum],
+        isnull: &[bool],
+        check_unique: pg_sys::IndexUniqueCheck::Type,
+        index_unchanged: bool,
+    ) -> bool;
+
+    /// Remove every entry for which `is_dead` returns `true`, updating `stats` as you go.
+    fn bulk_delete(
+        info: &pg_sys::IndexVacuumInfo,
+        stats: &mut pg_sys::IndexBulkDeleteResult,
+        is_dead: &dyn Fn(pg_sys::ItemPointerData) -> bool,
+    );
+
+    /// Perform post-`VACUUM` cleanup, such as reclaiming free pages.
+    fn vacuum_cleanup(_info: &pg_sys::IndexVacuumInfo, _stats: &mut pg_sys::IndexBulkDeleteResult) {
+    }
+
+    /// Estimate the cost of an index scan.
+    ///
+    /// The default uses Postgres' `genericcostestimate()`.
+    fn cost_estimate(
+        root: &mut pg_sys::PlannerInfo,
+        path: &mut pg_sys::IndexPath,
+        loop_count: f64,
+    ) -> IndexCosts {
+        generic_cost_estimate(root, path, loop_count)
+    }
+
+    /// Parse and validate the `WITH (...)` options of an index.
+    ///
+    /// The default accepts no options.
+    fn options(_reloptions: pg_sys::Datum, _validate: bool) -> *mut pg_sys::bytea {
+        std::ptr::null_mut()
+    }
+
+    /// Validate the operator class identified by `opclass`.
+    fn validate(_opclass: pg_sys::Oid) -> bool {
+        true
+    }
+
+    /// Prepare for an index scan.  `keys` are provided later by [`IndexAccessMethod::rescan`].
+    fn begin_scan(index: &PgRelation, nkeys: usize, norderbys: usize) -> Self::ScanState;
+
+    /// Start, or restart, a scan with new scan keys.
+    fn rescan(
+        state: &mut Self::ScanState,
+        keys: &[pg_sys::ScanKeyData],
+        orderbys: &[pg_sys::ScanKeyData],
+    );
+
+    /// Return the next match in the given direction, or `None` once the scan is exhausted.
+    fn get_tuple(
+        state: &mut Self::ScanState,
+        direction: pg_sys::ScanDirection::Type,
+    ) -> Option<IndexScanItem>;
+
+    /// Report every match to `add`, returning the number of matches.
+    ///
+    /// The default drains [`IndexAccessMethod::get_tuple`] in the forward direction.
+    fn get_bitmap(
+        state: &mut Self::ScanState,
+        add: &mut dyn FnMut(pg_sys::ItemPointerData, bool),
+    ) -> i64 {
+        let mut ntids = 0;
+        while let Some(item) = Self::get_tuple(state, pg_sys::ScanDirection::ForwardScanDirection) {
+            add(item.heap_tid, item.recheck);
+            ntids += 1;
+        }
+        ntids
+    }
+
+    /// End a scan.  The state is dropped once this returns.
+    fn end_scan(_state: Self::ScanState) {}
+}
+
+/// Build the [`pg_sys::IndexAmRoutine`] describing `T`.
+///
+/// This is what the `index_am_handler` generated by [`#[pg_index_am]`](pgrx_macros::pg_index_am)
+/// returns to Postgres.
+pub fn index_am_routine<T: IndexAccessMethod>() -> PgBox<pg_sys::IndexAmRoutine, AllocatedByRust> {
+    // SAFETY: `IndexAmRoutine` is a node and `alloc_node` zeroes it and sets its tag
+    let mut routine =
+        unsafe { PgBox::<pg_sys::IndexAmRoutine>::alloc_node(pg_sys::NodeTag::T_IndexAmRoutine) };
+
+    routine.amstrategies = T::STRATEGIES;
+    routine.amsupport = T::SUPPORT;
+    #[cfg(not(feature = "pg12"))]
+    {
+        routine.amoptsprocnum = T::OPTIONS_PROC_NUM;
+        routine.amusemaintenanceworkmem = false;
+        routine.amparallelvacuumoptions = pg_sys::VACUUM_OPTION_NO_PARALLEL as _;
+    }
+    routine.amcanorder = T::CAN_ORDER;
+    routine.amcanorderbyop = T::CAN_ORDER_BY_OP;
+    routine.amcanbackward = T::CAN_BACKWARD;
+    routine.amcanunique = T::CAN_UNIQUE;
+    routine.amcanmulticol = T::CAN_MULTICOL;
+    routine.amoptionalkey = T::OPTIONAL_KEY;
+    routine.amsearcharray = T::SEARCH_ARRAY;
+    routine.amsearchnulls = T::SEARCH_NULLS;
+    routine.amstorage = T::STORAGE;
+    routine.amclusterable = T::CLUSTERABLE;
+    routine.ampredlocks = T::PREDICATE_LOCKS;
+    routine.amcanparallel = false;
+    routine.amcaninclude = T::CAN_INCLUDE;
+    routine.amkeytype = T::KEY_TYPE;
+
+    routine.ambuild = Some(ambuild::<T>);
+    routine.ambuildempty = Some(ambuildempty::<T>);
+    routine.aminsert = Some(aminsert::<T>);
+    routine.ambulkdelete = Some(ambulkdelete::<T>);
+    routine.amvacuumcleanup = Some(amvacuumcleanup::<T>);
+    routine.amcostestimate = Some(amcostestimate::<T>);
+    routine.amoptions = Some(amoptions::<T>);
+    routine.amvalidate = Some(amvalidate::<T>);
+    routine.ambeginscan = Some(ambeginscan::<T>);
+    routine.amrescan = Some(amrescan::<T>);
+    routine.amendscan = Some(amendscan::<T>);
+    if T::SUPPORTS_TUPLE_SCAN {
+        routine.amgettuple = Some(amgettuple::<T>);
+    }
+    if T::SUPPORTS_BITMAP_SCAN {
+        routine.amgetbitmap = Some(amgetbitmap::<T>);
+    }
+
+    routine
+}
+
+/// Feed every live tuple of `heap` to `callback`, as Postgres' `table_index_build_scan()` does.
+///
+/// `callback` receives the heap TID, the index column values and null flags for the tuple, and
+/// whether the tuple is alive.
+pub fn scan_heap<F>(
+    heap: &PgRelation,
+    index: &PgRelation,
+    index_info: &mut pg_sys::IndexInfo,
+    mut callback: F,
+) where
+    F: FnMut(pg_sys::ItemPointerData, &[pg_sys::Datum], &[bool], bool),
+{
+    // SAFETY: the relations are open and `callback` outlives the scan
+    unsafe {
+        pg_sys::IndexBuildHeapScan(
+            heap.as_ptr(),
+            index.as_ptr(),
+            index_info,
+            Some(build_callback::<F>),
+            &mut callback,
+        );
+    }
+}
+
+#[cfg(feature = "pg12")]
+#[pg_guard]
+unsafe extern "C" fn build_callback<F>(
+    index: pg_sys::Relation,
+    htup: pg_sys::HeapTuple,
+    values: *mut pg_sys::Datum,
+    isnull: *mut bool,
+    tuple_is_alive: bool,
+    state: *mut c_void,
+) where
+    F: FnMut(pg_sys::ItemPointerData, &[pg_sys::Datum], &[bool], bool),
+{
+    let callback = &mut *state.cast::<F>();
+    let natts = index_natts(index);
+    callback(
+        (*htup).t_self,
+        std::slice::from_raw_parts(values, natts),
+        std::slice::from_raw_parts(isnull, natts),
+        tuple_is_alive,
+    );
+}
+
+#[cfg(not(feature = "pg12"))]
+#[pg_guard]
+unsafe extern "C" fn build_callback<F>(
+    index: pg_sys::Relation,
+    tid: pg_sys::ItemPointer,
+    values: *mut pg_sys::Datum,
+    isnull: *mut bool,
+    tuple_is_alive: bool,
+    state: *mut c_void,
+) where
+    F: FnMut(pg_sys::ItemPointerData, &[pg_sys::Datum], &[bool], bool),
+{
+    let callback = &mut *state.cast::<F>();
+    let natts = index_natts(index);
+    callback(
+        *tid,
+        std::slice::from_raw_parts(values, natts),
+        std::slice::from_raw_parts(isnull, natts),
+        tuple_is_alive,
+    );
+}
+
+/// Estimate index scan costs with Postgres' `genericcostestimate()`.
+pub fn generic_cost_estimate(
+    root: &mut pg_sys::PlannerInfo,
+    path: &mut pg_sys::IndexPath,
+    loop_count: f64,
+) -> IndexCosts {
+    let mut costs = pg_sys::GenericCosts::default();
+    // SAFETY: the planner gave us valid `root` and `path` nodes
+    unsafe {
+        pg_sys::genericcostestimate(root, path, loop_count, &mut costs);
+    }
+    IndexCosts {
+        startup_cost: costs.indexStartupCost,
+        total_cost: costs.indexTotalCost,
+        selectivity: costs.indexSelectivity,
+        correlation: costs.indexCorrelation,
+        pages: costs.numIndexPages,
+    }
+}
+
+#[inline]
+unsafe fn index_natts(index: pg_sys::Relation) -> usize {
+    (*(*index).rd_att).natts as usize
+}
+
+#[inline]
+unsafe fn scan_state<'a, T: IndexAccessMethod>(
+    scan: pg_sys::IndexScanDesc,
+) -> &'a mut Option<T::ScanState> {
+    &mut *(*scan).opaque.cast::<Option<T::ScanState>>()
+}
+
+#[inline]
+unsafe fn scan_keys<'a>(keys: pg_sys::ScanKey, nkeys: i32) -> &'a [pg_sys::ScanKeyData] {
+    if keys.is_null() || nkeys <= 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(keys, nkeys as usize)
+    }
+}
+
+#[pg_guard]
+unsafe extern "C" fn ambuild<T: IndexAccessMethod>(
+    heap_relation: pg_sys::Relation,
+    index_relation: pg_sys::Relation,
+    index_info: *mut pg_sys::IndexInfo,
+) -> *mut pg_sys::IndexBuildResult {
+    let heap = PgRelation::from_pg(heap_relation);
+    let index = PgRelation::from_pg(index_relation);
+    let stats = T::build(&heap, &index, &mut *index_info);
+
+    let mut result = PgBox::<pg_sys::IndexBuildResult>::alloc0();
+    result.heap_tuples = stats.heap_tuples;
+    result.index_tuples = stats.index_tuples;
+    result.into_pg()
+}
+
+#[pg_guard]
+unsafe extern "C" fn ambuildempty<T: IndexAccessMethod>(index_relation: pg_sys::Relation) {
+    T::build_empty(&PgRelation::from_pg(index_relation));
+}
+
+#[cfg(any(feature = "pg12", feature = "pg13"))]
+#[pg_guard]
+unsafe extern "C" fn aminsert<T: IndexAccessMethod>(
+    index_relation: pg_sys::Relation,
+    values: *mut pg_sys::Datum,
+    isnull: *mut bool,
+    heap_tid: pg_sys::ItemPointer,
+    heap_relation: pg_sys::Relation,
+    check_unique: pg_sys::IndexUniqueCheck::Type,
+    _index_info: *mut pg_sys::IndexInfo,
+) -> bool {
+    insert::<T>(index_relation, values, isnull, heap_tid, heap_relation, check_unique, false)
+}
+
+#[cfg(not(any(feature = "pg12", feature = "pg13")))]
+#[pg_guard]
+unsafe extern "C" fn aminsert<T: IndexAccessMethod>(
+    index_relation: pg_sys::Relation,
+    values: *mut pg_sys::Datum,
+    isnull: *mut bool,
+    heap_tid: pg_sys::ItemPointer,
+    heap_relation: pg_sys::Relation,
+    check_unique: pg_sys::IndexUniqueCheck::Type,
+    index_unchanged: bool,
+    _index_info: *mut pg_sys::IndexInfo,
+) -> bool {
+    insert::<T>(
+        index_relation,
+        values,
+        isnull,
+        heap_tid,
+        heap_relation,
+        check_unique,
+        index_unchanged,
+    )
+}
+
+#[inline]
+unsafe fn insert<T: IndexAccessMethod>(
+    index_relation: pg_sys::Relation,
+    values: *mut pg_sys::Datum,
+    isnull: *mut bool,
+    heap_tid: pg_sys::ItemPointer,
+    heap_relation: pg_sys::Relation,
+    check_unique: pg_sys::IndexUniqueCheck::Type,
+    index_unchanged: bool,
+) -> bool {
+    let natts = index_natts(index_relation);
+    T::insert(
+        &PgRelation::from_pg(index_relation),
+        &PgRelation::from_pg(heap_relation),
+        *heap_tid,
+        std::slice::from_raw_parts(values, natts),
+        std::slice::from_raw_parts(isnull, natts),
+        check_unique,
+        index_unchanged,
+    )
+}
+
+#[pg_guard]
+unsafe extern "C" fn ambulkdelete<T: IndexAccessMethod>(
+    info: *mut pg_sys::IndexVacuumInfo,
+    stats: *mut pg_sys::IndexBulkDeleteResult,
+    callback: pg_sys::IndexBulkDeleteCallback,
+    callback_state: *mut c_void,
+) -> *mut pg_sys::IndexBulkDeleteResult {
+    let stats = if stats.is_null() {
+        PgBox::<pg_sys::IndexBulkDeleteResult>::alloc0().into_pg()
+    } else {
+        stats
+    };
+    // the callback is C, so an ERROR it raises must be caught and unwound through `bulk_delete`
+    let is_dead = |mut tid: pg_sys::ItemPointerData| match callback {
+        Some(callback) => pg_sys::ffi::pg_guard_ffi_boundary(|| callback(&mut tid, callback_state)),
+        None => false,
+    };
+    T::bulk_delete(&*info, &mut *stats, &is_dead);
+    stats
+}
+
+#[pg_guard]
+unsafe extern "C" fn amvacuumcleanup<T: IndexAccessMethod>(
+    info: *mut pg_sys::IndexVacuumInfo,
+    stats: *mut pg_sys::IndexBulkDeleteResult,
+) -> *mut pg_sys::IndexBulkDeleteResult {
+    if (*info).analyze_only {
+        return stats;
+    }
+    let stats = if stats.is_null() {
+        PgBox::<pg_sys::IndexBulkDeleteResult>::alloc0().into_pg()
+    } else {
+        stats
+    };
+    T::vacuum_cleanup(&*info, &mut *stats);
+    stats
+}
+
+#[allow(clippy::too_many_arguments)]
+#[pg_guard]
+unsafe extern "C" fn amcostestimate<T: IndexAccessMethod>(
+    root: *mut pg_sys::PlannerInfo,
+    path: *mut pg_sys::IndexPath,
+    loop_count: f64,
+    index_startup_cost: *mut pg_sys::Cost,
+    index_total_cost: *mut pg_sys::Cost,
+    index_selectivity: *mut pg_sys::Selectivity,
+    index_correlation: *mut f64,
+    index_pages: *mut f64,
+) {
+    let costs = T::cost_estimate(&mut *root, &mut *path, loop_count);
+    *index_startup_cost = costs.startup_cost;
+    *index_total_cost = costs.total_cost;
+    *index_selectivity = costs.selectivity;
+    *index_correlation = costs.correlation;
+    *index_pages = costs.pages;
+}
+
+#[pg_guard]
+unsafe extern "C" fn amoptions<T: IndexAccessMethod>(
+    reloptions: pg_sys::Datum,
+    validate: bool,
+) -> *mut pg_sys::bytea {
+    T::options(reloptions, validate)
+}
+
+#[pg_guard]
+unsafe extern "C" fn amvalidate<T: IndexAccessMethod>(opclass: pg_sys::Oid) -> bool {
+    T::validate(opclass)
+}
+
+#[pg_guard]
+unsafe extern "C" fn ambeginscan<T: IndexAccessMethod>(
+    index_relation: pg_sys::Relation,
+    nkeys: i32,
+    norderbys: i32,
+) -> pg_sys::IndexScanDesc {
+    let scan = pg_sys::RelationGetIndexScan(index_relation, nkeys, norderbys);
+    let state = T::begin_scan(
+        &PgRelation::from_pg(index_relation),
+        nkeys.max(0) as usize,
+        norderbys.max(0) as usize,
+    );
+
+    // the state lives in the scan's memory context so it's dropped even if the scan errors out
+    // before `amendscan` gets a chance to take it back
+    (*scan).opaque =
+        PgMemoryContexts::CurrentMemoryContext.leak_and_drop_on_delete(Some(state)).cast();
+    scan
+}
+
+#[pg_guard]
+unsafe extern "C" fn amrescan<T: IndexAccessMethod>(
+    scan: pg_sys::IndexScanDesc,
+    keys: pg_sys::ScanKey,
+    nkeys: i32,
+    orderbys: pg_sys::ScanKey,
+    norderbys: i32,
+) {
+    // like the in-core access methods, remember the keys on the scan descriptor itself
+    if !keys.is_null() && (*scan).numberOfKeys > 0 {
+        std::ptr::copy(keys, (*scan).keyData, (*scan).numberOfKeys as usize);
+    }
+    if !orderbys.is_null() && (*scan).numberOfOrderBys > 0 {
+        std::ptr::copy(orderbys, (*scan).orderByData, (*scan).numberOfOrderBys as usize);
+    }
+
+    if let Some(state) = scan_state::<T>(scan) {
+        T::rescan(state, scan_keys(keys, nkeys), scan_keys(orderbys, norderbys));
+    }
+}
+
+#[pg_guard]
+unsafe extern "C" fn amgettuple<T: IndexAccessMethod>(
+    scan: pg_sys::IndexScanDesc,
+    direction: pg_sys::ScanDirection::Type,
+) -> bool {
+    let Some(state) = scan_state::<T>(scan) else {
+        return false;
+    };
+    match T::get_tuple(state, direction) {
+        Some(item) => {
+            (*scan).xs_heaptid = item.heap_tid;
+            (*scan).xs_recheck = item.recheck;
+            true
+        }
+        None => false,
+    }
+}
+
+#[pg_guard]
+unsafe extern "C" fn amgetbitmap<T: IndexAccessMethod>(
+    scan: pg_sys::IndexScanDesc,
+    tbm: *mut pg_sys::TIDBitmap,
+) -> i64 {
+    let Some(state) = scan_state::<T>(scan) else {
+        return 0;
+    };
+    T::get_bitmap(state, &mut |mut tid, recheck| {
+        pg_sys::tbm_add_tuples(tbm, &mut tid, 1, recheck);
+    })
+}
+
+#[pg_guard]
+unsafe extern "C" fn amendscan<T: IndexAccessMethod>(scan: pg_sys::IndexScanDesc) {
+    if let Some(state) = scan_state::<T>(scan).take() {
+        T::end_scan(state);
+    }
+}
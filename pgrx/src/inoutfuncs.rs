@@ -11,10 +11,13 @@
 //! input/output functions.
 //!
 //! The default implementations use `serde_json` to serialize a custom type to human-readable strings,
-//! and `serde_cbor` to serialize internally as a `varlena *` for storage on disk.
+//! and `serde_cbor` to serialize internally as a `varlena *` for storage on disk.  The optional
+//! binary send/receive functions also use `serde_cbor` by default.
 
-use crate::datum::PgVarlena;
+use crate::datum::{PgVarlena, Typmod};
 use crate::*;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 #[doc(hidden)]
 pub use serde_json::{from_slice as json_from_slice, to_vec as json_to_vec};
 
@@ -53,3 +56,60 @@ pub trait InOutFuncs {
     /// error message should be generated?
     const NULL_ERROR_MESSAGE: Option<&'static str> = None;
 }
+
+/// `#[derive(Serialize, Deserialize, PostgresType)]` types with a `#[sendrecvfuncs]` attribute need
+/// to implement this trait to provide the binary send/receive functions for that type, which are
+/// used by binary `COPY` and the binary wire protocol
+///
+/// The default implementations send the same CBOR bytes the type is stored as, so an empty
+/// `impl PgBinaryInOutFuncs for MyType {}` is enough.
+pub trait PgBinaryInOutFuncs {
+    /// Given the binary representation of `Self` at the cursor of `buffer`, decode it into `Self`,
+    /// leaving the cursor after the bytes that were read.
+    ///
+    /// It is expected that malformed input will raise an `error!()` or `panic!()`
+    fn receive(buffer: &mut StringInfo<AllocatedByPostgres>) -> Self
+    where
+        Self: Sized + DeserializeOwned,
+    {
+        serde_cbor::from_reader(buffer).unwrap_or_else(|e| {
+            ereport!(
+                ERROR,
+                PgSqlErrorCode::ERRCODE_INVALID_BINARY_REPRESENTATION,
+                format!("invalid binary representation: {e}")
+            );
+        })
+    }
+
+    /// Convert `Self` into its binary representation by writing to the supplied `StringInfo` buffer
+    fn send(&self, buffer: &mut StringInfo)
+    where
+        Self: Sized + Serialize,
+    {
+        serde_cbor::to_writer(buffer, self).expect("failed to encode as CBOR")
+    }
+}
+
+/// `#[derive(PostgresType)]` types with a `#[typmodfuncs]` attribute need to implement this trait to
+/// accept a type modifier, such as the `16` of `mytype(16)`
+///
+/// The typmod is applied by a generated `mytype(mytype, integer, boolean)` length-coercion cast,
+/// which Postgres calls whenever a value is stored into a column, or cast to a type, that has a
+/// modifier.  It's also applied by the type's input and receive functions, which is how values
+/// read by `COPY FROM` are coerced to their column's typmod.
+pub trait PgTypmodFuncs {
+    /// Given the modifiers written after the type's name, such as `["16"]` for `mytype(16)`, encode
+    /// them into a non-negative typmod.
+    ///
+    /// It is expected that invalid modifiers will raise an `error!()` or `panic!()`
+    fn typmod_in(modifiers: &[&core::ffi::CStr]) -> Typmod;
+
+    /// Convert a typmod back into the text written after the type's name, such as `(16)`
+    fn typmod_out(typmod: Typmod) -> alloc::ffi::CString;
+
+    /// Coerce `self` to the given typmod.  `explicit` is true for an explicit cast, where Postgres'
+    /// own types like `varchar` truncate a value that doesn't fit instead of raising an error.
+    fn coerce_typmod(self, typmod: Typmod, explicit: bool) -> Self
+    where
+        Self: Sized;
+}
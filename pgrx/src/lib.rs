@@ -41,9 +41,12 @@ pub mod atomics;
 pub mod bgworkers;
 pub mod callbacks;
 pub mod callconv;
+pub mod custom_scan;
 pub mod datum;
+pub mod dsm;
 pub mod enum_helper;
 pub mod fcinfo;
+pub mod fdw;
 pub mod ffi;
 pub mod fn_call;
 pub mod guc;
@@ -52,6 +55,7 @@ pub mod heap_tuple;
 #[allow(deprecated)]
 pub mod hooks;
 pub mod htup;
+pub mod index_am;
 pub mod inoutfuncs;
 pub mod itemptr;
 pub mod iter;
@@ -65,14 +69,21 @@ pub mod misc;
 pub mod namespace;
 pub mod nodes;
 pub mod nullable;
+pub mod output_plugin;
+pub mod parallel;
 pub mod pg_catalog;
 pub mod pgbox;
 pub mod rel;
+pub mod row;
+pub mod shm_mq;
 pub mod shmem;
+pub mod shmem_hash;
 pub mod spi;
 #[cfg(feature = "cshim")]
 pub mod spinlock;
 pub mod stringinfo;
+pub mod support;
+pub mod table_am;
 pub mod trigger_support;
 pub mod tupdesc;
 pub mod varlena;
@@ -109,6 +120,7 @@ pub use nodes::*;
 pub use pgbox::*;
 pub use rel::*;
 pub use shmem::*;
+pub use shmem_hash::*;
 pub use spi::Spi; // only Spi.  We don't want the top-level namespace polluted with spi::Result and spi::Error
 pub use stringinfo::*;
 pub use trigger_support::*;
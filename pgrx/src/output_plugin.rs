@@ -0,0 +1,685 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+/*!
+
+[Logical decoding output plugin](https://www.postgresql.org/docs/current/logicaldecoding-output-plugin.html) support.
+
+Output plugins are created by implementing [`OutputPlugin`] for a type and exporting it with
+[`pg_output_plugin!`](crate::pg_output_plugin).  A library can export only one output plugin, which
+replication slots then refer to by the library's name.
+
+Each replication slot decoding changes gets its own instance of the plugin, created by
+[`OutputPlugin::startup`] and dropped after [`OutputPlugin::shutdown`].  The instance is what every
+other callback receives as `self`, so it's the place to keep any per-slot state.
+
+# Example
+
+```rust,no_run
+use pgrx::output_plugin::{Change, OutputWriter, Transaction};
+use pgrx::prelude::*;
+use pgrx::PgRelation;
+
+pgrx::pg_module_magic!();
+
+pub struct CountChanges {
+    changes: usize,
+}
+
+impl OutputPlugin for CountChanges {
+    fn startup(_options: &[(String, Option<String>)], _is_init: bool) -> Self {
+        CountChanges { changes: 0 }
+    }
+
+    fn begin(&mut self, _out: &mut OutputWriter, _txn: &Transaction) {
+        self.changes = 0;
+    }
+
+    fn change(
+        &mut self,
+        _out: &mut OutputWriter,
+        _txn: &Transaction,
+        _relation: &PgRelation,
+        _change: Change,
+    ) {
+        self.changes += 1;
+    }
+
+    fn commit(&mut self, out: &mut OutputWriter, txn: &Transaction, _commit_lsn: pg_sys::XLogRecPtr) {
+        out.write(format!("transaction {} made {} changes", txn.xid(), self.changes));
+    }
+}
+
+pg_output_plugin!(CountChanges);
+```
+*/
+use crate as pgrx; // for #[pg_guard] support from within ourself
+use crate::datum::TimestampWithTimeZone;
+use crate::heap_tuple::PgHeapTuple;
+use crate::list::List;
+use crate::{memcx, pg_guard, pg_sys, AllocatedByPostgres, PgMemoryContexts, PgRelation};
+use std::borrow::Cow;
+use std::ffi::{c_char, c_int, CStr};
+use std::marker::PhantomData;
+
+/// The kind of data an [`OutputPlugin`] writes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum OutputType {
+    /// Text in the database encoding, which `pg_logical_slot_get_changes()` can return.
+    #[default]
+    Textual,
+    /// Arbitrary bytes, which can only be read with `pg_logical_slot_get_binary_changes()` or over
+    /// the replication protocol.
+    Binary,
+}
+
+impl From<OutputType> for pg_sys::OutputPluginOutputType::Type {
+    fn from(value: OutputType) -> Self {
+        match value {
+            OutputType::Textual => pg_sys::OutputPluginOutputType::OUTPUT_PLUGIN_TEXTUAL_OUTPUT,
+            OutputType::Binary => pg_sys::OutputPluginOutputType::OUTPUT_PLUGIN_BINARY_OUTPUT,
+        }
+    }
+}
+
+/// A Postgres logical decoding output plugin.
+///
+/// See the [module documentation](self) for an example.  Export the implementation with
+/// [`pg_output_plugin!`](crate::pg_output_plugin).
+///
+/// The default implementation of every callback other than [`OutputPlugin::startup`] and
+/// [`OutputPlugin::change`] does nothing.
+pub trait OutputPlugin: Sized + 'static {
+    /// Set to also receive changes made while rewriting tables, such as by `VACUUM FULL`.
+    const RECEIVE_REWRITES: bool = false;
+
+    /// Set to have large in-progress transactions streamed to the `stream_*` callbacks before
+    /// they commit, rather than spilled to disk.  Streaming needs Postgres 14 or later, and is
+    /// ignored on older versions.
+    const SUPPORTS_STREAMING: bool = false;
+
+    /// Create the plugin's state for a replication slot.
+    ///
+    /// `options` are the `name`/`value` pairs given to `pg_logical_slot_get_changes()` and
+    /// friends, or to `START_REPLICATION`.  `is_init` is set when the slot is being created, in
+    /// which case only [`OutputPlugin::shutdown`] follows.
+    fn startup(options: &[(String, Option<String>)], is_init: bool) -> Self;
+
+    /// The kind of data the plugin writes, asked once [`OutputPlugin::startup`] returns.
+    fn output_type(&self) -> OutputType {
+        OutputType::Textual
+    }
+
+    /// Called when decoding stops, before the plugin's state is dropped.
+    fn shutdown(self) {}
+
+    /// Called at the start of each committed transaction.
+    fn begin(&mut self, _out: &mut OutputWriter, _txn: &Transaction) {}
+
+    /// Called for each row inserted, updated, or deleted by a transaction.
+    fn change(
+        &mut self,
+        out: &mut OutputWriter,
+        txn: &Transaction,
+        relation: &PgRelation,
+        change: Change,
+    );
+
+    /// Called once all of a transaction's changes have been passed on.
+    fn commit(
+        &mut self,
+        _out: &mut OutputWriter,
+        _txn: &Transaction,
+        _commit_lsn: pg_sys::XLogRecPtr,
+    ) {
+    }
+
+    /// Called for each `TRUNCATE`, with all the relations it truncated.
+    fn truncate(
+        &mut self,
+        _out: &mut OutputWriter,
+        _txn: &Transaction,
+        _relations: &[PgRelation],
+        _options: TruncateOptions,
+    ) {
+    }
+
+    /// Called for each message written by `pg_logical_emit_message()`.
+    ///
+    /// Transactional messages are decoded along with the rest of their transaction, while `txn` is
+    /// `None` for non-transactional messages which weren't written by a transaction with an id.
+    fn message(&mut self, _out: &mut OutputWriter, _txn: Option<&Transaction>, _message: Message) {}
+
+    /// Return `true` to skip changes which were replayed from the given replication origin.
+    fn filter_by_origin(&mut self, _origin_id: pg_sys::RepOriginId) -> bool {
+        false
+    }
+
+    /// Called before a block of changes from an in-progress transaction is streamed.
+    fn stream_start(&mut self, _out: &mut OutputWriter, _txn: &Transaction) {}
+
+    /// Called after a block of changes from an in-progress transaction has been streamed.
+    fn stream_stop(&mut self, _out: &mut OutputWriter, _txn: &Transaction) {}
+
+    /// Called when a transaction, or subtransaction, whose changes were streamed aborts.
+    fn stream_abort(
+        &mut self,
+        _out: &mut OutputWriter,
+        _txn: &Transaction,
+        _abort_lsn: pg_sys::XLogRecPtr,
+    ) {
+    }
+
+    /// Called when a transaction whose changes were streamed commits.
+    fn stream_commit(
+        &mut self,
+        _out: &mut OutputWriter,
+        _txn: &Transaction,
+        _commit_lsn: pg_sys::XLogRecPtr,
+    ) {
+    }
+
+    /// Called for each streamed change, which might yet be aborted.
+    ///
+    /// Defaults to [`OutputPlugin::change`].
+    fn stream_change(
+        &mut self,
+        out: &mut OutputWriter,
+        txn: &Transaction,
+        relation: &PgRelation,
+        change: Change,
+    ) {
+        self.change(out, txn, relation, change)
+    }
+
+    /// Called for each streamed `TRUNCATE`, which might yet be aborted.
+    ///
+    /// Defaults to [`OutputPlugin::truncate`].
+    fn stream_truncate(
+        &mut self,
+        out: &mut OutputWriter,
+        txn: &Transaction,
+        relations: &[PgRelation],
+        options: TruncateOptions,
+    ) {
+        self.truncate(out, txn, relations, options)
+    }
+
+    /// Called for each streamed transactional message, which might yet be aborted.
+    ///
+    /// Defaults to [`OutputPlugin::message`].
+    fn stream_message(&mut self, out: &mut OutputWriter, txn: &Transaction, message: Message) {
+        self.message(out, Some(txn), message)
+    }
+}
+
+/// Where an [`OutputPlugin`] writes the data it decodes.
+pub struct OutputWriter<'a> {
+    ctx: *mut pg_sys::LogicalDecodingContext,
+    _marker: PhantomData<&'a mut pg_sys::LogicalDecodingContext>,
+}
+
+impl OutputWriter<'_> {
+    /// Write `data` as one row of `pg_logical_slot_get_changes()`, or one message of the
+    /// replication protocol.
+    ///
+    /// Plugins whose [`OutputPlugin::output_type`] is [`OutputType::Textual`] must only write text
+    /// in the database encoding.
+    pub fn write(&mut self, data: impl AsRef<[u8]>) {
+        let data = data.as_ref();
+        let len = c_int::try_from(data.len()).expect("output plugin data is too large");
+        unsafe {
+            // SAFETY: the writer only exists during a callback, when `ctx` is valid and ready
+            // to be written to
+            pg_sys::OutputPluginPrepareWrite(self.ctx, true);
+            pg_sys::appendBinaryStringInfo((*self.ctx).out, data.as_ptr().cast(), len);
+            pg_sys::OutputPluginWrite(self.ctx, true);
+        }
+    }
+
+    /// Tell the client how far decoding has progressed, without writing anything.
+    pub fn update_progress(&mut self) {
+        unsafe {
+            // SAFETY: `ctx` is valid for the duration of the callback
+            #[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14"))]
+            pg_sys::OutputPluginUpdateProgress(self.ctx);
+            #[cfg(not(any(feature = "pg12", feature = "pg13", feature = "pg14")))]
+            pg_sys::OutputPluginUpdateProgress(self.ctx, false);
+        }
+    }
+
+    /// The decoding context this writer writes to.
+    pub fn as_ptr(&self) -> *mut pg_sys::LogicalDecodingContext {
+        self.ctx
+    }
+}
+
+/// A decoded transaction.
+pub struct Transaction<'a> {
+    txn: &'a pg_sys::ReorderBufferTXN,
+}
+
+impl Transaction<'_> {
+    pub fn xid(&self) -> pg_sys::TransactionId {
+        self.txn.xid
+    }
+
+    /// The location of the transaction's commit or abort record.
+    pub fn final_lsn(&self) -> pg_sys::XLogRecPtr {
+        self.txn.final_lsn
+    }
+
+    /// The location just past the transaction's commit or abort record.
+    pub fn end_lsn(&self) -> pg_sys::XLogRecPtr {
+        self.txn.end_lsn
+    }
+
+    /// The replication origin the transaction was replayed from, if any.
+    pub fn origin_id(&self) -> Option<pg_sys::RepOriginId> {
+        // `InvalidRepOriginId` is 0
+        (self.txn.origin_id != 0).then_some(self.txn.origin_id)
+    }
+
+    /// The location of the transaction's commit on its replication origin.
+    pub fn origin_lsn(&self) -> pg_sys::XLogRecPtr {
+        self.txn.origin_lsn
+    }
+
+    /// When the transaction committed, which isn't known while it's being streamed.
+    pub fn commit_time(&self) -> Option<TimestampWithTimeZone> {
+        #[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14"))]
+        let commit_time = self.txn.commit_time;
+        #[cfg(not(any(feature = "pg12", feature = "pg13", feature = "pg14")))]
+        let commit_time = unsafe {
+            // SAFETY: every member of the union is a `TimestampTz`
+            self.txn.xact_time.commit_time
+        };
+        (commit_time != 0).then(|| commit_time.try_into().ok()).flatten()
+    }
+
+    pub fn as_ptr(&self) -> *const pg_sys::ReorderBufferTXN {
+        self.txn
+    }
+}
+
+/// A row change decoded by [`OutputPlugin::change`].
+///
+/// Tuples are `None` when the WAL doesn't carry them.  Old tuples are only logged for the columns
+/// of the table's `REPLICA IDENTITY`, and only when those columns change unless the replica
+/// identity is `FULL`.
+///
+/// Unchanged TOASTed values of a new tuple are not logged either, and can't be read from it.
+pub enum Change<'a> {
+    Insert {
+        new: Option<PgHeapTuple<'a, AllocatedByPostgres>>,
+    },
+    Update {
+        old: Option<PgHeapTuple<'a, AllocatedByPostgres>>,
+        new: Option<PgHeapTuple<'a, AllocatedByPostgres>>,
+    },
+    Delete {
+        old: Option<PgHeapTuple<'a, AllocatedByPostgres>>,
+    },
+}
+
+/// The options of a decoded `TRUNCATE`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TruncateOptions {
+    /// Set for `TRUNCATE ... CASCADE`.
+    pub cascade: bool,
+    /// Set for `TRUNCATE ... RESTART IDENTITY`.
+    pub restart_seqs: bool,
+}
+
+/// A message written by `pg_logical_emit_message()`.
+#[derive(Debug, Clone)]
+pub struct Message<'a> {
+    pub lsn: pg_sys::XLogRecPtr,
+    pub transactional: bool,
+    pub prefix: Cow<'a, str>,
+    pub content: &'a [u8],
+}
+
+/// Fill in the `callbacks` of the [`OutputPlugin`] `T`.
+///
+/// This is what [`pg_output_plugin!`](crate::pg_output_plugin) calls from the library's
+/// `_PG_output_plugin_init` function.
+pub fn init_callbacks<T: OutputPlugin>(callbacks: &mut pg_sys::OutputPluginCallbacks) {
+    callbacks.startup_cb = Some(startup::<T>);
+    callbacks.shutdown_cb = Some(shutdown::<T>);
+    callbacks.begin_cb = Some(begin::<T>);
+    callbacks.change_cb = Some(change::<T>);
+    callbacks.truncate_cb = Some(truncate::<T>);
+    callbacks.commit_cb = Some(commit::<T>);
+    callbacks.message_cb = Some(message::<T>);
+    callbacks.filter_by_origin_cb = Some(filter_by_origin::<T>);
+
+    #[cfg(not(any(feature = "pg12", feature = "pg13")))]
+    if T::SUPPORTS_STREAMING {
+        callbacks.stream_start_cb = Some(stream_start::<T>);
+        callbacks.stream_stop_cb = Some(stream_stop::<T>);
+        callbacks.stream_abort_cb = Some(stream_abort::<T>);
+        callbacks.stream_commit_cb = Some(stream_commit::<T>);
+        callbacks.stream_change_cb = Some(stream_change::<T>);
+        callbacks.stream_message_cb = Some(stream_message::<T>);
+        callbacks.stream_truncate_cb = Some(stream_truncate::<T>);
+    }
+}
+
+/// Export `_PG_output_plugin_init` for an [`OutputPlugin`](crate::output_plugin::OutputPlugin),
+/// making the library usable as the plugin of a logical replication slot.
+///
+/// ```rust,no_run
+/// # use pgrx::output_plugin::{Change, OutputWriter, Transaction};
+/// # use pgrx::prelude::*;
+/// # use pgrx::PgRelation;
+/// # pub struct MyPlugin;
+/// # impl OutputPlugin for MyPlugin {
+/// #     fn startup(_: &[(String, Option<String>)], _: bool) -> Self { MyPlugin }
+/// #     fn change(&mut self, _: &mut OutputWriter, _: &Transaction, _: &PgRelation, _: Change) {}
+/// # }
+/// pg_output_plugin!(MyPlugin);
+/// ```
+#[macro_export]
+macro_rules! pg_output_plugin {
+    ($plugin:ty) => {
+        #[no_mangle]
+        #[doc(hidden)]
+        #[allow(non_snake_case)]
+        pub unsafe extern "C" fn _PG_output_plugin_init(
+            callbacks: *mut $crate::pg_sys::OutputPluginCallbacks,
+        ) {
+            $crate::pgrx_extern_c_guard(move || {
+                $crate::output_plugin::init_callbacks::<$plugin>(unsafe { &mut *callbacks })
+            })
+        }
+    };
+}
+
+unsafe fn plugin<'a, T: OutputPlugin>(ctx: *mut pg_sys::LogicalDecodingContext) -> &'a mut T {
+    let state = (*ctx).output_plugin_private.cast::<Option<T>>();
+    state.as_mut().and_then(Option::as_mut).expect("output plugin state is missing")
+}
+
+fn writer<'a>(ctx: *mut pg_sys::LogicalDecodingContext) -> OutputWriter<'a> {
+    OutputWriter { ctx, _marker: PhantomData }
+}
+
+unsafe fn transaction<'a>(txn: *mut pg_sys::ReorderBufferTXN) -> Transaction<'a> {
+    Transaction { txn: &*txn }
+}
+
+unsafe fn decoding_options(list: *mut pg_sys::List) -> Vec<(String, Option<String>)> {
+    memcx::current_context(|cx| {
+        let Some(list) = List::<*mut std::ffi::c_void>::downcast_ptr_in_memcx(list, cx) else {
+            return Vec::new();
+        };
+        list.iter()
+            .map(|ptr| {
+                let def = ptr.cast::<pg_sys::DefElem>();
+                let name = CStr::from_ptr((*def).defname).to_string_lossy().into_owned();
+                let value = (!(*def).arg.is_null()).then(|| {
+                    CStr::from_ptr(pg_sys::defGetString(def)).to_string_lossy().into_owned()
+                });
+                (name, value)
+            })
+            .collect()
+    })
+}
+
+#[cfg(any(
+    feature = "pg12",
+    feature = "pg13",
+    feature = "pg14",
+    feature = "pg15",
+    feature = "pg16"
+))]
+unsafe fn heap_tuple(tuple: *mut pg_sys::ReorderBufferTupleBuf) -> pg_sys::HeapTuple {
+    if tuple.is_null() {
+        std::ptr::null_mut()
+    } else {
+        &mut (*tuple).tuple
+    }
+}
+
+#[cfg(not(any(
+    feature = "pg12",
+    feature = "pg13",
+    feature = "pg14",
+    feature = "pg15",
+    feature = "pg16"
+)))]
+unsafe fn heap_tuple(tuple: pg_sys::HeapTuple) -> pg_sys::HeapTuple {
+    tuple
+}
+
+unsafe fn decode_change<'a>(
+    relation: &'a PgRelation,
+    change: *mut pg_sys::ReorderBufferChange,
+) -> Option<Change<'a>> {
+    use pg_sys::ReorderBufferChangeType::*;
+
+    let tp = &(*change).data.tp;
+    let decode = |tuple: pg_sys::HeapTuple| {
+        (!tuple.is_null()).then(|| PgHeapTuple::from_heap_tuple(relation.tuple_desc(), tuple))
+    };
+    let old = decode(heap_tuple(tp.oldtuple));
+    let new = decode(heap_tuple(tp.newtuple));
+    match (*change).action {
+        REORDER_BUFFER_CHANGE_INSERT => Some(Change::Insert { new }),
+        REORDER_BUFFER_CHANGE_UPDATE => Some(Change::Update { old, new }),
+        REORDER_BUFFER_CHANGE_DELETE => Some(Change::Delete { old }),
+        // Postgres only hands output plugins the above
+        _ => None,
+    }
+}
+
+unsafe fn truncated_relations(
+    nrelations: c_int,
+    relations: *mut pg_sys::Relation,
+) -> Vec<PgRelation> {
+    if relations.is_null() {
+        return Vec::new();
+    }
+    let relations = std::slice::from_raw_parts(relations, nrelations as usize);
+    relations.iter().map(|relation| PgRelation::from_pg(*relation)).collect()
+}
+
+unsafe fn truncate_options(change: *mut pg_sys::ReorderBufferChange) -> TruncateOptions {
+    let truncate = &(*change).data.truncate;
+    TruncateOptions { cascade: truncate.cascade, restart_seqs: truncate.restart_seqs }
+}
+
+unsafe fn decode_message<'a>(
+    lsn: pg_sys::XLogRecPtr,
+    transactional: bool,
+    prefix: *const c_char,
+    message_size: pg_sys::Size,
+    message: *const c_char,
+) -> Message<'a> {
+    let content = if message_size == 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(message.cast::<u8>(), message_size)
+    };
+    Message { lsn, transactional, prefix: CStr::from_ptr(prefix).to_string_lossy(), content }
+}
+
+#[pg_guard]
+unsafe extern "C" fn startup<T: OutputPlugin>(
+    ctx: *mut pg_sys::LogicalDecodingContext,
+    options: *mut pg_sys::OutputPluginOptions,
+    is_init: bool,
+) {
+    let plugin = T::startup(&decoding_options((*ctx).output_plugin_options), is_init);
+    (*options).output_type = plugin.output_type().into();
+    (*options).receive_rewrites = T::RECEIVE_REWRITES;
+
+    // the decoding context's memory outlives the slot's use, so the plugin is dropped even if
+    // decoding ends with an error instead of a call to `shutdown`
+    (*ctx).output_plugin_private =
+        PgMemoryContexts::For((*ctx).context).leak_and_drop_on_delete(Some(plugin)).cast();
+}
+
+#[pg_guard]
+unsafe extern "C" fn shutdown<T: OutputPlugin>(ctx: *mut pg_sys::LogicalDecodingContext) {
+    let state = (*ctx).output_plugin_private.cast::<Option<T>>();
+    if let Some(plugin) = state.as_mut().and_then(Option::take) {
+        plugin.shutdown();
+    }
+}
+
+#[pg_guard]
+unsafe extern "C" fn begin<T: OutputPlugin>(
+    ctx: *mut pg_sys::LogicalDecodingContext,
+    txn: *mut pg_sys::ReorderBufferTXN,
+) {
+    plugin::<T>(ctx).begin(&mut writer(ctx), &transaction(txn));
+}
+
+#[pg_guard]
+unsafe extern "C" fn change<T: OutputPlugin>(
+    ctx: *mut pg_sys::LogicalDecodingContext,
+    txn: *mut pg_sys::ReorderBufferTXN,
+    relation: pg_sys::Relation,
+    change: *mut pg_sys::ReorderBufferChange,
+) {
+    let relation = PgRelation::from_pg(relation);
+    let Some(change) = decode_change(&relation, change) else {
+        return;
+    };
+    plugin::<T>(ctx).change(&mut writer(ctx), &transaction(txn), &relation, change);
+}
+
+#[pg_guard]
+unsafe extern "C" fn truncate<T: OutputPlugin>(
+    ctx: *mut pg_sys::LogicalDecodingContext,
+    txn: *mut pg_sys::ReorderBufferTXN,
+    nrelations: c_int,
+    relations: *mut pg_sys::Relation,
+    change: *mut pg_sys::ReorderBufferChange,
+) {
+    let relations = truncated_relations(nrelations, relations);
+    let options = truncate_options(change);
+    plugin::<T>(ctx).truncate(&mut writer(ctx), &transaction(txn), &relations, options);
+}
+
+#[pg_guard]
+unsafe extern "C" fn commit<T: OutputPlugin>(
+    ctx: *mut pg_sys::LogicalDecodingContext,
+    txn: *mut pg_sys::ReorderBufferTXN,
+    commit_lsn: pg_sys::XLogRecPtr,
+) {
+    plugin::<T>(ctx).commit(&mut writer(ctx), &transaction(txn), commit_lsn);
+}
+
+#[pg_guard]
+unsafe extern "C" fn message<T: OutputPlugin>(
+    ctx: *mut pg_sys::LogicalDecodingContext,
+    txn: *mut pg_sys::ReorderBufferTXN,
+    message_lsn: pg_sys::XLogRecPtr,
+    transactional: bool,
+    prefix: *const c_char,
+    message_size: pg_sys::Size,
+    message: *const c_char,
+) {
+    let txn = (!txn.is_null()).then(|| transaction(txn));
+    let message = decode_message(message_lsn, transactional, prefix, message_size, message);
+    plugin::<T>(ctx).message(&mut writer(ctx), txn.as_ref(), message);
+}
+
+#[pg_guard]
+unsafe extern "C" fn filter_by_origin<T: OutputPlugin>(
+    ctx: *mut pg_sys::LogicalDecodingContext,
+    origin_id: pg_sys::RepOriginId,
+) -> bool {
+    plugin::<T>(ctx).filter_by_origin(origin_id)
+}
+
+#[cfg(not(any(feature = "pg12", feature = "pg13")))]
+#[pg_guard]
+unsafe extern "C" fn stream_start<T: OutputPlugin>(
+    ctx: *mut pg_sys::LogicalDecodingContext,
+    txn: *mut pg_sys::ReorderBufferTXN,
+) {
+    plugin::<T>(ctx).stream_start(&mut writer(ctx), &transaction(txn));
+}
+
+#[cfg(not(any(feature = "pg12", feature = "pg13")))]
+#[pg_guard]
+unsafe extern "C" fn stream_stop<T: OutputPlugin>(
+    ctx: *mut pg_sys::LogicalDecodingContext,
+    txn: *mut pg_sys::ReorderBufferTXN,
+) {
+    plugin::<T>(ctx).stream_stop(&mut writer(ctx), &transaction(txn));
+}
+
+#[cfg(not(any(feature = "pg12", feature = "pg13")))]
+#[pg_guard]
+unsafe extern "C" fn stream_abort<T: OutputPlugin>(
+    ctx: *mut pg_sys::LogicalDecodingContext,
+    txn: *mut pg_sys::ReorderBufferTXN,
+    abort_lsn: pg_sys::XLogRecPtr,
+) {
+    plugin::<T>(ctx).stream_abort(&mut writer(ctx), &transaction(txn), abort_lsn);
+}
+
+#[cfg(not(any(feature = "pg12", feature = "pg13")))]
+#[pg_guard]
+unsafe extern "C" fn stream_commit<T: OutputPlugin>(
+    ctx: *mut pg_sys::LogicalDecodingContext,
+    txn: *mut pg_sys::ReorderBufferTXN,
+    commit_lsn: pg_sys::XLogRecPtr,
+) {
+    plugin::<T>(ctx).stream_commit(&mut writer(ctx), &transaction(txn), commit_lsn);
+}
+
+#[cfg(not(any(feature = "pg12", feature = "pg13")))]
+#[pg_guard]
+unsafe extern "C" fn stream_change<T: OutputPlugin>(
+    ctx: *mut pg_sys::LogicalDecodingContext,
+    txn: *mut pg_sys::ReorderBufferTXN,
+    relation: pg_sys::Relation,
+    change: *mut pg_sys::ReorderBufferChange,
+) {
+    let relation = PgRelation::from_pg(relation);
+    let Some(change) = decode_change(&relation, change) else {
+        return;
+    };
+    plugin::<T>(ctx).stream_change(&mut writer(ctx), &transaction(txn), &relation, change);
+}
+
+#[cfg(not(any(feature = "pg12", feature = "pg13")))]
+#[pg_guard]
+unsafe extern "C" fn stream_truncate<T: OutputPlugin>(
+    ctx: *mut pg_sys::LogicalDecodingContext,
+    txn: *mut pg_sys::ReorderBufferTXN,
+    nrelations: c_int,
+    relations: *mut pg_sys::Relation,
+    change: *mut pg_sys::ReorderBufferChange,
+) {
+    let relations = truncated_relations(nrelations, relations);
+    let options = truncate_options(change);
+    plugin::<T>(ctx).stream_truncate(&mut writer(ctx), &transaction(txn), &relations, options);
+}
+
+#[cfg(not(any(feature = "pg12", feature = "pg13")))]
+#[pg_guard]
+unsafe extern "C" fn stream_message<T: OutputPlugin>(
+    ctx: *mut pg_sys::LogicalDecodingContext,
+    txn: *mut pg_sys::ReorderBufferTXN,
+    message_lsn: pg_sys::XLogRecPtr,
+    transactional: bool,
+    prefix: *const c_char,
+    message_size: pg_sys::Size,
+    message: *const c_char,
+) {
+    let message = decode_message(message_lsn, transactional, prefix, message_size, message);
+    plugin::<T>(ctx).stream_message(&mut writer(ctx), &transaction(txn), message);
+}
@@ -0,0 +1,292 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+/*!
+
+Fan work out to Postgres parallel workers
+
+A [`ParallelContextBuilder`] launches parallel workers on behalf of the current backend, the
+"leader".  Every worker receives the same serialized payload, runs an entry point exported by the
+extension, and sends a serialized result back to the leader.
+
+Parallel workers share the leader's transaction, snapshot, and settings, but they can't write to
+the database.  An `ERROR` raised in a worker, including a Rust panic, is re-raised in the leader
+as an [`ErrorReport`](crate::pg_sys::panic::ErrorReport).  The parallel context is left for
+transaction abort to clean up, so the leader stays in parallel mode until then: an `ERROR` that's
+caught, such as with [`PgTryBuilder`](crate::PgTryBuilder), must be caught within a subtransaction
+that's then rolled back, as a PL/pgSQL `EXCEPTION` block does.
+
+# Example
+
+```rust,no_run
+use pgrx::parallel::{ParallelContextBuilder, ParallelWorker};
+use pgrx::prelude::*;
+
+#[pg_extern]
+fn parallel_sum(n: i64) -> i64 {
+    let sums: Vec<i64> = ParallelContextBuilder::new("my_extension", "parallel_sum_worker")
+        .set_nworkers(4)
+        .set_payload(&n)
+        .run();
+    sums.into_iter().sum()
+}
+
+#[pg_guard]
+#[no_mangle]
+pub extern "C" fn parallel_sum_worker(seg: *mut pg_sys::dsm_segment, toc: *mut pg_sys::shm_toc) {
+    unsafe {
+        ParallelWorker::run(seg, toc, |n: i64, worker| {
+            let (number, nworkers) = (worker.worker_number() as i64, worker.nworkers() as i64);
+            (0..n).filter(|i| i % nworkers == number).sum::<i64>()
+        })
+    }
+}
+```
+
+Fewer workers than requested may be launched, or even none at all, if the server is out of
+worker slots.  The leader gets exactly one result per launched worker, in order of their
+[worker number](ParallelWorker::worker_number), and is responsible for any work the missing
+workers would have done.
+*/
+#![deny(unsafe_op_in_unsafe_fn)]
+use crate::pg_sys;
+use crate::shm_mq::{self, ShmMqError};
+use alloc::ffi::CString;
+use core::ffi::c_int;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// The TOC keys we store our shared state under.  Postgres' own keys all have the high bits set,
+/// so these can't collide with them
+const KEY_HEADER: u64 = 0x5047_5258_0000_0001;
+const KEY_PAYLOAD: u64 = 0x5047_5258_0000_0002;
+const KEY_QUEUES: u64 = 0x5047_5258_0000_0003;
+
+/// The default size of the queue each worker sends its result through
+const DEFAULT_QUEUE_SIZE: usize = 65536;
+
+#[repr(C)]
+struct Header {
+    nworkers: usize,
+    payload_len: usize,
+    queue_size: usize,
+}
+
+/// A builder-style interface for launching parallel workers from the current backend
+///
+/// This must be used inside a transaction, and not from within a parallel worker.
+pub struct ParallelContextBuilder {
+    library_name: CString,
+    function_name: CString,
+    nworkers: usize,
+    payload: Vec<u8>,
+    queue_size: usize,
+}
+
+impl ParallelContextBuilder {
+    /// Run the `extern "C" fn(*mut pg_sys::dsm_segment, *mut pg_sys::shm_toc)` named `function_name`,
+    /// from the shared library `library_name`, in each worker.  It must call [`ParallelWorker::run`]
+    pub fn new(library_name: &str, function_name: &str) -> Self {
+        ParallelContextBuilder {
+            library_name: CString::new(library_name).expect("library name contains a null byte"),
+            function_name: CString::new(function_name).expect("function name contains a null byte"),
+            nworkers: 1,
+            payload: Vec::new(),
+            queue_size: DEFAULT_QUEUE_SIZE,
+        }
+    }
+
+    /// How many workers to request.  Defaults to 1
+    pub fn set_nworkers(mut self, nworkers: usize) -> Self {
+        self.nworkers = nworkers;
+        self
+    }
+
+    /// The value each worker receives.  Defaults to `()`
+    pub fn set_payload<P: Serialize>(mut self, payload: &P) -> Self {
+        self.payload =
+            serde_cbor::to_vec(payload).expect("could not encode the parallel worker payload");
+        self
+    }
+
+    /// The size, in bytes, of the queue each worker sends its result through.  Results larger
+    /// than this are streamed through the queue in pieces.  Defaults to 64kB
+    ///
+    /// The queues are laid out one after the other, so the size is rounded up to a multiple of
+    /// `MAXIMUM_ALIGNOF`.
+    pub fn set_queue_size(mut self, queue_size: usize) -> Self {
+        self.queue_size = queue_size
+            .max(unsafe { pg_sys::shm_mq_minimum_size })
+            .next_multiple_of(pg_sys::MAXIMUM_ALIGNOF as usize);
+        self
+    }
+
+    /// Launch the workers, and wait for each of them to return its result
+    ///
+    /// # Panics
+    ///
+    /// Re-raises any `ERROR` raised in a worker.  Also panics if a worker exits without returning
+    /// a result, or its result can't be decoded as an `R`
+    pub fn run<R: DeserializeOwned>(self) -> Vec<R> {
+        let nworkers = c_int::try_from(self.nworkers).expect("too many parallel workers");
+        let payload =
+            if self.payload.is_empty() { serde_cbor::to_vec(&()).unwrap() } else { self.payload };
+        let queues_len = self.queue_size.checked_mul(self.nworkers).expect("queues are too large");
+
+        unsafe {
+            pg_sys::EnterParallelMode();
+            let pcxt = ParallelContext(pg_sys::CreateParallelContext(
+                self.library_name.as_ptr(),
+                self.function_name.as_ptr(),
+                nworkers,
+            ));
+
+            let estimator = &mut (*pcxt.0).estimator;
+            estimate_chunk(estimator, std::mem::size_of::<Header>());
+            estimate_chunk(estimator, payload.len());
+            estimate_chunk(estimator, queues_len);
+            estimator.number_of_keys += 3;
+            pg_sys::InitializeParallelDSM(pcxt.0);
+
+            let toc = (*pcxt.0).toc;
+            let header =
+                pg_sys::shm_toc_allocate(toc, std::mem::size_of::<Header>()).cast::<Header>();
+            header.write(Header {
+                nworkers: self.nworkers,
+                payload_len: payload.len(),
+                queue_size: self.queue_size,
+            });
+            pg_sys::shm_toc_insert(toc, KEY_HEADER, header.cast());
+
+            let shared_payload = pg_sys::shm_toc_allocate(toc, payload.len()).cast::<u8>();
+            shared_payload.copy_from_nonoverlapping(payload.as_ptr(), payload.len());
+            pg_sys::shm_toc_insert(toc, KEY_PAYLOAD, shared_payload.cast());
+
+            let queues = pg_sys::shm_toc_allocate(toc, queues_len).cast::<u8>();
+            for i in 0..self.nworkers {
+                let mq =
+                    pg_sys::shm_mq_create(queues.add(i * self.queue_size).cast(), self.queue_size);
+                pg_sys::shm_mq_set_receiver(mq, pg_sys::MyProc);
+            }
+            pg_sys::shm_toc_insert(toc, KEY_QUEUES, queues.cast());
+
+            pg_sys::LaunchParallelWorkers(pcxt.0);
+
+            let launched = (*pcxt.0).nworkers_launched as usize;
+            let mut results = Vec::with_capacity(launched);
+            for i in 0..launched {
+                let mq = queues.add(i * self.queue_size).cast();
+                let mqh = pg_sys::shm_mq_attach(mq, (*pcxt.0).seg, std::ptr::null_mut());
+                pg_sys::shm_mq_set_handle(mqh, (*(*pcxt.0).worker.add(i)).bgwhandle);
+
+                let result = match shm_mq::receive(mqh, false) {
+                    Ok((data, len)) => serde_cbor::from_slice(shm_mq::message(data, len))
+                        .unwrap_or_else(|e| {
+                            panic!("could not decode parallel worker {i}'s result: {e}")
+                        }),
+                    Err(_) => {
+                        // if the worker exited because of an error, this re-raises it
+                        pg_sys::WaitForParallelWorkersToFinish(pcxt.0);
+                        panic!("parallel worker {i} exited without returning a result")
+                    }
+                };
+                pg_sys::shm_mq_detach(mqh);
+                results.push(result);
+            }
+
+            pg_sys::WaitForParallelWorkersToFinish(pcxt.0);
+            drop(pcxt);
+            results
+        }
+    }
+}
+
+/// Owns a `ParallelContext`, and the parallel mode it was created in
+struct ParallelContext(*mut pg_sys::ParallelContext);
+
+impl Drop for ParallelContext {
+    fn drop(&mut self) {
+        // When unwinding, leave it to transaction abort to destroy the context and leave parallel
+        // mode.  Doing it ourselves would wait for the workers, which could raise another ERROR
+        if std::thread::panicking() {
+            return;
+        }
+        unsafe {
+            pg_sys::DestroyParallelContext(self.0);
+            pg_sys::ExitParallelMode();
+        }
+    }
+}
+
+/// Equivalent to Postgres' `shm_toc_estimate_chunk()` macro
+fn estimate_chunk(estimator: &mut pg_sys::shm_toc_estimator, size: usize) {
+    let align = pg_sys::ALIGNOF_BUFFER as usize;
+    estimator.space_for_chunks += size.next_multiple_of(align);
+}
+
+/// The current parallel worker, as seen from within its entry point
+#[derive(Debug)]
+pub struct ParallelWorker {
+    worker_number: usize,
+    nworkers: usize,
+}
+
+impl ParallelWorker {
+    /// Decode the payload sent by the leader, call `f` with it, and send the result back to the
+    /// leader.  This is meant to be the entire body of a worker's entry point
+    ///
+    /// # Safety
+    ///
+    /// `seg` and `toc` must be the arguments Postgres passed to the entry point of a worker
+    /// launched by [`ParallelContextBuilder::run`]
+    pub unsafe fn run<P, R, F>(seg: *mut pg_sys::dsm_segment, toc: *mut pg_sys::shm_toc, f: F)
+    where
+        P: DeserializeOwned,
+        R: Serialize,
+        F: FnOnce(P, &ParallelWorker) -> R,
+    {
+        unsafe {
+            let header = &*pg_sys::shm_toc_lookup(toc, KEY_HEADER, false).cast::<Header>();
+            let payload = pg_sys::shm_toc_lookup(toc, KEY_PAYLOAD, false).cast::<u8>();
+            let queues = pg_sys::shm_toc_lookup(toc, KEY_QUEUES, false).cast::<u8>();
+
+            let worker = ParallelWorker {
+                worker_number: pg_sys::ParallelWorkerNumber as usize,
+                nworkers: header.nworkers,
+            };
+            let payload = std::slice::from_raw_parts(payload, header.payload_len);
+            let payload = serde_cbor::from_slice(payload)
+                .expect("could not decode the parallel worker payload");
+
+            let result = f(payload, &worker);
+            let result =
+                serde_cbor::to_vec(&result).expect("could not encode the parallel worker result");
+
+            let mq = queues.add(worker.worker_number * header.queue_size).cast::<pg_sys::shm_mq>();
+            pg_sys::shm_mq_set_sender(mq, pg_sys::MyProc);
+            let mqh = pg_sys::shm_mq_attach(mq, seg, std::ptr::null_mut());
+            match shm_mq::send(mqh, &result, false) {
+                // the leader only detaches early when it's erroring out itself
+                Ok(()) | Err(ShmMqError::Detached) => {}
+                Err(e) => panic!("could not send the parallel worker result: {e}"),
+            }
+            pg_sys::shm_mq_detach(mqh);
+        }
+    }
+
+    /// This worker's number, from `0` up to the number of workers launched
+    pub fn worker_number(&self) -> usize {
+        self.worker_number
+    }
+
+    /// The number of workers the leader requested.  Not all of them are necessarily launched
+    pub fn nworkers(&self) -> usize {
+        self.nworkers
+    }
+}
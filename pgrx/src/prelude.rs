@@ -41,12 +41,29 @@ pub use crate::{
 
 // Trigger support
 pub use crate::trigger_support::{
-    PgTrigger, PgTriggerError, PgTriggerLevel, PgTriggerOperation, PgTriggerWhen,
+    PgEventTrigger, PgEventTriggerEvent, PgTrigger, PgTriggerError, PgTriggerLevel,
+    PgTriggerOperation, PgTriggerWhen,
 };
 
 // Aggregate support
 pub use crate::aggregate::{Aggregate, FinalizeModify, ParallelOption};
 
+// Custom scan support
+pub use crate::custom_scan::CustomScan;
+
+// Foreign data wrapper support
+pub use crate::fdw::ForeignDataWrapper;
+
+// Index access method support
+pub use crate::index_am::IndexAccessMethod;
+
+// Table access method support
+pub use crate::table_am::TableAccessMethod;
+
+// Logical decoding output plugin support
+pub use crate::output_plugin::OutputPlugin;
+pub use crate::pg_output_plugin;
+
 pub use crate::pg_sys::oids::PgOid;
 pub use crate::pg_sys::pg_try::PgTryBuilder;
 pub use crate::pg_sys::utils::name_data_to_str;
@@ -0,0 +1,243 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+/*!
+
+Map Rust structs to and from rows
+
+[`#[derive(FromRow)]`](macro@FromRow) reads a struct out of a row, be that a row of an SPI result
+or a composite type's [`PgHeapTuple`].  [`#[derive(IntoRow)]`](macro@IntoRow) writes one into a
+composite type's [`PgHeapTuple`].
+
+The fields of a struct with named fields map to the columns of the same name, and the fields of a
+tuple struct map to the columns in the same position.  A field's column is looked up once, when
+the first row is read, rather than for every row.
+
+```rust,no_run
+use pgrx::prelude::*;
+use pgrx::row::FromRow;
+
+#[derive(FromRow)]
+struct Dog {
+    name: String,
+    // a NULL `age` is `None`.  A NULL in any other column is an error
+    age: Option<i32>,
+    // reads the `scritches` column
+    #[row(rename = "scritches")]
+    scritch_count: i64,
+    // `0` if there's no `treats` column, or it's NULL
+    #[row(default)]
+    treats: i32,
+}
+
+let dogs = Spi::connect(|client| {
+    client
+        .select("SELECT name, age, scritches FROM dogs", None, &[])?
+        .rows::<Dog>()?
+        .collect::<Result<Vec<Dog>, _>>()
+});
+```
+
+The field attributes are:
+
+- `#[row(rename = "column")]`: read the column with this name, rather than the field's name
+- `#[row(position = 2)]`: read the column in this (1-based) position
+- `#[row(default)]` or `#[row(default = "path::to::fn")]`: use this value when the column is
+  missing or `NULL`
+- `#[row(skip)]`: don't read or write this field, and use its [`Default`] value
+*/
+use crate::datum::{FromDatum, IntoDatum, TryFromDatumError};
+use crate::heap_tuple::{PgHeapTuple, PgHeapTupleError};
+use crate::pgbox::AllocatedByRust;
+use crate::tupdesc::PgTupleDesc;
+use core::fmt::{Display, Formatter};
+use std::num::NonZeroUsize;
+
+pub use pgrx_macros::{FromRow, IntoRow};
+
+/// Where in a row a field's value comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnRef {
+    /// The column of this name
+    Name(&'static str),
+    /// The column in this 1-based position
+    Position(usize),
+}
+
+impl Display for ColumnRef {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ColumnRef::Name(name) => write!(f, "`{name}`"),
+            ColumnRef::Position(position) => write!(f, "{position}"),
+        }
+    }
+}
+
+/// The column of one of a struct's fields, as described by `#[derive(FromRow)]` or
+/// `#[derive(IntoRow)]`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RowColumn {
+    pub column: ColumnRef,
+    /// Does the field have a default to fall back to if the column is missing?
+    pub has_default: bool,
+}
+
+impl RowColumn {
+    pub const fn name(name: &'static str, has_default: bool) -> Self {
+        RowColumn { column: ColumnRef::Name(name), has_default }
+    }
+
+    pub const fn position(position: usize, has_default: bool) -> Self {
+        RowColumn { column: ColumnRef::Position(position), has_default }
+    }
+}
+
+/// Problems mapping a struct to or from a row
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum RowError {
+    #[error("column {0} is not present")]
+    NoSuchColumn(ColumnRef),
+
+    #[error("column {0} is NULL")]
+    UnexpectedNull(ColumnRef),
+
+    #[error(transparent)]
+    DatumError(#[from] TryFromDatumError),
+
+    #[error(transparent)]
+    HeapTupleError(#[from] PgHeapTupleError),
+}
+
+/// A type that can be read out of a row
+///
+/// This is meant to be derived with [`#[derive(FromRow)]`](macro@FromRow).
+pub trait FromRow: Sized {
+    /// The column of each field that's read, in order
+    const COLUMNS: &'static [RowColumn];
+
+    /// Read a `Self` out of `row`, where the value of each of [`Self::COLUMNS`] is found by its
+    /// index into them
+    fn from_row<S: RowSource>(row: &RowReader<'_, S>) -> Result<Self, RowError>;
+}
+
+/// A type that can be written into a row
+///
+/// This is meant to be derived with [`#[derive(IntoRow)]`](macro@IntoRow).
+pub trait IntoRow {
+    /// The column of each field that's written, in order
+    const COLUMNS: &'static [RowColumn];
+
+    /// Write `self` into `row`, where the column of each of [`Self::COLUMNS`] is found by its
+    /// index into them
+    fn into_row(self, row: &mut RowWriter<'_, '_>) -> Result<(), RowError>;
+}
+
+/// Something rows can be read from, such as an SPI result row or a [`PgHeapTuple`]
+pub trait RowSource {
+    /// The value in the column at the 1-based `ordinal`, or `None` if it's NULL
+    fn value<T: FromDatum + IntoDatum>(
+        &self,
+        ordinal: NonZeroUsize,
+    ) -> Result<Option<T>, TryFromDatumError>;
+}
+
+/// The columns of a row that a struct's fields map to, looked up in its tuple descriptor
+#[derive(Debug, Clone)]
+pub struct RowColumns {
+    columns: &'static [RowColumn],
+    // `None` for a missing column of a field with a default
+    ordinals: Vec<Option<NonZeroUsize>>,
+}
+
+impl RowColumns {
+    /// Look up the ordinal of each of `columns` in `tupdesc`
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RowError::NoSuchColumn`] if a column without a default is missing
+    pub fn resolve(
+        columns: &'static [RowColumn],
+        tupdesc: &PgTupleDesc<'_>,
+    ) -> Result<Self, RowError> {
+        let ordinals = columns
+            .iter()
+            .map(|column| {
+                let ordinal = match column.column {
+                    ColumnRef::Name(name) => tupdesc
+                        .iter()
+                        .position(|att| !att.attisdropped && att.name() == name)
+                        .map(|i| i + 1),
+                    ColumnRef::Position(position) => tupdesc
+                        .get(position.wrapping_sub(1))
+                        .filter(|att| !att.attisdropped)
+                        .map(|_| position),
+                };
+                match ordinal.and_then(NonZeroUsize::new) {
+                    None if !column.has_default => Err(RowError::NoSuchColumn(column.column)),
+                    ordinal => Ok(ordinal),
+                }
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(RowColumns { columns, ordinals })
+    }
+
+    /// Read a `T` out of `source`.  These must have been resolved from `T::COLUMNS`
+    pub fn read<T: FromRow, S: RowSource>(&self, source: &S) -> Result<T, RowError> {
+        debug_assert_eq!(self.columns, T::COLUMNS);
+        T::from_row(&RowReader { source, columns: self })
+    }
+}
+
+/// A row being read by [`FromRow::from_row`]
+pub struct RowReader<'a, S> {
+    source: &'a S,
+    columns: &'a RowColumns,
+}
+
+impl<S: RowSource> RowReader<'_, S> {
+    /// The value of the `index`th column, or `None` if it's NULL or missing
+    pub fn get<T: FromDatum + IntoDatum>(&self, index: usize) -> Result<Option<T>, RowError> {
+        match self.columns.ordinals[index] {
+            Some(ordinal) => Ok(self.source.value(ordinal)?),
+            None => Ok(None),
+        }
+    }
+
+    /// The value of the `index`th column
+    ///
+    /// # Errors
+    ///
+    /// Returns [`RowError::UnexpectedNull`] if it's NULL
+    pub fn get_required<T: FromDatum + IntoDatum>(&self, index: usize) -> Result<T, RowError> {
+        self.get(index)?.ok_or(RowError::UnexpectedNull(self.columns.columns[index].column))
+    }
+}
+
+/// A row being written by [`IntoRow::into_row`]
+pub struct RowWriter<'a, 'mcx> {
+    tuple: &'a mut PgHeapTuple<'mcx, AllocatedByRust>,
+    columns: RowColumns,
+}
+
+impl<'a, 'mcx> RowWriter<'a, 'mcx> {
+    pub(crate) fn new(
+        tuple: &'a mut PgHeapTuple<'mcx, AllocatedByRust>,
+        columns: RowColumns,
+    ) -> Self {
+        RowWriter { tuple, columns }
+    }
+
+    /// Set the `index`th column to `value`.  Does nothing if the column is missing
+    pub fn set<T: IntoDatum>(&mut self, index: usize, value: T) -> Result<(), RowError> {
+        match self.columns.ordinals[index] {
+            Some(ordinal) => Ok(self.tuple.set_by_index(ordinal, value)?),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,341 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+/*!
+
+Shared memory message queues, for streaming data between backends and background workers
+
+A [`ShmMq`] is a single-reader, single-writer ring buffer placed inside a [`DsmSegment`].  One
+backend attaches to it as a [`ShmMqSender`] and another as a [`ShmMqReceiver`], after which
+messages of any length can be sent through it.  When either side detaches, including because its
+process exited, the other side's pending and future operations return [`ShmMqError::Detached`].
+
+The blocking operations integrate with [`BackgroundWorker::wait_latch`]: when called from a
+background worker, they wait on the worker's latch and return [`ShmMqError::Terminated`] once
+the worker has been asked to shut down.
+
+# Example
+
+```rust,no_run
+use pgrx::bgworkers::BackgroundWorkerBuilder;
+use pgrx::dsm::DsmSegment;
+use pgrx::prelude::*;
+use pgrx::shm_mq::ShmMq;
+
+let segment = DsmSegment::create(65536);
+let mut receiver = ShmMq::create(&segment, 0, segment.len()).attach_receiver();
+
+let worker = BackgroundWorkerBuilder::new("my worker")
+    .set_library("my_extension")
+    .set_function("my_worker_main")
+    .set_argument(segment.handle().into_datum())
+    .enable_shmem_access(None)
+    .load_dynamic()
+    .expect("could not start worker");
+receiver.set_worker(&worker);
+
+// and in `my_worker_main()`, after attaching to the segment by its handle:
+//   let mut sender = unsafe { ShmMq::from_segment(&segment, 0) }.attach_sender();
+//   sender.send_value(&vec![1, 2, 3])?;
+
+let results: Vec<i32> = receiver.receive_value().expect("worker detached");
+```
+*/
+#![deny(unsafe_op_in_unsafe_fn)]
+use crate::bgworkers::{BackgroundWorker, DynamicBackgroundWorker};
+use crate::dsm::DsmSegment;
+use crate::{pg_sys, PgMemoryContexts};
+use core::ffi::c_void;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::ptr::NonNull;
+
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ShmMqError {
+    #[error("the other end of the message queue has detached")]
+    Detached,
+    #[error("the message queue operation would block")]
+    WouldBlock,
+    #[error("the background worker was asked to terminate")]
+    Terminated,
+    #[error("could not encode message: {0}")]
+    Encode(String),
+    #[error("could not decode message: {0}")]
+    Decode(String),
+}
+
+/// A message queue inside a [`DsmSegment`] that hasn't been attached to yet
+pub struct ShmMq<'seg> {
+    mq: NonNull<pg_sys::shm_mq>,
+    segment: &'seg DsmSegment,
+}
+
+impl<'seg> ShmMq<'seg> {
+    /// The smallest `size` a queue can be created with
+    pub fn minimum_size() -> usize {
+        unsafe { pg_sys::shm_mq_minimum_size }
+    }
+
+    /// Create a queue occupying the `size` bytes at `offset` within `segment`
+    ///
+    /// # Panics
+    ///
+    /// If the queue doesn't fit in the segment, is smaller than [`ShmMq::minimum_size()`], or if
+    /// `offset` isn't `MAXALIGN`ed
+    pub fn create(segment: &'seg DsmSegment, offset: usize, size: usize) -> Self {
+        assert!(size >= Self::minimum_size(), "message queue of {size} bytes is too small");
+        assert!(
+            offset.checked_add(size).is_some_and(|end| end <= segment.len()),
+            "message queue does not fit in the segment"
+        );
+        assert_eq!(
+            offset % pg_sys::MAXIMUM_ALIGNOF as usize,
+            0,
+            "message queue offset must be MAXALIGNed"
+        );
+        let mq = unsafe { pg_sys::shm_mq_create(segment.as_ptr().add(offset).cast(), size) };
+        ShmMq { mq: NonNull::new(mq).expect("shm_mq_create returned null"), segment }
+    }
+
+    /// Locate the queue created at `offset` within `segment`, by this or another backend
+    ///
+    /// # Safety
+    ///
+    /// A queue must have been created with [`ShmMq::create`] at `offset`
+    pub unsafe fn from_segment(segment: &'seg DsmSegment, offset: usize) -> Self {
+        let mq = unsafe { segment.as_ptr().add(offset).cast() };
+        ShmMq { mq: NonNull::new(mq).expect("segment address should not be null"), segment }
+    }
+
+    /// Attach to the queue as its sender.  Each queue has exactly one sender, ever
+    pub fn attach_sender(self) -> ShmMqSender<'seg> {
+        unsafe { pg_sys::shm_mq_set_sender(self.mq.as_ptr(), pg_sys::MyProc) };
+        ShmMqSender { handle: self.attach() }
+    }
+
+    /// Attach to the queue as its receiver.  Each queue has exactly one receiver, ever
+    pub fn attach_receiver(self) -> ShmMqReceiver<'seg> {
+        unsafe { pg_sys::shm_mq_set_receiver(self.mq.as_ptr(), pg_sys::MyProc) };
+        ShmMqReceiver { handle: self.attach() }
+    }
+
+    fn attach(self) -> ShmMqHandle<'seg> {
+        // the handle, and the buffer it reassembles messages in, must outlive the transaction
+        let mqh = unsafe {
+            PgMemoryContexts::TopMemoryContext.switch_to(|_| {
+                pg_sys::shm_mq_attach(self.mq.as_ptr(), self.segment.as_raw(), std::ptr::null_mut())
+            })
+        };
+        ShmMqHandle {
+            mqh: NonNull::new(mqh).expect("shm_mq_attach returned null"),
+            _segment: self.segment,
+        }
+    }
+}
+
+/// The state both ends of an attached queue share
+struct ShmMqHandle<'seg> {
+    mqh: NonNull<pg_sys::shm_mq_handle>,
+    _segment: &'seg DsmSegment,
+}
+
+impl ShmMqHandle<'_> {
+    fn set_worker(&mut self, worker: &DynamicBackgroundWorker) {
+        unsafe { pg_sys::shm_mq_set_handle(self.mqh.as_ptr(), worker.handle) }
+    }
+
+    fn wait_for_attach(&mut self) -> Result<(), ShmMqError> {
+        let mqh = self.mqh.as_ptr();
+        if in_background_worker() {
+            // `shm_mq_wait_for_attach()` has no non-blocking form, so poll the queue ourselves
+            let mq = unsafe { pg_sys::shm_mq_get_queue(mqh) };
+            loop {
+                let (sender, receiver) =
+                    unsafe { (pg_sys::shm_mq_get_sender(mq), pg_sys::shm_mq_get_receiver(mq)) };
+                if !sender.is_null() && !receiver.is_null() {
+                    return Ok(());
+                }
+                wait_latch()?;
+            }
+        }
+        result(unsafe { pg_sys::shm_mq_wait_for_attach(mqh) })
+    }
+
+    /// Run a non-blocking operation until it stops returning [`ShmMqError::WouldBlock`]
+    ///
+    /// Outside of a background worker, `op` is simply called once with `nowait` set to `false`
+    fn blocking<R>(
+        &mut self,
+        mut op: impl FnMut(*mut pg_sys::shm_mq_handle, bool) -> Result<R, ShmMqError>,
+    ) -> Result<R, ShmMqError> {
+        if !in_background_worker() {
+            return op(self.mqh.as_ptr(), false);
+        }
+        loop {
+            match op(self.mqh.as_ptr(), true) {
+                Err(ShmMqError::WouldBlock) => wait_latch()?,
+                result => return result,
+            }
+        }
+    }
+}
+
+impl Drop for ShmMqHandle<'_> {
+    fn drop(&mut self) {
+        // this wakes up the other side, which will find the queue detached
+        unsafe { pg_sys::shm_mq_detach(self.mqh.as_ptr()) }
+    }
+}
+
+/// The sending end of a [`ShmMq`]
+///
+/// Dropping the sender detaches from the queue.  The receiver can still receive any messages
+/// that were fully sent, after which it will see [`ShmMqError::Detached`].
+pub struct ShmMqSender<'seg> {
+    handle: ShmMqHandle<'seg>,
+}
+
+impl ShmMqSender<'_> {
+    /// Associate the queue with the background worker that will receive from it, so blocking
+    /// operations fail with [`ShmMqError::Detached`] if the worker exits before attaching
+    pub fn set_worker(&mut self, worker: &DynamicBackgroundWorker) {
+        self.handle.set_worker(worker)
+    }
+
+    /// Block until the receiver has attached to the queue
+    pub fn wait_for_attach(&mut self) -> Result<(), ShmMqError> {
+        self.handle.wait_for_attach()
+    }
+
+    /// Send `message`, blocking until it has been entirely written into the queue
+    pub fn send(&mut self, message: &[u8]) -> Result<(), ShmMqError> {
+        self.handle.blocking(|mqh, nowait| send(mqh, message, nowait))
+    }
+
+    /// Send `message` without blocking
+    ///
+    /// Returns [`ShmMqError::WouldBlock`] if the queue filled up or the receiver hasn't attached
+    /// yet.  The message may have been partially written, and the next call to `try_send()` or
+    /// [`send()`](ShmMqSender::send) **must** be made with the same message.
+    pub fn try_send(&mut self, message: &[u8]) -> Result<(), ShmMqError> {
+        send(self.handle.mqh.as_ptr(), message, true)
+    }
+
+    /// Serialize `value` and [send](ShmMqSender::send) it, to be decoded with
+    /// [`ShmMqReceiver::receive_value()`]
+    pub fn send_value<T: Serialize>(&mut self, value: &T) -> Result<(), ShmMqError> {
+        let message = serde_cbor::to_vec(value).map_err(|e| ShmMqError::Encode(e.to_string()))?;
+        self.send(&message)
+    }
+}
+
+/// The receiving end of a [`ShmMq`]
+///
+/// Dropping the receiver detaches from the queue, after which the sender's operations will fail
+/// with [`ShmMqError::Detached`].
+pub struct ShmMqReceiver<'seg> {
+    handle: ShmMqHandle<'seg>,
+}
+
+impl ShmMqReceiver<'_> {
+    /// Associate the queue with the background worker that will send to it, so blocking
+    /// operations fail with [`ShmMqError::Detached`] if the worker exits before attaching
+    pub fn set_worker(&mut self, worker: &DynamicBackgroundWorker) {
+        self.handle.set_worker(worker)
+    }
+
+    /// Block until the sender has attached to the queue
+    pub fn wait_for_attach(&mut self) -> Result<(), ShmMqError> {
+        self.handle.wait_for_attach()
+    }
+
+    /// Block until the next message arrives.  The message is only valid until the next call to
+    /// receive from this queue
+    pub fn receive(&mut self) -> Result<&[u8], ShmMqError> {
+        let (data, len) = self.handle.blocking(receive)?;
+        Ok(unsafe { message(data, len) })
+    }
+
+    /// Receive the next message without blocking
+    ///
+    /// Returns [`ShmMqError::WouldBlock`] if the message hasn't arrived yet, or has only partly
+    /// arrived
+    pub fn try_receive(&mut self) -> Result<&[u8], ShmMqError> {
+        let (data, len) = receive(self.handle.mqh.as_ptr(), true)?;
+        Ok(unsafe { message(data, len) })
+    }
+
+    /// [Receive](ShmMqReceiver::receive) the next message, and deserialize it from the encoding
+    /// used by [`ShmMqSender::send_value()`]
+    pub fn receive_value<T: DeserializeOwned>(&mut self) -> Result<T, ShmMqError> {
+        let message = self.receive()?;
+        serde_cbor::from_slice(message).map_err(|e| ShmMqError::Decode(e.to_string()))
+    }
+}
+
+pub(crate) fn send(
+    mqh: *mut pg_sys::shm_mq_handle,
+    message: &[u8],
+    nowait: bool,
+) -> Result<(), ShmMqError> {
+    let data = message.as_ptr().cast::<c_void>();
+    result(unsafe {
+        #[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14"))]
+        {
+            pg_sys::shm_mq_send(mqh, message.len(), data, nowait)
+        }
+        #[cfg(any(feature = "pg15", feature = "pg16", feature = "pg17"))]
+        {
+            // wake the receiver right away, as older versions always did
+            pg_sys::shm_mq_send(mqh, message.len(), data, nowait, true)
+        }
+    })
+}
+
+pub(crate) fn receive(
+    mqh: *mut pg_sys::shm_mq_handle,
+    nowait: bool,
+) -> Result<(*mut c_void, usize), ShmMqError> {
+    let mut len = 0;
+    let mut data = std::ptr::null_mut();
+    result(unsafe { pg_sys::shm_mq_receive(mqh, &mut len, &mut data, nowait) })?;
+    Ok((data, len))
+}
+
+/// # Safety
+///
+/// `data` and `len` must have come from `shm_mq_receive()`
+pub(crate) unsafe fn message<'a>(data: *mut c_void, len: usize) -> &'a [u8] {
+    if len == 0 {
+        &[]
+    } else {
+        unsafe { std::slice::from_raw_parts(data.cast(), len) }
+    }
+}
+
+pub(crate) fn result(result: pg_sys::shm_mq_result::Type) -> Result<(), ShmMqError> {
+    match result {
+        pg_sys::shm_mq_result::SHM_MQ_SUCCESS => Ok(()),
+        pg_sys::shm_mq_result::SHM_MQ_WOULD_BLOCK => Err(ShmMqError::WouldBlock),
+        pg_sys::shm_mq_result::SHM_MQ_DETACHED => Err(ShmMqError::Detached),
+        other => panic!("unrecognized shm_mq_result: {other}"),
+    }
+}
+
+fn in_background_worker() -> bool {
+    unsafe { !pg_sys::MyBgworkerEntry.is_null() }
+}
+
+fn wait_latch() -> Result<(), ShmMqError> {
+    if BackgroundWorker::wait_latch(None) {
+        Ok(())
+    } else {
+        Err(ShmMqError::Terminated)
+    }
+}
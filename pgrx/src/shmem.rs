@@ -24,7 +24,8 @@ pub unsafe trait PGRXSharedMemory {}
 /// > Types that allocate on the heap, such as `String` and `Vec` are not supported.
 ///
 /// For complex data structures like vecs and maps, `pgrx` prefers the use of types from
-/// [`heapless`](https://crates.io/crates/heapless).
+/// [`heapless`](https://crates.io/crates/heapless).  Data whose size isn't known until runtime
+/// can instead be allocated from dynamic shared memory, with the [`dsm`](crate::dsm) module.
 ///
 /// Custom types need to also implement the `PGRXSharedMemory` trait.
 ///
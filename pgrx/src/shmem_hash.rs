@@ -0,0 +1,764 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+/*!
+
+Hash tables that live in shared memory
+
+[`PgSharedHashMap`] is built on Postgres' `ShmemInitHash()`.  Its capacity is fixed when the
+server starts, and it is set up with [`pg_shmem_init!`](crate::pg_shmem_init) like the other
+shared memory types.
+
+[`PgDshashMap`] is built on Postgres' `dshash`, and lives in a [`DsaArea`].  It grows as needed
+and can be created at any time, so it doesn't require `shared_preload_libraries`.
+
+Both are divided into partitions, each with its own lock, so backends working with keys in
+different partitions don't contend with each other.  Looking up an entry returns a guard that
+holds its partition's lock until it is dropped.  Holding more than one guard at a time can
+deadlock, if the entries happen to be in the same partition.
+*/
+#![deny(unsafe_op_in_unsafe_fn)]
+use crate as pgrx; // for #[pg_guard] support from within ourself
+use crate::dsm::DsaArea;
+use crate::lwlock::release_unless_elog_unwinding;
+use crate::{pg_guard, pg_sys, PGRXSharedMemory, PgSharedMemoryInitialization};
+use core::ffi::{c_int, c_void, CStr};
+use once_cell::sync::OnceCell;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::ptr::NonNull;
+use uuid::Uuid;
+
+#[derive(thiserror::Error, Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PgSharedHashMapError {
+    #[error("shared memory hash table is full")]
+    HashTableFull,
+}
+
+/// Both Postgres hash table implementations require the key to be at the start of the entry
+#[repr(C)]
+struct Entry<K, V> {
+    key: K,
+    value: V,
+}
+
+/// The number of partitions, and partition locks, a [`PgSharedHashMap`] is divided into
+const NUM_PARTITIONS: usize = 16;
+
+/// A fixed-capacity hash table in shared memory
+///
+/// Like [`PgLwLock`](crate::PgLwLock), this must be a `static` that is passed to
+/// `pg_shmem_init!()` during `_PG_init()`, and the extension must be loaded via
+/// `shared_preload_libraries`.
+///
+/// Keys are hashed with their [`Hash`] implementation, which must be the same in every backend.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use pgrx::prelude::*;
+/// use pgrx::{pg_shmem_init, PgSharedHashMap, PgSharedMemoryInitialization};
+///
+/// static COUNTERS: PgSharedHashMap<i64, u64> = PgSharedHashMap::new(1024);
+///
+/// #[pg_guard]
+/// pub extern "C" fn _PG_init() {
+///     pg_shmem_init!(COUNTERS);
+/// }
+///
+/// fn increment(key: i64) {
+///     *COUNTERS.get_or_insert_with(key, || 0).expect("too many counters") += 1;
+/// }
+/// ```
+pub struct PgSharedHashMap<K, V> {
+    capacity: usize,
+    name: OnceCell<&'static CStr>,
+    inner: OnceCell<SharedHashMapInner>,
+    _marker: PhantomData<Entry<K, V>>,
+}
+
+struct SharedHashMapInner {
+    htab: *mut pg_sys::HTAB,
+    locks: *mut pg_sys::LWLockPadded,
+}
+
+unsafe impl Send for SharedHashMapInner {}
+unsafe impl Sync for SharedHashMapInner {}
+
+unsafe impl<K: Send, V: Send> Send for PgSharedHashMap<K, V> {}
+unsafe impl<K: Send + Sync, V: Send + Sync> Sync for PgSharedHashMap<K, V> {}
+
+impl<K, V> PgSharedHashMap<K, V>
+where
+    K: PGRXSharedMemory + Eq + Hash,
+    V: PGRXSharedMemory,
+{
+    /// Create an empty map, to be attached to shared memory by `pg_shmem_init!()`, that can hold
+    /// `capacity` entries
+    pub const fn new(capacity: usize) -> Self {
+        PgSharedHashMap {
+            capacity,
+            name: OnceCell::new(),
+            inner: OnceCell::new(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// The number of entries the map was sized for
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// The number of entries currently in the map
+    pub fn len(&self) -> usize {
+        unsafe { pg_sys::hash_get_num_entries(self.inner().htab) as usize }
+    }
+
+    /// Is the map empty?
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Look up `key`, holding a shared lock on its partition while the returned guard is alive
+    pub fn get(&self, key: &K) -> Option<PgSharedHashMapRef<'_, V>> {
+        let hashcode = self.hash(key);
+        let lock = self.lock(hashcode, pg_sys::LWLockMode::LW_SHARED);
+        let entry = self.search(key, hashcode, pg_sys::HASHACTION::HASH_FIND)?;
+        Some(PgSharedHashMapRef { value: unsafe { &(*entry).value }, _lock: lock })
+    }
+
+    /// Look up `key`, holding an exclusive lock on its partition while the returned guard is
+    /// alive
+    pub fn get_mut(&self, key: &K) -> Option<PgSharedHashMapRefMut<'_, V>> {
+        let hashcode = self.hash(key);
+        let lock = self.lock(hashcode, pg_sys::LWLockMode::LW_EXCLUSIVE);
+        let entry = self.search(key, hashcode, pg_sys::HASHACTION::HASH_FIND)?;
+        Some(PgSharedHashMapRefMut { value: unsafe { &mut (*entry).value }, _lock: lock })
+    }
+
+    /// Look up `key`, inserting the result of `f` if it isn't present, and hold an exclusive lock
+    /// on its partition while the returned guard is alive
+    pub fn get_or_insert_with(
+        &self,
+        key: K,
+        f: impl FnOnce() -> V,
+    ) -> Result<PgSharedHashMapRefMut<'_, V>, PgSharedHashMapError> {
+        let hashcode = self.hash(&key);
+        let lock = self.lock(hashcode, pg_sys::LWLockMode::LW_EXCLUSIVE);
+        let entry = match self.search(&key, hashcode, pg_sys::HASHACTION::HASH_FIND) {
+            Some(entry) => entry,
+            None => {
+                // build the value before entering the key, so a panic in `f` can't leave behind
+                // an entry without one
+                let value = f();
+                let (entry, _) = self.enter(&key, hashcode)?;
+                unsafe { std::ptr::addr_of_mut!((*entry).value).write(value) };
+                entry
+            }
+        };
+        Ok(PgSharedHashMapRefMut { value: unsafe { &mut (*entry).value }, _lock: lock })
+    }
+
+    /// Insert `value` under `key`, returning the value it replaced, if any
+    pub fn insert(&self, key: K, value: V) -> Result<Option<V>, PgSharedHashMapError> {
+        let hashcode = self.hash(&key);
+        let _lock = self.lock(hashcode, pg_sys::LWLockMode::LW_EXCLUSIVE);
+        let (entry, found) = self.enter(&key, hashcode)?;
+        unsafe {
+            let slot = std::ptr::addr_of_mut!((*entry).value);
+            if found {
+                Ok(Some(slot.replace(value)))
+            } else {
+                slot.write(value);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Remove `key`, returning its value, if it was present
+    pub fn remove(&self, key: &K) -> Option<V> {
+        let hashcode = self.hash(key);
+        let _lock = self.lock(hashcode, pg_sys::LWLockMode::LW_EXCLUSIVE);
+        let entry = self.search(key, hashcode, pg_sys::HASHACTION::HASH_FIND)?;
+        // once removed, the entry is back on a freelist that inserts into other partitions,
+        // which our lock doesn't cover, may take it from
+        let value = unsafe { std::ptr::addr_of!((*entry).value).read() };
+        self.search(key, hashcode, pg_sys::HASHACTION::HASH_REMOVE);
+        Some(value)
+    }
+
+    /// Call `f` with every entry in the map, while holding a shared lock on every partition
+    pub fn for_each(&self, mut f: impl FnMut(&K, &V)) {
+        let _locks = self.lock_all(pg_sys::LWLockMode::LW_SHARED);
+        self.scan(|entry| {
+            let entry = unsafe { &*entry };
+            f(&entry.key, &entry.value);
+        });
+    }
+
+    /// Keep only the entries for which `f` returns `true`, while holding an exclusive lock on
+    /// every partition
+    pub fn retain(&self, mut f: impl FnMut(&K, &mut V) -> bool) {
+        let _locks = self.lock_all(pg_sys::LWLockMode::LW_EXCLUSIVE);
+        self.scan(|entry| unsafe {
+            if !f(&(*entry).key, &mut (*entry).value) {
+                // removing the entry the scan just returned is explicitly allowed
+                let mut found = false;
+                pg_sys::hash_search(
+                    self.inner().htab,
+                    std::ptr::addr_of!((*entry).key).cast(),
+                    pg_sys::HASHACTION::HASH_REMOVE,
+                    &mut found,
+                );
+            }
+        });
+    }
+
+    fn inner(&self) -> &SharedHashMapInner {
+        self.inner.get().expect("PgSharedHashMap was not initialized")
+    }
+
+    fn get_name(&self) -> &'static CStr {
+        self.name.get_or_init(|| {
+            let name =
+                alloc::ffi::CString::new(Uuid::new_v4().to_string()).expect("CString::new failed");
+            Box::leak(name.into_boxed_c_str())
+        })
+    }
+
+    fn hash(&self, key: &K) -> u32 {
+        unsafe { pg_sys::get_hash_value(self.inner().htab, (key as *const K).cast()) }
+    }
+
+    fn lock(&self, hashcode: u32, mode: pg_sys::LWLockMode::Type) -> PartitionLock {
+        let partition = hashcode as usize % NUM_PARTITIONS;
+        unsafe { PartitionLock::acquire(&mut (*self.inner().locks.add(partition)).lock, mode) }
+    }
+
+    fn lock_all(&self, mode: pg_sys::LWLockMode::Type) -> Vec<PartitionLock> {
+        // always acquired in the same order, so concurrent callers can't deadlock
+        (0..NUM_PARTITIONS as u32).map(|partition| self.lock(partition, mode)).collect()
+    }
+
+    fn search(
+        &self,
+        key: &K,
+        hashcode: u32,
+        action: pg_sys::HASHACTION::Type,
+    ) -> Option<*mut Entry<K, V>> {
+        let mut found = false;
+        let entry = unsafe {
+            pg_sys::hash_search_with_hash_value(
+                self.inner().htab,
+                (key as *const K).cast(),
+                hashcode,
+                action,
+                &mut found,
+            )
+        };
+        (!entry.is_null()).then_some(entry.cast())
+    }
+
+    fn enter(
+        &self,
+        key: &K,
+        hashcode: u32,
+    ) -> Result<(*mut Entry<K, V>, bool), PgSharedHashMapError> {
+        let mut found = false;
+        let entry = unsafe {
+            pg_sys::hash_search_with_hash_value(
+                self.inner().htab,
+                (key as *const K).cast(),
+                hashcode,
+                pg_sys::HASHACTION::HASH_ENTER_NULL,
+                &mut found,
+            )
+        };
+        if entry.is_null() {
+            Err(PgSharedHashMapError::HashTableFull)
+        } else {
+            Ok((entry.cast(), found))
+        }
+    }
+
+    fn scan(&self, mut f: impl FnMut(*mut Entry<K, V>)) {
+        struct Scan(pg_sys::HASH_SEQ_STATUS, bool);
+        impl Drop for Scan {
+            fn drop(&mut self) {
+                // a scan that ran to completion has already terminated itself
+                if !self.1 {
+                    unsafe { pg_sys::hash_seq_term(&mut self.0) }
+                }
+            }
+        }
+
+        let mut scan = Scan(Default::default(), false);
+        unsafe { pg_sys::hash_seq_init(&mut scan.0, self.inner().htab) };
+        loop {
+            let entry = unsafe { pg_sys::hash_seq_search(&mut scan.0) };
+            if entry.is_null() {
+                scan.1 = true;
+                break;
+            }
+            f(entry.cast());
+        }
+    }
+}
+
+impl<K, V> PgSharedMemoryInitialization for PgSharedHashMap<K, V>
+where
+    K: PGRXSharedMemory + Eq + Hash,
+    V: PGRXSharedMemory,
+{
+    fn pg_init(&'static self) {
+        assert!(std::mem::size_of::<K>() > 0, "PgSharedHashMap keys cannot be zero-sized");
+        assert!(
+            std::mem::align_of::<Entry<K, V>>() <= pg_sys::MAXIMUM_ALIGNOF as usize,
+            "PgSharedHashMap entries cannot be aligned to more than MAXIMUM_ALIGNOF"
+        );
+        unsafe {
+            pg_sys::RequestAddinShmemSpace(pg_sys::hash_estimate_size(
+                self.capacity as _,
+                std::mem::size_of::<Entry<K, V>>(),
+            ));
+            pg_sys::RequestNamedLWLockTranche(self.get_name().as_ptr(), NUM_PARTITIONS as _);
+        }
+    }
+
+    /// SAFETY: Must only be called from inside the Postgres shared memory init hook
+    unsafe fn shmem_init(&'static self) {
+        let mut info = pg_sys::HASHCTL {
+            keysize: std::mem::size_of::<K>(),
+            entrysize: std::mem::size_of::<Entry<K, V>>(),
+            hash: Some(hash_key::<K>),
+            match_: Some(match_key::<K>),
+            num_partitions: NUM_PARTITIONS as _,
+            ..Default::default()
+        };
+        let flags = pg_sys::HASH_ELEM
+            | pg_sys::HASH_FUNCTION
+            | pg_sys::HASH_COMPARE
+            | pg_sys::HASH_PARTITION;
+
+        unsafe {
+            let addin_shmem_init_lock: *mut pg_sys::LWLock =
+                &mut (*pg_sys::MainLWLockArray.add(21)).lock;
+            pg_sys::LWLockAcquire(addin_shmem_init_lock, pg_sys::LWLockMode::LW_EXCLUSIVE);
+
+            let htab = pg_sys::ShmemInitHash(
+                self.get_name().as_ptr(),
+                self.capacity as _,
+                self.capacity as _,
+                &mut info,
+                flags as _,
+            );
+            let locks = pg_sys::GetNamedLWLockTranche(self.get_name().as_ptr());
+            // the postmaster and each backend forked from it only ever do this once
+            let _ = self.inner.set(SharedHashMapInner { htab, locks });
+            pg_sys::LWLockRelease(addin_shmem_init_lock);
+        }
+    }
+}
+
+#[pg_guard]
+unsafe extern "C" fn hash_key<K: Hash>(key: *const c_void, _keysize: pg_sys::Size) -> u32 {
+    let mut hasher = DefaultHasher::new();
+    unsafe { (*key.cast::<K>()).hash(&mut hasher) };
+    hasher.finish() as u32
+}
+
+#[pg_guard]
+unsafe extern "C" fn match_key<K: Eq>(
+    key1: *const c_void,
+    key2: *const c_void,
+    _keysize: pg_sys::Size,
+) -> c_int {
+    let equal = unsafe { *key1.cast::<K>() == *key2.cast::<K>() };
+    if equal {
+        0
+    } else {
+        1
+    }
+}
+
+/// A held lock on one partition of a [`PgSharedHashMap`]
+struct PartitionLock(*mut pg_sys::LWLock);
+
+impl PartitionLock {
+    unsafe fn acquire(lock: *mut pg_sys::LWLock, mode: pg_sys::LWLockMode::Type) -> Self {
+        unsafe { pg_sys::LWLockAcquire(lock, mode) };
+        PartitionLock(lock)
+    }
+}
+
+impl Drop for PartitionLock {
+    fn drop(&mut self) {
+        // SAFETY: self.0 is always valid
+        unsafe { release_unless_elog_unwinding(self.0) }
+    }
+}
+
+/// Shared access to a value in a [`PgSharedHashMap`]
+pub struct PgSharedHashMapRef<'a, V> {
+    value: &'a V,
+    _lock: PartitionLock,
+}
+
+impl<V> Deref for PgSharedHashMapRef<'_, V> {
+    type Target = V;
+
+    fn deref(&self) -> &V {
+        self.value
+    }
+}
+
+/// Exclusive access to a value in a [`PgSharedHashMap`]
+pub struct PgSharedHashMapRefMut<'a, V> {
+    value: &'a mut V,
+    _lock: PartitionLock,
+}
+
+impl<V> Deref for PgSharedHashMapRefMut<'_, V> {
+    type Target = V;
+
+    fn deref(&self) -> &V {
+        self.value
+    }
+}
+
+impl<V> DerefMut for PgSharedHashMapRefMut<'_, V> {
+    fn deref_mut(&mut self) -> &mut V {
+        self.value
+    }
+}
+
+/// Identifies a [`PgDshashMap`] within its [`DsaArea`], across backends
+pub type DshashHandle = pg_sys::dshash_table_handle;
+
+/// A hash table in dynamic shared memory, which grows as needed
+///
+/// The table is created in a [`DsaArea`], and other backends attached to that area can attach
+/// to it by its [`DshashHandle`].  The table can't outlive the area it was created or attached
+/// through.
+///
+/// Keys are hashed with their [`Hash`] implementation, which must be the same in every backend.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use pgrx::dsm::DsaArea;
+/// use pgrx::shmem_hash::PgDshashMap;
+///
+/// let area = DsaArea::create(c"my_extension_dsa");
+/// let map = PgDshashMap::<i32, f64>::create(&area, c"my_extension_map");
+/// map.insert(1, 1.5);
+/// assert_eq!(map.get(&1).as_deref(), Some(&1.5));
+/// ```
+pub struct PgDshashMap<'area, K, V> {
+    table: NonNull<pg_sys::dshash_table>,
+    _area: PhantomData<&'area DsaArea>,
+    _marker: PhantomData<Entry<K, V>>,
+}
+
+impl<'area, K, V> PgDshashMap<'area, K, V>
+where
+    K: PGRXSharedMemory + Eq + Hash,
+    V: PGRXSharedMemory,
+{
+    /// Create a new, empty table in `area`.  `tranche_name` names the LWLock tranche that guards
+    /// its partitions, as shown in `pg_stat_activity`
+    pub fn create(area: &'area DsaArea, tranche_name: &'static CStr) -> Self {
+        unsafe {
+            let tranche_id = pg_sys::LWLockNewTrancheId();
+            pg_sys::LWLockRegisterTranche(tranche_id, tranche_name.as_ptr());
+            let table = pg_sys::dshash_create(
+                area.as_raw(),
+                &Self::parameters(tranche_id),
+                std::ptr::null_mut(),
+            );
+            Self::from_raw(table)
+        }
+    }
+
+    /// Attach to a table previously created in `area`, by this or another backend
+    pub fn attach(area: &'area DsaArea, handle: DshashHandle) -> Self {
+        unsafe {
+            // the tranche is only used when creating the table
+            let table = pg_sys::dshash_attach(
+                area.as_raw(),
+                &Self::parameters(0),
+                handle,
+                std::ptr::null_mut(),
+            );
+            Self::from_raw(table)
+        }
+    }
+
+    unsafe fn from_raw(table: *mut pg_sys::dshash_table) -> Self {
+        PgDshashMap {
+            table: NonNull::new(table).expect("dshash_table should not be null"),
+            _area: PhantomData,
+            _marker: PhantomData,
+        }
+    }
+
+    fn parameters(tranche_id: c_int) -> pg_sys::dshash_parameters {
+        assert!(std::mem::size_of::<K>() > 0, "PgDshashMap keys cannot be zero-sized");
+        pg_sys::dshash_parameters {
+            key_size: std::mem::size_of::<K>(),
+            entry_size: std::mem::size_of::<Entry<K, V>>(),
+            compare_function: Some(dshash_compare::<K>),
+            hash_function: Some(dshash_hash::<K>),
+            #[cfg(feature = "pg17")]
+            copy_function: Some(dshash_copy::<K>),
+            tranche_id,
+        }
+    }
+
+    /// The handle other backends can use to [attach](PgDshashMap::attach) to this table
+    pub fn handle(&self) -> DshashHandle {
+        unsafe { pg_sys::dshash_get_hash_table_handle(self.table.as_ptr()) }
+    }
+
+    /// Look up `key`, holding a shared lock on its partition while the returned guard is alive
+    pub fn get(&self, key: &K) -> Option<PgDshashMapRef<'_, K, V>> {
+        let entry = self.find(key, false)?;
+        Some(PgDshashMapRef { map: self, entry })
+    }
+
+    /// Look up `key`, holding an exclusive lock on its partition while the returned guard is
+    /// alive
+    pub fn get_mut(&self, key: &K) -> Option<PgDshashMapRefMut<'_, K, V>> {
+        let entry = self.find(key, true)?;
+        Some(PgDshashMapRefMut { map: self, entry })
+    }
+
+    /// Look up `key`, inserting the result of `f` if it isn't present, and hold an exclusive lock
+    /// on its partition while the returned guard is alive
+    ///
+    /// `f` runs without the lock held, so if another backend inserts `key` in the meantime, the
+    /// value it built is dropped in favor of the one already there.
+    pub fn get_or_insert_with(&self, key: K, f: impl FnOnce() -> V) -> PgDshashMapRefMut<'_, K, V> {
+        if let Some(entry) = self.find(&key, true) {
+            return PgDshashMapRefMut { map: self, entry };
+        }
+
+        // build the value before entering the key, so a panic in `f` can't leave behind an entry
+        // without one
+        let value = f();
+        let (entry, found) = self.find_or_insert(&key);
+        if !found {
+            unsafe { std::ptr::addr_of_mut!((*entry.as_ptr()).value).write(value) };
+        }
+        PgDshashMapRefMut { map: self, entry }
+    }
+
+    /// Insert `value` under `key`, returning the value it replaced, if any
+    pub fn insert(&self, key: K, value: V) -> Option<V> {
+        let (entry, found) = self.find_or_insert(&key);
+        let _guard = PgDshashMapRefMut { map: self, entry };
+        unsafe {
+            let slot = std::ptr::addr_of_mut!((*entry.as_ptr()).value);
+            if found {
+                Some(slot.replace(value))
+            } else {
+                slot.write(value);
+                None
+            }
+        }
+    }
+
+    /// Remove `key`, returning its value, if it was present
+    pub fn remove(&self, key: &K) -> Option<V> {
+        let entry = self.find(key, true)?;
+        unsafe {
+            let value = std::ptr::addr_of!((*entry.as_ptr()).value).read();
+            // this also releases the partition lock
+            pg_sys::dshash_delete_entry(self.table.as_ptr(), entry.as_ptr().cast());
+            Some(value)
+        }
+    }
+
+    /// Call `f` with every entry in the table.  Partitions are locked in shared mode one at a
+    /// time, as the scan reaches them
+    #[cfg(any(feature = "pg15", feature = "pg16", feature = "pg17"))]
+    pub fn for_each(&self, mut f: impl FnMut(&K, &V)) {
+        self.scan(false, |_, entry| {
+            let entry = unsafe { &*entry };
+            f(&entry.key, &entry.value);
+        });
+    }
+
+    /// Keep only the entries for which `f` returns `true`.  Partitions are locked in exclusive
+    /// mode one at a time, as the scan reaches them
+    #[cfg(any(feature = "pg15", feature = "pg16", feature = "pg17"))]
+    pub fn retain(&self, mut f: impl FnMut(&K, &mut V) -> bool) {
+        self.scan(true, |status, entry| unsafe {
+            if !f(&(*entry).key, &mut (*entry).value) {
+                pg_sys::dshash_delete_current(status);
+            }
+        });
+    }
+
+    /// Destroy the table, freeing its memory for every backend
+    ///
+    /// # Safety
+    ///
+    /// No other backend may be attached to the table
+    pub unsafe fn destroy(self) {
+        let this = std::mem::ManuallyDrop::new(self);
+        unsafe { pg_sys::dshash_destroy(this.table.as_ptr()) }
+    }
+
+    fn find(&self, key: &K, exclusive: bool) -> Option<NonNull<Entry<K, V>>> {
+        let entry = unsafe {
+            pg_sys::dshash_find(self.table.as_ptr(), (key as *const K).cast(), exclusive)
+        };
+        NonNull::new(entry.cast())
+    }
+
+    fn find_or_insert(&self, key: &K) -> (NonNull<Entry<K, V>>, bool) {
+        let mut found = false;
+        let entry = unsafe {
+            pg_sys::dshash_find_or_insert(self.table.as_ptr(), (key as *const K).cast(), &mut found)
+        };
+        (NonNull::new(entry.cast()).expect("dshash_find_or_insert returned null"), found)
+    }
+
+    #[cfg(any(feature = "pg15", feature = "pg16", feature = "pg17"))]
+    fn scan(
+        &self,
+        exclusive: bool,
+        mut f: impl FnMut(*mut pg_sys::dshash_seq_status, *mut Entry<K, V>),
+    ) {
+        struct Scan(pg_sys::dshash_seq_status);
+        impl Drop for Scan {
+            fn drop(&mut self) {
+                if !elog_unwinding() {
+                    unsafe { pg_sys::dshash_seq_term(&mut self.0) }
+                }
+            }
+        }
+
+        let mut scan = Scan(Default::default());
+        unsafe { pg_sys::dshash_seq_init(&mut scan.0, self.table.as_ptr(), exclusive) };
+        loop {
+            let entry = unsafe { pg_sys::dshash_seq_next(&mut scan.0) };
+            if entry.is_null() {
+                break;
+            }
+            f(&mut scan.0, entry.cast());
+        }
+    }
+}
+
+impl<K, V> Drop for PgDshashMap<'_, K, V> {
+    fn drop(&mut self) {
+        unsafe { pg_sys::dshash_detach(self.table.as_ptr()) }
+    }
+}
+
+#[pg_guard]
+unsafe extern "C" fn dshash_compare<K: Eq>(
+    a: *const c_void,
+    b: *const c_void,
+    _size: usize,
+    _arg: *mut c_void,
+) -> c_int {
+    let equal = unsafe { *a.cast::<K>() == *b.cast::<K>() };
+    if equal {
+        0
+    } else {
+        1
+    }
+}
+
+#[pg_guard]
+unsafe extern "C" fn dshash_hash<K: Hash>(
+    v: *const c_void,
+    _size: usize,
+    _arg: *mut c_void,
+) -> pg_sys::dshash_hash {
+    let mut hasher = DefaultHasher::new();
+    unsafe { (*v.cast::<K>()).hash(&mut hasher) };
+    hasher.finish() as u32
+}
+
+#[cfg(feature = "pg17")]
+#[pg_guard]
+unsafe extern "C" fn dshash_copy<K>(
+    dest: *mut c_void,
+    src: *const c_void,
+    _size: usize,
+    _arg: *mut c_void,
+) {
+    unsafe { std::ptr::copy_nonoverlapping(src.cast::<K>(), dest.cast::<K>(), 1) }
+}
+
+/// Like [`release_unless_elog_unwinding`], dshash's partition locks are released on
+/// (sub)transaction abort, and releasing them ourselves after an `elog(ERROR)` would fail
+fn elog_unwinding() -> bool {
+    // SAFETY: mut static access is ok from a single (main) thread.
+    unsafe { pg_sys::InterruptHoldoffCount == 0 }
+}
+
+/// Shared access to a value in a [`PgDshashMap`]
+pub struct PgDshashMapRef<'a, K, V> {
+    map: &'a PgDshashMap<'a, K, V>,
+    entry: NonNull<Entry<K, V>>,
+}
+
+impl<K, V> Deref for PgDshashMapRef<'_, K, V> {
+    type Target = V;
+
+    fn deref(&self) -> &V {
+        unsafe { &(*self.entry.as_ptr()).value }
+    }
+}
+
+impl<K, V> Drop for PgDshashMapRef<'_, K, V> {
+    fn drop(&mut self) {
+        if !elog_unwinding() {
+            unsafe {
+                pg_sys::dshash_release_lock(self.map.table.as_ptr(), self.entry.as_ptr().cast())
+            }
+        }
+    }
+}
+
+/// Exclusive access to a value in a [`PgDshashMap`]
+pub struct PgDshashMapRefMut<'a, K, V> {
+    map: &'a PgDshashMap<'a, K, V>,
+    entry: NonNull<Entry<K, V>>,
+}
+
+impl<K, V> Deref for PgDshashMapRefMut<'_, K, V> {
+    type Target = V;
+
+    fn deref(&self) -> &V {
+        unsafe { &(*self.entry.as_ptr()).value }
+    }
+}
+
+impl<K, V> DerefMut for PgDshashMapRefMut<'_, K, V> {
+    fn deref_mut(&mut self) -> &mut V {
+        unsafe { &mut (*self.entry.as_ptr()).value }
+    }
+}
+
+impl<K, V> Drop for PgDshashMapRefMut<'_, K, V> {
+    fn drop(&mut self) {
+        if !elog_unwinding() {
+            unsafe {
+                pg_sys::dshash_release_lock(self.map.table.as_ptr(), self.entry.as_ptr().cast())
+            }
+        }
+    }
+}
@@ -11,6 +11,7 @@
 
 use crate::datum::{DatumWithOid, FromDatum, IntoDatum, Json, TryFromDatumError};
 use crate::pg_sys;
+use crate::row::RowError;
 use core::fmt::Formatter;
 use std::ffi::{CStr, CString};
 use std::fmt::Debug;
@@ -20,7 +21,7 @@ mod client;
 mod cursor;
 mod query;
 mod tuple;
-pub use client::SpiClient;
+pub use client::{NonAtomicSpiClient, ProcedureCall, SpiClient};
 pub use cursor::SpiCursor;
 pub use query::{OwnedPreparedStatement, PreparedStatement, Query};
 pub use tuple::{SpiHeapTupleData, SpiHeapTupleDataEntry, SpiTupleTable};
@@ -187,6 +188,10 @@ pub enum SpiError {
     /// The [`pg_sys::SPI_tuptable`] is null
     #[error("The active `SPI_tuptable` is NULL")]
     NoTupleTable,
+
+    /// A row couldn't be read as the requested type
+    #[error("Row error: {0}")]
+    RowError(#[from] RowError),
 }
 
 pub type Error = SpiError;
@@ -428,6 +433,55 @@ impl Spi {
         f(&mut client)
     }
 
+    /// Execute SPI commands via the provided [`NonAtomicSpiClient`], which can commit or roll back
+    /// the current transaction.
+    ///
+    /// This is only useful from a [`#[pg_procedure]`](macro@crate::pg_procedure) that's `CALL`ed
+    /// outside of an explicit transaction block.  If the procedure was `CALL`ed atomically, such as
+    /// inside a transaction block or from a function, the client connects atomically and Postgres
+    /// raises an "invalid transaction termination" ERROR when the transaction is committed or
+    /// rolled back.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use pgrx::prelude::*;
+    /// # fn foo() -> spi::Result<()> {
+    /// Spi::connect_nonatomic(|client| {
+    ///     client.update("INSERT INTO users VALUES ('Bob')", None, &[])?;
+    ///     client.commit();
+    ///     client.update("INSERT INTO users VALUES ('Alice')", None, &[])?;
+    ///     client.rollback();
+    ///     Ok(())
+    /// })
+    /// # }
+    /// ```
+    ///
+    /// Tuple tables borrow the client, so they can't be used after the transaction that made
+    /// them has ended.  The following code will not compile:
+    ///
+    /// ```rust,compile_fail
+    /// use pgrx::prelude::*;
+    /// Spi::connect_nonatomic(|client| {
+    ///     let users = client.select("SELECT * FROM users", None, &[]).unwrap();
+    ///     client.commit();
+    ///     users.len()
+    /// });
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// This function will panic if for some reason it's unable to "connect" to Postgres' SPI
+    /// system, just like [`Spi::connect_mut`].
+    pub fn connect_nonatomic<R, F>(f: F) -> R
+    where
+        F: FnOnce(&mut NonAtomicSpiClient<'_>) -> R,
+    {
+        let mut client =
+            NonAtomicSpiClient::connect().expect("SPI_connect_ext indicated an unexpected failure");
+        f(&mut client)
+    }
+
     #[track_caller]
     pub fn check_status(status_code: i32) -> std::result::Result<SpiOkCodes, Error> {
         match SpiOkCodes::try_from(status_code) {
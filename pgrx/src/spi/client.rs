@@ -1,5 +1,6 @@
 use std::marker::PhantomData;
 use std::ptr::NonNull;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 use crate::datum::DatumWithOid;
 use crate::pg_sys::{self, PgOid};
@@ -173,3 +174,109 @@ impl Drop for SpiClient<'_> {
         Spi::check_status(unsafe { pg_sys::SPI_finish() }).ok();
     }
 }
+
+/// Whether the innermost [`#[pg_procedure]`](macro@crate::pg_procedure) being executed was
+/// `CALL`ed atomically, such as inside a transaction block or from a function
+static ATOMIC_CALL: AtomicBool = AtomicBool::new(false);
+
+/// Records whether a [`#[pg_procedure]`](macro@crate::pg_procedure) was `CALL`ed atomically for as
+/// long as it runs.  Used by the code `#[pg_procedure]` generates.
+#[doc(hidden)]
+pub struct ProcedureCall {
+    outer: bool,
+}
+
+impl ProcedureCall {
+    /// # Safety
+    ///
+    /// `fcinfo` must be the `FunctionCallInfo` the procedure was called with
+    #[doc(hidden)]
+    pub unsafe fn enter(fcinfo: pg_sys::FunctionCallInfo) -> Self {
+        // like plpgsql, a procedure may only end the transaction if its `CallContext` allows it
+        let context = (*fcinfo).context;
+        let atomic = context.is_null()
+            || !crate::is_a(context, pg_sys::NodeTag::T_CallContext)
+            || (*context.cast::<pg_sys::CallContext>()).atomic;
+        ProcedureCall { outer: ATOMIC_CALL.swap(atomic, Ordering::Relaxed) }
+    }
+}
+
+impl Drop for ProcedureCall {
+    fn drop(&mut self) {
+        ATOMIC_CALL.store(self.outer, Ordering::Relaxed);
+    }
+}
+
+/// An [`SpiClient`] connected in non-atomic mode, from which the current transaction can be
+/// committed or rolled back.
+///
+/// Every [`SpiTupleTable`] it returns borrows the client, so none can be used after a
+/// [`commit`](Self::commit) or [`rollback`](Self::rollback) frees it.
+pub struct NonAtomicSpiClient<'conn> {
+    client: SpiClient<'conn>,
+}
+
+impl<'conn> NonAtomicSpiClient<'conn> {
+    /// Connect to Postgres' SPI system in non-atomic mode, unless the procedure we're in was
+    /// `CALL`ed atomically, in which case committing or rolling back raises an ERROR.
+    pub(super) fn connect() -> SpiResult<Self> {
+        let options = if ATOMIC_CALL.load(Ordering::Relaxed) {
+            0
+        } else {
+            pg_sys::SPI_OPT_NONATOMIC as libc::c_int
+        };
+        Spi::check_status(unsafe { pg_sys::SPI_connect_ext(options) })?;
+        Ok(NonAtomicSpiClient { client: SpiClient { __marker: PhantomData } })
+    }
+
+    /// Perform a SELECT statement.
+    pub fn select<'a, 'mcx, Q: Query<'a>>(
+        &'a self,
+        query: Q,
+        limit: Option<libc::c_long>,
+        args: &[DatumWithOid<'mcx>],
+    ) -> SpiResult<SpiTupleTable<'a>> {
+        query.execute(&self.client, limit, args)
+    }
+
+    /// Perform any query (including utility statements) that modify the database in some way.
+    pub fn update<'a, 'mcx, Q: Query<'a>>(
+        &'a mut self,
+        query: Q,
+        limit: Option<libc::c_long>,
+        args: &[DatumWithOid<'mcx>],
+    ) -> SpiResult<SpiTupleTable<'a>> {
+        Spi::mark_mutable();
+        query.execute(&self.client, limit, args)
+    }
+
+    /// Commit the current transaction and start a new one.
+    ///
+    /// # Panics
+    ///
+    /// Raises a Postgres ERROR if the procedure wasn't `CALL`ed in a context that allows
+    /// transaction control, such as from inside an explicit transaction block.
+    pub fn commit(&mut self) {
+        // SAFETY: every tuple table borrows `self`, so none can outlive the transaction
+        unsafe {
+            pg_sys::SPI_commit();
+            #[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14"))]
+            pg_sys::SPI_start_transaction();
+        }
+    }
+
+    /// Roll back the current transaction and start a new one.
+    ///
+    /// # Panics
+    ///
+    /// Raises a Postgres ERROR if the procedure wasn't `CALL`ed in a context that allows
+    /// transaction control, such as from inside an explicit transaction block.
+    pub fn rollback(&mut self) {
+        // SAFETY: every tuple table borrows `self`, so none can outlive the transaction
+        unsafe {
+            pg_sys::SPI_rollback();
+            #[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14"))]
+            pg_sys::SPI_start_transaction();
+        }
+    }
+}
@@ -1,12 +1,16 @@
 use std::ffi::{CStr, CString};
 use std::fmt::Debug;
 use std::marker::PhantomData;
+use std::num::NonZeroUsize;
 use std::ops::Index;
 use std::ptr::NonNull;
 
+use crate::datum::TryFromDatumError;
 use crate::memcxt::PgMemoryContexts;
 use crate::pg_sys::panic::ErrorReportable;
 use crate::prelude::*;
+use crate::row::{FromRow, RowColumns, RowSource};
+use crate::tupdesc::PgTupleDesc;
 
 use super::{SpiError, SpiErrorCodes, SpiOkCodes, SpiResult};
 
@@ -71,6 +75,41 @@ impl<'conn> SpiTupleTable<'conn> {
         Ok((a, b, c))
     }
 
+    /// Read the current row as a `T`
+    ///
+    /// Returns `None` if there are no rows.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`SpiError::RowError`] if the row doesn't fit a `T`
+    pub fn get_row<T: FromRow>(&self) -> SpiResult<Option<T>> {
+        self.get_heap_tuple()?.map(|row| row.get_row()).transpose()
+    }
+
+    /// Read each of the remaining rows as a `T`
+    ///
+    /// The columns `T`'s fields map to are looked up once, up front, rather than for every row.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`SpiError::RowError`] if a column a field maps to is missing.  Each row can
+    /// also fail to fit a `T` on its own, such as when a column is unexpectedly NULL
+    pub fn rows<T: FromRow + 'conn>(self) -> SpiResult<impl Iterator<Item = SpiResult<T>> + 'conn> {
+        let columns = self
+            .table
+            .as_deref()
+            // SAFETY:  the tuple table's tupdesc is valid for as long as it is
+            .map(|table| unsafe {
+                RowColumns::resolve(T::COLUMNS, &PgTupleDesc::from_pg_unchecked(table.tupdesc))
+            })
+            .transpose()?;
+        Ok(self.map(move |row| {
+            // we only get rows when there's a tuple table
+            let columns = columns.as_ref().expect("no columns for the tuple table");
+            Ok(columns.read(&row)?)
+        }))
+    }
+
     #[inline(always)]
     fn get_spi_tuptable(
         &self,
@@ -369,6 +408,20 @@ impl<'conn> SpiHeapTupleData<'conn> {
         self.get_datum_by_name(name.as_ref()).map(|entry| entry.value())?
     }
 
+    /// Read this HeapTuple as a `T`
+    ///
+    /// When reading many rows, [`SpiTupleTable::rows`] only looks up the columns `T`'s fields map
+    /// to once.
+    ///
+    /// # Errors
+    ///
+    /// Returns an [`SpiError::RowError`] if the row doesn't fit a `T`
+    pub fn get_row<T: FromRow>(&self) -> SpiResult<T> {
+        // SAFETY:  our tupdesc is valid for as long as we are
+        let tupdesc = unsafe { PgTupleDesc::from_pg_unchecked(self.tupdesc.as_ptr()) };
+        Ok(RowColumns::resolve(T::COLUMNS, &tupdesc)?.read(self)?)
+    }
+
     /// Get a raw Datum from this HeapTuple by its ordinal position.
     ///
     /// The ordinal position is 1-based.
@@ -462,8 +515,24 @@ impl<'conn> SpiHeapTupleData<'conn> {
     }
 }
 
+impl RowSource for SpiHeapTupleData<'_> {
+    fn value<T: FromDatum + IntoDatum>(
+        &self,
+        ordinal: NonZeroUsize,
+    ) -> Result<Option<T>, TryFromDatumError> {
+        match self.entries.get(ordinal.get() - 1) {
+            Some(entry) => entry.try_value(),
+            None => Err(TryFromDatumError::NoSuchAttributeNumber(ordinal)),
+        }
+    }
+}
+
 impl<'conn> SpiHeapTupleDataEntry<'conn> {
     pub fn value<T: IntoDatum + FromDatum>(&self) -> SpiResult<Option<T>> {
+        self.try_value().map_err(SpiError::DatumError)
+    }
+
+    fn try_value<T: IntoDatum + FromDatum>(&self) -> Result<Option<T>, TryFromDatumError> {
         match self.datum.as_ref() {
             Some(datum) => unsafe {
                 T::try_from_datum_in_memory_context(
@@ -474,7 +543,6 @@ impl<'conn> SpiHeapTupleDataEntry<'conn> {
                     false,
                     self.type_oid,
                 )
-                .map_err(SpiError::DatumError)
             },
             None => Ok(None),
         }
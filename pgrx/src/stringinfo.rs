@@ -34,6 +34,19 @@ impl<AllocatedBy: WhoAllocated> std::io::Write for StringInfo<AllocatedBy> {
     }
 }
 
+impl<AllocatedBy: WhoAllocated> std::io::Read for StringInfo<AllocatedBy> {
+    /// Read from the `cursor` of the underlying `StringInfoData`, which is advanced past the bytes
+    /// that were read
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize, Error> {
+        let cursor = (self.inner.cursor as usize).min(self.len());
+        let unread = &self.as_bytes()[cursor..];
+        let len = unread.len().min(buf.len());
+        buf[..len].copy_from_slice(&unread[..len]);
+        self.inner.cursor += len as i32;
+        Ok(len)
+    }
+}
+
 impl<AllocatedBy: WhoAllocated> std::fmt::Write for StringInfo<AllocatedBy> {
     fn write_str(&mut self, s: &str) -> core::fmt::Result {
         self.push_str(s);
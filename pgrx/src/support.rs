@@ -0,0 +1,353 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+/*! Planner support functions, which tell the planner more about a function than its catalog entry
+
+A function names its support function with `#[pg_extern(support = my_support_fn)]`. The support
+function takes a [`SupportRequest`] and returns an [`Internal`]: either the answer to the request, or
+[`SupportRequest::unhandled()`] to leave the planner to its defaults.
+
+```rust,no_run
+use pgrx::prelude::*;
+use pgrx::support::SupportRequest;
+use pgrx::Internal;
+
+#[pg_extern(immutable, parallel_safe)]
+fn digits_support(request: SupportRequest) -> Internal {
+    match request {
+        SupportRequest::Rows(mut rows) => {
+            rows.set_rows(10.0);
+            rows.handled()
+        }
+        _ => SupportRequest::unhandled(),
+    }
+}
+
+#[pg_extern(immutable, parallel_safe, support = digits_support)]
+fn digits() -> SetOfIterator<'static, i32> {
+    SetOfIterator::new(0..10)
+}
+```
+
+See <https://www.postgresql.org/docs/current/xfunc-optimization.html> for what Postgres asks of
+support functions.
+*/
+use crate::callconv::{Arg, ArgAbi};
+use crate::datum::{FromDatum, Internal, IntoDatum};
+use crate::list::List;
+use crate::{is_a, memcx, pg_sys};
+use core::ffi::c_void;
+use pgrx_sql_entity_graph::metadata::{
+    ArgumentError, Returns, ReturnsError, SqlMapping, SqlTranslatable,
+};
+
+/// A request from the planner to a function's support function
+pub enum SupportRequest<'fcx> {
+    /// Replace a call of the function with a simpler expression
+    Simplify(SimplifyRequest<'fcx>),
+    /// Estimate the selectivity of the function, when it's used as a boolean filter
+    Selectivity(SelectivityRequest<'fcx>),
+    /// Estimate the cost of executing the function
+    Cost(CostRequest<'fcx>),
+    /// Estimate the number of rows a set-returning function returns
+    Rows(RowsRequest<'fcx>),
+    /// Derive conditions an index can use from a call of the function in a `WHERE` clause
+    IndexCondition(IndexConditionRequest<'fcx>),
+    /// A request which isn't wrapped, such as the window function requests of newer Postgres versions
+    Other(pg_sys::NodeTag),
+}
+
+impl SupportRequest<'_> {
+    /// Wrap a support function's argument
+    ///
+    /// # Safety
+    ///
+    /// `request` must be a valid pointer to one of the `SupportRequest*` nodes which Postgres passes
+    /// to a support function, and must outlive the returned `SupportRequest`.
+    pub unsafe fn from_node<'fcx>(request: *mut pg_sys::Node) -> SupportRequest<'fcx> {
+        assert!(!request.is_null(), "support request must not be null");
+        unsafe {
+            match (*request).type_ {
+                pg_sys::NodeTag::T_SupportRequestSimplify => {
+                    SupportRequest::Simplify(SimplifyRequest(&mut *request.cast()))
+                }
+                pg_sys::NodeTag::T_SupportRequestSelectivity => {
+                    SupportRequest::Selectivity(SelectivityRequest(&mut *request.cast()))
+                }
+                pg_sys::NodeTag::T_SupportRequestCost => {
+                    SupportRequest::Cost(CostRequest(&mut *request.cast()))
+                }
+                pg_sys::NodeTag::T_SupportRequestRows => {
+                    SupportRequest::Rows(RowsRequest(&mut *request.cast()))
+                }
+                pg_sys::NodeTag::T_SupportRequestIndexCondition => {
+                    SupportRequest::IndexCondition(IndexConditionRequest(&mut *request.cast()))
+                }
+                other => SupportRequest::Other(other),
+            }
+        }
+    }
+
+    /// The answer to a request the support function doesn't handle
+    pub fn unhandled() -> Internal {
+        Internal::default()
+    }
+}
+
+/// Asks to replace a call of the function with a simpler expression, such as a constant
+pub struct SimplifyRequest<'fcx>(&'fcx mut pg_sys::SupportRequestSimplify);
+
+impl SimplifyRequest<'_> {
+    /// The call being simplified
+    pub fn fcall(&self) -> &pg_sys::FuncExpr {
+        // SAFETY: Postgres always sets `fcall` for a simplify request
+        unsafe { &*self.0.fcall }
+    }
+
+    /// The value of the call's argument at `index`, if the planner already knows it's a non-NULL
+    /// constant
+    pub fn const_arg<T: FromDatum + IntoDatum>(&self, index: usize) -> Option<T> {
+        unsafe { const_arg(self.fcall().args, index) }
+    }
+
+    /// Replace the call with a constant, which must be of the function's return type
+    pub fn replace_with_const<T: IntoDatum>(self, value: Option<T>) -> Internal {
+        let fcall = self.fcall();
+        let typid = fcall.funcresulttype;
+        assert!(
+            T::is_compatible_with(typid),
+            "`{}` is not compatible with the function's return type",
+            std::any::type_name::<T>()
+        );
+        let collation = fcall.funccollid;
+        let datum = value.and_then(IntoDatum::into_datum);
+        unsafe {
+            let mut typlen = 0;
+            let mut typbyval = false;
+            pg_sys::get_typlenbyval(typid, &mut typlen, &mut typbyval);
+            let constant = pg_sys::makeConst(
+                typid,
+                -1,
+                collation,
+                typlen as _,
+                datum.unwrap_or_else(pg_sys::Datum::null),
+                datum.is_none(),
+                typbyval,
+            );
+            // SAFETY: a Const of the function's return type is a valid replacement for the call
+            self.replace_with(constant.cast())
+        }
+    }
+
+    /// Replace the call with `expr`
+    ///
+    /// # Safety
+    ///
+    /// `expr` must be a valid expression tree, allocated in the current memory context, which
+    /// evaluates to the function's return type.
+    pub unsafe fn replace_with(self, expr: *mut pg_sys::Node) -> Internal {
+        Internal::from(Some(pg_sys::Datum::from(expr)))
+    }
+}
+
+/// Asks for the selectivity of the function, when it's used as a boolean filter
+pub struct SelectivityRequest<'fcx>(&'fcx mut pg_sys::SupportRequestSelectivity);
+
+impl SelectivityRequest<'_> {
+    /// The function being estimated
+    pub fn funcid(&self) -> pg_sys::Oid {
+        self.0.funcid
+    }
+
+    /// Whether the function is used as a join clause, rather than to filter a single relation
+    pub fn is_join(&self) -> bool {
+        self.0.is_join
+    }
+
+    /// The value of the function's argument at `index`, if the planner already knows it's a non-NULL
+    /// constant
+    pub fn const_arg<T: FromDatum + IntoDatum>(&self, index: usize) -> Option<T> {
+        unsafe { const_arg(self.0.args, index) }
+    }
+
+    /// Set the fraction of rows the function is expected to return `true` for, from `0.0` to `1.0`
+    pub fn set_selectivity(&mut self, selectivity: f64) {
+        self.0.selectivity = selectivity.clamp(0.0, 1.0);
+    }
+
+    /// The answer to the request, once the selectivity is set
+    pub fn handled(self) -> Internal {
+        Internal::from(Some(pg_sys::Datum::from(self.0 as *mut pg_sys::SupportRequestSelectivity)))
+    }
+}
+
+/// Asks for the cost of executing the function
+pub struct CostRequest<'fcx>(&'fcx mut pg_sys::SupportRequestCost);
+
+impl CostRequest<'_> {
+    /// The function being estimated
+    pub fn funcid(&self) -> pg_sys::Oid {
+        self.0.funcid
+    }
+
+    /// The value of the call's argument at `index`, if the planner already knows it's a non-NULL
+    /// constant
+    pub fn const_arg<T: FromDatum + IntoDatum>(&self, index: usize) -> Option<T> {
+        unsafe { const_arg(node_args(self.0.node), index) }
+    }
+
+    /// Set the one-time cost of starting the function, and the cost of each call of it, in units
+    /// of `cpu_operator_cost`
+    pub fn set_cost(&mut self, startup: f64, per_tuple: f64) {
+        self.0.startup = startup;
+        self.0.per_tuple = per_tuple;
+    }
+
+    /// The answer to the request, once the cost is set
+    pub fn handled(self) -> Internal {
+        Internal::from(Some(pg_sys::Datum::from(self.0 as *mut pg_sys::SupportRequestCost)))
+    }
+}
+
+/// Asks for the number of rows a set-returning function returns
+pub struct RowsRequest<'fcx>(&'fcx mut pg_sys::SupportRequestRows);
+
+impl RowsRequest<'_> {
+    /// The function being estimated
+    pub fn funcid(&self) -> pg_sys::Oid {
+        self.0.funcid
+    }
+
+    /// The value of the call's argument at `index`, if the planner already knows it's a non-NULL
+    /// constant
+    pub fn const_arg<T: FromDatum + IntoDatum>(&self, index: usize) -> Option<T> {
+        unsafe { const_arg(node_args(self.0.node), index) }
+    }
+
+    /// Set the number of rows the function is expected to return
+    pub fn set_rows(&mut self, rows: f64) {
+        self.0.rows = rows;
+    }
+
+    /// The answer to the request, once the number of rows is set
+    pub fn handled(self) -> Internal {
+        Internal::from(Some(pg_sys::Datum::from(self.0 as *mut pg_sys::SupportRequestRows)))
+    }
+}
+
+/// Asks for conditions an index can use, derived from a call of the function in a `WHERE` clause
+///
+/// This is asked when the argument at [`IndexConditionRequest::indexarg()`] matches the column of an
+/// index, such as `my_filter(tab.col, 'value')` when `tab.col` is indexed.
+pub struct IndexConditionRequest<'fcx>(&'fcx mut pg_sys::SupportRequestIndexCondition);
+
+impl IndexConditionRequest<'_> {
+    /// The function being called
+    pub fn funcid(&self) -> pg_sys::Oid {
+        self.0.funcid
+    }
+
+    /// The call of the function, a [`pg_sys::FuncExpr`] or a [`pg_sys::OpExpr`]
+    pub fn node(&self) -> *mut pg_sys::Node {
+        self.0.node
+    }
+
+    /// The value of the call's argument at `index`, if the planner already knows it's a non-NULL
+    /// constant
+    pub fn const_arg<T: FromDatum + IntoDatum>(&self, index: usize) -> Option<T> {
+        unsafe { const_arg(node_args(self.0.node), index) }
+    }
+
+    /// The argument which matches the index column
+    pub fn indexarg(&self) -> usize {
+        self.0.indexarg as usize
+    }
+
+    /// The index column, counted from zero
+    pub fn indexcol(&self) -> usize {
+        self.0.indexcol as usize
+    }
+
+    /// The operator family of the index column
+    pub fn opfamily(&self) -> pg_sys::Oid {
+        self.0.opfamily
+    }
+
+    /// The collation of the index column
+    pub fn indexcollation(&self) -> pg_sys::Oid {
+        self.0.indexcollation
+    }
+
+    /// Answer with a list of conditions an index scan can use in place of the call.  If `lossy`,
+    /// the call is still evaluated for each row the index returns.
+    ///
+    /// # Safety
+    ///
+    /// `conditions` must be a valid `List` of operator clauses, allocated in the current memory
+    /// context, whose operators belong to [`IndexConditionRequest::opfamily()`] and which have the
+    /// index column on their left.
+    pub unsafe fn replace_with(self, conditions: *mut pg_sys::List, lossy: bool) -> Internal {
+        self.0.lossy = lossy;
+        Internal::from(Some(pg_sys::Datum::from(conditions)))
+    }
+}
+
+/// The arguments of a call, which the planner represents as a `FuncExpr` or an `OpExpr`
+unsafe fn node_args(node: *mut pg_sys::Node) -> *mut pg_sys::List {
+    unsafe {
+        if is_a(node, pg_sys::NodeTag::T_FuncExpr) {
+            (*node.cast::<pg_sys::FuncExpr>()).args
+        } else if is_a(node, pg_sys::NodeTag::T_OpExpr) {
+            (*node.cast::<pg_sys::OpExpr>()).args
+        } else {
+            core::ptr::null_mut()
+        }
+    }
+}
+
+/// The value of `args[index]`, if it's a non-NULL `Const`
+unsafe fn const_arg<T: FromDatum + IntoDatum>(args: *mut pg_sys::List, index: usize) -> Option<T> {
+    unsafe {
+        let arg = memcx::current_context(|mcx| {
+            List::<*mut c_void>::downcast_ptr_in_memcx(args, mcx)?.get(index).copied()
+        })?
+        .cast::<pg_sys::Node>();
+        if !is_a(arg, pg_sys::NodeTag::T_Const) {
+            return None;
+        }
+        let constant = &*arg.cast::<pg_sys::Const>();
+        T::try_from_datum(constant.constvalue, constant.constisnull, constant.consttype)
+            .unwrap_or_else(|e| panic!("{e}"))
+    }
+}
+
+unsafe impl<'fcx> ArgAbi<'fcx> for SupportRequest<'fcx> {
+    unsafe fn unbox_arg_unchecked(arg: Arg<'_, 'fcx>) -> Self {
+        let index = arg.index();
+        let request = unsafe { arg.unbox_arg_using_from_datum::<Internal>() }
+            .and_then(Internal::unwrap)
+            .unwrap_or_else(|| panic!("argument {index} must not be null"));
+        // SAFETY: only Postgres can pass an `internal` that isn't NULL, and it only passes support
+        // requests to support functions
+        unsafe { SupportRequest::from_node(request.cast_mut_ptr()) }
+    }
+}
+
+unsafe impl SqlTranslatable for SupportRequest<'_> {
+    fn argument_sql() -> Result<SqlMapping, ArgumentError> {
+        Ok(SqlMapping::literal("internal"))
+    }
+    fn return_sql() -> Result<Returns, ReturnsError> {
+        Ok(Returns::One(SqlMapping::literal("internal")))
+    }
+    // We don't want to strict upgrade if internal is present.
+    fn optional() -> bool {
+        true
+    }
+}
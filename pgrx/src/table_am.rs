@@ -0,0 +1,996 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+/*!
+
+[Table Access Method](https://www.postgresql.org/docs/current/tableam.html) support.
+
+Table access methods are created by implementing [`TableAccessMethod`] for a type and decorating
+the implementation with [`#[pg_table_am]`](pgrx_macros::pg_table_am).  The macro generates the
+`table_am_handler` function Postgres calls to obtain the access method's [`pg_sys::TableAmRoutine`]
+and emits the [`CREATE ACCESS METHOD`](https://www.postgresql.org/docs/current/sql-create-access-method.html)
+SQL for it.
+
+The `TableAmRoutine` is assembled by pgrx, which absorbs the differences in its callbacks between
+the supported Postgres versions.  Implementations provide sequential scans, tuple insertion and,
+optionally, deletion and update.  The remaining callbacks raise a "feature not supported" error, so
+an access method built on this trait can't be indexed, sampled with `TABLESAMPLE`, scanned by
+`ctid`, locked with `SELECT ... FOR UPDATE`, or scanned in parallel.
+
+Tuples move in and out of the access method through [`pg_sys::TupleTableSlot`]s.  By default
+these are virtual slots, or heap tuple slots on Postgres 12, which [`slot_values`] and
+[`store_values`] read and fill.
+
+# Example
+
+```rust,no_run
+use pgrx::prelude::*;
+use pgrx::PgRelation;
+
+/// Accepts every row and returns none of them
+pub struct BlackHole;
+
+#[pg_table_am]
+impl TableAccessMethod for BlackHole {
+    type ScanState = ();
+
+    fn begin_scan(
+        _relation: &PgRelation,
+        _snapshot: pg_sys::Snapshot,
+        _keys: &[pg_sys::ScanKeyData],
+    ) -> Self::ScanState {
+    }
+
+    fn rescan(_state: &mut (), _keys: &[pg_sys::ScanKeyData]) {}
+
+    fn next_slot(
+        _state: &mut (),
+        _direction: pg_sys::ScanDirection::Type,
+        _slot: &mut pg_sys::TupleTableSlot,
+    ) -> bool {
+        false
+    }
+
+    fn insert(
+        _relation: &PgRelation,
+        _slot: &mut pg_sys::TupleTableSlot,
+        _cid: pg_sys::CommandId,
+        _options: i32,
+    ) {
+    }
+}
+```
+
+With the extension installed, `CREATE TABLE ... USING black_hole` creates tables stored by it.
+*/
+use crate as pgrx; // for #[pg_guard] support from within ourself
+use crate::{ereport, pg_guard, pg_sys, PgMemoryContexts, PgRelation, PgSqlErrorCode};
+use std::ffi::{c_char, c_int, c_void};
+
+/// The physical storage identifier given to [`TableAccessMethod::set_new_filelocator`].
+///
+/// This is a `RelFileNode` before Postgres 16.
+#[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14", feature = "pg15"))]
+pub type RelFileLocator = pg_sys::RelFileNode;
+/// The physical storage identifier given to [`TableAccessMethod::set_new_filelocator`].
+///
+/// This is a `RelFileNode` before Postgres 16.
+#[cfg(not(any(feature = "pg12", feature = "pg13", feature = "pg14", feature = "pg15")))]
+pub type RelFileLocator = pg_sys::RelFileLocator;
+
+/// The planner's size estimate for a table, as returned by
+/// [`TableAccessMethod::estimate_size`].
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct TableSizeEstimate {
+    /// The number of pages the table occupies.
+    pub pages: pg_sys::BlockNumber,
+    /// The number of tuples in the table.
+    pub tuples: f64,
+    /// The fraction of pages marked all-visible in the visibility map.
+    pub allvisfrac: f64,
+}
+
+impl Default for TableSizeEstimate {
+    fn default() -> Self {
+        Self { pages: 10, tuples: 1000.0, allvisfrac: 0.0 }
+    }
+}
+
+/// The circumstances of a [`TableAccessMethod::delete`] or [`TableAccessMethod::update`].
+pub struct TupleModify<'a> {
+    /// The command performing the modification.
+    pub cid: pg_sys::CommandId,
+    /// The snapshot the tuple to be modified was found with.
+    pub snapshot: pg_sys::Snapshot,
+    /// A snapshot the tuple must also be visible to, for RI checks at serializable isolation.
+    pub crosscheck: pg_sys::Snapshot,
+    /// Whether to wait for a conflicting transaction to finish rather than fail.
+    pub wait: bool,
+    /// Details of a failed modification, to be filled in when returning anything but
+    /// [`pg_sys::TM_Result::TM_Ok`].
+    pub failure: &'a mut pg_sys::TM_FailureData,
+}
+
+/// A Postgres table access method.
+///
+/// See the [module documentation](self) for an example.  Apply
+/// [`#[pg_table_am]`](pgrx_macros::pg_table_am) to the implementation.
+///
+/// Rows are identified by the TID stored in their slot's `tts_tid`.  [`TableAccessMethod::insert`]
+/// and [`TableAccessMethod::update`] must assign one, and [`TableAccessMethod::next_slot`] must
+/// report it for `UPDATE` and `DELETE` to find the row again.  TIDs are opaque to Postgres, but
+/// must be valid `ItemPointer`s, ie. have a non-zero offset number.
+pub trait TableAccessMethod: Sized + 'static {
+    /// The name of the access method. (eg. What you'd pass to `CREATE TABLE ... USING name`.)
+    ///
+    /// **Optional:** `#[pg_table_am]` defaults this to the snake case name of the type.
+    const NAME: &'static str;
+
+    /// Per-scan state created by [`TableAccessMethod::begin_scan`] and dropped after
+    /// [`TableAccessMethod::end_scan`], or when the scan's memory context is deleted.
+    type ScanState;
+
+    /// The kind of slot tuples of this table are stored in.
+    ///
+    /// The default is a virtual slot.  Postgres 12 can't read the `ctid` of a tuple in a virtual
+    /// slot, which `UPDATE` and `DELETE` need, so there the default is a heap tuple slot, whose
+    /// `ctid` [`store_values`] sets.
+    fn slot_ops(_relation: &PgRelation) -> *const pg_sys::TupleTableSlotOps {
+        #[cfg(feature = "pg12")]
+        {
+            std::ptr::addr_of!(pg_sys::TTSOpsHeapTuple)
+        }
+        #[cfg(not(feature = "pg12"))]
+        {
+            std::ptr::addr_of!(pg_sys::TTSOpsVirtual)
+        }
+    }
+
+    /// Prepare a sequential scan of `relation`.
+    ///
+    /// `keys` are an optimization hint: the executor still checks every tuple returned against
+    /// the query's quals.
+    fn begin_scan(
+        relation: &PgRelation,
+        snapshot: pg_sys::Snapshot,
+        keys: &[pg_sys::ScanKeyData],
+    ) -> Self::ScanState;
+
+    /// Restart a scan from the beginning, possibly with new scan keys.
+    fn rescan(state: &mut Self::ScanState, keys: &[pg_sys::ScanKeyData]);
+
+    /// Store the next tuple of the scan in `slot`, returning `false` once the scan is exhausted.
+    fn next_slot(
+        state: &mut Self::ScanState,
+        direction: pg_sys::ScanDirection::Type,
+        slot: &mut pg_sys::TupleTableSlot,
+    ) -> bool;
+
+    /// End a scan.  The state is dropped once this returns.
+    fn end_scan(_state: Self::ScanState) {}
+
+    /// Insert the tuple in `slot`, assigning its `tts_tid`.
+    ///
+    /// `options` is a bitmask of `TABLE_INSERT_*` flags.
+    fn insert(
+        relation: &PgRelation,
+        slot: &mut pg_sys::TupleTableSlot,
+        cid: pg_sys::CommandId,
+        options: i32,
+    );
+
+    /// Insert a batch of tuples, such as those from `COPY`.
+    ///
+    /// The default calls [`TableAccessMethod::insert`] for each of them.
+    fn multi_insert(
+        relation: &PgRelation,
+        slots: &mut [&mut pg_sys::TupleTableSlot],
+        cid: pg_sys::CommandId,
+        options: i32,
+    ) {
+        for slot in slots {
+            Self::insert(relation, slot, cid, options);
+        }
+    }
+
+    /// Delete the tuple identified by `tid`.
+    ///
+    /// On Postgres 12, `tid` is the `ctid` of the tuple's physical form in the slot
+    /// [`TableAccessMethod::next_slot`] filled, so it must be a heap tuple slot filled by
+    /// [`store_values`], as it is when [`TableAccessMethod::slot_ops`] isn't overridden.  The
+    /// default raises an error.
+    fn delete(
+        _relation: &PgRelation,
+        _tid: pg_sys::ItemPointerData,
+        _modify: TupleModify<'_>,
+    ) -> pg_sys::TM_Result::Type {
+        unsupported::<Self>("DELETE")
+    }
+
+    /// Replace the tuple identified by `otid` with the one in `slot`, assigning its `tts_tid`.
+    ///
+    /// All of the table's indexes are updated when this returns [`pg_sys::TM_Result::TM_Ok`].
+    /// `otid` is found as the `tid` of [`TableAccessMethod::delete`] is, with the same
+    /// requirements on Postgres 12.  The default raises an error.
+    fn update(
+        _relation: &PgRelation,
+        _otid: pg_sys::ItemPointerData,
+        _slot: &mut pg_sys::TupleTableSlot,
+        _modify: TupleModify<'_>,
+    ) -> pg_sys::TM_Result::Type {
+        unsupported::<Self>("UPDATE")
+    }
+
+    /// Store the version of the tuple identified by `tid` that's visible to `snapshot` in `slot`,
+    /// returning `false` if there is none.
+    ///
+    /// Postgres 14 and later need this to `UPDATE` a table, as do triggers.  The default raises an
+    /// error.
+    fn fetch_row_version(
+        _relation: &PgRelation,
+        _tid: pg_sys::ItemPointerData,
+        _snapshot: pg_sys::Snapshot,
+        _slot: &mut pg_sys::TupleTableSlot,
+    ) -> bool {
+        unsupported::<Self>("fetching tuples by TID")
+    }
+
+    /// Create the storage for a new relfilenode of the table, which happens when it's created
+    /// and when it's rewritten, such as by `TRUNCATE`.
+    ///
+    /// The default creates the usual, empty, main fork with [`create_storage`].  Tables whose
+    /// contents live elsewhere should discard them here.
+    fn set_new_filelocator(_relation: &PgRelation, locator: &RelFileLocator, persistence: c_char) {
+        create_storage(locator, persistence);
+    }
+
+    /// Remove every tuple from the table, for a `TRUNCATE` in the transaction which created the
+    /// table's current relfilenode.
+    ///
+    /// The default truncates the table's main fork to nothing.
+    fn truncate(relation: &PgRelation) {
+        // SAFETY: the relation is open and locked by our caller
+        unsafe {
+            pg_sys::RelationTruncate(relation.as_ptr(), 0);
+        }
+    }
+
+    /// Perform a plain `VACUUM` of the table.
+    fn vacuum(_relation: &PgRelation, _params: &pg_sys::VacuumParams) {}
+
+    /// The size in bytes of `fork` of the table, or of every fork if it's
+    /// [`pg_sys::ForkNumber::InvalidForkNumber`].
+    ///
+    /// The default measures the table's files.
+    fn relation_size(relation: &PgRelation, fork: pg_sys::ForkNumber::Type) -> u64 {
+        // SAFETY: the relation is open
+        unsafe { block_relation_size(relation.as_ptr(), fork) }
+    }
+
+    /// Estimate the size of the table for the planner.
+    ///
+    /// Tables estimated larger than `min_parallel_table_scan_size` may be planned with parallel
+    /// sequential scans, which aren't supported.
+    fn estimate_size(_relation: &PgRelation) -> TableSizeEstimate {
+        TableSizeEstimate::default()
+    }
+}
+
+/// Build the [`pg_sys::TableAmRoutine`] describing `T`.
+///
+/// Postgres keeps pointers to the routine, so the `table_am_handler` generated by
+/// [`#[pg_table_am]`](pgrx_macros::pg_table_am) builds it once and hands out a `'static` copy.
+pub fn table_am_routine<T: TableAccessMethod>() -> pg_sys::TableAmRoutine {
+    let mut routine =
+        pg_sys::TableAmRoutine { type_: pg_sys::NodeTag::T_TableAmRoutine, ..Default::default() };
+
+    routine.slot_callbacks = Some(slot_callbacks::<T>);
+
+    routine.scan_begin = Some(scan_begin::<T>);
+    routine.scan_end = Some(scan_end::<T>);
+    routine.scan_rescan = Some(scan_rescan::<T>);
+    routine.scan_getnextslot = Some(scan_getnextslot::<T>);
+
+    routine.parallelscan_estimate = Some(parallelscan_estimate::<T>);
+    routine.parallelscan_initialize = Some(parallelscan_initialize::<T>);
+    routine.parallelscan_reinitialize = Some(parallelscan_reinitialize::<T>);
+
+    routine.index_fetch_begin = Some(index_fetch_begin::<T>);
+    routine.index_fetch_reset = Some(index_fetch_reset::<T>);
+    routine.index_fetch_end = Some(index_fetch_end::<T>);
+    routine.index_fetch_tuple = Some(index_fetch_tuple::<T>);
+
+    routine.tuple_fetch_row_version = Some(tuple_fetch_row_version::<T>);
+    routine.tuple_tid_valid = Some(tuple_tid_valid::<T>);
+    routine.tuple_get_latest_tid = Some(tuple_get_latest_tid::<T>);
+    routine.tuple_satisfies_snapshot = Some(tuple_satisfies_snapshot::<T>);
+    #[cfg(any(feature = "pg12", feature = "pg13"))]
+    {
+        routine.compute_xid_horizon_for_tuples = Some(compute_xid_horizon_for_tuples::<T>);
+    }
+    #[cfg(not(any(feature = "pg12", feature = "pg13")))]
+    {
+        routine.index_delete_tuples = Some(index_delete_tuples::<T>);
+    }
+
+    routine.tuple_insert = Some(tuple_insert::<T>);
+    routine.tuple_insert_speculative = Some(tuple_insert_speculative::<T>);
+    routine.tuple_complete_speculative = Some(tuple_complete_speculative::<T>);
+    routine.multi_insert = Some(multi_insert::<T>);
+    routine.tuple_delete = Some(tuple_delete::<T>);
+    routine.tuple_update = Some(tuple_update::<T>);
+    routine.tuple_lock = Some(tuple_lock::<T>);
+
+    #[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14", feature = "pg15"))]
+    {
+        routine.relation_set_new_filenode = Some(relation_set_new_filelocator::<T>);
+    }
+    #[cfg(not(any(feature = "pg12", feature = "pg13", feature = "pg14", feature = "pg15")))]
+    {
+        routine.relation_set_new_filelocator = Some(relation_set_new_filelocator::<T>);
+    }
+    routine.relation_nontransactional_truncate = Some(relation_nontransactional_truncate::<T>);
+    routine.relation_copy_data = Some(relation_copy_data::<T>);
+    routine.relation_copy_for_cluster = Some(relation_copy_for_cluster::<T>);
+    routine.relation_vacuum = Some(relation_vacuum::<T>);
+    routine.scan_analyze_next_block = Some(scan_analyze_next_block::<T>);
+    routine.scan_analyze_next_tuple = Some(scan_analyze_next_tuple::<T>);
+    routine.index_build_range_scan = Some(index_build_range_scan::<T>);
+    routine.index_validate_scan = Some(index_validate_scan::<T>);
+
+    routine.relation_size = Some(relation_size::<T>);
+    routine.relation_needs_toast_table = Some(relation_needs_toast_table::<T>);
+    routine.relation_estimate_size = Some(relation_estimate_size::<T>);
+
+    routine.scan_bitmap_next_block = Some(scan_bitmap_next_block::<T>);
+    routine.scan_bitmap_next_tuple = Some(scan_bitmap_next_tuple::<T>);
+    routine.scan_sample_next_block = Some(scan_sample_next_block::<T>);
+    routine.scan_sample_next_tuple = Some(scan_sample_next_tuple::<T>);
+
+    routine
+}
+
+/// The values and null flags of every attribute of the tuple in `slot`.
+pub fn slot_values(slot: &mut pg_sys::TupleTableSlot) -> (&[pg_sys::Datum], &[bool]) {
+    // SAFETY: Postgres gave us a valid slot, and `slot_getsomeattrs_int()` fills its arrays up to
+    // the number of attributes in its tuple descriptor
+    unsafe {
+        let natts = (*slot.tts_tupleDescriptor).natts;
+        if natts <= 0 {
+            return (&[], &[]);
+        }
+        if (slot.tts_nvalid as c_int) < natts {
+            pg_sys::slot_getsomeattrs_int(slot, natts);
+        }
+        let natts = natts as usize;
+        (
+            std::slice::from_raw_parts(slot.tts_values, natts),
+            std::slice::from_raw_parts(slot.tts_isnull, natts),
+        )
+    }
+}
+
+/// Replace the contents of `slot` with a virtual tuple of `values`, identified by `tid`.
+///
+/// Values passed by reference must remain valid for as long as the slot holds them.  On Postgres
+/// 12, a heap tuple slot is then materialized, copying them, so that its tuple's `ctid` is `tid`.
+///
+/// # Panics
+///
+/// If `values` or `isnull` don't have one entry for every attribute of the slot.
+pub fn store_values(
+    slot: &mut pg_sys::TupleTableSlot,
+    tid: pg_sys::ItemPointerData,
+    values: &[pg_sys::Datum],
+    isnull: &[bool],
+) {
+    // SAFETY: Postgres gave us a valid slot, whose arrays have room for every attribute in its
+    // tuple descriptor
+    unsafe {
+        let natts = (*slot.tts_tupleDescriptor).natts.max(0) as usize;
+        assert!(
+            values.len() == natts && isnull.len() == natts,
+            "slot has {natts} attributes, but {} values and {} null flags were provided",
+            values.len(),
+            isnull.len()
+        );
+        if let Some(clear) = (*slot.tts_ops).clear {
+            clear(slot);
+        }
+        if natts > 0 {
+            std::ptr::copy_nonoverlapping(values.as_ptr(), slot.tts_values, natts);
+            std::ptr::copy_nonoverlapping(isnull.as_ptr(), slot.tts_isnull, natts);
+        }
+        pg_sys::ExecStoreVirtualTuple(slot);
+        slot.tts_tid = tid;
+
+        // Postgres 12 reads the `ctid` from the heap tuple, rather than `tts_tid`
+        #[cfg(feature = "pg12")]
+        if std::ptr::eq(slot.tts_ops, std::ptr::addr_of!(pg_sys::TTSOpsHeapTuple)) {
+            if let Some(materialize) = (*slot.tts_ops).materialize {
+                materialize(slot);
+            }
+            let heap_slot =
+                (slot as *mut pg_sys::TupleTableSlot).cast::<pg_sys::HeapTupleTableSlot>();
+            (*(*heap_slot).tuple).t_self = tid;
+        }
+    }
+}
+
+/// Create the storage for a relfilenode, as Postgres' heap access method does.
+///
+/// Unlogged relations are rejected: they also need an init fork, which crash recovery resets them
+/// to, and this doesn't create one.
+pub fn create_storage(locator: &RelFileLocator, persistence: c_char) {
+    if persistence as u8 == pg_sys::RELPERSISTENCE_UNLOGGED {
+        ereport!(
+            ERROR,
+            PgSqlErrorCode::ERRCODE_FEATURE_NOT_SUPPORTED,
+            "unlogged tables are not supported by this table access method"
+        );
+    }
+    // SAFETY: `RelationCreateStorage()` only registers the new file to be deleted on abort
+    unsafe {
+        #[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14"))]
+        pg_sys::RelationCreateStorage(*locator, persistence);
+        #[cfg(not(any(feature = "pg12", feature = "pg13", feature = "pg14")))]
+        pg_sys::RelationCreateStorage(*locator, persistence, true);
+    }
+}
+
+fn unsupported<T: TableAccessMethod>(operation: &str) -> ! {
+    ereport!(
+        ERROR,
+        PgSqlErrorCode::ERRCODE_FEATURE_NOT_SUPPORTED,
+        format!("table access method \"{}\" does not support {operation}", T::NAME)
+    );
+}
+
+#[cfg(not(feature = "pg12"))]
+unsafe fn block_relation_size(rel: pg_sys::Relation, fork: pg_sys::ForkNumber::Type) -> u64 {
+    pg_sys::table_block_relation_size(rel, fork)
+}
+
+#[cfg(feature = "pg12")]
+unsafe fn block_relation_size(rel: pg_sys::Relation, fork: pg_sys::ForkNumber::Type) -> u64 {
+    let smgr = pg_sys::smgropen((*rel).rd_node, (*rel).rd_backend);
+    let forks = if fork == pg_sys::ForkNumber::InvalidForkNumber {
+        pg_sys::ForkNumber::MAIN_FORKNUM..=pg_sys::ForkNumber::INIT_FORKNUM
+    } else {
+        fork..=fork
+    };
+    forks
+        .filter(|&fork| pg_sys::smgrexists(smgr, fork))
+        .map(|fork| pg_sys::smgrnblocks(smgr, fork) as u64 * pg_sys::BLCKSZ as u64)
+        .sum()
+}
+
+/// Our scan descriptor.  Postgres only knows about the `TableScanDescData` at its start.
+#[repr(C)]
+struct ScanDesc {
+    base: pg_sys::TableScanDescData,
+    state: *mut c_void,
+}
+
+#[inline]
+unsafe fn scan_state<'a, T: TableAccessMethod>(
+    scan: pg_sys::TableScanDesc,
+) -> &'a mut Option<T::ScanState> {
+    &mut *(*scan.cast::<ScanDesc>()).state.cast::<Option<T::ScanState>>()
+}
+
+#[inline]
+unsafe fn scan_keys<'a>(keys: *mut pg_sys::ScanKeyData, nkeys: c_int) -> &'a [pg_sys::ScanKeyData] {
+    if keys.is_null() || nkeys <= 0 {
+        &[]
+    } else {
+        std::slice::from_raw_parts(keys, nkeys as usize)
+    }
+}
+
+#[pg_guard]
+unsafe extern "C" fn slot_callbacks<T: TableAccessMethod>(
+    rel: pg_sys::Relation,
+) -> *const pg_sys::TupleTableSlotOps {
+    T::slot_ops(&PgRelation::from_pg(rel))
+}
+
+#[pg_guard]
+unsafe extern "C" fn scan_begin<T: TableAccessMethod>(
+    rel: pg_sys::Relation,
+    snapshot: pg_sys::Snapshot,
+    nkeys: c_int,
+    key: *mut pg_sys::ScanKeyData,
+    pscan: pg_sys::ParallelTableScanDesc,
+    flags: u32,
+) -> pg_sys::TableScanDesc {
+    if !pscan.is_null() {
+        unsupported::<T>("parallel scans");
+    }
+
+    let state = T::begin_scan(&PgRelation::from_pg(rel), snapshot, scan_keys(key, nkeys));
+
+    let scan = pg_sys::palloc0(std::mem::size_of::<ScanDesc>()).cast::<ScanDesc>();
+    (*scan).base.rs_rd = rel;
+    (*scan).base.rs_snapshot = snapshot;
+    (*scan).base.rs_nkeys = nkeys;
+    (*scan).base.rs_key = key;
+    (*scan).base.rs_flags = flags;
+    (*scan).base.rs_parallel = pscan;
+
+    // the state lives in the scan's memory context so it's dropped even if the scan errors out
+    // before `scan_end` gets a chance to take it back
+    (*scan).state =
+        PgMemoryContexts::CurrentMemoryContext.leak_and_drop_on_delete(Some(state)).cast();
+    scan.cast()
+}
+
+#[pg_guard]
+unsafe extern "C" fn scan_end<T: TableAccessMethod>(scan: pg_sys::TableScanDesc) {
+    if let Some(state) = scan_state::<T>(scan).take() {
+        T::end_scan(state);
+    }
+    if (*scan).rs_flags & pg_sys::ScanOptions::SO_TEMP_SNAPSHOT != 0 {
+        pg_sys::UnregisterSnapshot((*scan).rs_snapshot);
+    }
+    pg_sys::pfree(scan.cast());
+}
+
+#[pg_guard]
+unsafe extern "C" fn scan_rescan<T: TableAccessMethod>(
+    scan: pg_sys::TableScanDesc,
+    key: *mut pg_sys::ScanKeyData,
+    _set_params: bool,
+    _allow_strat: bool,
+    _allow_sync: bool,
+    _allow_pagemode: bool,
+) {
+    if let Some(state) = scan_state::<T>(scan) {
+        T::rescan(state, scan_keys(key, (*scan).rs_nkeys));
+    }
+}
+
+#[pg_guard]
+unsafe extern "C" fn scan_getnextslot<T: TableAccessMethod>(
+    scan: pg_sys::TableScanDesc,
+    direction: pg_sys::ScanDirection::Type,
+    slot: *mut pg_sys::TupleTableSlot,
+) -> bool {
+    let found = match scan_state::<T>(scan) {
+        Some(state) => T::next_slot(state, direction, &mut *slot),
+        None => false,
+    };
+    if found {
+        (*slot).tts_tableOid = (*(*scan).rs_rd).rd_id;
+    } else if let Some(clear) = (*(*slot).tts_ops).clear {
+        clear(slot);
+    }
+    found
+}
+
+#[pg_guard]
+unsafe extern "C" fn parallelscan_estimate<T: TableAccessMethod>(
+    _rel: pg_sys::Relation,
+) -> pg_sys::Size {
+    unsupported::<T>("parallel scans")
+}
+
+#[pg_guard]
+unsafe extern "C" fn parallelscan_initialize<T: TableAccessMethod>(
+    _rel: pg_sys::Relation,
+    _pscan: pg_sys::ParallelTableScanDesc,
+) -> pg_sys::Size {
+    unsupported::<T>("parallel scans")
+}
+
+#[pg_guard]
+unsafe extern "C" fn parallelscan_reinitialize<T: TableAccessMethod>(
+    _rel: pg_sys::Relation,
+    _pscan: pg_sys::ParallelTableScanDesc,
+) {
+    unsupported::<T>("parallel scans")
+}
+
+#[pg_guard]
+unsafe extern "C" fn index_fetch_begin<T: TableAccessMethod>(
+    _rel: pg_sys::Relation,
+) -> *mut pg_sys::IndexFetchTableData {
+    unsupported::<T>("indexes")
+}
+
+#[pg_guard]
+unsafe extern "C" fn index_fetch_reset<T: TableAccessMethod>(
+    _data: *mut pg_sys::IndexFetchTableData,
+) {
+}
+
+#[pg_guard]
+unsafe extern "C" fn index_fetch_end<T: TableAccessMethod>(
+    _data: *mut pg_sys::IndexFetchTableData,
+) {
+}
+
+#[pg_guard]
+unsafe extern "C" fn index_fetch_tuple<T: TableAccessMethod>(
+    _scan: *mut pg_sys::IndexFetchTableData,
+    _tid: pg_sys::ItemPointer,
+    _snapshot: pg_sys::Snapshot,
+    _slot: *mut pg_sys::TupleTableSlot,
+    _call_again: *mut bool,
+    _all_dead: *mut bool,
+) -> bool {
+    unsupported::<T>("indexes")
+}
+
+#[pg_guard]
+unsafe extern "C" fn tuple_fetch_row_version<T: TableAccessMethod>(
+    rel: pg_sys::Relation,
+    tid: pg_sys::ItemPointer,
+    snapshot: pg_sys::Snapshot,
+    slot: *mut pg_sys::TupleTableSlot,
+) -> bool {
+    let found = T::fetch_row_version(&PgRelation::from_pg(rel), *tid, snapshot, &mut *slot);
+    if found {
+        (*slot).tts_tableOid = (*rel).rd_id;
+    }
+    found
+}
+
+#[pg_guard]
+unsafe extern "C" fn tuple_tid_valid<T: TableAccessMethod>(
+    _scan: pg_sys::TableScanDesc,
+    _tid: pg_sys::ItemPointer,
+) -> bool {
+    unsupported::<T>("TID scans")
+}
+
+#[pg_guard]
+unsafe extern "C" fn tuple_get_latest_tid<T: TableAccessMethod>(
+    _scan: pg_sys::TableScanDesc,
+    _tid: pg_sys::ItemPointer,
+) {
+    unsupported::<T>("TID scans")
+}
+
+#[pg_guard]
+unsafe extern "C" fn tuple_satisfies_snapshot<T: TableAccessMethod>(
+    _rel: pg_sys::Relation,
+    _slot: *mut pg_sys::TupleTableSlot,
+    _snapshot: pg_sys::Snapshot,
+) -> bool {
+    unsupported::<T>("snapshot visibility checks")
+}
+
+#[cfg(any(feature = "pg12", feature = "pg13"))]
+#[pg_guard]
+unsafe extern "C" fn compute_xid_horizon_for_tuples<T: TableAccessMethod>(
+    _rel: pg_sys::Relation,
+    _items: *mut pg_sys::ItemPointerData,
+    _nitems: c_int,
+) -> pg_sys::TransactionId {
+    unsupported::<T>("indexes")
+}
+
+#[cfg(not(any(feature = "pg12", feature = "pg13")))]
+#[pg_guard]
+unsafe extern "C" fn index_delete_tuples<T: TableAccessMethod>(
+    _rel: pg_sys::Relation,
+    _delstate: *mut pg_sys::TM_IndexDeleteOp,
+) -> pg_sys::TransactionId {
+    unsupported::<T>("indexes")
+}
+
+#[pg_guard]
+unsafe extern "C" fn tuple_insert<T: TableAccessMethod>(
+    rel: pg_sys::Relation,
+    slot: *mut pg_sys::TupleTableSlot,
+    cid: pg_sys::CommandId,
+    options: c_int,
+    _bistate: *mut pg_sys::BulkInsertStateData,
+) {
+    (*slot).tts_tableOid = (*rel).rd_id;
+    T::insert(&PgRelation::from_pg(rel), &mut *slot, cid, options);
+}
+
+#[pg_guard]
+unsafe extern "C" fn tuple_insert_speculative<T: TableAccessMethod>(
+    _rel: pg_sys::Relation,
+    _slot: *mut pg_sys::TupleTableSlot,
+    _cid: pg_sys::CommandId,
+    _options: c_int,
+    _bistate: *mut pg_sys::BulkInsertStateData,
+    _spec_token: u32,
+) {
+    unsupported::<T>("INSERT ... ON CONFLICT")
+}
+
+#[pg_guard]
+unsafe extern "C" fn tuple_complete_speculative<T: TableAccessMethod>(
+    _rel: pg_sys::Relation,
+    _slot: *mut pg_sys::TupleTableSlot,
+    _spec_token: u32,
+    _succeeded: bool,
+) {
+    unsupported::<T>("INSERT ... ON CONFLICT")
+}
+
+#[pg_guard]
+unsafe extern "C" fn multi_insert<T: TableAccessMethod>(
+    rel: pg_sys::Relation,
+    slots: *mut *mut pg_sys::TupleTableSlot,
+    nslots: c_int,
+    cid: pg_sys::CommandId,
+    options: c_int,
+    _bistate: *mut pg_sys::BulkInsertStateData,
+) {
+    if slots.is_null() || nslots <= 0 {
+        return;
+    }
+    let mut slots = std::slice::from_raw_parts(slots, nslots as usize)
+        .iter()
+        .map(|&slot| {
+            (*slot).tts_tableOid = (*rel).rd_id;
+            &mut *slot
+        })
+        .collect::<Vec<_>>();
+    T::multi_insert(&PgRelation::from_pg(rel), &mut slots, cid, options);
+}
+
+#[allow(clippy::too_many_arguments)]
+#[pg_guard]
+unsafe extern "C" fn tuple_delete<T: TableAccessMethod>(
+    rel: pg_sys::Relation,
+    tid: pg_sys::ItemPointer,
+    cid: pg_sys::CommandId,
+    snapshot: pg_sys::Snapshot,
+    crosscheck: pg_sys::Snapshot,
+    wait: bool,
+    tmfd: *mut pg_sys::TM_FailureData,
+    _changing_part: bool,
+) -> pg_sys::TM_Result::Type {
+    let modify = TupleModify { cid, snapshot, crosscheck, wait, failure: &mut *tmfd };
+    T::delete(&PgRelation::from_pg(rel), *tid, modify)
+}
+
+#[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14", feature = "pg15"))]
+type UpdateIndexes = bool;
+#[cfg(not(any(feature = "pg12", feature = "pg13", feature = "pg14", feature = "pg15")))]
+type UpdateIndexes = pg_sys::TU_UpdateIndexes::Type;
+
+#[allow(clippy::too_many_arguments)]
+#[pg_guard]
+unsafe extern "C" fn tuple_update<T: TableAccessMethod>(
+    rel: pg_sys::Relation,
+    otid: pg_sys::ItemPointer,
+    slot: *mut pg_sys::TupleTableSlot,
+    cid: pg_sys::CommandId,
+    snapshot: pg_sys::Snapshot,
+    crosscheck: pg_sys::Snapshot,
+    wait: bool,
+    tmfd: *mut pg_sys::TM_FailureData,
+    lockmode: *mut pg_sys::LockTupleMode::Type,
+    update_indexes: *mut UpdateIndexes,
+) -> pg_sys::TM_Result::Type {
+    (*slot).tts_tableOid = (*rel).rd_id;
+    *lockmode = pg_sys::LockTupleMode::LockTupleExclusive;
+
+    let modify = TupleModify { cid, snapshot, crosscheck, wait, failure: &mut *tmfd };
+    let result = T::update(&PgRelation::from_pg(rel), *otid, &mut *slot, modify);
+
+    // the new tuple has a new TID, so every index needs a new entry for it
+    let updated = result == pg_sys::TM_Result::TM_Ok;
+    #[cfg(any(feature = "pg12", feature = "pg13", feature = "pg14", feature = "pg15"))]
+    {
+        *update_indexes = updated;
+    }
+    #[cfg(not(any(feature = "pg12", feature = "pg13", feature = "pg14", feature = "pg15")))]
+    {
+        *update_indexes = if updated {
+            pg_sys::TU_UpdateIndexes::TU_All
+        } else {
+            pg_sys::TU_UpdateIndexes::TU_None
+        };
+    }
+    result
+}
+
+#[allow(clippy::too_many_arguments)]
+#[pg_guard]
+unsafe extern "C" fn tuple_lock<T: TableAccessMethod>(
+    _rel: pg_sys::Relation,
+    _tid: pg_sys::ItemPointer,
+    _snapshot: pg_sys::Snapshot,
+    _slot: *mut pg_sys::TupleTableSlot,
+    _cid: pg_sys::CommandId,
+    _mode: pg_sys::LockTupleMode::Type,
+    _wait_policy: pg_sys::LockWaitPolicy::Type,
+    _flags: u8,
+    _tmfd: *mut pg_sys::TM_FailureData,
+) -> pg_sys::TM_Result::Type {
+    unsupported::<T>("row locking")
+}
+
+#[pg_guard]
+unsafe extern "C" fn relation_set_new_filelocator<T: TableAccessMethod>(
+    rel: pg_sys::Relation,
+    locator: *const RelFileLocator,
+    persistence: c_char,
+    freeze_xid: *mut pg_sys::TransactionId,
+    minmulti: *mut pg_sys::MultiXactId,
+) {
+    // we don't store transaction ids, so there's nothing for `VACUUM` to freeze
+    *freeze_xid = pg_sys::InvalidTransactionId;
+    *minmulti = 0;
+    T::set_new_filelocator(&PgRelation::from_pg(rel), &*locator, persistence);
+}
+
+#[pg_guard]
+unsafe extern "C" fn relation_nontransactional_truncate<T: TableAccessMethod>(
+    rel: pg_sys::Relation,
+) {
+    T::truncate(&PgRelation::from_pg(rel));
+}
+
+#[pg_guard]
+unsafe extern "C" fn relation_copy_data<T: TableAccessMethod>(
+    _rel: pg_sys::Relation,
+    _locator: *const RelFileLocator,
+) {
+    unsupported::<T>("changing tablespaces")
+}
+
+#[allow(clippy::too_many_arguments)]
+#[pg_guard]
+unsafe extern "C" fn relation_copy_for_cluster<T: TableAccessMethod>(
+    _old_table: pg_sys::Relation,
+    _new_table: pg_sys::Relation,
+    _old_index: pg_sys::Relation,
+    _use_sort: bool,
+    _oldest_xmin: pg_sys::TransactionId,
+    _xid_cutoff: *mut pg_sys::TransactionId,
+    _multi_cutoff: *mut pg_sys::MultiXactId,
+    _num_tuples: *mut f64,
+    _tups_vacuumed: *mut f64,
+    _tups_recently_dead: *mut f64,
+) {
+    unsupported::<T>("CLUSTER or VACUUM FULL")
+}
+
+#[pg_guard]
+unsafe extern "C" fn relation_vacuum<T: TableAccessMethod>(
+    rel: pg_sys::Relation,
+    params: *mut pg_sys::VacuumParams,
+    _bstrategy: pg_sys::BufferAccessStrategy,
+) {
+    T::vacuum(&PgRelation::from_pg(rel), &*params);
+}
+
+// `ANALYZE` finds no blocks to sample, and so leaves the table's statistics alone
+
+#[cfg(not(feature = "pg17"))]
+#[pg_guard]
+unsafe extern "C" fn scan_analyze_next_block<T: TableAccessMethod>(
+    _scan: pg_sys::TableScanDesc,
+    _blockno: pg_sys::BlockNumber,
+    _bstrategy: pg_sys::BufferAccessStrategy,
+) -> bool {
+    false
+}
+
+#[cfg(feature = "pg17")]
+#[pg_guard]
+unsafe extern "C" fn scan_analyze_next_block<T: TableAccessMethod>(
+    _scan: pg_sys::TableScanDesc,
+    _stream: *mut pg_sys::ReadStream,
+) -> bool {
+    false
+}
+
+#[pg_guard]
+unsafe extern "C" fn scan_analyze_next_tuple<T: TableAccessMethod>(
+    _scan: pg_sys::TableScanDesc,
+    _oldest_xmin: pg_sys::TransactionId,
+    _liverows: *mut f64,
+    _deadrows: *mut f64,
+    _slot: *mut pg_sys::TupleTableSlot,
+) -> bool {
+    false
+}
+
+#[allow(clippy::too_many_arguments)]
+#[pg_guard]
+unsafe extern "C" fn index_build_range_scan<T: TableAccessMethod>(
+    _table_rel: pg_sys::Relation,
+    _index_rel: pg_sys::Relation,
+    _index_info: *mut pg_sys::IndexInfo,
+    _allow_sync: bool,
+    _anyvisible: bool,
+    _progress: bool,
+    _start_blockno: pg_sys::BlockNumber,
+    _numblocks: pg_sys::BlockNumber,
+    _callback: pg_sys::IndexBuildCallback,
+    _callback_state: *mut c_void,
+    _scan: pg_sys::TableScanDesc,
+) -> f64 {
+    unsupported::<T>("indexes")
+}
+
+#[pg_guard]
+unsafe extern "C" fn index_validate_scan<T: TableAccessMethod>(
+    _table_rel: pg_sys::Relation,
+    _index_rel: pg_sys::Relation,
+    _index_info: *mut pg_sys::IndexInfo,
+    _snapshot: pg_sys::Snapshot,
+    _state: *mut pg_sys::ValidateIndexState,
+) {
+    unsupported::<T>("indexes")
+}
+
+#[pg_guard]
+unsafe extern "C" fn relation_size<T: TableAccessMethod>(
+    rel: pg_sys::Relation,
+    fork: pg_sys::ForkNumber::Type,
+) -> u64 {
+    T::relation_size(&PgRelation::from_pg(rel), fork)
+}
+
+#[pg_guard]
+unsafe extern "C" fn relation_needs_toast_table<T: TableAccessMethod>(
+    _rel: pg_sys::Relation,
+) -> bool {
+    false
+}
+
+#[pg_guard]
+unsafe extern "C" fn relation_estimate_size<T: TableAccessMethod>(
+    rel: pg_sys::Relation,
+    _attr_widths: *mut i32,
+    pages: *mut pg_sys::BlockNumber,
+    tuples: *mut f64,
+    allvisfrac: *mut f64,
+) {
+    let estimate = T::estimate_size(&PgRelation::from_pg(rel));
+    *pages = estimate.pages;
+    *tuples = estimate.tuples;
+    *allvisfrac = estimate.allvisfrac;
+}
+
+#[pg_guard]
+unsafe extern "C" fn scan_bitmap_next_block<T: TableAccessMethod>(
+    _scan: pg_sys::TableScanDesc,
+    _tbmres: *mut pg_sys::TBMIterateResult,
+) -> bool {
+    unsupported::<T>("bitmap scans")
+}
+
+#[pg_guard]
+unsafe extern "C" fn scan_bitmap_next_tuple<T: TableAccessMethod>(
+    _scan: pg_sys::TableScanDesc,
+    _tbmres: *mut pg_sys::TBMIterateResult,
+    _slot: *mut pg_sys::TupleTableSlot,
+) -> bool {
+    unsupported::<T>("bitmap scans")
+}
+
+#[pg_guard]
+unsafe extern "C" fn scan_sample_next_block<T: TableAccessMethod>(
+    _scan: pg_sys::TableScanDesc,
+    _scanstate: *mut pg_sys::SampleScanState,
+) -> bool {
+    unsupported::<T>("TABLESAMPLE")
+}
+
+#[pg_guard]
+unsafe extern "C" fn scan_sample_next_tuple<T: TableAccessMethod>(
+    _scan: pg_sys::TableScanDesc,
+    _scanstate: *mut pg_sys::SampleScanState,
+    _slot: *mut pg_sys::TupleTableSlot,
+) -> bool {
+    unsupported::<T>("TABLESAMPLE")
+}
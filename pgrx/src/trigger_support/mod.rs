@@ -177,9 +177,52 @@ Unsafe [`pgrx::pg_sys::FunctionCallInfo`][crate::pg_sys::FunctionCallInfo] and
 [`pgrx::pg_sys::TriggerData`][crate::pg_sys::TriggerData] (include its contained
 [`pgrx::pg_sys::Trigger`][crate::pg_sys::Trigger]) accessors are available..
 
+# Event triggers
+
+[Event triggers](https://www.postgresql.org/docs/current/event-triggers.html) fire for DDL
+rather than for changes to a table's rows.  Their functions accept a [`PgEventTrigger`] and
+return a [`Result`] with nothing in it.  Given an `event`, `#[pg_event_trigger]` also creates the
+event trigger:
+
+```rust,no_run
+use pgrx::prelude::*;
+
+#[pg_event_trigger(event = ddl_command_end, tags = ["CREATE TABLE"])]
+fn log_new_tables(trigger: &PgEventTrigger) -> Result<(), spi::Error> {
+    for command in trigger.ddl_commands()? {
+        log!("created {}", command.object_identity.unwrap_or_default());
+    }
+    Ok(())
+}
+```
+
+This would generate something like the following SQL:
+
+```sql
+CREATE FUNCTION "log_new_tables"()
+    RETURNS event_trigger
+    LANGUAGE c
+    AS 'MODULE_PATHNAME', 'log_new_tables_wrapper';
+CREATE EVENT TRIGGER "log_new_tables"
+    ON ddl_command_end
+    WHEN TAG IN ('CREATE TABLE')
+    EXECUTE FUNCTION "log_new_tables"();
+```
+
+Creating an event trigger requires superuser.  Without an `event`, only the function is created,
+and it can be used in a `CREATE EVENT TRIGGER` of your own.
+
+During `ddl_command_end` the commands the statement ran are available from
+[`PgEventTrigger::ddl_commands`], during `sql_drop` the objects it dropped are available from
+[`PgEventTrigger::dropped_objects`], and during `table_rewrite` the table being rewritten is
+available from [`PgEventTrigger::table_rewrite_oid`].
+
 [`PgHeapTuple`]: crate::heap_tuple::PgHeapTuple
 */
 
+mod pg_event_trigger;
+mod pg_event_trigger_event;
+mod pg_event_trigger_rows;
 mod pg_trigger;
 mod pg_trigger_error;
 mod pg_trigger_level;
@@ -187,6 +230,9 @@ mod pg_trigger_option;
 mod pg_trigger_when;
 mod trigger_tuple;
 
+pub use pg_event_trigger::PgEventTrigger;
+pub use pg_event_trigger_event::PgEventTriggerEvent;
+pub use pg_event_trigger_rows::{PgDdlCommand, PgDroppedObject};
 pub use pg_trigger::PgTrigger;
 pub use pg_trigger_error::PgTriggerError;
 pub use pg_trigger_level::PgTriggerLevel;
@@ -257,6 +303,12 @@ pub unsafe fn called_as_trigger(fcinfo: pg_sys::FunctionCallInfo) -> bool {
     !fcinfo.context.is_null() && is_a(fcinfo.context, pg_sys::NodeTag::T_TriggerData)
 }
 
+#[inline]
+pub unsafe fn called_as_event_trigger(fcinfo: pg_sys::FunctionCallInfo) -> bool {
+    let fcinfo = fcinfo.as_ref().expect("fcinfo was null");
+    !fcinfo.context.is_null() && is_a(fcinfo.context, pg_sys::NodeTag::T_EventTriggerData)
+}
+
 #[inline]
 pub fn trigger_fired_by_insert(event: u32) -> bool {
     event & pg_sys::TRIGGER_EVENT_OPMASK == pg_sys::TRIGGER_EVENT_INSERT
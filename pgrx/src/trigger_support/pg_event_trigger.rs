@@ -0,0 +1,142 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+use crate::fcinfo::direct_function_call;
+use crate::pg_sys;
+use crate::pgbox::{AllocatedByPostgres, PgBox};
+use crate::spi::{self, Spi};
+use crate::trigger_support::{
+    called_as_event_trigger, PgDdlCommand, PgDroppedObject, PgEventTriggerEvent, PgTriggerError,
+};
+use core::ffi::CStr;
+
+/**
+The datatype accepted by an event trigger
+
+A safe view of the `EventTriggerData` Postgres passes to a [`#[pg_event_trigger]`][crate::pg_event_trigger]
+function, along with the functions Postgres provides for learning more about the event.
+
+Usage examples exist in the module level docs.
+*/
+pub struct PgEventTrigger<'a> {
+    event_trigger_data: &'a pg_sys::EventTriggerData,
+}
+
+impl<'a> PgEventTrigger<'a> {
+    /// Construct a new [`PgEventTrigger`] from a [`FunctionCallInfo`][pg_sys::FunctionCallInfo]
+    ///
+    /// Generally this would be automatically done for the user in a [`#[pg_event_trigger]`][crate::pg_event_trigger].
+    ///
+    /// # Safety
+    ///
+    /// Users should ensure the provided `fcinfo` is one provided by PostgreSQL during an event
+    /// trigger invocation, and that it is unharmed.  If it isn't, this or any other function on
+    /// this type is undefined behavior, hopefully panicking.
+    #[doc(hidden)]
+    pub unsafe fn from_fcinfo(
+        fcinfo: &'a pg_sys::FunctionCallInfoBaseData,
+    ) -> Result<Self, PgTriggerError> {
+        if !called_as_event_trigger(fcinfo as *const _ as *mut _) {
+            return Err(PgTriggerError::NotEventTrigger);
+        }
+
+        let event_trigger_data = (fcinfo.context as *mut pg_sys::EventTriggerData)
+            .as_ref()
+            .ok_or(PgTriggerError::NullEventTriggerData)?;
+
+        Ok(Self { event_trigger_data })
+    }
+
+    /// The event that fired the event trigger
+    pub fn event(&self) -> Result<PgEventTriggerEvent, PgTriggerError> {
+        PgEventTriggerEvent::try_from(self.event_name()?)
+    }
+
+    /// The name of the event that fired the event trigger, such as `ddl_command_end`
+    pub fn event_name(&self) -> Result<&str, PgTriggerError> {
+        // SAFETY:  Postgres always names the event it fires
+        let event = unsafe { CStr::from_ptr(self.event_trigger_data.event) };
+        Ok(event.to_str()?)
+    }
+
+    /// The command tag of the statement that fired the event trigger, such as `CREATE TABLE`
+    pub fn tag(&self) -> Result<&str, PgTriggerError> {
+        #[cfg(feature = "pg12")]
+        let tag = self.event_trigger_data.tag;
+        #[cfg(not(feature = "pg12"))]
+        // SAFETY:  command tag names are static strings
+        let tag = unsafe { pg_sys::GetCommandTagName(self.event_trigger_data.tag) };
+        // SAFETY:  Postgres always tags the statement that fires an event trigger
+        let tag = unsafe { CStr::from_ptr(tag) };
+        Ok(tag.to_str()?)
+    }
+
+    /// The parse tree of the statement that fired the event trigger
+    ///
+    /// Its structure is internal to Postgres and may change between major versions.  Use
+    /// [`pgrx::is_a`][crate::is_a] to find out what kind of statement it is.
+    pub fn parse_tree(&self) -> PgBox<pg_sys::Node, AllocatedByPostgres> {
+        // SAFETY:  the parse tree belongs to Postgres and outlives the event trigger
+        unsafe { PgBox::from_pg(self.event_trigger_data.parsetree) }
+    }
+
+    /// The commands run by the statement that fired a `ddl_command_end` event trigger
+    ///
+    /// Postgres raises an error if this is called during any other event.
+    pub fn ddl_commands(&self) -> Result<Vec<PgDdlCommand>, spi::Error> {
+        Spi::connect(|client| {
+            client
+                .select(
+                    "SELECT classid, objid, objsubid, command_tag, object_type, schema_name, \
+                     object_identity, in_extension \
+                     FROM pg_catalog.pg_event_trigger_ddl_commands()",
+                    None,
+                    &[],
+                )?
+                .rows::<PgDdlCommand>()?
+                .collect()
+        })
+    }
+
+    /// The objects dropped by the statement that fired a `sql_drop` event trigger
+    ///
+    /// Postgres raises an error if this is called during any other event.
+    pub fn dropped_objects(&self) -> Result<Vec<PgDroppedObject>, spi::Error> {
+        Spi::connect(|client| {
+            client
+                .select("SELECT * FROM pg_catalog.pg_event_trigger_dropped_objects()", None, &[])?
+                .rows::<PgDroppedObject>()?
+                .collect()
+        })
+    }
+
+    /// The oid of the table being rewritten by the statement that fired a `table_rewrite` event
+    /// trigger
+    ///
+    /// Postgres raises an error if this is called during any other event.
+    pub fn table_rewrite_oid(&self) -> pg_sys::Oid {
+        // SAFETY:  the function takes no arguments
+        unsafe { direct_function_call(pg_sys::pg_event_trigger_table_rewrite_oid, &[]) }
+            .expect("pg_event_trigger_table_rewrite_oid() returned NULL")
+    }
+
+    /// Why the table is being rewritten, as a bitmask of the `AT_REWRITE_*` reasons
+    ///
+    /// Postgres raises an error if this is called during any event other than `table_rewrite`.
+    pub fn table_rewrite_reason(&self) -> i32 {
+        // SAFETY:  the function takes no arguments
+        unsafe { direct_function_call(pg_sys::pg_event_trigger_table_rewrite_reason, &[]) }
+            .expect("pg_event_trigger_table_rewrite_reason() returned NULL")
+    }
+
+    /// A reference to the underlying [`EventTriggerData`][pgrx_pg_sys::EventTriggerData]
+    pub fn event_trigger_data(&self) -> &'a pgrx_pg_sys::EventTriggerData {
+        self.event_trigger_data
+    }
+}
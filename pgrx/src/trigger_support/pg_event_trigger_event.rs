@@ -0,0 +1,55 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+use crate::trigger_support::PgTriggerError;
+
+/// The event that fired an event trigger
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum PgEventTriggerEvent {
+    /// `ddl_command_start`
+    DdlCommandStart,
+    /// `ddl_command_end`
+    DdlCommandEnd,
+    /// `sql_drop`
+    SqlDrop,
+    /// `table_rewrite`
+    TableRewrite,
+}
+
+impl PgEventTriggerEvent {
+    /// The name of the event, as used in `CREATE EVENT TRIGGER ... ON`
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PgEventTriggerEvent::DdlCommandStart => "ddl_command_start",
+            PgEventTriggerEvent::DdlCommandEnd => "ddl_command_end",
+            PgEventTriggerEvent::SqlDrop => "sql_drop",
+            PgEventTriggerEvent::TableRewrite => "table_rewrite",
+        }
+    }
+}
+
+impl TryFrom<&str> for PgEventTriggerEvent {
+    type Error = PgTriggerError;
+
+    fn try_from(event: &str) -> Result<Self, Self::Error> {
+        match event {
+            "ddl_command_start" => Ok(PgEventTriggerEvent::DdlCommandStart),
+            "ddl_command_end" => Ok(PgEventTriggerEvent::DdlCommandEnd),
+            "sql_drop" => Ok(PgEventTriggerEvent::SqlDrop),
+            "table_rewrite" => Ok(PgEventTriggerEvent::TableRewrite),
+            _ => Err(PgTriggerError::InvalidPgEventTriggerEvent),
+        }
+    }
+}
+
+impl std::fmt::Display for PgEventTriggerEvent {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
@@ -0,0 +1,126 @@
+//LICENSE Portions Copyright 2019-2021 ZomboDB, LLC.
+//LICENSE
+//LICENSE Portions Copyright 2021-2023 Technology Concepts & Design, Inc.
+//LICENSE
+//LICENSE Portions Copyright 2023-2023 PgCentral Foundation, Inc. <contact@pgcentral.org>
+//LICENSE
+//LICENSE All rights reserved.
+//LICENSE
+//LICENSE Use of this source code is governed by the MIT license that can be found in the LICENSE file.
+use crate::pg_sys;
+use crate::row::{FromRow, RowColumn, RowError, RowReader, RowSource};
+
+/// A command run by the statement that fired a `ddl_command_end` event trigger
+///
+/// A row of [`pg_event_trigger_ddl_commands()`](https://www.postgresql.org/docs/current/functions-event-triggers.html#PG-EVENT-TRIGGER-DDL-COMMAND-END-FUNCTIONS),
+/// without its internal `command` column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PgDdlCommand {
+    /// The oid of the catalog the object belongs in
+    pub classid: pg_sys::Oid,
+    /// The oid of the object itself
+    pub objid: pg_sys::Oid,
+    /// The sub-object id, such as a column's attribute number
+    pub objsubid: i32,
+    /// The command tag, such as `CREATE TABLE`
+    pub command_tag: String,
+    /// The type of the object
+    pub object_type: String,
+    /// The name of the schema the object belongs in, if any
+    pub schema_name: Option<String>,
+    /// The text rendering of the object's identity, schema-qualified
+    pub object_identity: Option<String>,
+    /// Is the command part of an extension's script?
+    pub in_extension: bool,
+}
+
+impl FromRow for PgDdlCommand {
+    const COLUMNS: &'static [RowColumn] = &[
+        RowColumn::name("classid", false),
+        RowColumn::name("objid", false),
+        RowColumn::name("objsubid", false),
+        RowColumn::name("command_tag", false),
+        RowColumn::name("object_type", false),
+        RowColumn::name("schema_name", false),
+        RowColumn::name("object_identity", false),
+        RowColumn::name("in_extension", false),
+    ];
+
+    fn from_row<S: RowSource>(row: &RowReader<'_, S>) -> Result<Self, RowError> {
+        Ok(PgDdlCommand {
+            classid: row.get_required(0)?,
+            objid: row.get_required(1)?,
+            objsubid: row.get_required(2)?,
+            command_tag: row.get_required(3)?,
+            object_type: row.get_required(4)?,
+            schema_name: row.get(5)?,
+            object_identity: row.get(6)?,
+            in_extension: row.get_required(7)?,
+        })
+    }
+}
+
+/// An object dropped by the statement that fired a `sql_drop` event trigger
+///
+/// A row of [`pg_event_trigger_dropped_objects()`](https://www.postgresql.org/docs/current/functions-event-triggers.html#PG-EVENT-TRIGGER-SQL-DROP-FUNCTIONS).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PgDroppedObject {
+    /// The oid of the catalog the object belonged in
+    pub classid: pg_sys::Oid,
+    /// The oid of the object itself
+    pub objid: pg_sys::Oid,
+    /// The sub-object id, such as a column's attribute number
+    pub objsubid: i32,
+    /// Was this one of the objects named in the statement?
+    pub original: bool,
+    /// Was this object dropped through a normal dependency of an original one?
+    pub normal: bool,
+    /// Was this a temporary object?
+    pub is_temporary: bool,
+    /// The type of the object
+    pub object_type: String,
+    /// The name of the schema the object belonged in, if any
+    pub schema_name: Option<String>,
+    /// The name of the object, if it's unique enough to identify it
+    pub object_name: Option<String>,
+    /// The text rendering of the object's identity, schema-qualified
+    pub object_identity: Option<String>,
+    /// The object's address, as understood by `pg_get_object_address()`
+    pub address_names: Vec<String>,
+    /// The arguments of the object's address
+    pub address_args: Vec<String>,
+}
+
+impl FromRow for PgDroppedObject {
+    const COLUMNS: &'static [RowColumn] = &[
+        RowColumn::name("classid", false),
+        RowColumn::name("objid", false),
+        RowColumn::name("objsubid", false),
+        RowColumn::name("original", false),
+        RowColumn::name("normal", false),
+        RowColumn::name("is_temporary", false),
+        RowColumn::name("object_type", false),
+        RowColumn::name("schema_name", false),
+        RowColumn::name("object_name", false),
+        RowColumn::name("object_identity", false),
+        RowColumn::name("address_names", false),
+        RowColumn::name("address_args", false),
+    ];
+
+    fn from_row<S: RowSource>(row: &RowReader<'_, S>) -> Result<Self, RowError> {
+        Ok(PgDroppedObject {
+            classid: row.get_required(0)?,
+            objid: row.get_required(1)?,
+            objsubid: row.get_required(2)?,
+            original: row.get_required(3)?,
+            normal: row.get_required(4)?,
+            is_temporary: row.get_required(5)?,
+            object_type: row.get_required(6)?,
+            schema_name: row.get(7)?,
+            object_name: row.get(8)?,
+            object_identity: row.get(9)?,
+            address_names: row.get(10)?.unwrap_or_default(),
+            address_args: row.get(11)?.unwrap_or_default(),
+        })
+    }
+}
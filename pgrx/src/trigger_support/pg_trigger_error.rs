@@ -31,4 +31,10 @@ pub enum PgTriggerError {
     NullTriggerData,
     #[error("The `pgrx::pg_sys::TriggerData`'s `tg_relation` field was a NULL pointer")]
     NullRelation,
+    #[error("`PgEventTrigger`s can only be built from `FunctionCallInfo` instances which `pgrx::called_as_event_trigger(fcinfo)` returns `true`")]
+    NotEventTrigger,
+    #[error("The `pgrx::pg_sys::FunctionCallInfo`'s `context` field was a NULL pointer")]
+    NullEventTriggerData,
+    #[error("`PgEventTriggerEvent` cannot be built from an unknown event")]
+    InvalidPgEventTriggerEvent,
 }